@@ -0,0 +1,68 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+
+use tokio::sync::mpsc::UnboundedSender;
+
+/// What [`PlayerBackend`] calls that talk to the player return.
+pub type BackendFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Where a backend reports changes, tagged with the generation of the
+/// player they came from.
+pub type ChangeSender = UnboundedSender<(u64, PlayerChange)>;
+
+/// A change a player reported without being asked.
+pub enum PlayerChange {
+    /// Backend volume, after the volume curve.
+    Volume(f64),
+    Pause(bool),
+    Title(String),
+    /// Buffer fill in percent while buffering; `None` once enough is
+    /// buffered.
+    Buffering(Option<u32>),
+}
+
+/// A running player: a child process, or libvlc playing in this process.
+/// Changes it cannot make in place fail, and the caller restarts it with
+/// the new settings instead.
+pub trait PlayerBackend: Send {
+    /// Resolves once the player has ended, by itself or after `start_kill`.
+    fn wait(&mut self) -> BackendFuture<'_, ()>;
+
+    /// Whether the player has ended, without waiting.
+    fn has_ended(&mut self) -> bool;
+
+    /// Start stopping the player; `wait` resolves once it is gone.
+    fn start_kill(&mut self);
+
+    /// Set the output level, after the volume curve.
+    fn set_volume(&mut self, level: f64) -> BackendFuture<'_, io::Result<()>>;
+
+    fn set_paused(&mut self, _paused: bool) -> BackendFuture<'_, io::Result<()>> {
+        unsupported("pause")
+    }
+
+    /// Change the playback speed, keeping the pitch.
+    fn set_speed(&mut self, _speed: f64) -> BackendFuture<'_, io::Result<()>> {
+        unsupported("speed")
+    }
+
+    /// A reading that keeps increasing while audio plays, for stall
+    /// detection. `None` when the player cannot tell.
+    fn progress(&mut self) -> BackendFuture<'_, Option<f64>> {
+        Box::pin(async { None })
+    }
+
+    /// Report title, buffering and level changes to `changes` from now on,
+    /// tagged with `generation`.
+    fn observe(&mut self, _generation: u64, _changes: ChangeSender) {}
+}
+
+/// Failure of a change the player cannot make while it runs.
+pub fn unsupported(what: &str) -> BackendFuture<'static, io::Result<()>> {
+    let error = io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("No runtime {}, restart needed", what),
+    );
+    Box::pin(async { Err(error) })
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::{config_dir, write_private, Config};
+use crate::state::{instance_path, state_dir};
+use crate::stats::unix_now;
+
+/// Bumped when the archive layout changes incompatibly.
+const FORMAT: u32 = 1;
+/// Files kept in the config directory.
+const CONFIG_FILES: [&str; 1] = ["config.toml"];
+/// Files kept in the state directory: UI state, stats, likes, history,
+/// recordings and scrobbles not sent yet.
+const STATE_FILES: [&str; 6] = [
+    "state.toml",
+    "stats.json",
+    "likes.json",
+    "history.jsonl",
+    "recordings.json",
+    "scrobble_queue.json",
+];
+
+/// Everything `lofi_rs backup` saves, as one JSON file: the files' contents
+/// by name, per directory. Missing files are left out.
+#[derive(Deserialize, Serialize)]
+struct Backup {
+    format: u32,
+    /// Version of lofi_rs that wrote it.
+    version: String,
+    /// Unix time of the backup.
+    created_at: u64,
+    config: BTreeMap<String, String>,
+    state: BTreeMap<String, String>,
+}
+
+fn config_file(name: &str) -> Option<PathBuf> {
+    Some(config_dir()?.join(name))
+}
+
+/// Where the state file `name` is: the UI state is kept per instance, the
+/// rest is shared.
+fn state_file(name: &str) -> Option<PathBuf> {
+    match name {
+        "state.toml" => instance_path(name),
+        _ => Some(state_dir()?.join(name)),
+    }
+}
+
+fn read_files(names: &[&str], path: fn(&str) -> Option<PathBuf>) -> BTreeMap<String, String> {
+    names
+        .iter()
+        .filter_map(|name| {
+            let text = std::fs::read_to_string(path(name)?).ok()?;
+            Some((name.to_string(), text))
+        })
+        .collect()
+}
+
+/// `lofi_rs backup FILE`: bundle config, stations, likes, history and
+/// stats into `path`.
+pub fn backup(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let backup = Backup {
+        format: FORMAT,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: unix_now(),
+        config: read_files(&CONFIG_FILES, config_file),
+        state: read_files(&STATE_FILES, state_file),
+    };
+    let count = backup.config.len() + backup.state.len();
+    if count == 0 {
+        return Err("nothing to back up yet".into());
+    }
+    // Holds the config, tokens and scrobbler keys included
+    write_private(path, serde_json::to_string_pretty(&backup)?.as_bytes())?;
+    println!("Saved {} file(s) to {}", count, path.display());
+    Ok(())
+}
+
+/// `lofi_rs restore FILE`: write a backup's files back, replacing the
+/// current ones after confirmation unless `yes`.
+pub fn restore(path: &Path, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path)?;
+    let backup: Backup = serde_json::from_str(&text)
+        .map_err(|e| format!("{}: not a lofi_rs backup ({})", path.display(), e))?;
+    if backup.format > FORMAT {
+        return Err(format!(
+            "{} was written by lofi_rs {}, which is newer than this version",
+            path.display(),
+            backup.version
+        )
+        .into());
+    }
+    if let Some(config) = backup.config.get("config.toml") {
+        toml::from_str::<Config>(config).map_err(|e| format!("config.toml in backup: {}", e))?;
+    }
+    config_dir().ok_or("cannot locate config directory (HOME unset)")?;
+    state_dir().ok_or("cannot locate state directory (HOME unset)")?;
+    // Only names this version knows; anything else in the file is ignored
+    let files: Vec<(PathBuf, &String)> = CONFIG_FILES
+        .iter()
+        .filter_map(|name| Some((config_file(name)?, backup.config.get(*name)?)))
+        .chain(
+            STATE_FILES
+                .iter()
+                .filter_map(|name| Some((state_file(name)?, backup.state.get(*name)?))),
+        )
+        .collect();
+    if files.is_empty() {
+        println!("The backup holds no files.");
+        return Ok(());
+    }
+    println!("Backup from lofi_rs {}, restoring:", backup.version);
+    for (target, _) in &files {
+        let note = if target.exists() {
+            " (replaces current)"
+        } else {
+            ""
+        };
+        println!("  {}{}", target.display(), note);
+    }
+    if !yes {
+        print!("Continue? [y/N] ");
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            return Ok(());
+        }
+    }
+    for (target, text) in &files {
+        if let Some(dir) = target.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        write_private(target, text.as_bytes())?;
+    }
+    println!("Restored {} file(s).", files.len());
+    Ok(())
+}
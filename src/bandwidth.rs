@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// Bitrate assumed when the backend does not report one (only mpv does).
+const FALLBACK_KBPS: u32 = 128;
+
+/// What happens once the month's data reaches the cap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapAction {
+    /// Only say so.
+    #[default]
+    Warn,
+    /// Also play stations' `low_bitrate_url` until the month is over.
+    LowBitrate,
+}
+
+/// Data use on metered connections, under `[bandwidth]`. Players do not
+/// report what they download, so it is estimated from the stream bitrate
+/// and the time played; a recording counts as a second download.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BandwidthConfig {
+    /// Data allowed per calendar month, in MB; 0 sets no cap.
+    pub monthly_cap_mb: u64,
+    /// Warn once the month's data passes this share of the cap, 1-100.
+    pub warn_percent: u32,
+    pub on_cap: CapAction,
+}
+
+impl Default for BandwidthConfig {
+    fn default() -> Self {
+        Self {
+            monthly_cap_mb: 0,
+            warn_percent: 80,
+            on_cap: CapAction::Warn,
+        }
+    }
+}
+
+impl BandwidthConfig {
+    fn cap_bytes(&self) -> Option<u64> {
+        (self.monthly_cap_mb > 0).then(|| self.monthly_cap_mb * 1_000_000)
+    }
+}
+
+/// Where the month stands against the cap, as reported by [`DataMeter::check`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CapLevel {
+    Warning,
+    Reached,
+}
+
+/// Data used this session and what was said about the cap.
+pub struct DataMeter {
+    config: BandwidthConfig,
+    session: u64,
+    last: Option<Instant>,
+    level: Option<CapLevel>,
+}
+
+impl DataMeter {
+    pub fn new(config: BandwidthConfig) -> Self {
+        Self {
+            config,
+            session: 0,
+            last: None,
+            level: None,
+        }
+    }
+
+    /// Bytes downloaded since the last sample at `kbps`, over `streams`
+    /// connections; nothing while none is open.
+    pub fn sample(&mut self, kbps: Option<u32>, streams: u32) -> u64 {
+        let now = Instant::now();
+        let since = self.last.replace(now).map(|last| now - last);
+        let (Some(since), true) = (since, streams > 0) else {
+            return 0;
+        };
+        let bytes_per_sec = f64::from(kbps.unwrap_or(FALLBACK_KBPS)) * 1000.0 / 8.0;
+        let bytes = (bytes_per_sec * since.as_secs_f64()) as u64 * u64::from(streams);
+        self.session += bytes;
+        bytes
+    }
+
+    /// Bytes downloaded this session.
+    pub fn session(&self) -> u64 {
+        self.session
+    }
+
+    /// Compare the month's `used` bytes with the cap; returns a level the
+    /// first time it is reached, so each is announced once.
+    pub fn check(&mut self, used: u64) -> Option<CapLevel> {
+        let cap = self.config.cap_bytes()?;
+        let level = if used >= cap {
+            CapLevel::Reached
+        } else if used * 100 >= cap * u64::from(self.config.warn_percent.clamp(1, 100)) {
+            CapLevel::Warning
+        } else {
+            // A new month starts over
+            self.level = None;
+            return None;
+        };
+        if self.level == Some(level) || self.level == Some(CapLevel::Reached) {
+            return None;
+        }
+        self.level = Some(level);
+        Some(level)
+    }
+
+    /// Whether streams should be played at their low bitrate to save data.
+    pub fn capped(&self) -> bool {
+        self.config.on_cap == CapAction::LowBitrate && self.level == Some(CapLevel::Reached)
+    }
+
+    /// "1.2 GB of 10 GB this month" or "1.2 GB this month".
+    pub fn month_label(&self, used: u64) -> String {
+        match self.config.cap_bytes() {
+            Some(cap) => format!("{} of {} this month", format_bytes(used), format_bytes(cap)),
+            None => format!("{} this month", format_bytes(used)),
+        }
+    }
+}
+
+/// Decimal units, as carriers count: "840 kB", "45 MB", "1.2 GB".
+pub fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1_000_000_000 => format!("{:.1} GB", b as f64 / 1e9),
+        b if b >= 1_000_000 => format!("{} MB", b / 1_000_000),
+        b => format!("{} kB", b / 1000),
+    }
+}
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+use std::path::PathBuf;
+use tokio::process::Child;
+
+use crate::chime::{wav_bytes, CHIME_RATE};
+use crate::config::runtime_dir;
+use crate::noise::{play_pcm, AmbientBackend};
+
+/// Seconds in the WAV loop; frequencies are rounded to 0.1 Hz so it is seamless.
+const LOOP_SECS: usize = 10;
+/// Peak amplitude before the volume is applied; tones are tiring when loud.
+const TONE_LEVEL: f64 = 0.4;
+
+/// Beat frequency presets, by brainwave band.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BeatPreset {
+    /// Beta, 16 Hz.
+    #[default]
+    Focus,
+    /// Alpha, 10 Hz.
+    Relax,
+    /// Delta, 3 Hz.
+    Sleep,
+}
+
+impl BeatPreset {
+    pub const ALL: [BeatPreset; 3] = [BeatPreset::Focus, BeatPreset::Relax, BeatPreset::Sleep];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BeatPreset::Focus => "focus",
+            BeatPreset::Relax => "relax",
+            BeatPreset::Sleep => "sleep",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+
+    pub fn beat_hz(self) -> f64 {
+        match self {
+            BeatPreset::Focus => 16.0,
+            BeatPreset::Relax => 10.0,
+            BeatPreset::Sleep => 3.0,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            BeatPreset::Focus => BeatPreset::Relax,
+            BeatPreset::Relax => BeatPreset::Sleep,
+            BeatPreset::Sleep => BeatPreset::Focus,
+        }
+    }
+}
+
+/// How the beat is produced.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BeatMode {
+    /// A slightly different tone in each ear; needs headphones.
+    #[default]
+    Binaural,
+    /// One tone pulsed at the beat frequency; works on speakers.
+    Isochronic,
+}
+
+/// `[beats]` in the config.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BeatsConfig {
+    /// Play beats from startup; `g` toggles them either way.
+    pub enabled: bool,
+    pub preset: BeatPreset,
+    /// Beat frequency in Hz, overriding the preset's.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beat_hz: Option<f64>,
+    /// Tone the beat is carried on, in Hz.
+    pub carrier_hz: f64,
+    pub mode: BeatMode,
+    /// Loudness of the tones, 0-100, independent of the stream volume.
+    pub volume: u32,
+}
+
+impl Default for BeatsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            preset: BeatPreset::Focus,
+            beat_hz: None,
+            carrier_hz: 200.0,
+            mode: BeatMode::Binaural,
+            volume: 20,
+        }
+    }
+}
+
+/// Stereo tone pair (or pulsed tone) at `CHIME_RATE`.
+pub struct Tone {
+    carrier: f64,
+    beat: f64,
+    mode: BeatMode,
+    frame: u64,
+}
+
+impl Tone {
+    pub fn new(carrier_hz: f64, beat_hz: f64, mode: BeatMode) -> Self {
+        let round = |hz: f64| (hz.clamp(0.1, 1000.0) * 10.0).round() / 10.0;
+        Self {
+            carrier: round(carrier_hz),
+            beat: round(beat_hz),
+            mode,
+            frame: 0,
+        }
+    }
+
+    /// Fill `out` with interleaved stereo samples at `gain` (0.0-1.0).
+    pub fn fill(&mut self, out: &mut [i16], gain: f32) {
+        let amp = TONE_LEVEL * gain as f64 * i16::MAX as f64;
+        for frame in out.chunks_exact_mut(2) {
+            // Wrap every 10 s, a whole number of cycles at 0.1 Hz resolution
+            let t = (self.frame % (CHIME_RATE as u64 * 10)) as f64 / CHIME_RATE as f64;
+            self.frame += 1;
+            let (left, right) = match self.mode {
+                BeatMode::Binaural => (
+                    (TAU * self.carrier * t).sin(),
+                    (TAU * (self.carrier + self.beat) * t).sin(),
+                ),
+                BeatMode::Isochronic => {
+                    let pulse = 0.5 - 0.5 * (TAU * self.beat * t).cos();
+                    let v = (TAU * self.carrier * t).sin() * pulse;
+                    (v, v)
+                }
+            };
+            frame[0] = (left * amp) as i16;
+            frame[1] = (right * amp) as i16;
+        }
+    }
+}
+
+/// `lofi_rs beats`: play the tone into the first raw PCM player found.
+pub fn play(
+    carrier_hz: f64,
+    beat_hz: f64,
+    mode: BeatMode,
+    volume: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tone = Tone::new(carrier_hz, beat_hz, mode);
+    let gain = volume.min(100) as f32 / 100.0;
+    play_pcm(|samples| tone.fill(samples, gain))
+}
+
+fn wav_path() -> PathBuf {
+    runtime_dir().join(format!("lofi_rs_beats_{}.wav", std::process::id()))
+}
+
+/// Render a seamless loop of the tone for the mpv/ffplay layer backends.
+fn write_wav(carrier_hz: f64, beat_hz: f64, mode: BeatMode) -> Option<PathBuf> {
+    let mut samples = vec![0i16; CHIME_RATE as usize * LOOP_SECS * 2];
+    Tone::new(carrier_hz, beat_hz, mode).fill(&mut samples, 1.0);
+    let path = wav_path();
+    std::fs::write(&path, wav_bytes(&samples)).ok()?;
+    Some(path)
+}
+
+/// The beat generator layered over the stream, toggled with `g`.
+pub struct Beats {
+    config: BeatsConfig,
+    child: Option<Child>,
+    /// Detected on first use; `Some(None)` when nothing can play the tones.
+    backend: Option<Option<AmbientBackend>>,
+}
+
+impl Beats {
+    pub fn new(config: BeatsConfig) -> Self {
+        Self {
+            config,
+            child: None,
+            backend: None,
+        }
+    }
+
+    fn beat_hz(&self) -> f64 {
+        self.config.beat_hz.unwrap_or(self.config.preset.beat_hz())
+    }
+
+    /// Status bar text while the beats are on, e.g. "Focus 16 Hz".
+    pub fn label(&self) -> Option<String> {
+        if !self.config.enabled {
+            return None;
+        }
+        let name = match (self.config.beat_hz, self.config.preset) {
+            (Some(_), _) => "Custom",
+            (None, BeatPreset::Focus) => "Focus",
+            (None, BeatPreset::Relax) => "Relax",
+            (None, BeatPreset::Sleep) => "Sleep",
+        };
+        Some(format!("{} {} Hz", name, self.beat_hz()))
+    }
+
+    /// Start the tones if they are enabled.
+    pub fn start(&mut self) -> Result<(), String> {
+        self.child = None;
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let backend = *self.backend.get_or_insert_with(AmbientBackend::detect);
+        let backend = backend.ok_or("Beats need pw-cat, pacat, aplay, mpv or ffplay")?;
+        let (carrier, beat, mode) = (self.config.carrier_hz, self.beat_hz(), self.config.mode);
+        let mut subcommand = vec![
+            "beats".to_string(),
+            beat.to_string(),
+            "--carrier".to_string(),
+            carrier.to_string(),
+        ];
+        if mode == BeatMode::Isochronic {
+            subcommand.push("--isochronic".to_string());
+        }
+        let child = backend
+            .spawn(
+                &subcommand,
+                || write_wav(carrier, beat, mode),
+                self.config.volume,
+            )
+            .map_err(|e| format!("Could not start beats: {}", e))?;
+        self.child = Some(child);
+        Ok(())
+    }
+
+    /// Silence the tones, keeping them enabled.
+    pub fn stop(&mut self) {
+        self.child = None;
+    }
+
+    /// Turn the beats off until enabled again.
+    pub fn disable(&mut self) {
+        self.config.enabled = false;
+        self.child = None;
+    }
+
+    /// Turn the beats on or off.
+    pub fn toggle(&mut self) -> Result<(), String> {
+        if self.config.enabled {
+            self.disable();
+            return Ok(());
+        }
+        self.config.enabled = true;
+        self.start().inspect_err(|_| self.config.enabled = false)
+    }
+
+    /// Switch to `preset` (or the next one) and turn the beats on.
+    pub fn set_preset(&mut self, preset: Option<BeatPreset>) -> Result<(), String> {
+        self.config.preset = preset.unwrap_or(self.config.preset.next());
+        self.config.beat_hz = None;
+        self.config.enabled = true;
+        self.start().inspect_err(|_| self.config.enabled = false)
+    }
+
+    /// Whether the tones need headphones to work.
+    pub fn binaural(&self) -> bool {
+        self.config.mode == BeatMode::Binaural
+    }
+}
+
+/// Remove the WAV loop written for the layer backends.
+pub fn remove_wav() {
+    let _ = std::fs::remove_file(wav_path());
+}
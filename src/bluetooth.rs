@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::output::{list_output_devices, OutputEvent};
+
+/// How long to wait for a freshly connected device's sink to appear.
+const SINK_TIMEOUT: Duration = Duration::from_secs(10);
+const SINK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A paired Bluetooth audio device, as reported by bluez.
+#[derive(Clone)]
+pub struct BluetoothDevice {
+    /// MAC address, "AA:BB:CC:DD:EE:FF".
+    pub address: String,
+    pub name: String,
+    pub connected: bool,
+}
+
+async fn bluetoothctl(args: &[&str]) -> Option<String> {
+    let out = TokioCommand::new("bluetoothctl")
+        .args(args)
+        .output()
+        .await
+        .ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Paired devices that can play audio. Empty without bluez (and off Linux).
+pub async fn paired_audio_devices() -> Vec<BluetoothDevice> {
+    if !cfg!(target_os = "linux") {
+        return Vec::new();
+    }
+    // bluez 5.65+ takes a filter; older versions have `paired-devices`
+    let listing = match bluetoothctl(&["devices", "Paired"]).await {
+        Some(text) if !text.trim().is_empty() => text,
+        _ => bluetoothctl(&["paired-devices"]).await.unwrap_or_default(),
+    };
+    let mut devices = Vec::new();
+    for line in listing.lines() {
+        // "Device AA:BB:CC:DD:EE:FF Name"
+        let Some(rest) = line.trim().strip_prefix("Device ") else {
+            continue;
+        };
+        let (address, name) = rest.split_once(' ').unwrap_or((rest, rest));
+        let Some(info) = bluetoothctl(&["info", address]).await else {
+            continue;
+        };
+        if !info.contains("Audio Sink") {
+            continue;
+        }
+        devices.push(BluetoothDevice {
+            address: address.to_string(),
+            name: name.trim().to_string(),
+            connected: info.contains("Connected: yes"),
+        });
+    }
+    devices
+}
+
+/// Connect to `address` in the background, wait for its sink to show up
+/// and report it as `OutputEvent::BluetoothReady`.
+pub fn spawn_connect(device: BluetoothDevice, tx: UnboundedSender<OutputEvent>) {
+    tokio::spawn(async move {
+        let result = connect(&device).await;
+        let _ = tx.send(OutputEvent::BluetoothReady(device.name, result));
+    });
+}
+
+async fn connect(device: &BluetoothDevice) -> Result<String, String> {
+    let out = TokioCommand::new("bluetoothctl")
+        .args(["connect", &device.address])
+        .output()
+        .await
+        .map_err(|e| format!("bluetoothctl: {}", e))?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    if !out.status.success() || text.contains("Failed to connect") {
+        return Err(format!("could not connect to {}", device.name));
+    }
+    // PulseAudio names the sink bluez_sink.AA_BB_…, PipeWire bluez_output.AA_BB_…
+    let tag = device.address.replace(':', "_");
+    let started = Instant::now();
+    while started.elapsed() < SINK_TIMEOUT {
+        if let Some(sink) = list_output_devices()
+            .await
+            .into_iter()
+            .find(|d| d.id.starts_with("bluez") && d.id.contains(&tag))
+        {
+            return Ok(sink.id);
+        }
+        tokio::time::sleep(SINK_POLL_INTERVAL).await;
+    }
+    Err(format!(
+        "{} connected but no audio output appeared",
+        device.name
+    ))
+}
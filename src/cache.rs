@@ -0,0 +1,101 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::state::cache_dir;
+use crate::stats::unix_now;
+
+/// Entries written longer ago than this are deleted at startup; longer
+/// than MusicBrainz lookups are kept, so their cover art stays.
+const PRUNE_AFTER: Duration = Duration::from_secs(200 * 24 * 60 * 60);
+
+/// A cached response and when it was fetched.
+#[derive(Deserialize, Serialize)]
+struct Entry<T> {
+    stored_at: u64,
+    value: T,
+}
+
+/// File for `key` under the cache's `kind` directory ("radiobrowser",
+/// "artwork"), named by the key's SHA-256 so any key makes a valid name.
+fn entry_path(kind: &str, key: &str, extension: &str) -> Option<PathBuf> {
+    let digest = ring::digest::digest(&ring::digest::SHA256, key.as_bytes());
+    let name: String = digest.as_ref()[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Some(
+        cache_dir()?
+            .join(kind)
+            .join(format!("{}.{}", name, extension)),
+    )
+}
+
+/// What was cached for `key`, when younger than `ttl`; `None` as `ttl`
+/// takes stale entries too, for when the network is down.
+pub fn get<T: DeserializeOwned>(kind: &str, key: &str, ttl: Option<Duration>) -> Option<T> {
+    let text = std::fs::read_to_string(entry_path(kind, key, "json")?).ok()?;
+    let entry: Entry<T> = serde_json::from_str(&text).ok()?;
+    let age = unix_now().saturating_sub(entry.stored_at);
+    match ttl {
+        Some(ttl) if age >= ttl.as_secs() => None,
+        _ => Some(entry.value),
+    }
+}
+
+/// Cache `value` for `key`. Failing to write only costs a fetch later.
+pub fn put<T: Serialize>(kind: &str, key: &str, value: &T) {
+    let Some(path) = entry_path(kind, key, "json") else {
+        return;
+    };
+    let entry = Entry {
+        stored_at: unix_now(),
+        value,
+    };
+    if let (Some(dir), Ok(text)) = (path.parent(), serde_json::to_string(&entry)) {
+        let _ = std::fs::create_dir_all(dir);
+        let _ = std::fs::write(path, text);
+    }
+}
+
+/// Cached file for `key` (an image), if there is one.
+pub fn file(kind: &str, key: &str) -> Option<PathBuf> {
+    entry_path(kind, key, "img").filter(|path| path.is_file())
+}
+
+/// Keep `bytes` as the file for `key`; returns where it went.
+pub fn put_file(kind: &str, key: &str, bytes: &[u8]) -> Option<PathBuf> {
+    let path = entry_path(kind, key, "img")?;
+    std::fs::create_dir_all(path.parent()?).ok()?;
+    std::fs::write(&path, bytes).ok()?;
+    Some(path)
+}
+
+/// Delete entries older than `PRUNE_AFTER`, so the cache does not grow
+/// without bound. Runs in the background.
+pub fn spawn_prune() {
+    let Some(dir) = cache_dir() else {
+        return;
+    };
+    tokio::task::spawn_blocking(move || {
+        let kinds = std::fs::read_dir(&dir).into_iter().flatten().flatten();
+        for kind in kinds.filter(|k| k.file_name() != "timeshift") {
+            let entries = std::fs::read_dir(kind.path())
+                .into_iter()
+                .flatten()
+                .flatten();
+            for entry in entries {
+                let stale = entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                    .is_some_and(|age| age > PRUNE_AFTER);
+                if stale {
+                    let _ = std::fs::remove_file(entry.path());
+                }
+            }
+        }
+    });
+}
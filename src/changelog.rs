@@ -0,0 +1,49 @@
+use crate::update::version_key;
+
+/// What a release brought, shown once after upgrading to it.
+pub struct ReleaseNotes {
+    pub version: &'static str,
+    pub features: &'static [&'static str],
+    /// Default key bindings that now do something else.
+    pub keys: &'static [KeyChange],
+}
+
+/// A default key binding that changed.
+pub struct KeyChange {
+    pub key: &'static str,
+    pub before: &'static str,
+    pub after: &'static str,
+}
+
+/// Releases, newest first. Add an entry with each version bump.
+const RELEASES: &[ReleaseNotes] = &[ReleaseNotes {
+    version: "0.1.0",
+    features: &[
+        "Crash recovery: after an unclean exit, offers to resume the station, volume, timers and recording",
+        "[startup] actions run on launch; [[macros]] run with `macro <name>` in the palette",
+        "--connect ADDR controls another player's remote from the terminal",
+        "--instance NAME runs named players side by side",
+        "Low-data mode (--low-data, `low-data`), data use estimates and a monthly cap",
+        "Radio-browser answers, MusicBrainz lookups and cover art are cached on disk",
+        "Recordings view (f) to play, rename and delete saved recordings",
+    ],
+    keys: &[KeyChange {
+        key: "←/→",
+        before: "Previous / next station",
+        after: "Seek 30 s while mpv keeps a timeshift buffer",
+    }],
+}];
+
+/// Notes of the releases after `previous`, up to the running one, newest
+/// first.
+pub fn since(previous: &str) -> Vec<&'static ReleaseNotes> {
+    let previous = version_key(previous);
+    let current = version_key(env!("CARGO_PKG_VERSION"));
+    RELEASES
+        .iter()
+        .filter(|notes| {
+            let version = version_key(notes.version);
+            version > previous && version <= current
+        })
+        .collect()
+}
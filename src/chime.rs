@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use crate::config::runtime_dir;
+
+/// Sample rate of the chime; matches the PCM pipeline so it can be mixed in.
+pub const CHIME_RATE: u32 = 48_000;
+
+/// Two short decaying tones (E6 then B5), interleaved stereo s16.
+pub fn chime_samples() -> Vec<i16> {
+    const NOTES: [(f32, f32); 2] = [(1318.5, 0.12), (987.8, 0.18)];
+    const AMPLITUDE: f32 = 0.3;
+
+    let mut samples = Vec::new();
+    for (freq, secs) in NOTES {
+        let n = (CHIME_RATE as f32 * secs) as usize;
+        for i in 0..n {
+            let t = i as f32 / CHIME_RATE as f32;
+            // Quick attack avoids a click; exponential tail keeps it soft.
+            let attack = (t / 0.005).min(1.0);
+            let env = attack * (-t * 18.0).exp();
+            let v = (std::f32::consts::TAU * freq * t).sin() * env * AMPLITUDE;
+            let s = (v * i16::MAX as f32) as i16;
+            samples.push(s);
+            samples.push(s);
+        }
+    }
+    samples
+}
+
+/// Encode interleaved stereo samples as a 16-bit PCM WAV file.
+pub fn wav_bytes(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&2u16.to_le_bytes()); // channels
+    out.extend_from_slice(&CHIME_RATE.to_le_bytes());
+    out.extend_from_slice(&(CHIME_RATE * 4).to_le_bytes()); // byte rate
+    out.extend_from_slice(&4u16.to_le_bytes()); // block align
+    out.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for s in samples {
+        out.extend_from_slice(&s.to_le_bytes());
+    }
+    out
+}
+
+/// Write the chime as a WAV file for backends that play it out-of-process.
+pub fn write_chime_wav() -> Option<PathBuf> {
+    let path = runtime_dir().join(format!("lofi_rs_chime_{}.wav", std::process::id()));
+    std::fs::write(&path, wav_bytes(&chime_samples())).ok()?;
+    Some(path)
+}
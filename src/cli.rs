@@ -0,0 +1,446 @@
+use std::path::PathBuf;
+
+use crate::beats::{BeatMode, BeatPreset};
+use crate::import::DuplicateAction;
+use crate::likes::ExportFormat;
+use crate::noise::NoiseKind;
+use crate::report::{ReportFormat, ReportPeriod};
+
+pub const USAGE: &str = "\
+Usage:
+  lofi_rs [OPTIONS]                          Start the player
+      --instance NAME                        Run or address (with status) a named
+                                             instance, with its own sockets and state
+      --volume N                             Start at volume N (0-100)
+      --station NAME                         Start on the station matching NAME
+      --paused / --autoplay                  Wait for F8 before playing, or play at once
+      --low-data                             Low bitrates, more buffering, no artwork or
+                                             info lookups (`low-data` in the palette)
+  lofi_rs export-likes [--json] [FILE]       Write liked tracks as search URLs
+                                             (to stdout without FILE)
+  lofi_rs status [--json] [--watch]          Show what the running player is playing;
+                                             --watch prints each change (NDJSON with --json)
+  lofi_rs join URL                           Listen along with a shared session
+  lofi_rs --connect ADDR [--token T] [CMD]   Control another player's [remote] at
+                                             HOST:PORT, an http(s) URL or a socket
+                                             path forwarded with ssh -L; CMD: status
+                                             [--json] [--watch], stations, play N|NAME,
+                                             volume N, toggle (token also from
+                                             LOFI_RS_TOKEN)
+  lofi_rs auth lastfm                        Connect a Last.fm account
+  lofi_rs import FILE [DUP]                  Add the stations of an M3U/PLS playlist,
+                                             pyradio CSV, tera JSON or curseradio OPML
+  lofi_rs import pyradio|tera|curseradio [DUP]
+                                             Bring over that player's saved stations
+  lofi_rs add QUERY... [DUP]                 Search radio-browser.info and add a station
+                                             DUP: --skip, --merge or --replace
+                                             duplicates (asks by default)
+  lofi_rs prune [--yes]                      Remove stations that keep failing
+  lofi_rs probe URL|STATION                  Show a stream's codec, bitrate, channels,
+                                             ICY headers and latency
+  lofi_rs report [--day|--week] [--markdown|--html] [FILE]
+                                             Print listening time, top stations and
+                                             tracks; also write FILE (.md/.html)
+  lofi_rs backup FILE                        Save config, stations, likes, history
+                                             and stats into one file
+  lofi_rs restore FILE [--yes]               Put a backup's files back in place
+  lofi_rs noise KIND [--volume N]            Play generated white, pink, brown or rain
+                                             noise without a network or player
+  lofi_rs beats [PRESET|HZ] [--carrier HZ] [--isochronic] [--volume N]
+                                             Play binaural beats (focus, relax, sleep)
+  lofi_rs help                               Show this help";
+
+/// Startup overrides from the command line; `None` falls back to the config.
+#[derive(Default)]
+pub struct StartOptions {
+    pub volume: Option<u32>,
+    pub station: Option<String>,
+    pub paused: Option<bool>,
+    pub low_data: bool,
+}
+
+/// What the binary was asked to do.
+pub enum Command {
+    /// Start the interactive player.
+    Run(StartOptions),
+    ExportLikes {
+        format: ExportFormat,
+        output: Option<PathBuf>,
+    },
+    /// Print the running player's status, once or on every change.
+    Status {
+        json: bool,
+        watch: bool,
+    },
+    /// Play (and follow) the session shared at `url`.
+    Join {
+        url: String,
+    },
+    /// Send `action` to the remote API of a player at `address`.
+    Connect {
+        address: String,
+        token: Option<String>,
+        action: RemoteAction,
+    },
+    Import {
+        path: PathBuf,
+        on_duplicate: Option<DuplicateAction>,
+    },
+    Add {
+        query: String,
+        on_duplicate: Option<DuplicateAction>,
+    },
+    /// Print a listening report, and write it to `output` when given.
+    Report {
+        period: ReportPeriod,
+        output: Option<(PathBuf, ReportFormat)>,
+    },
+    /// Inspect a stream URL, or a configured station's.
+    Probe {
+        target: String,
+    },
+    /// Remove likely-dead stations; `yes` skips the confirmation.
+    Prune {
+        yes: bool,
+    },
+    Backup {
+        path: PathBuf,
+    },
+    /// Write a backup back; `yes` skips the confirmation.
+    Restore {
+        path: PathBuf,
+        yes: bool,
+    },
+    /// Generate noise straight to the sound card.
+    Noise {
+        kind: NoiseKind,
+        volume: u32,
+    },
+    /// Generate binaural beats or an isochronic tone.
+    Beats {
+        beat_hz: f64,
+        carrier_hz: f64,
+        mode: BeatMode,
+        volume: u32,
+    },
+    /// Interactive Last.fm authorization.
+    AuthLastfm,
+    Help,
+}
+
+/// Take `--instance NAME`, which goes with any command, out of `args`.
+pub fn take_instance(args: &mut Vec<String>) -> Result<Option<String>, String> {
+    let Some(i) = args.iter().position(|a| a == "--instance") else {
+        return Ok(None);
+    };
+    if i + 1 >= args.len() {
+        return Err("--instance needs a name".to_string());
+    }
+    let name = args.remove(i + 1);
+    args.remove(i);
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!(
+            "invalid instance name {:?}: use letters, digits, - and _",
+            name
+        ));
+    }
+    Ok(Some(name))
+}
+
+/// What `--connect` asks of the other player.
+pub enum RemoteAction {
+    Status {
+        json: bool,
+        watch: bool,
+    },
+    Stations,
+    /// Station number as `stations` lists it, or a name to match.
+    Play(String),
+    Volume(u32),
+    Toggle,
+}
+
+fn parse_remote_action<I: Iterator<Item = String>>(mut args: I) -> Result<RemoteAction, String> {
+    let Some(action) = args.next() else {
+        return Ok(RemoteAction::Status {
+            json: false,
+            watch: false,
+        });
+    };
+    let action = match action.as_str() {
+        "status" => {
+            let (mut json, mut watch) = (false, false);
+            for arg in args.by_ref() {
+                match arg.as_str() {
+                    "--json" => json = true,
+                    "--watch" | "-w" => watch = true,
+                    other => return Err(format!("unknown option: {}", other)),
+                }
+            }
+            RemoteAction::Status { json, watch }
+        }
+        "stations" => RemoteAction::Stations,
+        "play" => {
+            let target: Vec<String> = args.by_ref().collect();
+            if target.is_empty() {
+                return Err("play needs a station number or name".to_string());
+            }
+            RemoteAction::Play(target.join(" "))
+        }
+        "volume" => {
+            let value = args.next().ok_or("volume needs a value")?;
+            let volume = value
+                .trim_end_matches('%')
+                .parse::<u32>()
+                .map_err(|_| format!("invalid volume: {}", value))?;
+            RemoteAction::Volume(volume.min(100))
+        }
+        "toggle" | "pause" => RemoteAction::Toggle,
+        other => return Err(format!("unknown remote command: {}", other)),
+    };
+    if args.next().is_some() {
+        return Err("too many arguments".to_string());
+    }
+    Ok(action)
+}
+
+/// Parse the command line (without the program name).
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let Some(command) = args.next() else {
+        return Ok(Command::Run(StartOptions::default()));
+    };
+    match command.as_str() {
+        "--volume" | "--station" | "--paused" | "--autoplay" | "--low-data" => {
+            parse_start_options(std::iter::once(command).chain(args)).map(Command::Run)
+        }
+        "export-likes" => {
+            let mut format = ExportFormat::Text;
+            let mut output = None;
+            for arg in args {
+                match arg.as_str() {
+                    "--json" => format = ExportFormat::Json,
+                    "--text" => format = ExportFormat::Text,
+                    "-" => output = None,
+                    a if a.starts_with('-') => return Err(format!("unknown option: {}", a)),
+                    _ if output.is_some() => return Err("too many arguments".to_string()),
+                    _ => output = Some(PathBuf::from(arg)),
+                }
+            }
+            Ok(Command::ExportLikes { format, output })
+        }
+        "--connect" => {
+            let address = args.next().ok_or("--connect needs an address")?;
+            let mut rest: Vec<String> = args.collect();
+            let token = match rest.iter().position(|a| a == "--token") {
+                Some(i) if i + 1 < rest.len() => {
+                    let token = rest.remove(i + 1);
+                    rest.remove(i);
+                    Some(token)
+                }
+                Some(_) => return Err("--token needs a value".to_string()),
+                None => std::env::var("LOFI_RS_TOKEN").ok(),
+            };
+            Ok(Command::Connect {
+                address,
+                token,
+                action: parse_remote_action(rest.into_iter())?,
+            })
+        }
+        "join" => {
+            let url = args.next().ok_or("join needs the share URL")?;
+            if args.next().is_some() {
+                return Err("too many arguments".to_string());
+            }
+            Ok(Command::Join { url })
+        }
+        "import" | "add" => {
+            let mut on_duplicate = None;
+            let mut words = Vec::new();
+            for arg in args {
+                match arg.as_str() {
+                    "--skip" => on_duplicate = Some(DuplicateAction::Skip),
+                    "--merge" => on_duplicate = Some(DuplicateAction::Merge),
+                    "--replace" => on_duplicate = Some(DuplicateAction::Replace),
+                    a if a.starts_with("--") => return Err(format!("unknown option: {}", a)),
+                    _ => words.push(arg),
+                }
+            }
+            if words.is_empty() {
+                return Err(format!("{} needs an argument", command));
+            }
+            if command == "add" {
+                return Ok(Command::Add {
+                    query: words.join(" "),
+                    on_duplicate,
+                });
+            }
+            if words.len() > 1 {
+                return Err("too many arguments".to_string());
+            }
+            Ok(Command::Import {
+                path: PathBuf::from(&words[0]),
+                on_duplicate,
+            })
+        }
+        "status" => {
+            let (mut json, mut watch) = (false, false);
+            for arg in args {
+                match arg.as_str() {
+                    "--json" => json = true,
+                    "--watch" | "-w" => watch = true,
+                    other => return Err(format!("unknown option: {}", other)),
+                }
+            }
+            Ok(Command::Status { json, watch })
+        }
+        "report" => {
+            let mut period = ReportPeriod::Week;
+            let mut format = None;
+            let mut path: Option<PathBuf> = None;
+            for arg in args {
+                match arg.as_str() {
+                    "--day" | "--today" => period = ReportPeriod::Day,
+                    "--week" => period = ReportPeriod::Week,
+                    "--markdown" | "--md" => format = Some(ReportFormat::Markdown),
+                    "--html" => format = Some(ReportFormat::Html),
+                    a if a.starts_with('-') => return Err(format!("unknown option: {}", a)),
+                    _ if path.is_some() => return Err("too many arguments".to_string()),
+                    _ => path = Some(PathBuf::from(arg)),
+                }
+            }
+            if format.is_some() && path.is_none() {
+                return Err("--markdown and --html need a file to write".to_string());
+            }
+            let output = path.map(|path| {
+                let format = format.unwrap_or_else(|| ReportFormat::from_path(&path));
+                (path, format)
+            });
+            Ok(Command::Report { period, output })
+        }
+        "probe" => {
+            let target: Vec<String> = args.collect();
+            if target.is_empty() {
+                return Err("probe needs a URL or station name".to_string());
+            }
+            Ok(Command::Probe {
+                target: target.join(" "),
+            })
+        }
+        "prune" => match args.next().as_deref() {
+            None => Ok(Command::Prune { yes: false }),
+            Some("--yes") | Some("-y") => Ok(Command::Prune { yes: true }),
+            Some(other) => Err(format!("unknown option: {}", other)),
+        },
+        "backup" => {
+            let path = args.next().ok_or("backup needs a file name")?;
+            if args.next().is_some() {
+                return Err("too many arguments".to_string());
+            }
+            Ok(Command::Backup {
+                path: PathBuf::from(path),
+            })
+        }
+        "restore" => {
+            let mut path = None;
+            let mut yes = false;
+            for arg in args {
+                match arg.as_str() {
+                    "--yes" | "-y" => yes = true,
+                    a if a.starts_with('-') => return Err(format!("unknown option: {}", a)),
+                    _ if path.is_some() => return Err("too many arguments".to_string()),
+                    _ => path = Some(PathBuf::from(arg)),
+                }
+            }
+            let path = path.ok_or("restore needs a backup file")?;
+            Ok(Command::Restore { path, yes })
+        }
+        "noise" => {
+            let name = args
+                .next()
+                .ok_or("noise needs a kind: white, pink, brown or rain")?;
+            let kind = NoiseKind::parse(&name).ok_or(format!("unknown noise: {}", name))?;
+            let volume = parse_start_options(args)?.volume.unwrap_or(70);
+            Ok(Command::Noise { kind, volume })
+        }
+        "beats" => parse_beats(args),
+        "auth" => match args.next().as_deref() {
+            Some("lastfm") => Ok(Command::AuthLastfm),
+            Some(service) => Err(format!("unknown auth service: {}", service)),
+            None => Err("auth needs a service (lastfm)".to_string()),
+        },
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        other => Err(format!("unknown command: {}", other)),
+    }
+}
+
+fn parse_start_options<I: Iterator<Item = String>>(mut args: I) -> Result<StartOptions, String> {
+    let mut options = StartOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--volume" => {
+                let value = args.next().ok_or("--volume needs a value")?;
+                let volume = value
+                    .trim_end_matches('%')
+                    .parse::<u32>()
+                    .map_err(|_| format!("invalid volume: {}", value))?;
+                options.volume = Some(volume.min(100));
+            }
+            "--station" => options.station = Some(args.next().ok_or("--station needs a name")?),
+            "--paused" => options.paused = Some(true),
+            "--autoplay" => options.paused = Some(false),
+            "--low-data" => options.low_data = true,
+            other => return Err(format!("unknown option: {}", other)),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_beats<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let mut beat_hz = BeatPreset::Focus.beat_hz();
+    let mut carrier_hz = 200.0;
+    let mut mode = BeatMode::Binaural;
+    let mut volume = 20;
+    let hz = |value: Option<String>, what: &str| -> Result<f64, String> {
+        let value = value.ok_or(format!("{} needs a frequency", what))?;
+        value
+            .trim_end_matches("Hz")
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|hz| *hz > 0.0)
+            .ok_or(format!("invalid frequency: {}", value))
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--carrier" => carrier_hz = hz(args.next(), "--carrier")?,
+            "--isochronic" => mode = BeatMode::Isochronic,
+            "--binaural" => mode = BeatMode::Binaural,
+            "--volume" => {
+                let value = args.next().ok_or("--volume needs a value")?;
+                volume = value
+                    .trim_end_matches('%')
+                    .parse::<u32>()
+                    .map_err(|_| format!("invalid volume: {}", value))?
+                    .min(100);
+            }
+            a if a.starts_with("--") => return Err(format!("unknown option: {}", a)),
+            a => {
+                beat_hz = match BeatPreset::parse(a) {
+                    Some(preset) => preset.beat_hz(),
+                    None => hz(Some(arg.clone()), "beats")?,
+                }
+            }
+        }
+    }
+    Ok(Command::Beats {
+        beat_hz,
+        carrier_hz,
+        mode,
+        volume,
+    })
+}
@@ -0,0 +1,553 @@
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::bandwidth::BandwidthConfig;
+use crate::beats::BeatsConfig;
+use crate::error::LofiError;
+use crate::lastfm::LastfmConfig;
+use crate::listenbrainz::ListenBrainzConfig;
+use crate::macros::{Macro, StartupConfig};
+use crate::metadata::MetadataRules;
+use crate::mpris::MprisConfig;
+use crate::net::IpFamily;
+use crate::night::NightConfig;
+use crate::player::{PlayerPreference, VolumeCurve};
+use crate::power::PowerSaverMode;
+use crate::radiobrowser::StationInfo;
+use crate::reconnect::{ReconnectOverrides, ReconnectPolicy};
+use crate::record::RecordingConfig;
+use crate::remote::RemoteConfig;
+use crate::rotation::Rotation;
+use crate::scrobble::ScrobbleRules;
+use crate::share::ShareConfig;
+use crate::state::{cache_dir, instance};
+use crate::timefmt::DEFAULT_ELAPSED_FORMAT;
+use crate::tls::StationTls;
+use crate::ui::{DetailScreen, IconStyle};
+
+/// User configuration, read from `$XDG_CONFIG_HOME/lofi_rs/config.toml`
+/// (falling back to `~/.config/lofi_rs/config.toml`). Every key is optional.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Volume at startup, 0-100 (capped at `max_volume`).
+    pub default_volume: u32,
+    /// Station to start on, matched fuzzily by name; the first one otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startup_station: Option<String>,
+    /// Open without connecting to the stream; F8 starts playback.
+    pub start_paused: bool,
+    /// Percentage points per volume key press.
+    pub volume_step: u32,
+    /// Color theme name, see `ui::THEMES`.
+    pub theme: String,
+    /// Glyphs in the list and status bar: "off", "unicode" or "nerd".
+    pub icons: IconStyle,
+    /// Desktop notification when the track changes.
+    pub notifications: bool,
+    /// Play a short chime when the track or station changes.
+    pub chime: bool,
+    /// Chime loudness relative to the current volume, 0-100.
+    pub chime_volume: u32,
+    /// Preferred player: "auto", "mpv", "ffplay", "afplay", "vlc" or
+    /// "gstreamer". Read at startup.
+    pub backend: PlayerPreference,
+    /// Seconds of stream to buffer ahead (mpv only); 0 keeps mpv's default.
+    pub cache_secs: u32,
+    /// Address family of lofi_rs's own connections (reachability checks,
+    /// metadata, scrobbling): "auto" races IPv6 and IPv4, "ipv4" or "ipv6"
+    /// keeps to one, for networks with broken IPv6. Players connect on
+    /// their own.
+    pub ip_family: IpFamily,
+    /// Directory for player IPC sockets and temporary WAV files; defaults
+    /// to `$XDG_RUNTIME_DIR/lofi_rs`, else a per-user directory in /tmp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime_dir: Option<PathBuf>,
+    /// Directory mpv keeps the timeshift buffer in; defaults to
+    /// `$XDG_CACHE_HOME/lofi_rs/timeshift`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeshift_dir: Option<PathBuf>,
+    /// Start players with reduced privileges (Linux): no new privileges,
+    /// and inside bubblewrap with a read-only system when it is installed.
+    pub sandbox_player: bool,
+    /// Minutes of played stream kept for seeking back with ←/→ (mpv only,
+    /// up to 240); 0 turns timeshift off.
+    pub timeshift_minutes: u32,
+    /// Downmix to mono on both channels.
+    pub mono: bool,
+    /// Left/right balance, -100 (left only) to 100 (right only).
+    pub balance: i32,
+    /// Hard ceiling for the volume, 0-100. Volume keys and boost never go above it.
+    pub max_volume: u32,
+    /// Run the stream through a peak limiter so loud intros cannot spike.
+    pub limiter: bool,
+    /// Limiter ceiling as linear amplitude (0.0625-1.0).
+    pub limiter_ceiling: f64,
+    /// How the 0-100 volume maps to backend levels: "linear", "log" or "cubic".
+    pub volume_curve: VolumeCurve,
+    /// Look for AirPlay (RAOP) speakers when the output picker opens.
+    /// Linux only, where it loads PulseAudio/PipeWire's `module-raop-discover`.
+    pub airplay: bool,
+    /// Slower UI refresh and `low_bitrate_url` streams: "off", "battery"
+    /// (while on battery power) or "always".
+    pub power_saver: PowerSaverMode,
+    /// UI refresh interval in milliseconds, 100-5000. Animations such as the
+    /// switching spinner tick faster while shown; the power saver forces 5 s.
+    pub tick_ms: u64,
+    /// How elapsed time is written in the status bar, `lofi_rs status` and
+    /// the exit summary: `%H`/`%M`/`%S` (zero-padded) or `%h`/`%m`/`%s`,
+    /// `%:` for the locale's separator. "%hh %Mm" gives "1h 23m",
+    /// "%M:%S" counts minutes without hours.
+    pub elapsed_format: String,
+    /// List the generated white/pink/brown noise and rain stations after
+    /// the configured ones.
+    pub noise_stations: bool,
+    /// Binaural beat / isochronic tone generator, off unless enabled.
+    pub beats: BeatsConfig,
+    /// Night mode: lower volume cap, less bass and compression at night.
+    pub night: NightConfig,
+    /// Look for a newer release on GitHub at startup (at most once a day)
+    /// and mention it in the status bar.
+    pub check_updates: bool,
+    /// Minutes of listening a day to aim for, with progress and a streak in
+    /// the stats view (h); 0 sets no goal.
+    pub daily_goal_minutes: u32,
+    /// Look tracks up on MusicBrainz for their release and cover art,
+    /// caching what was found in the state directory.
+    pub musicbrainz: bool,
+    /// Default reconnect behaviour for every station.
+    pub reconnect: ReconnectPolicy,
+    /// Where the ListenAlong session is published.
+    pub share: ShareConfig,
+    /// Web remote for phones on the same network.
+    pub remote: RemoteConfig,
+    /// Other media players (Linux, MPRIS): paused or ducked while lofi_rs
+    /// plays, and shown in the status bar.
+    pub mpris: MprisConfig,
+    /// Estimated data use, and a monthly cap for metered connections.
+    pub bandwidth: BandwidthConfig,
+    /// Last.fm account, set up with `lofi_rs auth lastfm`.
+    pub lastfm: LastfmConfig,
+    /// ListenBrainz account; listens go there too once a token is set.
+    pub listenbrainz: ListenBrainzConfig,
+    /// Which listens get scrobbled once Last.fm or ListenBrainz is set up.
+    pub scrobble: ScrobbleRules,
+    /// Where `record` in the palette saves streams, and how much space
+    /// recordings may take.
+    pub recording: RecordingConfig,
+    /// Station rotations, started with `w` (the first) or `rotate <name>`
+    /// in the palette.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rotations: Vec<Rotation>,
+    /// Palette commands run on launch, in order.
+    pub startup: StartupConfig,
+    /// Named lists of palette commands, run with `macro <name>`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub macros: Vec<Macro>,
+    /// Station list; `[[stations]]` tables replace the built-in stations.
+    pub stations: Vec<Station>,
+}
+
+/// A playable station. Only `name` and `url` are required in the config.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Station {
+    pub name: String,
+    pub url: String,
+    /// AzuraCast-style now-playing API endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_url: Option<String>,
+    /// Live radio; on-demand sources (podcasts, files) support speed control.
+    #[serde(default = "default_true")]
+    pub live: bool,
+    /// Shell command printing a fresh stream URL, run when the current URL's
+    /// token has expired (HTTP 401/403/410).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_command: Option<String>,
+    /// Endpoint returning a fresh stream URL, either as plain text or as a
+    /// JSON object with a `url` field. Used like `refresh_command`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_url: Option<String>,
+    /// Overrides of the global `[reconnect]` policy for this station.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconnect: Option<ReconnectOverrides>,
+    /// Short description shown in the detail popup (`i`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Free-text personal notes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Station website. Country and language are informational; missing
+    /// fields are looked up on radio-browser.info when the popup opens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Track search opened by `O`, with `{query}` standing for the track;
+    /// defaults to a YouTube Music search.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_url: Option<String>,
+    /// Lower-bitrate variant of `url`, played while the power saver is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_bitrate_url: Option<String>,
+    /// How to read artist and title from this station's stream titles.
+    #[serde(default, skip_serializing_if = "MetadataRules::is_default")]
+    pub metadata: MetadataRules,
+    /// Scrobble this station's tracks under the `[scrobble]` rules.
+    #[serde(default = "default_true")]
+    pub scrobble: bool,
+    /// Marked as a favorite from the detail popup.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub favorite: bool,
+    /// Certificate trust for a self-hosted server with a private certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<StationTls>,
+}
+
+impl Station {
+    pub fn new(name: &str, url: &str, metadata_url: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            metadata_url: metadata_url.map(str::to_string),
+            live: true,
+            refresh_command: None,
+            refresh_url: None,
+            reconnect: None,
+            description: None,
+            notes: None,
+            homepage: None,
+            country: None,
+            language: None,
+            search_url: None,
+            low_bitrate_url: None,
+            metadata: MetadataRules::default(),
+            scrobble: true,
+            favorite: false,
+            tls: None,
+        }
+    }
+
+    /// URL to play: the low-bitrate variant when saving power and there is one.
+    pub fn stream_url(&self, power_saver: bool) -> &str {
+        match &self.low_bitrate_url {
+            Some(url) if power_saver => url,
+            _ => &self.url,
+        }
+    }
+
+    /// The global reconnect policy with this station's overrides applied.
+    pub fn reconnect_policy(&self, global: ReconnectPolicy) -> ReconnectPolicy {
+        global.with_overrides(self.reconnect.as_ref())
+    }
+
+    /// Whether the popup has nothing beyond the name and URL to show, so
+    /// radio-browser.info is worth asking.
+    pub fn lacks_info(&self) -> bool {
+        self.description.is_none()
+            && self.homepage.is_none()
+            && self.country.is_none()
+            && self.language.is_none()
+    }
+
+    /// Fill fields missing from the config with radio-browser.info metadata.
+    /// Returns whether anything changed.
+    pub fn merge_info(&mut self, info: &StationInfo) -> bool {
+        let mut changed = false;
+        let mut fill = |field: &mut Option<String>, value: &str| {
+            if field.is_none() && !value.trim().is_empty() {
+                *field = Some(value.trim().to_string());
+                changed = true;
+            }
+        };
+        fill(&mut self.homepage, &info.homepage);
+        fill(&mut self.country, &info.country);
+        fill(&mut self.language, &info.language);
+        if !info.tags.is_empty() {
+            fill(&mut self.description, &info.tags.replace(',', ", "));
+        }
+        changed
+    }
+
+    /// Snapshot handed to the UI for the detail popup.
+    pub fn detail_screen(&self) -> DetailScreen {
+        let mut rows = Vec::new();
+        let mut row = |label: &'static str, value: Option<&String>| {
+            if let Some(v) = value {
+                rows.push((label, v.clone()));
+            }
+        };
+        row("Description", self.description.as_ref());
+        row("Notes", self.notes.as_ref());
+        row("Country", self.country.as_ref());
+        row("Language", self.language.as_ref());
+        row("Homepage", self.homepage.as_ref());
+        rows.push(("Stream", self.url.clone()));
+        DetailScreen {
+            title: self.name.clone(),
+            rows,
+            actions: Vec::new(),
+            selected: 0,
+            editing: None,
+        }
+    }
+
+    /// Whether an expired stream URL can be re-resolved.
+    pub fn can_refresh(&self) -> bool {
+        self.refresh_command.is_some() || self.refresh_url.is_some()
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+pub fn default_stations() -> Vec<Station> {
+    vec![
+        Station::new("Lofi 1", "https://stream.zeno.fm/0r0xa792kwzuv", None),
+        Station::new("Lofi 2", "https://stream.zeno.fm/v5reddyk8rhvv", None),
+        Station::new(
+            "Code Radio",
+            "https://coderadio-admin-v2.freecodecamp.org/listen/coderadio/radio.mp3",
+            Some("https://coderadio-admin-v2.freecodecamp.org/api/nowplaying/coderadio"),
+        ),
+    ]
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_volume: 70,
+            startup_station: None,
+            start_paused: false,
+            volume_step: 5,
+            theme: "default".to_string(),
+            icons: IconStyle::Off,
+            notifications: false,
+            chime: false,
+            chime_volume: 50,
+            backend: PlayerPreference::Auto,
+            cache_secs: 0,
+            ip_family: IpFamily::Auto,
+            runtime_dir: None,
+            timeshift_dir: None,
+            sandbox_player: false,
+            timeshift_minutes: 60,
+            mono: false,
+            balance: 0,
+            max_volume: 100,
+            limiter: false,
+            limiter_ceiling: 0.7,
+            volume_curve: VolumeCurve::Linear,
+            airplay: false,
+            power_saver: PowerSaverMode::Off,
+            tick_ms: 1000,
+            elapsed_format: DEFAULT_ELAPSED_FORMAT.to_string(),
+            noise_stations: true,
+            beats: BeatsConfig::default(),
+            night: NightConfig::default(),
+            check_updates: false,
+            daily_goal_minutes: 0,
+            musicbrainz: false,
+            reconnect: ReconnectPolicy::default(),
+            share: ShareConfig::default(),
+            remote: RemoteConfig::default(),
+            mpris: MprisConfig::default(),
+            bandwidth: BandwidthConfig::default(),
+            lastfm: LastfmConfig::default(),
+            listenbrainz: ListenBrainzConfig::default(),
+            scrobble: ScrobbleRules::default(),
+            recording: RecordingConfig::default(),
+            rotations: Vec::new(),
+            startup: StartupConfig::default(),
+            macros: Vec::new(),
+            stations: default_stations(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file; a missing file yields the defaults.
+    pub fn load() -> Result<Self, LofiError> {
+        let Some(path) = config_path() else {
+            return Ok(Self::default());
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(LofiError::Config(format!("{}: {}", path.display(), e))),
+        };
+        let mut config: Config = toml::from_str(&text)
+            .map_err(|e| LofiError::Config(format!("{}: {}", path.display(), e)))?;
+        config.default_volume = config.default_volume.min(100);
+        config.volume_step = config.volume_step.clamp(1, 25);
+        config.balance = config.balance.clamp(-100, 100);
+        config.chime_volume = config.chime_volume.min(100);
+        config.max_volume = config.max_volume.min(100);
+        config.timeshift_minutes = config.timeshift_minutes.min(240);
+        config.beats.volume = config.beats.volume.min(100);
+        config.limiter_ceiling = config.limiter_ceiling.clamp(0.0625, 1.0);
+        for station in &config.stations {
+            if let Some(Err(e)) = station.tls.as_ref().map(StationTls::validate) {
+                return Err(LofiError::Config(format!(
+                    "{}: station {}: {}",
+                    path.display(),
+                    station.name,
+                    e
+                )));
+            }
+        }
+        if config.night.window().is_none() {
+            return Err(LofiError::Config(format!(
+                "{}: [night] start and end must be HH:MM",
+                path.display()
+            )));
+        }
+        if config.stations.is_empty() {
+            config.stations = default_stations();
+        }
+        if config.has_secrets() {
+            // Saved before saves were private
+            use std::os::unix::fs::PermissionsExt;
+            if std::fs::metadata(&path).is_ok_and(|m| m.permissions().mode() & 0o077 != 0) {
+                let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Whether the file holds credentials.
+    fn has_secrets(&self) -> bool {
+        self.lastfm.api_secret.is_some()
+            || self.lastfm.session_key.is_some()
+            || self.listenbrainz.token.is_some()
+            || self.share.gist_token.is_some()
+            || self.remote.token.is_some()
+    }
+
+    /// Drop the station called `name`. The last station is never removed.
+    pub fn remove_station(&mut self, name: &str) -> bool {
+        let before = self.stations.len();
+        if before <= 1 {
+            return false;
+        }
+        self.stations.retain(|s| s.name != name);
+        self.stations.len() < before
+    }
+
+    /// Write the config back to `config.toml`, creating the directory.
+    /// Comments in a hand-edited file are not preserved.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = config_path().ok_or("cannot locate config directory (HOME unset)")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Holds the Last.fm, gist and remote secrets
+        write_private(&path, toml::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Replace `path` with `contents`, readable by this user only: written to
+/// a new 0600 file, then renamed over the old one.
+pub fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let tmp = path.with_file_name(name);
+    // A leftover would keep its own permissions
+    let _ = std::fs::remove_file(&tmp);
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Directory holding `config.toml`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("lofi_rs"))
+}
+
+/// Sockets and temp files directory, fixed by `init_dirs` at startup.
+static RUNTIME_DIR: OnceLock<PathBuf> = OnceLock::new();
+/// Timeshift buffer directory, fixed by `init_dirs` at startup.
+static TIMESHIFT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Create `dir` readable by this user only. A directory someone else owns
+/// (e.g. planted in a shared /tmp) is refused.
+fn private_dir(dir: PathBuf) -> Option<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    let _ = std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir);
+    let meta = std::fs::metadata(&dir).ok()?;
+    (meta.is_dir() && meta.uid() == nix::unistd::getuid().as_raw()).then_some(dir)
+}
+
+fn default_runtime_dir() -> PathBuf {
+    let uid = nix::unistd::getuid();
+    let preferred = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("lofi_rs"),
+        _ => std::env::temp_dir().join(format!("lofi_rs-{}", uid)),
+    };
+    // Someone else owns the shared name: fall back to one for this process
+    let own = std::env::temp_dir().join(format!("lofi_rs-{}-{}", uid, std::process::id()));
+    private_dir(preferred)
+        .or_else(|| private_dir(own))
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+fn default_timeshift_dir() -> Option<PathBuf> {
+    private_dir(cache_dir()?.join("timeshift"))
+}
+
+/// Fix the runtime and timeshift directories from the config, creating
+/// them. Later calls have no effect.
+pub fn init_dirs(config: &Config) {
+    let runtime = config
+        .runtime_dir
+        .clone()
+        .and_then(private_dir)
+        .unwrap_or_else(default_runtime_dir);
+    // Instances keep their sockets and temporary files apart
+    let runtime = match instance() {
+        Some(name) => private_dir(runtime.join(name)).unwrap_or(runtime),
+        None => runtime,
+    };
+    let timeshift = config
+        .timeshift_dir
+        .clone()
+        .and_then(private_dir)
+        .or_else(default_timeshift_dir)
+        .unwrap_or_else(|| runtime.clone());
+    let _ = RUNTIME_DIR.set(runtime);
+    let _ = TIMESHIFT_DIR.set(timeshift);
+}
+
+/// Directory for player IPC sockets and temporary WAV files.
+pub fn runtime_dir() -> &'static PathBuf {
+    RUNTIME_DIR.get_or_init(default_runtime_dir)
+}
+
+/// Directory mpv keeps the timeshift buffer in.
+pub fn timeshift_dir() -> &'static PathBuf {
+    TIMESHIFT_DIR.get_or_init(|| default_timeshift_dir().unwrap_or_else(|| runtime_dir().clone()))
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
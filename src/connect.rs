@@ -0,0 +1,188 @@
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+use crate::cli::RemoteAction;
+use crate::net;
+use crate::palette::best_match;
+use crate::remote::RemoteView;
+use crate::status;
+
+/// Longest wait for the other player to answer.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where `--connect` finds the other player's remote.
+enum Endpoint {
+    /// Base URL of the `[remote]` server.
+    Http(String),
+    /// Its `remote.sock`, usually forwarded here with `ssh -L`.
+    Socket(PathBuf),
+}
+
+impl Endpoint {
+    /// "HOST:PORT", "http(s)://HOST:PORT" or a socket path.
+    fn parse(address: &str) -> Self {
+        if address.starts_with("http://") || address.starts_with("https://") {
+            Endpoint::Http(address.trim_end_matches('/').to_string())
+        } else if address.contains('/') {
+            Endpoint::Socket(PathBuf::from(address))
+        } else {
+            Endpoint::Http(format!("http://{}", address))
+        }
+    }
+}
+
+/// A client of another player's remote API.
+struct Remote {
+    endpoint: Endpoint,
+    token: Option<String>,
+}
+
+impl Remote {
+    /// Send a request and return the body of a 2xx answer; other answers
+    /// fail with their message.
+    async fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let body = body.map(|b| b.to_string());
+        let (code, text) = match &self.endpoint {
+            Endpoint::Http(base) => {
+                let client = net::client_builder().timeout(TIMEOUT).build()?;
+                let mut request = match method {
+                    "POST" => client.post(format!("{}{}", base, path)),
+                    _ => client.get(format!("{}{}", base, path)),
+                };
+                if let Some(token) = &self.token {
+                    request = request.bearer_auth(token);
+                }
+                if let Some(body) = body {
+                    request = request
+                        .header("Content-Type", "application/json")
+                        .body(body);
+                }
+                let response = request.send().await?;
+                (response.status().as_u16(), response.text().await?)
+            }
+            Endpoint::Socket(path_on_disk) => tokio::time::timeout(
+                TIMEOUT,
+                socket_request(path_on_disk, method, path, self.token.as_deref(), body),
+            )
+            .await
+            .map_err(|_| "timed out")??,
+        };
+        match code {
+            200..=299 => Ok(text),
+            401 => Err("the remote wants a token (--token or LOFI_RS_TOKEN)".into()),
+            _ => Err(format!("remote answered {}: {}", code, text.trim()).into()),
+        }
+    }
+
+    async fn view(&self) -> Result<RemoteView, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_str(
+            &self.request("GET", "/api/status", None).await?,
+        )?)
+    }
+}
+
+/// HTTP/1.1 over a Unix socket, which reqwest does not speak; the server
+/// closes the connection after answering.
+async fn socket_request(
+    socket: &Path,
+    method: &str,
+    path: &str,
+    token: Option<&str>,
+    body: Option<String>,
+) -> Result<(u16, String), Box<dyn std::error::Error>> {
+    let mut stream = UnixStream::connect(socket)
+        .await
+        .map_err(|e| format!("{}: {}", socket.display(), e))?;
+    let body = body.unwrap_or_default();
+    let mut head = format!("{} {} HTTP/1.1\r\nHost: localhost\r\n", method, path);
+    if let Some(token) = token {
+        head.push_str(&format!("Authorization: Bearer {}\r\n", token));
+    }
+    if !body.is_empty() {
+        head.push_str("Content-Type: application/json\r\n");
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+    let response = String::from_utf8_lossy(&response);
+    let (head, text) = response.split_once("\r\n\r\n").ok_or("malformed answer")?;
+    let code = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or("malformed answer")?;
+    Ok((code, text.to_string()))
+}
+
+/// `lofi_rs --connect ADDRESS ...`: carry out `action` on the player
+/// serving its remote at `address`.
+pub async fn run(
+    address: &str,
+    token: Option<String>,
+    action: RemoteAction,
+    elapsed_format: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let remote = Remote {
+        endpoint: Endpoint::parse(address),
+        token: token.filter(|t| !t.is_empty()),
+    };
+    match action {
+        RemoteAction::Status { json, watch: false } => {
+            status::print(remote.view().await?.status.as_ref(), json, elapsed_format)?;
+        }
+        RemoteAction::Status { json, watch: true } => {
+            // Unreachable counts as not running, until it is back
+            status::follow(json, true, elapsed_format, || async {
+                remote.view().await.ok().and_then(|view| view.status)
+            })
+            .await?;
+        }
+        RemoteAction::Stations => {
+            let view = remote.view().await?;
+            let current = view.status.as_ref().map(|s| s.station.as_str());
+            for (i, name) in view.stations.iter().enumerate() {
+                let mark = if Some(name.as_str()) == current {
+                    "▶"
+                } else {
+                    " "
+                };
+                println!("{} {:>2}. {}", mark, i + 1, name);
+            }
+        }
+        RemoteAction::Play(target) => {
+            let view = remote.view().await?;
+            let index = match target.parse::<usize>() {
+                Ok(n) if (1..=view.stations.len()).contains(&n) => n - 1,
+                Ok(n) => return Err(format!("no station {}", n).into()),
+                Err(_) => best_match(&target, view.stations.iter().map(String::as_str))
+                    .ok_or_else(|| format!("no station matches \"{}\"", target))?,
+            };
+            remote
+                .request("POST", "/api/play", Some(json!({ "station": index })))
+                .await?;
+            println!("Playing {}", view.stations[index]);
+        }
+        RemoteAction::Volume(volume) => {
+            remote
+                .request("POST", "/api/volume", Some(json!({ "volume": volume })))
+                .await?;
+        }
+        RemoteAction::Toggle => {
+            remote.request("POST", "/api/toggle", None).await?;
+        }
+    }
+    Ok(())
+}
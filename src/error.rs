@@ -0,0 +1,88 @@
+use thiserror::Error;
+
+/// Errors of the player and the app around it, for callers that need to
+/// tell them apart.
+#[derive(Debug, Error)]
+pub enum LofiError {
+    /// None of the supported players is installed.
+    #[error("no suitable player found")]
+    PlayerNotFound,
+    /// The player process could not be started.
+    #[error("cannot start {player}: {reason}")]
+    SpawnFailed {
+        player: &'static str,
+        reason: String,
+    },
+    /// The running player did not take a command over its IPC socket.
+    #[error("{player}: {reason}")]
+    IpcFailed {
+        player: &'static str,
+        reason: String,
+    },
+    /// The player task is gone; nothing can play any more.
+    #[error("player task stopped")]
+    PlayerStopped,
+    /// The config file could not be read or written.
+    #[error("{0}")]
+    Config(String),
+    /// A stream or HTTP request failed: unreachable, refused or cut off.
+    #[error(transparent)]
+    Stream(#[from] reqwest::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Settings could not be written as TOML.
+    #[error(transparent)]
+    Toml(#[from] toml::ser::Error),
+    /// Anything else, as its message.
+    #[error("{0}")]
+    Other(String),
+}
+
+/// `e` as the variant `wrap` makes when it holds a `T`, else `e` back.
+fn downcast<T: std::error::Error + 'static>(
+    e: Box<dyn std::error::Error>,
+    wrap: fn(T) -> LofiError,
+) -> Result<LofiError, Box<dyn std::error::Error>> {
+    e.downcast::<T>().map(|e| wrap(*e))
+}
+
+/// Modules return boxed errors; the kinds above keep their variant and
+/// only the rest become `Other`.
+impl From<Box<dyn std::error::Error>> for LofiError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        downcast(e, LofiError::Stream)
+            .or_else(|e| downcast(e, LofiError::Io))
+            .or_else(|e| downcast(e, LofiError::Json))
+            .or_else(|e| downcast(e, LofiError::Toml))
+            .unwrap_or_else(|e| LofiError::Other(e.to_string()))
+    }
+}
+
+impl From<String> for LofiError {
+    fn from(e: String) -> Self {
+        LofiError::Other(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxed_errors_keep_their_kind() {
+        let http = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let boxed: Box<dyn std::error::Error> = Box::new(http);
+        assert!(matches!(LofiError::from(boxed), LofiError::Stream(_)));
+
+        let io = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let boxed: Box<dyn std::error::Error> = Box::new(io);
+        assert!(
+            matches!(LofiError::from(boxed), LofiError::Io(e) if e.kind() == std::io::ErrorKind::NotFound)
+        );
+
+        let boxed: Box<dyn std::error::Error> = "no station".into();
+        assert!(matches!(LofiError::from(boxed), LofiError::Other(m) if m == "no station"));
+    }
+}
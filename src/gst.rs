@@ -0,0 +1,254 @@
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use gstreamer::prelude::*;
+use gstreamer::{Bus, BusSyncReply, ClockTime, Element, ElementFactory, MessageView, State};
+use tokio::sync::watch;
+
+use crate::backend::{BackendFuture, ChangeSender, PlayerBackend, PlayerChange};
+
+/// How long a chime player is kept before it is stopped; the chime itself
+/// is well under a second.
+const CHIME_KEEP: Duration = Duration::from_secs(2);
+
+/// Where `observe` wants bus messages reported.
+type Observer = Arc<Mutex<Option<(u64, ChangeSender)>>>;
+
+/// Whether GStreamer initialises, checked once.
+fn initialised() -> bool {
+    static INIT: OnceLock<bool> = OnceLock::new();
+    *INIT.get_or_init(|| gstreamer::init().is_ok())
+}
+
+/// Whether GStreamer can be used: it initialises and has `playbin`
+/// (gst-plugins-base).
+pub fn available() -> bool {
+    initialised() && ElementFactory::find("playbin").is_some()
+}
+
+fn failed(what: &str) -> io::Error {
+    io::Error::other(format!("GStreamer could not {}", what))
+}
+
+/// A URI for `location`: URLs as they are, local files as `file://`.
+fn uri(location: &str) -> io::Result<String> {
+    if location.contains("://") {
+        return Ok(location.to_string());
+    }
+    let path = std::path::absolute(location)?;
+    gstreamer::glib::filename_to_uri(path, None)
+        .map(|uri| uri.to_string())
+        .map_err(|_| failed("open the file"))
+}
+
+/// A `playbin` for `uri` at `level` (after the volume curve), audio only.
+fn playbin(uri: &str, level: f64) -> io::Result<Element> {
+    if !initialised() {
+        return Err(failed("start"));
+    }
+    ElementFactory::make("playbin")
+        .property("uri", uri)
+        .property("volume", level / 100.0)
+        .property_from_str("flags", "audio")
+        .build()
+        .map_err(|_| failed("create a playbin (is gst-plugins-base installed?)"))
+}
+
+/// Report what `message` tells `observer`, and mark the end of the stream.
+fn on_message(message: &gstreamer::Message, ended: &watch::Sender<bool>, observer: &Observer) {
+    let change = match message.view() {
+        MessageView::Eos(_) | MessageView::Error(_) => {
+            ended.send_replace(true);
+            return;
+        }
+        MessageView::Buffering(buffering) => {
+            let percent = buffering.percent();
+            PlayerChange::Buffering((percent < 100).then_some(percent.max(0) as u32))
+        }
+        MessageView::Tag(tag) => {
+            let tags = tag.tags();
+            let Some(title) = tags.get::<gstreamer::tags::Title>() else {
+                return;
+            };
+            let title = title.get();
+            match tags.get::<gstreamer::tags::Artist>() {
+                Some(artist) if !title.contains(artist.get()) => {
+                    PlayerChange::Title(format!("{} - {}", artist.get(), title))
+                }
+                _ => PlayerChange::Title(title.to_string()),
+            }
+        }
+        _ => return,
+    };
+    if let Some((generation, changes)) = observer.lock().ok().and_then(|o| o.clone()) {
+        let _ = changes.send((generation, change));
+    }
+}
+
+/// A stream playing through a GStreamer `playbin` in this process. Its bus
+/// reports the end of the stream, buffering and tags; volume and mute are
+/// its own properties, so they change in place.
+pub struct GstPlayer {
+    /// Taken by `start_kill`, which stops it off the runtime.
+    playbin: Option<Element>,
+    ended: watch::Receiver<bool>,
+    ended_tx: Arc<watch::Sender<bool>>,
+    observer: Observer,
+}
+
+impl GstPlayer {
+    /// Play `location` with `options` at `level` (after the volume curve).
+    /// Options are `property=value` settings for the playbin; `loop` plays
+    /// the media over and over.
+    pub fn start(location: &str, options: &[String], level: f64) -> io::Result<Self> {
+        let uri = uri(location)?;
+        let playbin = playbin(&uri, level)?;
+        for option in options {
+            match option.split_once('=') {
+                Some((name, value)) if playbin.find_property(name).is_some() => {
+                    playbin.set_property_from_str(name, value);
+                }
+                Some(_) => {}
+                // Queue the same media again as it is about to end, gaplessly
+                None if option == "loop" => {
+                    let uri = uri.clone();
+                    playbin.connect("about-to-finish", false, move |args| {
+                        if let Ok(playbin) = args[0].get::<Element>() {
+                            playbin.set_property("uri", &uri);
+                        }
+                        None
+                    });
+                }
+                None => {}
+            }
+        }
+        let (ended_tx, ended) = watch::channel(false);
+        let ended_tx = Arc::new(ended_tx);
+        let observer = Observer::default();
+        let bus: Bus = playbin
+            .bus()
+            .ok_or_else(|| failed("reach the playbin's bus"))?;
+        {
+            let (ended_tx, observer) = (ended_tx.clone(), observer.clone());
+            // Runs on GStreamer's threads; nothing is queued for a main loop
+            bus.set_sync_handler(move |_, message| {
+                on_message(message, &ended_tx, &observer);
+                BusSyncReply::Drop
+            });
+        }
+        if playbin.set_state(State::Playing).is_err() {
+            let _ = playbin.set_state(State::Null);
+            return Err(failed("play the stream"));
+        }
+        Ok(Self {
+            playbin: Some(playbin),
+            ended,
+            ended_tx,
+            observer,
+        })
+    }
+
+    fn playbin(&self) -> io::Result<&Element> {
+        self.playbin
+            .as_ref()
+            .ok_or_else(|| failed("reach the stopped player"))
+    }
+}
+
+impl PlayerBackend for GstPlayer {
+    fn wait(&mut self) -> BackendFuture<'_, ()> {
+        let mut ended = self.ended.clone();
+        Box::pin(async move {
+            let _ = ended.wait_for(|&ended| ended).await;
+        })
+    }
+
+    fn has_ended(&mut self) -> bool {
+        *self.ended.borrow()
+    }
+
+    fn start_kill(&mut self) {
+        let Some(playbin) = self.playbin.take() else {
+            return;
+        };
+        let ended_tx = self.ended_tx.clone();
+        // Stopping waits for the streaming threads to finish
+        tokio::task::spawn_blocking(move || {
+            let _ = playbin.set_state(State::Null);
+            ended_tx.send_replace(true);
+        });
+    }
+
+    fn set_volume(&mut self, level: f64) -> BackendFuture<'_, io::Result<()>> {
+        let result = self
+            .playbin()
+            .map(|playbin| playbin.set_property("volume", (level / 100.0).max(0.0)));
+        Box::pin(async { result })
+    }
+
+    /// Mutes rather than pauses, so a live stream stays live.
+    fn set_paused(&mut self, paused: bool) -> BackendFuture<'_, io::Result<()>> {
+        let result = self
+            .playbin()
+            .map(|playbin| playbin.set_property("mute", paused));
+        Box::pin(async { result })
+    }
+
+    fn progress(&mut self) -> BackendFuture<'_, Option<f64>> {
+        let position = self
+            .playbin()
+            .ok()
+            .and_then(|playbin| playbin.query_position::<ClockTime>());
+        Box::pin(async move { position.map(|p| p.nseconds() as f64 / 1e9) })
+    }
+
+    fn observe(&mut self, generation: u64, changes: ChangeSender) {
+        let Some(playbin) = self.playbin.as_ref() else {
+            return;
+        };
+        // Set by the sound server too (pulsesink follows the stream volume)
+        let volume = changes.clone();
+        playbin.connect_notify(Some("volume"), move |playbin, _| {
+            let level = playbin.property::<f64>("volume") * 100.0;
+            let _ = volume.send((generation, PlayerChange::Volume(level)));
+        });
+        let mute = changes.clone();
+        playbin.connect_notify(Some("mute"), move |playbin, _| {
+            let muted = playbin.property::<bool>("mute");
+            let _ = mute.send((generation, PlayerChange::Pause(muted)));
+        });
+        if let Ok(mut observer) = self.observer.lock() {
+            *observer = Some((generation, changes));
+        }
+    }
+}
+
+impl Drop for GstPlayer {
+    fn drop(&mut self) {
+        // A pipeline must not be released while it plays
+        if let Some(playbin) = self.playbin.take() {
+            let _ = playbin.set_state(State::Null);
+        }
+    }
+}
+
+/// Play the chime `wav` at `level` (after the volume curve) on a playbin of
+/// its own.
+pub fn play_chime(wav: &Path, level: f64) {
+    let Some(path) = wav.to_str() else {
+        return;
+    };
+    let Ok(playbin) = uri(path).and_then(|uri| playbin(&uri, level)) else {
+        return;
+    };
+    if playbin.set_state(State::Playing).is_err() {
+        let _ = playbin.set_state(State::Null);
+        return;
+    }
+    std::thread::spawn(move || {
+        std::thread::sleep(CHIME_KEEP);
+        let _ = playbin.set_state(State::Null);
+    });
+}
@@ -0,0 +1,131 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::state::state_dir;
+use crate::stats::unix_now;
+use crate::ui::{TimelineEntry, TimelineScreen};
+
+/// Most recent switches shown in the timeline view.
+const TIMELINE_ENTRIES: usize = 500;
+/// Stations `u` can walk back through.
+const UNDO_DEPTH: usize = 50;
+
+/// One line of the session log: from `at` on, `station` was playing, or
+/// nothing was when `station` is `None` (the player quit).
+#[derive(Deserialize, Serialize)]
+pub struct HistoryEntry {
+    /// Unix time of the switch.
+    pub at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub station: Option<String>,
+}
+
+/// Append a switch to `history.jsonl` in the state directory. The log is
+/// append-only so a crash loses at most the line being written.
+pub fn record(station: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let path = history_path().ok_or("cannot locate state directory (HOME unset)")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let entry = HistoryEntry {
+        at: unix_now(),
+        station: station.map(str::to_string),
+    };
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    Ok(())
+}
+
+/// Every logged switch, oldest first; unreadable lines are skipped.
+pub fn load() -> Vec<HistoryEntry> {
+    history_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|text| {
+            text.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Stations played before the current one, most recent last, for `u` to
+/// walk back through. Going back does not push, so repeated presses keep
+/// going further back instead of bouncing between two stations.
+pub struct UndoStack {
+    stations: Vec<String>,
+}
+
+impl UndoStack {
+    /// Seeded from the session log, so undo reaches into earlier runs;
+    /// `current` (logged last at startup) is left out.
+    pub fn from_log(entries: &[HistoryEntry], current: &str) -> Self {
+        let mut undo = Self {
+            stations: Vec::new(),
+        };
+        for station in entries.iter().filter_map(|e| e.station.as_deref()) {
+            undo.push(station);
+        }
+        if undo.stations.last().is_some_and(|s| s == current) {
+            undo.stations.pop();
+        }
+        undo
+    }
+
+    /// Remember `station` as left for another one.
+    pub fn push(&mut self, station: &str) {
+        if self.stations.last().is_some_and(|s| s == station) {
+            return;
+        }
+        self.stations.push(station.to_string());
+        let excess = self.stations.len().saturating_sub(UNDO_DEPTH);
+        self.stations.drain(..excess);
+    }
+
+    /// The most recent station `find` still knows, other than those it
+    /// rejects (removed from the config, or the one playing), as its index.
+    pub fn back(&mut self, find: impl Fn(&str) -> Option<usize>) -> Option<usize> {
+        while let Some(station) = self.stations.pop() {
+            if let Some(i) = find(&station) {
+                return Some(i);
+            }
+        }
+        None
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("history.jsonl"))
+}
+
+/// Timeline view snapshot: recent stations grouped by local day, newest
+/// first, each with its start time and how long it played. The entry still
+/// playing runs until `now`.
+pub fn timeline_screen(entries: &[HistoryEntry], now: u64) -> TimelineScreen {
+    let start = entries.len().saturating_sub(TIMELINE_ENTRIES);
+    let mut days: Vec<(String, Vec<TimelineEntry>)> = Vec::new();
+    for (i, entry) in entries.iter().enumerate().skip(start).rev() {
+        let Some(station) = &entry.station else {
+            continue;
+        };
+        let Some(at) = Local.timestamp_opt(entry.at as i64, 0).single() else {
+            continue;
+        };
+        let until = entries.get(i + 1).map(|next| next.at).unwrap_or(now);
+        let day = at.format("%A %Y-%m-%d").to_string();
+        let row = TimelineEntry {
+            time: at.format("%H:%M").to_string(),
+            length: until.saturating_sub(entry.at),
+            station: station.clone(),
+        };
+        match days.last_mut() {
+            Some((d, rows)) if *d == day => rows.push(row),
+            _ => days.push((day, vec![row])),
+        }
+    }
+    TimelineScreen { days, scroll: 0 }
+}
@@ -0,0 +1,488 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, Station};
+use crate::net;
+use crate::radiobrowser::StationInfo;
+
+/// What to do with an incoming station that duplicates an existing one.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateAction {
+    /// Keep the existing entry untouched.
+    Skip,
+    /// Keep the existing entry, filling its empty fields from the new one.
+    Merge,
+    /// Overwrite the existing entry in place.
+    Replace,
+}
+
+/// Outcome of an import, printed by the CLI.
+#[derive(Default)]
+pub struct ImportReport {
+    pub added: usize,
+    pub merged: usize,
+    pub replaced: usize,
+    pub skipped: usize,
+    /// Stations whose stream is not an http(s) URL.
+    pub rejected: usize,
+}
+
+impl std::fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} merged, {} replaced, {} skipped",
+            self.added, self.merged, self.replaced, self.skipped
+        )?;
+        if self.rejected > 0 {
+            write!(f, ", {} rejected (not an http(s) stream)", self.rejected)?;
+        }
+        Ok(())
+    }
+}
+
+/// Stream URL reduced to what identifies the stream: scheme (http and
+/// https count as one), `www.`, default ports and trailing slashes dropped.
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let host = host.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let host = host
+        .strip_suffix(":80")
+        .or_else(|| host.strip_suffix(":443"))
+        .unwrap_or(host);
+    format!("{}{}", host, path.trim_end_matches('/'))
+}
+
+/// Station name reduced to lowercase words, so "Lofi Girl" and "LOFI-girl" match.
+pub fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Index of the station in `stations` that `candidate` duplicates.
+pub fn find_duplicate(stations: &[Station], candidate: &Station) -> Option<usize> {
+    let url = normalize_url(&candidate.url);
+    let name = normalize_name(&candidate.name);
+    stations
+        .iter()
+        .position(|s| normalize_url(&s.url) == url)
+        .or_else(|| {
+            stations
+                .iter()
+                .position(|s| normalize_name(&s.name) == name)
+        })
+}
+
+/// Fill the empty optional fields of `existing` from `new`.
+fn merge_station(existing: &mut Station, new: Station) {
+    fn fill(field: &mut Option<String>, value: Option<String>) {
+        if field.is_none() {
+            *field = value;
+        }
+    }
+    fill(&mut existing.metadata_url, new.metadata_url);
+    fill(&mut existing.refresh_command, new.refresh_command);
+    fill(&mut existing.refresh_url, new.refresh_url);
+    fill(&mut existing.description, new.description);
+    fill(&mut existing.notes, new.notes);
+    fill(&mut existing.homepage, new.homepage);
+    fill(&mut existing.country, new.country);
+    fill(&mut existing.language, new.language);
+    fill(&mut existing.search_url, new.search_url);
+    fill(&mut existing.low_bitrate_url, new.low_bitrate_url);
+}
+
+/// Add `incoming` to the config, asking `decide` about each duplicate
+/// (including duplicates within `incoming` itself). Stations that would
+/// play anything but an http(s) URL are left out: imported lists are not
+/// trusted like the config the user writes.
+pub fn import_stations<F>(
+    config: &mut Config,
+    incoming: Vec<Station>,
+    mut decide: F,
+) -> ImportReport
+where
+    F: FnMut(&Station, &Station) -> DuplicateAction,
+{
+    let mut report = ImportReport::default();
+    for mut station in incoming {
+        if !net::is_stream_url(&station.url) {
+            report.rejected += 1;
+            continue;
+        }
+        station.low_bitrate_url = station
+            .low_bitrate_url
+            .filter(|url| net::is_stream_url(url));
+        let Some(i) = find_duplicate(&config.stations, &station) else {
+            config.stations.push(station);
+            report.added += 1;
+            continue;
+        };
+        match decide(&config.stations[i], &station) {
+            DuplicateAction::Skip => report.skipped += 1,
+            DuplicateAction::Merge => {
+                merge_station(&mut config.stations[i], station);
+                report.merged += 1;
+            }
+            DuplicateAction::Replace => {
+                config.stations[i] = station;
+                report.replaced += 1;
+            }
+        }
+    }
+    report
+}
+
+// ─── Playlist formats ─────────────────────────────────────────────────────────
+
+/// Stations of an M3U playlist; `#EXTINF` titles name them, bare URLs are
+/// named after their host.
+pub fn parse_m3u(text: &str) -> Vec<Station> {
+    let mut stations = Vec::new();
+    let mut title: Option<String> = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            title = info.split_once(',').map(|(_, t)| t.trim().to_string());
+        } else if !line.is_empty() && !line.starts_with('#') {
+            let name = title.take().unwrap_or_else(|| name_from_url(line));
+            stations.push(Station::new(&name, line, None));
+        }
+    }
+    stations
+}
+
+/// Stations of a PLS playlist (`FileN=` / `TitleN=` pairs).
+pub fn parse_pls(text: &str) -> Vec<Station> {
+    let mut entries: Vec<(u32, Option<String>, Option<String>)> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let (field, n) = if let Some(n) = key.strip_prefix("file") {
+            ("file", n)
+        } else if let Some(n) = key.strip_prefix("title") {
+            ("title", n)
+        } else {
+            continue;
+        };
+        let Ok(n) = n.parse::<u32>() else {
+            continue;
+        };
+        let i = match entries.iter().position(|e| e.0 == n) {
+            Some(i) => i,
+            None => {
+                entries.push((n, None, None));
+                entries.len() - 1
+            }
+        };
+        let value = Some(value.trim().to_string());
+        if field == "file" {
+            entries[i].1 = value;
+        } else {
+            entries[i].2 = value;
+        }
+    }
+    entries.sort_by_key(|e| e.0);
+    entries
+        .into_iter()
+        .filter_map(|(_, url, title)| {
+            let url = url?;
+            let name = title.unwrap_or_else(|| name_from_url(&url));
+            Some(Station::new(&name, &url, None))
+        })
+        .collect()
+}
+
+// ─── Other terminal radio players ─────────────────────────────────────────────
+
+/// Fields of one CSV line; `"` quotes a field, `""` inside quotes is a quote.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Stations of a pyradio `stations.csv` (`name,url[,encoding,icon]` rows;
+/// `#` comments and group headers without a URL are skipped).
+pub fn parse_pyradio(text: &str) -> Vec<Station> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields = csv_fields(line);
+            let (name, url) = (fields.first()?.trim(), fields.get(1)?.trim());
+            if !url.contains("://") {
+                return None;
+            }
+            let name = if name.is_empty() {
+                name_from_url(url)
+            } else {
+                name.to_string()
+            };
+            Some(Station::new(&name, url, None))
+        })
+        .collect()
+}
+
+/// Stations of a tera favorites file: a JSON list of radio-browser entries.
+pub fn parse_tera(text: &str) -> Result<Vec<Station>, serde_json::Error> {
+    let infos: Vec<StationInfo> = serde_json::from_str(text)?;
+    Ok(infos
+        .iter()
+        .filter(|info| !info.url.is_empty() || !info.url_resolved.is_empty())
+        .map(StationInfo::to_station)
+        .collect())
+}
+
+/// Value of `name="..."` in an XML tag, matching the name case-insensitively.
+/// ASCII-only lowering keeps byte offsets valid in `tag`.
+fn xml_attr(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let needle = format!(" {}=", name);
+    let start = lower.find(&needle)? + needle.len();
+    let quote = tag[start..]
+        .chars()
+        .next()
+        .filter(|q| *q == '"' || *q == '\'')?;
+    let value = &tag[start + 1..];
+    let value = &value[..value.find(quote)?];
+    Some(
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// Stations of a curseradio `favourites.opml`: `<outline>` entries with a
+/// `URL` attribute, named by `text`.
+pub fn parse_opml(text: &str) -> Vec<Station> {
+    text.split("<outline")
+        .skip(1)
+        .filter_map(|rest| {
+            let tag = &rest[..rest.find('>')?];
+            let url = xml_attr(tag, "url")?;
+            let name = xml_attr(tag, "text").unwrap_or_else(|| name_from_url(&url));
+            Some(Station::new(name.trim(), url.trim(), None))
+        })
+        .collect()
+}
+
+/// Where `player` (pyradio, tera or curseradio) keeps its station list, for
+/// `lofi_rs import NAME`.
+fn player_list_path(player: &str) -> Option<PathBuf> {
+    let home = PathBuf::from(std::env::var_os("HOME")?);
+    let config = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => home.join(".config"),
+    };
+    match player {
+        "pyradio" => Some(config.join("pyradio/stations.csv")),
+        // One JSON list per favorites list
+        "tera" => Some(config.join("tera/favorite")),
+        "curseradio" => Some(home.join(".local/share/curseradio/favourites.opml")),
+        _ => None,
+    }
+}
+
+/// Host of `url`, as a name for stations that have none.
+pub fn name_from_url(url: &str) -> String {
+    normalize_url(url)
+        .split('/')
+        .next()
+        .unwrap_or(url)
+        .to_string()
+}
+
+/// Read a playlist or station list, picking the format by extension (or
+/// content). A directory is read as tera's favorites folder, and a bare
+/// player name as that player's usual list.
+pub fn read_playlist(path: &Path) -> Result<Vec<Station>, Box<dyn std::error::Error>> {
+    if !path.exists() {
+        if let Some(list) = path.to_str().and_then(player_list_path) {
+            if !list.exists() {
+                return Err(format!("{}: not found", list.display()).into());
+            }
+            return read_playlist(&list);
+        }
+    }
+    if path.is_dir() {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "json"))
+            .collect();
+        files.sort();
+        let mut stations = Vec::new();
+        for file in files {
+            stations.extend(read_playlist(&file)?);
+        }
+        if stations.is_empty() {
+            return Err(format!("{}: no stations found", path.display()).into());
+        }
+        return Ok(stations);
+    }
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let head = text.trim_start();
+    let stations = if ext == "pls" || head.starts_with("[playlist]") {
+        parse_pls(&text)
+    } else if ext == "json" || head.starts_with('[') {
+        parse_tera(&text).map_err(|e| format!("{}: {}", path.display(), e))?
+    } else if ext == "opml" || ext == "xml" || head.starts_with('<') {
+        parse_opml(&text)
+    } else if ext == "csv" {
+        parse_pyradio(&text)
+    } else {
+        parse_m3u(&text)
+    };
+    if stations.is_empty() {
+        return Err(format!("{}: no stations found", path.display()).into());
+    }
+    Ok(stations)
+}
+
+// ─── CLI ──────────────────────────────────────────────────────────────────────
+
+/// Ask on the terminal what to do with a duplicate.
+fn prompt_duplicate(existing: &Station, new: &Station) -> DuplicateAction {
+    println!("\n\"{}\" ({})", new.name, new.url);
+    println!("  duplicates \"{}\" ({})", existing.name, existing.url);
+    loop {
+        print!("  [s]kip, [m]erge, [r]eplace? ");
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+            return DuplicateAction::Skip;
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "s" | "skip" | "" => return DuplicateAction::Skip,
+            "m" | "merge" => return DuplicateAction::Merge,
+            "r" | "replace" => return DuplicateAction::Replace,
+            _ => {}
+        }
+    }
+}
+
+/// Add `incoming` to the saved config; duplicates get `on_duplicate`, or a
+/// prompt when it is `None`.
+pub fn add_to_config(
+    incoming: Vec<Station>,
+    on_duplicate: Option<DuplicateAction>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = Config::load()?;
+    let report = import_stations(&mut config, incoming, |existing, new| {
+        on_duplicate.unwrap_or_else(|| prompt_duplicate(existing, new))
+    });
+    config.save()?;
+    println!("{}", report);
+    Ok(())
+}
+
+/// `lofi_rs add QUERY`: search radio-browser.info and add the picked station.
+pub async fn add_from_radio_browser(
+    query: &str,
+    on_duplicate: Option<DuplicateAction>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let found = crate::radiobrowser::search(query, 10).await?;
+    if found.is_empty() {
+        return Err(format!("no station matches \"{}\"", query).into());
+    }
+    for (i, info) in found.iter().enumerate() {
+        let place = [info.country.as_str(), info.tags.as_str()]
+            .iter()
+            .filter(|s| !s.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" · ");
+        println!("{:>2}. {}  {}", i + 1, info.name.trim(), place);
+    }
+    print!("Add which (1-{}, empty to cancel)? ", found.len());
+    std::io::Write::flush(&mut std::io::stdout())?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    let Ok(n) = answer.trim().parse::<usize>() else {
+        return Ok(());
+    };
+    let info = found.get(n.wrapping_sub(1)).ok_or("no such entry")?;
+    add_to_config(vec![info.to_station()], on_duplicate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_attr_matches_names_case_insensitively() {
+        let tag = r#" type="audio" URL="http://a.example/s?x=1&amp;y=2" text='Jazz'"#;
+        assert_eq!(
+            xml_attr(tag, "url").as_deref(),
+            Some("http://a.example/s?x=1&y=2")
+        );
+        assert_eq!(xml_attr(tag, "text").as_deref(), Some("Jazz"));
+        assert_eq!(xml_attr(tag, "missing"), None);
+        assert_eq!(xml_attr(" url=unquoted", "url"), None);
+    }
+
+    #[test]
+    fn xml_attr_keeps_offsets_after_non_ascii_values() {
+        // 'İ' lowercases to two chars (three bytes) under full Unicode rules
+        let tag = r#" text="İİİ Radio Ünïcødé" URL="http://b.example/live""#;
+        assert_eq!(xml_attr(tag, "text").as_deref(), Some("İİİ Radio Ünïcødé"));
+        assert_eq!(
+            xml_attr(tag, "url").as_deref(),
+            Some("http://b.example/live")
+        );
+    }
+
+    #[test]
+    fn parse_opml_reads_outlines_with_urls() {
+        let opml = r#"<?xml version="1.0"?>
+<opml version="2.0"><body>
+  <outline text="Favourites">
+    <outline type="audio" text=" Café Ñoño " URL="http://c.example/1"/>
+    <outline type="audio" URL="http://c.example/night.mp3"/>
+    <outline text="No stream here"/>
+  </outline>
+</body></opml>"#;
+        let stations = parse_opml(opml);
+        let got: Vec<(&str, &str)> = stations
+            .iter()
+            .map(|s| (s.name.as_str(), s.url.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("Café Ñoño", "http://c.example/1"),
+                (
+                    name_from_url("http://c.example/night.mp3").as_str(),
+                    "http://c.example/night.mp3"
+                ),
+            ]
+        );
+    }
+}
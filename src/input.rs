@@ -0,0 +1,158 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Entries kept in a text input's history.
+const HISTORY_LEN: usize = 50;
+
+/// Single-line text input: cursor movement, readline-style editing keys,
+/// pasted text and ↑/↓ through earlier entries.
+#[derive(Default)]
+pub struct TextInput {
+    text: String,
+    /// Cursor position, in chars.
+    cursor: usize,
+    /// Earlier entries, oldest first.
+    history: Vec<String>,
+    /// Entry shown while browsing the history, and the text typed before.
+    browsing: Option<(usize, String)>,
+}
+
+impl TextInput {
+    pub fn with_history(history: Vec<String>) -> Self {
+        Self {
+            history,
+            ..Default::default()
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Byte offset of the char at `cursor`.
+    fn offset(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn set(&mut self, text: String) {
+        self.cursor = text.chars().count();
+        self.text = text;
+    }
+
+    /// Insert `text` at the cursor. Line breaks in pasted text become
+    /// spaces, other control characters are dropped.
+    pub fn insert(&mut self, text: &str) {
+        let text: String = text
+            .chars()
+            .map(|c| if c == '\n' || c == '\t' { ' ' } else { c })
+            .filter(|c| !c.is_control())
+            .collect();
+        let at = self.offset(self.cursor);
+        self.text.insert_str(at, &text);
+        self.cursor += text.chars().count();
+        self.browsing = None;
+    }
+
+    /// Remove the chars between the cursor positions `from` and `to`.
+    fn remove(&mut self, from: usize, to: usize) {
+        let range = self.offset(from)..self.offset(to);
+        self.text.replace_range(range, "");
+        self.cursor = from;
+        self.browsing = None;
+    }
+
+    /// Start of the word before the cursor.
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().take(self.cursor).collect();
+        let mut i = chars.len();
+        while i > 0 && chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    /// Step through the history: `back` towards older entries. Leaving the
+    /// newest entry brings back what was typed before browsing.
+    fn browse(&mut self, back: bool) {
+        let next = match (&self.browsing, back) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => return,
+            (Some((i, _)), true) => Some(i.saturating_sub(1)),
+            (Some((i, _)), false) => Some(i + 1).filter(|&i| i < self.history.len()),
+        };
+        match next {
+            Some(i) => {
+                let draft = match self.browsing.take() {
+                    Some((_, draft)) => draft,
+                    None => self.text.clone(),
+                };
+                self.set(self.history[i].clone());
+                self.browsing = Some((i, draft));
+            }
+            None => {
+                if let Some((_, draft)) = self.browsing.take() {
+                    self.set(draft);
+                }
+            }
+        }
+    }
+
+    /// Apply an editing key. Returns `false` for keys the input does not
+    /// use (Enter, Esc, ...), which are left to the caller.
+    pub fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        match code {
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.len(),
+            KeyCode::Char('u') if ctrl => self.remove(0, self.cursor),
+            KeyCode::Char('k') if ctrl => self.remove(self.cursor, self.len()),
+            KeyCode::Char('w') if ctrl => self.remove(self.word_start(), self.cursor),
+            KeyCode::Char(c) if !ctrl => self.insert(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Backspace if self.cursor > 0 => self.remove(self.cursor - 1, self.cursor),
+            KeyCode::Delete if self.cursor < self.len() => {
+                self.remove(self.cursor, self.cursor + 1)
+            }
+            KeyCode::Backspace | KeyCode::Delete => {}
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            KeyCode::Up => self.browse(true),
+            KeyCode::Down => self.browse(false),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Take the text, remembering it in the history.
+    pub fn submit(&mut self) -> String {
+        let text = std::mem::take(&mut self.text);
+        self.cursor = 0;
+        self.browsing = None;
+        let trimmed = text.trim();
+        if !trimmed.is_empty() && self.history.last().map(String::as_str) != Some(trimmed) {
+            self.history.push(trimmed.to_string());
+            if self.history.len() > HISTORY_LEN {
+                self.history.remove(0);
+            }
+        }
+        text
+    }
+}
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::metadata::Track;
+use crate::net;
+use crate::notify::open_url;
+use crate::scrobble::SubmitError;
+
+const API_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+/// Auth tokens are valid for 60 minutes; give up on approval well before.
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+/// Methods that change data, which Last.fm only accepts as POST.
+const WRITE_METHODS: [&str; 2] = ["track.scrobble", "track.updateNowPlaying"];
+
+/// Last.fm credentials under `[lastfm]`. `api_key` and `api_secret` come from
+/// an API account (https://www.last.fm/api/account/create); the session key
+/// is filled in by `lofi_rs auth lastfm`.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LastfmConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_key: Option<String>,
+    /// Account the session key belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+/// Signature Last.fm expects on authenticated calls: the MD5 of every
+/// parameter (sorted, `format` excluded) followed by the shared secret.
+fn api_sig(params: &BTreeMap<&str, String>, secret: &str) -> String {
+    let mut raw = String::new();
+    for (k, v) in params {
+        if *k != "format" {
+            raw.push_str(k);
+            raw.push_str(v);
+        }
+    }
+    raw.push_str(secret);
+    format!("{:x}", md5::compute(raw))
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    error: u32,
+    message: String,
+}
+
+/// Call a signed Last.fm method and return the JSON body.
+async fn call(
+    client: &reqwest::Client,
+    method: &str,
+    mut params: BTreeMap<&str, String>,
+    secret: &str,
+) -> Result<serde_json::Value, ApiFailure> {
+    params.insert("method", method.to_string());
+    let sig = api_sig(&params, secret);
+    params.insert("api_sig", sig);
+    params.insert("format", "json".to_string());
+    let request = if WRITE_METHODS.contains(&method) {
+        client.post(API_URL).form(&params)
+    } else {
+        client.get(API_URL).query(&params)
+    };
+    let body: serde_json::Value = request
+        .send()
+        .await
+        .map_err(|e| ApiFailure::Http(e.to_string()))?
+        .json()
+        .await
+        .map_err(|e| ApiFailure::Http(e.to_string()))?;
+    if let Ok(err) = serde_json::from_value::<ApiError>(body.clone()) {
+        return Err(ApiFailure::Api(err.error, err.message));
+    }
+    Ok(body)
+}
+
+enum ApiFailure {
+    Http(String),
+    /// Last.fm error code and message.
+    Api(u32, String),
+}
+
+impl std::fmt::Display for ApiFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiFailure::Http(e) => write!(f, "{}", e),
+            ApiFailure::Api(code, msg) => write!(f, "{} (error {})", msg, code),
+        }
+    }
+}
+
+/// Last.fm error 14: the token has not been authorized yet.
+const ERR_TOKEN_UNAUTHORIZED: u32 = 14;
+
+fn prompt(label: &str) -> std::io::Result<String> {
+    print!("{}: ", label);
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+/// `lofi_rs auth lastfm`: ask for API credentials if missing, have the user
+/// approve a token in the browser, then store the session key in the config.
+pub async fn authorize(config: &mut Config) -> Result<(), Box<dyn std::error::Error>> {
+    let lastfm = &mut config.lastfm;
+    if lastfm.api_key.is_none() || lastfm.api_secret.is_none() {
+        println!("Create an API account at https://www.last.fm/api/account/create");
+        println!("(the callback URL can be left empty), then paste its credentials.");
+        lastfm.api_key = Some(prompt("API key")?).filter(|s| !s.is_empty());
+        lastfm.api_secret = Some(prompt("Shared secret")?).filter(|s| !s.is_empty());
+    }
+    let (Some(api_key), Some(secret)) = (lastfm.api_key.clone(), lastfm.api_secret.clone()) else {
+        return Err("an API key and shared secret are required".into());
+    };
+
+    let client = net::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let params = BTreeMap::from([("api_key", api_key.clone())]);
+    let token = call(&client, "auth.getToken", params, &secret)
+        .await
+        .map_err(|e| format!("auth.getToken failed: {}", e))?["token"]
+        .as_str()
+        .ok_or("auth.getToken returned no token")?
+        .to_string();
+
+    let url = format!(
+        "https://www.last.fm/api/auth/?api_key={}&token={}",
+        api_key, token
+    );
+    println!("\nApprove lofi_rs in your browser:\n  {}\n", url);
+    if !open_url(&url) {
+        println!("(could not open a browser; open the URL manually)");
+    }
+    print!("Waiting for approval");
+    std::io::stdout().flush()?;
+
+    let started = Instant::now();
+    let session = loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let params = BTreeMap::from([("api_key", api_key.clone()), ("token", token.clone())]);
+        match call(&client, "auth.getSession", params, &secret).await {
+            Ok(body) => break body,
+            Err(ApiFailure::Api(ERR_TOKEN_UNAUTHORIZED, _)) | Err(ApiFailure::Http(_))
+                if started.elapsed() < APPROVAL_TIMEOUT =>
+            {
+                print!(".");
+                std::io::stdout().flush()?;
+            }
+            Err(e) => return Err(format!("\nauth.getSession failed: {}", e).into()),
+        }
+    };
+    let key = session["session"]["key"]
+        .as_str()
+        .ok_or("auth.getSession returned no session key")?;
+    let name = session["session"]["name"].as_str().map(str::to_string);
+
+    lastfm.session_key = Some(key.to_string());
+    lastfm.username = name.clone();
+    config.save()?;
+    println!(
+        "\nAuthorized as {}. Session key saved to the config.",
+        name.as_deref().unwrap_or("unknown user")
+    );
+    Ok(())
+}
+
+/// Submits listens to the account in `[lastfm]`.
+#[derive(Clone)]
+pub struct Scrobbler {
+    client: reqwest::Client,
+    api_key: String,
+    secret: String,
+    session_key: String,
+}
+
+impl Scrobbler {
+    /// `None` until `lofi_rs auth lastfm` has stored a session key.
+    pub fn new(config: &LastfmConfig) -> Option<Self> {
+        Some(Self {
+            client: net::client_builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .ok()?,
+            api_key: config.api_key.clone()?,
+            secret: config.api_secret.clone()?,
+            session_key: config.session_key.clone()?,
+        })
+    }
+
+    fn params(&self, track: &Track) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("api_key", self.api_key.clone()),
+            ("sk", self.session_key.clone()),
+            ("artist", track.artist.clone().unwrap_or_default()),
+            ("track", track.title.clone()),
+        ])
+    }
+
+    /// Show `track` as playing on the profile.
+    pub async fn now_playing(&self, track: &Track) -> Result<(), SubmitError> {
+        call(
+            &self.client,
+            "track.updateNowPlaying",
+            self.params(track),
+            &self.secret,
+        )
+        .await
+        .map(|_| ())
+        .map_err(SubmitError::from)
+    }
+
+    /// Scrobble `track`, heard from `started_at` (Unix time).
+    pub async fn scrobble(&self, track: &Track, started_at: u64) -> Result<(), SubmitError> {
+        let mut params = self.params(track);
+        params.insert("timestamp", started_at.to_string());
+        call(&self.client, "track.scrobble", params, &self.secret)
+            .await
+            .map(|_| ())
+            .map_err(SubmitError::from)
+    }
+}
+
+/// Last.fm errors that pass: operation failed, service offline,
+/// temporarily unavailable, rate limit exceeded.
+const TRANSIENT_ERRORS: [u32; 4] = [8, 11, 16, 29];
+
+impl From<ApiFailure> for SubmitError {
+    fn from(failure: ApiFailure) -> Self {
+        match failure {
+            ApiFailure::Api(code, _) if !TRANSIENT_ERRORS.contains(&code) => {
+                SubmitError::Rejected(format!("Last.fm: {}", failure))
+            }
+            _ => SubmitError::Retry(format!("Last.fm: {}", failure)),
+        }
+    }
+}
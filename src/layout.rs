@@ -0,0 +1,102 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+use crate::state::LayoutPrefs;
+
+/// Terminal width from which the player view uses two columns.
+pub const TWO_COLUMN_MIN_WIDTH: u16 = 120;
+/// Share of the width the left column gets in the two-column layout.
+const LEFT_COLUMN_PERCENT: u16 = 55;
+
+/// Where each pane of the player view goes. Panes that are not shown in
+/// the current layout are `None`.
+pub struct PlayerLayout {
+    pub stations: Rect,
+    pub status: Rect,
+    pub now_playing: Option<Rect>,
+    pub controls: Option<Rect>,
+    /// Recently heard tracks; only in the two-column layout.
+    pub history: Option<Rect>,
+}
+
+/// Lay out the player view in `area` for `station_count` stations.
+///
+/// Narrow terminals stack stations, status, now playing and controls.
+/// From [`TWO_COLUMN_MIN_WIDTH`] on, stations and now playing go on the
+/// left and the track history on the right, with status and controls
+/// spanning both columns below. The compact view always stacks.
+pub fn player_layout(area: Rect, prefs: &LayoutPrefs, station_count: usize) -> PlayerLayout {
+    let list_height = prefs.list_height.unwrap_or(station_count as u16 + 2);
+    if prefs.compact {
+        let rows = split(
+            Direction::Vertical,
+            area,
+            [Constraint::Length(list_height), Constraint::Length(3)],
+        );
+        return PlayerLayout {
+            stations: rows[0],
+            status: rows[1],
+            now_playing: None,
+            controls: None,
+            history: None,
+        };
+    }
+    if area.width < TWO_COLUMN_MIN_WIDTH {
+        let rows = split(
+            Direction::Vertical,
+            area,
+            [
+                Constraint::Length(list_height),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Min(0),
+            ],
+        );
+        return PlayerLayout {
+            stations: rows[0],
+            status: rows[1],
+            now_playing: Some(rows[2]),
+            controls: prefs.show_controls.then_some(rows[3]),
+            history: None,
+        };
+    }
+    let rows = split(
+        Direction::Vertical,
+        area,
+        [
+            Constraint::Length(list_height + 3),
+            Constraint::Length(3),
+            Constraint::Min(0),
+        ],
+    );
+    let columns = split(
+        Direction::Horizontal,
+        rows[0],
+        [
+            Constraint::Percentage(LEFT_COLUMN_PERCENT),
+            Constraint::Percentage(100 - LEFT_COLUMN_PERCENT),
+        ],
+    );
+    let left = split(
+        Direction::Vertical,
+        columns[0],
+        [Constraint::Min(0), Constraint::Length(3)],
+    );
+    PlayerLayout {
+        stations: left[0],
+        status: rows[1],
+        now_playing: Some(left[1]),
+        controls: prefs.show_controls.then_some(rows[2]),
+        history: Some(columns[1]),
+    }
+}
+
+fn split<const N: usize>(
+    direction: Direction,
+    area: Rect,
+    constraints: [Constraint; N],
+) -> std::rc::Rc<[Rect]> {
+    Layout::default()
+        .direction(direction)
+        .constraints(constraints)
+        .split(area)
+}
@@ -0,0 +1,151 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::state::state_dir;
+use crate::stats::{track_key, unix_now};
+
+/// A track hearted from the player.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct LikedTrack {
+    pub title: String,
+    /// Station it was playing on.
+    pub station: String,
+    /// Unix time it was liked.
+    pub liked_at: u64,
+}
+
+/// Liked tracks, oldest first, stored as JSON in the state directory.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Likes {
+    pub tracks: Vec<LikedTrack>,
+}
+
+impl Likes {
+    /// Load the likes file; a missing or corrupt file starts empty.
+    pub fn load() -> Self {
+        likes_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = likes_path().ok_or("cannot locate state directory (HOME unset)")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Whether `title` is liked, matching titles the way play counts do.
+    pub fn contains(&self, title: &str) -> bool {
+        let key = track_key(title);
+        self.tracks.iter().any(|t| track_key(&t.title) == key)
+    }
+
+    /// Like or unlike `title`. Returns whether it is liked afterwards.
+    pub fn toggle(&mut self, title: &str, station: &str) -> bool {
+        let key = track_key(title);
+        let before = self.tracks.len();
+        self.tracks.retain(|t| track_key(&t.title) != key);
+        if self.tracks.len() < before {
+            return false;
+        }
+        self.tracks.push(LikedTrack {
+            title: title.to_string(),
+            station: station.to_string(),
+            liked_at: unix_now(),
+        });
+        true
+    }
+}
+
+fn likes_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("likes.json"))
+}
+
+// ─── Export ───────────────────────────────────────────────────────────────────
+
+/// Output format of `lofi_rs export-likes`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Title followed by one search URL per service.
+    Text,
+    Json,
+}
+
+#[derive(Serialize)]
+struct ExportedTrack<'a> {
+    title: &'a str,
+    station: &'a str,
+    liked_at: u64,
+    spotify: String,
+    youtube_music: String,
+}
+
+fn spotify_search_url(query: &str) -> String {
+    let mut url = Url::parse("https://open.spotify.com/search").expect("static URL");
+    url.path_segments_mut().expect("static URL").push(query);
+    url.into()
+}
+
+fn youtube_music_search_url(query: &str) -> String {
+    Url::parse_with_params("https://music.youtube.com/search", &[("q", query)])
+        .expect("static URL")
+        .into()
+}
+
+/// Search URL for `query`: `template` with `{query}` replaced by the
+/// percent-encoded query, or a YouTube Music search without a template.
+pub fn search_url(template: Option<&str>, query: &str) -> String {
+    let Some(template) = template else {
+        return youtube_music_search_url(query);
+    };
+    let mut encoded = String::new();
+    for b in query.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    template.replace("{query}", &encoded)
+}
+
+/// Render the liked tracks as search URLs for rebuilding a playlist.
+pub fn export_likes(likes: &Likes, format: ExportFormat) -> Result<String, serde_json::Error> {
+    match format {
+        ExportFormat::Text => {
+            let mut out = String::new();
+            for t in &likes.tracks {
+                out.push_str(&format!(
+                    "{}\n  {}\n  {}\n",
+                    t.title,
+                    spotify_search_url(&t.title),
+                    youtube_music_search_url(&t.title)
+                ));
+            }
+            Ok(out)
+        }
+        ExportFormat::Json => {
+            let tracks: Vec<ExportedTrack> = likes
+                .tracks
+                .iter()
+                .map(|t| ExportedTrack {
+                    title: &t.title,
+                    station: &t.station,
+                    liked_at: t.liked_at,
+                    spotify: spotify_search_url(&t.title),
+                    youtube_music: youtube_music_search_url(&t.title),
+                })
+                .collect();
+            let mut out = serde_json::to_string_pretty(&tracks)?;
+            out.push('\n');
+            Ok(out)
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::metadata::Track;
+use crate::net;
+use crate::scrobble::SubmitError;
+
+/// ListenBrainz account under `[listenbrainz]`; the user token is on
+/// https://listenbrainz.org/settings/.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ListenBrainzConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// API root, for self-hosted instances.
+    pub api_url: String,
+}
+
+impl Default for ListenBrainzConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            api_url: "https://api.listenbrainz.org".to_string(),
+        }
+    }
+}
+
+/// Submits listens to ListenBrainz.
+#[derive(Clone)]
+pub struct ListenBrainz {
+    client: reqwest::Client,
+    url: String,
+    token: String,
+}
+
+impl ListenBrainz {
+    /// `None` without a token.
+    pub fn new(config: &ListenBrainzConfig) -> Option<Self> {
+        Some(Self {
+            client: net::client_builder()
+                .timeout(Duration::from_secs(10))
+                .user_agent(concat!("lofi_rs/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .ok()?,
+            url: format!("{}/1/submit-listens", config.api_url.trim_end_matches('/')),
+            token: config.token.clone().filter(|t| !t.is_empty())?,
+        })
+    }
+
+    async fn send(&self, listen_type: &str, listen: serde_json::Value) -> Result<(), SubmitError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .header("Authorization", format!("Token {}", self.token))
+            .json(&json!({ "listen_type": listen_type, "payload": [listen] }))
+            .send()
+            .await
+            .map_err(|e| SubmitError::Retry(e.to_string()))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let message = format!("ListenBrainz answered {}", status);
+        // Rate limits and server trouble pass; anything else is our listen
+        if status.as_u16() == 429 || status.is_server_error() {
+            Err(SubmitError::Retry(message))
+        } else {
+            Err(SubmitError::Rejected(message))
+        }
+    }
+
+    fn metadata(track: &Track) -> serde_json::Value {
+        json!({
+            "artist_name": track.artist.as_deref().unwrap_or_default(),
+            "track_name": track.title,
+            "additional_info": { "submission_client": "lofi_rs" },
+        })
+    }
+
+    /// Show `track` as playing now on the profile.
+    pub async fn playing_now(&self, track: &Track) -> Result<(), SubmitError> {
+        self.send(
+            "playing_now",
+            json!({ "track_metadata": Self::metadata(track) }),
+        )
+        .await
+    }
+
+    /// Submit a listen of `track` that started at `listened_at` (Unix time).
+    pub async fn submit(&self, track: &Track, listened_at: u64) -> Result<(), SubmitError> {
+        self.send(
+            "single",
+            json!({ "listened_at": listened_at, "track_metadata": Self::metadata(track) }),
+        )
+        .await
+    }
+}
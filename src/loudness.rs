@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command as TokioCommand;
+
+/// Loudness ReplayGain 2.0 gains are relative to, in LUFS.
+pub const REPLAYGAIN_REFERENCE_LUFS: f64 = -18.0;
+/// Highest true peak a normalized copy may reach, in dBTP; louder targets
+/// get less gain instead of clipping.
+const MAX_TRUE_PEAK: f64 = -1.0;
+
+/// What happens once a recording is saved, under `[recording]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoudnessMode {
+    #[default]
+    Off,
+    /// Measure it and write ReplayGain tags for players to apply.
+    Tag,
+    /// Measure it and save a copy brought to `target_lufs`.
+    Normalize,
+}
+
+/// EBU R128 measurement of a file.
+#[derive(Clone, Copy, Debug)]
+pub struct Loudness {
+    /// Integrated loudness, in LUFS.
+    pub integrated: f64,
+    /// True peak, in dBTP.
+    pub true_peak: f64,
+}
+
+/// What ffmpeg's loudnorm filter prints; the numbers come as strings.
+#[derive(Deserialize)]
+struct LoudnormReport {
+    input_i: String,
+    input_tp: String,
+}
+
+/// The last line ffmpeg wrote to stderr, as an error.
+pub fn ffmpeg_error(output: &std::process::Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.lines().rev().find(|l| !l.trim().is_empty()) {
+        Some(line) => format!("ffmpeg: {}", line.trim()),
+        None => format!("ffmpeg exited with {}", output.status),
+    }
+}
+
+impl Loudness {
+    /// Measure `path` with ffmpeg's loudnorm filter.
+    pub async fn measure(path: &Path) -> Result<Self, String> {
+        let output = TokioCommand::new("ffmpeg")
+            .args(["-hide_banner", "-nostats", "-i"])
+            .arg(path)
+            .args([
+                "-vn",
+                "-af",
+                "loudnorm=print_format=json",
+                "-f",
+                "null",
+                "-",
+            ])
+            .stdin(std::process::Stdio::null())
+            .output()
+            .await
+            .map_err(|e| format!("cannot run ffmpeg: {}", e))?;
+        if !output.status.success() {
+            return Err(ffmpeg_error(&output));
+        }
+        // The report is the last JSON object on stderr
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let report = stderr
+            .rfind('{')
+            .and_then(|start| {
+                let end = stderr[start..].find('}')?;
+                serde_json::from_str::<LoudnormReport>(&stderr[start..=start + end]).ok()
+            })
+            .ok_or("ffmpeg printed no loudness report")?;
+        let integrated: f64 = report
+            .input_i
+            .trim()
+            .parse()
+            .map_err(|_| "no loudness measured")?;
+        let true_peak: f64 = report
+            .input_tp
+            .trim()
+            .parse()
+            .map_err(|_| "no peak measured")?;
+        // Silence measures as -inf (or -70, loudnorm's floor)
+        if !integrated.is_finite() || integrated <= -70.0 {
+            return Err("the recording is silent".to_string());
+        }
+        Ok(Self {
+            integrated,
+            true_peak,
+        })
+    }
+
+    /// Gain in dB that brings it to `target` LUFS.
+    pub fn gain(&self, target: f64) -> f64 {
+        target - self.integrated
+    }
+
+    /// True peak as a linear sample value, the way ReplayGain peaks are
+    /// written.
+    pub fn peak(&self) -> f64 {
+        10f64.powf(self.true_peak / 20.0)
+    }
+
+    /// ReplayGain track gain and peak tags, formatted as players expect.
+    pub fn replaygain_tags(&self) -> [(&'static str, String); 2] {
+        [
+            (
+                "REPLAYGAIN_TRACK_GAIN",
+                format!("{:+.2} dB", self.gain(REPLAYGAIN_REFERENCE_LUFS)),
+            ),
+            ("REPLAYGAIN_TRACK_PEAK", format!("{:.6}", self.peak())),
+        ]
+    }
+}
+
+/// Encoder for a normalized copy, by file extension.
+fn encoder(ext: &str) -> Option<&'static str> {
+    match ext {
+        "mp3" => Some("libmp3lame"),
+        "aac" => Some("aac"),
+        "ogg" => Some("libvorbis"),
+        "opus" => Some("libopus"),
+        "flac" => Some("flac"),
+        _ => None,
+    }
+}
+
+/// Save a copy of `path`, measured as `loudness`, brought to `target` LUFS
+/// as "<name> (normalized).<ext>" next to it. Tags are carried over.
+pub async fn normalized_copy(
+    path: &Path,
+    loudness: Loudness,
+    target: f64,
+    bitrate_kbps: u32,
+) -> Result<PathBuf, String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let encoder = encoder(&ext).ok_or_else(|| format!("cannot re-encode .{} files", ext))?;
+    let gain = loudness
+        .gain(target)
+        .min(MAX_TRUE_PEAK - loudness.true_peak);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let out = path.with_file_name(format!("{} (normalized).{}", stem, ext));
+    let mut cmd = TokioCommand::new("ffmpeg");
+    cmd.args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(path)
+        .args([
+            "-vn",
+            "-af",
+            &format!("volume={:.2}dB", gain),
+            "-c:a",
+            encoder,
+        ]);
+    if encoder != "flac" {
+        cmd.arg("-b:a").arg(format!("{}k", bitrate_kbps));
+    }
+    let output = cmd
+        .arg(&out)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("cannot run ffmpeg: {}", e))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&out);
+        return Err(ffmpeg_error(&output));
+    }
+    Ok(out)
+}
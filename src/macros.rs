@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::palette::best_match;
+
+/// Palette commands run on launch, under `[startup]`, e.g.
+/// `actions = ["volume 40", "play Jazz Cafe", "sleep 60", "record"]`.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StartupConfig {
+    pub actions: Vec<String>,
+}
+
+/// Palette commands run together by `macro <name>`, under `[[macros]]`.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Macro {
+    pub name: String,
+    pub actions: Vec<String>,
+}
+
+/// The macro best matching `name`.
+pub fn find<'a>(macros: &'a [Macro], name: &str) -> Option<&'a Macro> {
+    best_match(name, macros.iter().map(|m| m.name.as_str())).map(|i| &macros[i])
+}
+
+/// Whether `action` runs a macro itself, which macros may not do.
+pub fn is_macro(action: &str) -> bool {
+    action.split_whitespace().next() == Some("macro")
+}
@@ -1,22 +1,133 @@
+mod backend;
+mod backup;
+mod bandwidth;
+mod beats;
+mod bluetooth;
+mod cache;
+mod changelog;
+mod chime;
+mod cli;
+mod config;
+mod connect;
+mod error;
+#[cfg(feature = "gstreamer")]
+mod gst;
+mod history;
+mod import;
+mod input;
+mod lastfm;
+mod layout;
+mod likes;
+mod listenbrainz;
+mod loudness;
+mod macros;
+mod metadata;
+mod mixer;
+mod mpris;
+mod musicbrainz;
+mod net;
+mod night;
+mod noise;
+mod notify;
+mod output;
+mod palette;
+mod playback;
 mod player;
+mod power;
+mod probe;
+mod radiobrowser;
+mod reconnect;
+mod record;
+mod refresh;
+mod remote;
+mod report;
+mod rotation;
+mod scrobble;
+mod settings;
+mod share;
+mod signals;
+mod snapshot;
+mod state;
+mod stats;
+mod status;
+mod ticker;
+mod timefmt;
+mod tls;
 mod ui;
+mod update;
+#[cfg(feature = "vlc")]
+mod vlc;
 
+use chrono::{Local, TimeZone};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    KeyboardEnhancementFlags, MouseButton, MouseEvent, MouseEventKind, PopKeyboardEnhancementFlags,
+    PushKeyboardEnhancementFlags,
+};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, Clear, ClearType,
+};
 use crossterm::{
-    cursor::{Hide, Show, MoveTo},
+    cursor::{Hide, MoveTo, Show},
     execute,
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
+use ratatui::{backend::CrosstermBackend, layout::Rect, Terminal, TerminalOptions, Viewport};
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{ErrorKind, IsTerminal};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
+use crate::bandwidth::{format_bytes, CapLevel, DataMeter};
+use crate::beats::Beats;
+use crate::bluetooth::spawn_connect;
+use crate::chime::{chime_samples, write_chime_wav};
+use crate::cli::{parse_args, take_instance, Command, StartOptions, USAGE};
+use crate::config::{config_path, Config, Station};
+use crate::error::LofiError;
+use crate::history::UndoStack;
+use crate::input::TextInput;
+use crate::layout::player_layout;
+use crate::likes::{export_likes, search_url, Likes};
+use crate::metadata::{MetadataRules, Track};
+use crate::mixer::Mixer;
+use crate::mpris::{AudioFocus, FocusAction, OtherPlayer};
+use crate::musicbrainz::{MusicBrainzCache, Recording};
+use crate::night::NightMode;
+use crate::noise::{AmbientMixer, NoiseKind};
+use crate::notify::{desktop_notify, open_url};
+use crate::output::{
+    enable_airplay_discovery, set_default_output, spawn_output_monitor, OutputEvent, OutputPicker,
+};
+use crate::palette::{best_match, parse_command, Palette, PaletteCommand};
+use crate::playback::{apply_config, spawn_supervisor, PlayerCommand, PlayerEvent, PlayerState};
 use crate::player::{
-    build_player_args, detect_player, spawn_player, PlayerType, VolumeControl,
+    detect_player, next_player, player_available, HoldLevel, PlayerType, VolumeControl, SEEK_STEP,
+};
+use crate::power::{spawn_power_monitor, PowerEvent};
+use crate::radiobrowser::{spawn_lookup, StationInfo};
+use crate::reconnect::ReconnectSupervisor;
+use crate::record::{RecordEvent, Recorder, RecordingsView};
+use crate::refresh::{recheck, Recheck};
+use crate::remote::{RemoteCommand, SharedView};
+use crate::rotation::{Rotation, RotationRun};
+use crate::scrobble::{spawn_queue, Listen, ListenQueue, ScrobbleRules, Submitter};
+use crate::settings::{Setting, SettingsView, AUDIO_PANE};
+use crate::share::{fetch_session, spawn_follower, spawn_publish, ShareEvent, SharedSession};
+use crate::signals::{suspend_headless, suspend_to_shell, SignalEvent, Signals, CAN_SUSPEND};
+use crate::snapshot::{RecordingSnapshot, Snapshot, SNAPSHOT_INTERVAL};
+use crate::state::{init_instance, AppState, MIN_LIST_HEIGHT};
+use crate::stats::{sort_stations, track_key, unix_now, SessionSummary, Stats, LIKELY_DEAD_AFTER};
+use crate::status::{PlayState, Status};
+use crate::ticker::{Tick, TickClient, Ticker};
+use crate::ui::{
+    detect_color_support, draw_ui, icon_set, theme_by_name, ConfirmScreen, DetailScreen, Focus,
+    Toast, UiState, SPINNER_FRAME, THEMES,
 };
-use crate::ui::{draw_ui, UiState, STATIONS};
+use crate::update::{spawn_check, Release};
 
 // ─── Metadata ────────────────────────────────────────────────────────────────
 
@@ -36,145 +147,668 @@ struct NpSong {
     artist: String,
 }
 
-async fn fetch_now_playing(url: &str) -> Option<String> {
-    let client = reqwest::Client::builder()
+/// Queue `listen` for every configured service if it played long enough,
+/// and wake the submitting task.
+fn submit_listen(
+    listen: Option<Listen>,
+    rules: &ScrobbleRules,
+    submitter: Option<&Submitter>,
+    queue: &std::sync::Mutex<ListenQueue>,
+    wake: &tokio::sync::Notify,
+) {
+    let (Some(listen), Some(submitter)) = (listen, submitter) else {
+        return;
+    };
+    if !listen.counts(rules) {
+        return;
+    }
+    if let Ok(mut queue) = queue.lock() {
+        queue.push(&listen, &submitter.services());
+        let _ = queue.save();
+    }
+    wake.notify_one();
+}
+
+/// What the now-playing poller reads for `station`: its metadata API and
+/// the rules for cleaning up the fields, if it has one.
+fn metadata_source(station: &Station) -> Option<(String, MetadataRules)> {
+    let url = station.metadata_url.clone()?;
+    Some((url, station.metadata.clone()))
+}
+
+async fn fetch_now_playing(url: &str, rules: &MetadataRules) -> Option<Track> {
+    let client = tls::client_builder(url)
         .timeout(Duration::from_secs(5))
         .build()
         .ok()?;
     let resp = client.get(url).send().await.ok()?;
     let np: NpResponse = resp.json().await.ok()?;
-    let artist = np.now_playing.song.artist.trim();
-    let title = np.now_playing.song.title.trim();
-    if artist.is_empty() && title.is_empty() {
-        None
-    } else if artist.is_empty() {
-        Some(title.to_string())
+    Track::from_parts(
+        &np.now_playing.song.artist,
+        &np.now_playing.song.title,
+        rules,
+    )
+}
+
+// ─── Player helpers ───────────────────────────────────────────────────────────
+
+/// Persist the current layout preferences to the state file.
+fn save_layout(app_state: &mut AppState, ui_state: &mut UiState) {
+    app_state.layout = ui_state.layout.clone();
+    if let Err(e) = app_state.save() {
+        ui_state.notify(Toast::error(format!("Could not save UI state: {}", e)));
+    }
+}
+
+/// Index of the next station after `from` whose name starts with `letter`,
+/// wrapping around the list (file-manager style type-to-jump).
+fn next_station_with_letter(stations: &[Station], from: usize, letter: char) -> Option<usize> {
+    let letter = letter.to_lowercase().next()?;
+    (1..=stations.len())
+        .map(|offset| (from + offset) % stations.len())
+        .find(|&i| {
+            stations[i]
+                .name
+                .chars()
+                .next()
+                .and_then(|c| c.to_lowercase().next())
+                == Some(letter)
+        })
+}
+
+/// Speed to resume `station` at: remembered per source, 1× for live radio.
+fn remembered_speed(speeds: &HashMap<String, f64>, station: &Station) -> f64 {
+    if station.live {
+        1.0
     } else {
-        Some(format!("{} — {}", artist, title))
+        speeds.get(&station.url).copied().unwrap_or(1.0)
     }
 }
 
-// ─── Player helpers ───────────────────────────────────────────────────────────
+/// Station the current step of `run` plays, and the toast announcing it.
+fn rotation_step(run: &RotationRun, stations: &[Station]) -> (Option<usize>, Toast) {
+    let step = run.step();
+    match step.station_index(stations) {
+        Some(i) => (
+            Some(i),
+            Toast::info(format!(
+                "{}: {} for {} min",
+                run.name(),
+                stations[i].name,
+                step.minutes.max(1)
+            )),
+        ),
+        None => (
+            None,
+            Toast::warning(format!(
+                "{}: no station matches \"{}\"",
+                run.name(),
+                step.station
+            )),
+        ),
+    }
+}
+
+/// Toast after the beats were turned on, off or changed.
+fn beats_toast(beats: &Beats) -> Toast {
+    match beats.label() {
+        Some(label) if beats.binaural() => {
+            Toast::info(format!("Beats: {} — use headphones", label))
+        }
+        Some(label) => Toast::info(format!("Beats: {}", label)),
+        None => Toast::info("Beats off"),
+    }
+}
+
+/// Whether this machine looks offline for `url`: its host does not
+/// resolve, or there is no route to it. A host that refuses or does not
+/// answer is only down, which reconnecting handles. Generated and
+/// non-network sources are never offline.
+async fn network_unavailable(url: &str) -> bool {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let (Some(host), Some(port)) = (parsed.host_str(), parsed.port_or_known_default()) else {
+        return false;
+    };
+    let lookup = tokio::time::timeout(REACHABILITY_TIMEOUT, tokio::net::lookup_host((host, port)));
+    if !matches!(lookup.await, Ok(Ok(_))) {
+        return true;
+    }
+    matches!(
+        tokio::time::timeout(REACHABILITY_TIMEOUT, net::connect(host, port)).await,
+        Ok(Err(e)) if matches!(e.kind(), ErrorKind::NetworkUnreachable | ErrorKind::HostUnreachable)
+    )
+}
 
-/// Kill the current child and spawn a fresh one for `stream_url` at `volume`.
-/// Updates the IPC socket in `volume_control` if needed.
-async fn restart_player(
-    child: &mut tokio::process::Child,
-    volume_control: &Arc<Mutex<VolumeControl>>,
-    stream_url: &str,
-    volume: u32,
-) -> Result<tokio::process::Child, Box<dyn std::error::Error>> {
-    let _ = child.start_kill();
-    let _ = tokio::time::timeout(Duration::from_millis(500), child.wait()).await;
+/// Index of the station a shared session is playing, matched by URL or
+/// name; unknown stations are added for this run only.
+fn shared_station_index(stations: &mut Vec<Station>, session: &SharedSession) -> usize {
+    if let Some(i) = stations
+        .iter()
+        .position(|s| s.url == session.url || s.name == session.station)
+    {
+        return i;
+    }
+    stations.push(session.to_station());
+    stations.len() - 1
+}
+
+/// `lofi_rs prune`: remove stations flagged as likely dead from the config.
+fn prune_stations(yes: bool) -> Result<(), LofiError> {
+    let mut config = Config::load()?;
+    let mut stats = Stats::load();
+    let dead: Vec<String> = config
+        .stations
+        .iter()
+        .filter(|s| stats.is_likely_dead(&s.name))
+        .map(|s| s.name.clone())
+        .collect();
+    if dead.is_empty() {
+        println!(
+            "No station has failed {} times in a row.",
+            LIKELY_DEAD_AFTER
+        );
+        return Ok(());
+    }
+    println!(
+        "Likely dead (failed {}+ times in a row):",
+        LIKELY_DEAD_AFTER
+    );
+    for name in &dead {
+        println!("  {}", name);
+    }
+    if !yes {
+        print!("Remove from the config? [y/N] ");
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            return Ok(());
+        }
+    }
+    let mut removed = 0;
+    for name in &dead {
+        if config.remove_station(name) {
+            stats.stations.remove(name);
+            removed += 1;
+        }
+    }
+    config.save()?;
+    stats.save()?;
+    println!("Removed {} station(s).", removed);
+    Ok(())
+}
 
-    let player_type = volume_control.lock().await.player_type;
-    let (cmd, args, new_socket) = build_player_args(player_type, stream_url, volume);
+/// Actions of the station detail popup, in the order shown.
+#[derive(Clone, Copy)]
+enum DetailAction {
+    Play,
+    EditNotes,
+    Favorite,
+    Delete,
+}
+
+const DETAIL_ACTIONS: [DetailAction; 4] = [
+    DetailAction::Play,
+    DetailAction::EditNotes,
+    DetailAction::Favorite,
+    DetailAction::Delete,
+];
+
+/// The detail popup for `station`: its config fields, then the stream
+/// format while it plays (`playing`), its listening stats and health.
+fn station_detail(
+    station: &Station,
+    stats: &Stats,
+    playing: Option<&PlayerState>,
+    selected: usize,
+    notes: Option<&TextInput>,
+) -> DetailScreen {
+    let mut screen = station.detail_screen();
+    let format = playing.and_then(|p| match (&p.codec, p.bitrate) {
+        (Some(codec), Some(kbps)) => Some(format!("{} {} kbit/s", codec, kbps)),
+        (Some(codec), None) => Some(codec.clone()),
+        (None, Some(kbps)) => Some(format!("{} kbit/s", kbps)),
+        (None, None) => None,
+    });
+    if let Some(format) = format {
+        screen.rows.push(("Format", format));
+    }
+    let s = stats.get(&station.name).cloned().unwrap_or_default();
+    if let Some(at) = Local.timestamp_opt(s.last_played as i64, 0).single() {
+        if s.last_played > 0 {
+            screen
+                .rows
+                .push(("Last played", at.format("%Y-%m-%d %H:%M").to_string()));
+        }
+    }
+    if s.listen_secs > 0 {
+        screen.rows.push((
+            "Listened",
+            format!(
+                "{}h {:02}m",
+                s.listen_secs / 3600,
+                (s.listen_secs % 3600) / 60
+            ),
+        ));
+    }
+    let health = match s.consecutive_failures {
+        0 => "OK".to_string(),
+        n if n >= LIKELY_DEAD_AFTER => format!("likely dead ({} failed sessions)", n),
+        n => format!("{} failed session(s)", n),
+    };
+    screen.rows.push(("Health", health));
+    screen.actions = DETAIL_ACTIONS
+        .iter()
+        .map(|action| match action {
+            DetailAction::Play => "Play",
+            DetailAction::EditNotes => "Edit notes",
+            DetailAction::Favorite if station.favorite => "Unfavorite",
+            DetailAction::Favorite => "Favorite",
+            DetailAction::Delete => "Delete",
+        })
+        .map(str::to_string)
+        .collect();
+    screen.selected = selected.min(DETAIL_ACTIONS.len() - 1);
+    screen.editing = notes.map(|input| (input.text().to_string(), input.cursor()));
+    screen
+}
 
-    if let Some(s) = new_socket {
-        volume_control.lock().await.mpv_socket = Some(s);
+/// What `o` opens: the station homepage, else a search for the track.
+/// `O` (`track_first`) prefers the track search.
+fn link_for(station: &Station, track: Option<&str>, track_first: bool) -> Option<String> {
+    let search = track.map(|t| search_url(station.search_url.as_deref(), t));
+    if track_first {
+        search.or_else(|| station.homepage.clone())
+    } else {
+        station.homepage.clone().or(search)
     }
+}
 
-    Ok(spawn_player(&cmd, &args).await?)
+/// Open `link` in the browser, reporting problems as a toast.
+fn open_link(link: Option<String>, ui_state: &mut UiState) {
+    match link {
+        Some(url) if open_url(&url) => ui_state.notify(Toast::info("Opened in browser")),
+        Some(_) => ui_state.notify(Toast::error("Could not start a browser")),
+        None => ui_state.notify(Toast::error("No homepage or track to open")),
+    }
 }
 
 // ─── Key handling ─────────────────────────────────────────────────────────────
 
-/// Poll for a single key-press event (non-blocking, 100 ms timeout).
-/// Returns `Some((KeyCode, KeyModifiers))` on press, `None` otherwise.
-fn poll_key() -> Option<(KeyCode, KeyModifiers)> {
+/// Without key-release reporting, a hold key counts as released once the
+/// terminal's auto-repeat has been silent for this long.
+const HOLD_RELEASE_TIMEOUT: Duration = Duration::from_millis(600);
+
+/// Held this long, the volume keys move two steps per repeat.
+const VOLUME_ACCEL_AFTER: Duration = Duration::from_secs(1);
+
+/// Minimum spacing of backend volume updates while a volume key repeats.
+const VOLUME_APPLY_INTERVAL: Duration = Duration::from_millis(120);
+
+/// Tracks kept for the two-column layout's history pane.
+const RECENT_TRACKS: usize = 50;
+
+/// How long the startup reachability check waits for the station's host.
+const REACHABILITY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Destructive action held until the confirmation dialog is answered.
+enum ConfirmAction {
+    /// Remove the named station from the config.
+    RemoveStation(String),
+    /// Delete a saved recording.
+    DeleteRecording(PathBuf),
+    /// Pick up a session that crashed where it left off.
+    ResumeSession(Snapshot),
+}
+
+/// Poll for a single key, mouse or paste event (non-blocking, 100 ms
+/// timeout). Key release events only arrive when keyboard enhancement is
+/// enabled.
+fn poll_input() -> Option<Event> {
     if event::poll(Duration::from_millis(100)).unwrap_or(false) {
-        if let Ok(Event::Key(KeyEvent {
-            code,
-            kind: KeyEventKind::Press,
-            modifiers,
-            ..
-        })) = event::read()
-        {
-            return Some((code, modifiers));
+        if let Ok(event @ (Event::Key(_) | Event::Mouse(_) | Event::Paste(_))) = event::read() {
+            return Some(event);
         }
     }
     None
 }
 
+/// Map a key to the momentary level it holds, if any.
+fn hold_level_for(code: KeyCode) -> Option<HoldLevel> {
+    match code {
+        KeyCode::Char('d') | KeyCode::Char('D') => Some(HoldLevel::Duck),
+        KeyCode::Char('b') | KeyCode::Char('B') => Some(HoldLevel::Boost),
+        _ => None,
+    }
+}
+
 // ─── Main ─────────────────────────────────────────────────────────────────────
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut station_index: usize = 0;
-    let mut stream_url: &str = STATIONS[station_index].url;
-    let mut ui_state = UiState::new();
-    ui_state.station_index = station_index;
-
-    // Detect available player: prefer mpv → ffplay → afplay+curl
-    let player_type = match detect_player() {
-        Some(p) => p,
-        None => {
-            eprintln!("Error: No suitable player found");
+async fn main() {
+    let Err(e) = run().await else {
+        return;
+    };
+    eprintln!("Error: {}", e);
+    match e {
+        LofiError::PlayerNotFound => {
             eprintln!("Please install one of the following:");
             if cfg!(target_os = "macos") {
                 eprintln!("  macOS: brew install ffmpeg or brew install mpv");
             } else {
                 eprintln!("  Linux: sudo apt-get install ffmpeg or sudo apt-get install mpv");
             }
-            return Err("Player not found".into());
+        }
+        LofiError::SpawnFailed { .. } => {
+            eprintln!(
+                "Set `backend` in {} to try another player",
+                config_file_hint()
+            );
+        }
+        LofiError::Config(_) => eprintln!("Fix or move {} and start again", config_file_hint()),
+        LofiError::Stream(_) => eprintln!("Check the network connection and try again"),
+        _ => {}
+    }
+    std::process::exit(1);
+}
+
+/// The config file's path for messages, or a description without HOME.
+fn config_file_hint() -> String {
+    config_path().map_or("the config file".to_string(), |p| p.display().to_string())
+}
+
+async fn run() -> Result<(), LofiError> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    match take_instance(&mut args) {
+        Ok(Some(name)) => init_instance(name),
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("Error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    }
+    let (join_url, start) = match parse_args(args) {
+        Ok(Command::Run(start)) => (None, start),
+        Ok(Command::Join { url }) => (Some(url), StartOptions::default()),
+        Ok(Command::ExportLikes { format, output }) => {
+            let text = export_likes(&Likes::load(), format)?;
+            match output {
+                Some(path) => std::fs::write(path, text)?,
+                None => print!("{}", text),
+            }
+            return Ok(());
+        }
+        Ok(Command::Import { path, on_duplicate }) => {
+            import::add_to_config(import::read_playlist(&path)?, on_duplicate)?;
+            return Ok(());
+        }
+        Ok(Command::Add {
+            query,
+            on_duplicate,
+        }) => {
+            import::add_from_radio_browser(&query, on_duplicate).await?;
+            return Ok(());
+        }
+        Ok(Command::Probe { target }) => {
+            probe::run(&target).await?;
+            return Ok(());
+        }
+        Ok(Command::Prune { yes }) => {
+            prune_stations(yes)?;
+            return Ok(());
+        }
+        Ok(Command::Connect {
+            address,
+            token,
+            action,
+        }) => {
+            connect::run(&address, token, action, &Config::load()?.elapsed_format).await?;
+            return Ok(());
+        }
+        Ok(Command::Status { json, watch }) => {
+            status::run(json, watch, &Config::load()?.elapsed_format).await?;
+            return Ok(());
+        }
+        Ok(Command::Report { period, output }) => {
+            report::run(
+                period,
+                output
+                    .as_ref()
+                    .map(|(path, format)| (path.as_path(), *format)),
+            )?;
+            return Ok(());
+        }
+        Ok(Command::Backup { path }) => {
+            backup::backup(&path)?;
+            return Ok(());
+        }
+        Ok(Command::Restore { path, yes }) => {
+            backup::restore(&path, yes)?;
+            return Ok(());
+        }
+        Ok(Command::Noise { kind, volume }) => {
+            noise::play(kind, volume)?;
+            return Ok(());
+        }
+        Ok(Command::Beats {
+            beat_hz,
+            carrier_hz,
+            mode,
+            volume,
+        }) => {
+            beats::play(carrier_hz, beat_hz, mode, volume)?;
+            return Ok(());
+        }
+        Ok(Command::AuthLastfm) => {
+            let mut config = Config::load()?;
+            lastfm::authorize(&mut config).await?;
+            return Ok(());
+        }
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("Error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
         }
     };
 
-    let mut volume_control = VolumeControl::new(player_type);
+    let mut config = Config::load()?;
+    config::init_dirs(&config);
+    net::init(config.ip_family);
+    tls::init(&config.stations);
+    let mut app_state = AppState::load();
+    let mut stats = Stats::load();
+    let mut likes = Likes::load();
+    let mut stations = config.stations.clone();
+    sort_stations(
+        &mut stations,
+        app_state.station_sort,
+        &stats,
+        &config.stations,
+    );
+    if config.noise_stations {
+        noise::add_stations(&mut stations);
+    }
+    // Command-line flags win over the config
+    let startup_station = start.station.as_ref().or(config.startup_station.as_ref());
+    let mut station_index = match startup_station {
+        Some(name) => best_match(name, stations.iter().map(|s| s.name.as_str()))
+            .ok_or_else(|| format!("No station matches \"{}\"", name))?,
+        None => 0,
+    };
+    // Without a terminal to draw on (piped, CI, some IDE consoles) the player
+    // runs headless, printing status changes and notifications as lines
+    let mut headless = !std::io::stdout().is_terminal();
+    // Headless, nothing could press F8 to start it
+    let start_paused = !headless && start.paused.unwrap_or(config.start_paused);
+
+    // ListenAlong: start on the shared station and follow it
+    let (share_tx, mut share_rx) = tokio::sync::mpsc::unbounded_channel::<ShareEvent>();
+    if let Some(url) = join_url {
+        let session = fetch_session(&url)
+            .await
+            .map_err(|e| format!("Could not read shared session: {}", e))?;
+        station_index = shared_station_index(&mut stations, &session);
+        spawn_follower(url, session, share_tx.clone());
+    }
+    let mut ui_state = UiState::new();
+    ui_state.headless = headless;
+    ui_state.power_saver = config.power_saver.active(false);
+    ui_state.low_data = start.low_data;
+    let mut stream_url = stations[station_index]
+        .stream_url(ui_state.low_bitrate())
+        .to_string();
+    ui_state.station_sort = app_state.station_sort;
+    ui_state.layout = app_state.layout.clone();
+    if !app_state.welcomed && !headless {
+        ui_state.welcome = Some(
+            config_path()
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| "~/.config/lofi_rs/config.toml".to_string()),
+        );
+    }
+    // Upgraded since the last run: what changed, once. A first run has its
+    // cheat sheet instead, and headless runs leave it for the next terminal
+    let version = env!("CARGO_PKG_VERSION");
+    if app_state.welcomed && !headless && app_state.last_version.as_deref() != Some(version) {
+        let releases = changelog::since(app_state.last_version.as_deref().unwrap_or("0"));
+        if releases.is_empty() {
+            app_state.last_version = Some(version.to_string());
+            let _ = app_state.save();
+        } else {
+            ui_state.whats_new = Some(releases);
+        }
+    }
+    ui_state.theme = theme_by_name(ui_state.layout.theme.as_deref().unwrap_or(&config.theme));
+    ui_state.color_support = detect_color_support();
+    ui_state.icons = icon_set(config.icons);
+    ui_state.elapsed_format = config.elapsed_format.clone();
+    ui_state.likely_dead = stats.likely_dead();
 
-    let (player_cmd, player_args, socket_path) =
-        build_player_args(player_type, stream_url, volume_control.volume);
+    // Detect available player: configured backend, else mpv → ffplay → afplay+curl,
+    // else the built-in noise generator with its stations only
+    let mut offline_toast = None;
+    let mut player_type = match detect_player(config.backend) {
+        Some(p) => p,
+        None if noise::pcm_output_command().is_some() => {
+            stations.clear();
+            station_index = noise::add_stations(&mut stations);
+            stream_url = stations[station_index].url.clone();
+            offline_toast = Some("No player found — built-in noise only");
+            PlayerType::Builtin
+        }
+        None => return Err(LofiError::PlayerNotFound),
+    };
+    // Offline: checked while the station starts; `Event_::Offline` moves
+    // to the generated stations, added for this run
+    let (offline_tx, mut offline_rx) = tokio::sync::mpsc::unbounded_channel::<usize>();
+    if offline_toast.is_none() && !start_paused {
+        let (url, index) = (stream_url.clone(), station_index);
+        tokio::spawn(async move {
+            if network_unavailable(&url).await {
+                let _ = offline_tx.send(index);
+            }
+        });
+    }
+    ui_state.station_index = station_index;
 
-    if let Some(socket) = socket_path {
-        volume_control.mpv_socket = Some(socket);
+    let mut volume_control = VolumeControl::new(player_type);
+    volume_control.volume = start.volume.unwrap_or(config.default_volume);
+    apply_config(&mut volume_control, &config);
+    volume_control.low_data = start.low_data;
+    if let Some(text) = offline_toast {
+        ui_state.notify(Toast::info(text));
+    }
+    if start_paused {
+        ui_state.notify(Toast::sticky("Not playing — F8 to start"));
     }
 
-    let volume_control = Arc::new(Mutex::new(volume_control));
+    // Chime for track changes: mixed in-process for the PCM pipeline,
+    // played from a temp WAV by the other backends
+    let chime_wav = write_chime_wav();
+    // The player process lives in its own task, driven by commands
+    let (player, mut player_rx) =
+        spawn_supervisor(volume_control, chime_samples(), chime_wav.clone());
 
-    // Set up terminal
-    enable_raw_mode()?;
-    {
+    // Set up terminal; raw mode still fails without a controlling TTY
+    if !headless && enable_raw_mode().is_err() {
+        headless = true;
+        ui_state.headless = true;
+        ui_state.welcome = None;
+        ui_state.whats_new = None;
+    }
+    if !headless {
         let mut stdout = std::io::stdout();
-        let _ = execute!(stdout, Hide, Clear(ClearType::All), MoveTo(0, 0));
+        let _ = execute!(
+            stdout,
+            Hide,
+            Clear(ClearType::All),
+            MoveTo(0, 0),
+            EnableMouseCapture,
+            EnableBracketedPaste
+        );
+    }
+    // Key-release events make the duck/boost keys exact; otherwise we fall
+    // back to watching for the end of the terminal's auto-repeat.
+    let key_release_events = !headless && supports_keyboard_enhancement().unwrap_or(false);
+    if key_release_events {
+        let _ = execute!(
+            std::io::stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        );
     }
-    let stdout = std::io::stdout();
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    terminal.clear()?;
+    let backend = CrosstermBackend::new(std::io::stdout());
+    let mut terminal = if headless {
+        // Never drawn; a fixed viewport keeps ratatui from asking its size
+        let viewport = Viewport::Fixed(Rect::new(0, 0, 80, 24));
+        println!("No terminal — running headless; Ctrl+C or SIGTERM quits");
+        Terminal::with_options(backend, TerminalOptions { viewport })?
+    } else {
+        let mut terminal = Terminal::new(backend)?;
+        terminal.clear()?;
+        terminal
+    };
 
-    // Spawn player
-    let mut child = spawn_player(&player_cmd, &player_args).await?;
+    // Start playback; when starting paused nothing connects until F8
+    if !start_paused {
+        player.send(PlayerCommand::Play(stream_url.clone())).await?;
+    }
 
     // Initial UI render
     {
-        let vol = volume_control.lock().await;
-        ui_state.volume = vol.volume;
-        ui_state.muted = vol.muted;
+        let levels = player.state();
+        ui_state.volume = levels.volume;
+        ui_state.applied_volume = levels.applied_volume;
+        ui_state.muted = levels.muted;
+        ui_state.backend = player_type.name();
+        ui_state.capabilities = levels.capabilities;
     }
-    draw_ui(&mut terminal, &ui_state, STATIONS);
+    draw_ui(&mut terminal, &ui_state, &stations);
 
     let start_time = Instant::now();
 
-    // Ctrl+C signal (unix only)
-    #[cfg(unix)]
-    let mut ctrl_c =
-        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())?;
+    // Interrupt and suspend signals; platform differences live in `signals`
+    let mut signals = Signals::new()?;
+    let mut suspend_requested = false;
 
     // Now-playing background poller
-    let now_playing_state: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    let now_playing_state: Arc<Mutex<Option<Track>>> = Arc::new(Mutex::new(None));
     let track_changed = Arc::new(tokio::sync::Notify::new());
-    let (md_tx, md_rx) =
-        tokio::sync::watch::channel::<Option<&'static str>>(STATIONS[station_index].metadata_url);
+    let (md_tx, md_rx) = tokio::sync::watch::channel(metadata_source(&stations[station_index]));
     {
         let np = now_playing_state.clone();
         let tc = track_changed.clone();
         let mut rx = md_rx;
         tokio::spawn(async move {
-            let mut last_track: Option<String> = None;
+            let mut last_track: Option<Track> = None;
             loop {
-                let url = *rx.borrow();
-                let result = if let Some(u) = url {
-                    fetch_now_playing(u).await
+                let source = rx.borrow().clone();
+                let result = if let Some((u, rules)) = source {
+                    fetch_now_playing(&u, &rules).await
                 } else {
                     None
                 };
@@ -193,280 +827,2515 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         });
     }
 
-    // 1 Hz UI ticker
-    let mut ui_tick = tokio::time::interval(Duration::from_secs(1));
-    ui_tick.tick().await; // consume immediate first tick
+    // Suspend/resume watcher
+    let (power_tx, mut power_rx) = tokio::sync::mpsc::unbounded_channel::<PowerEvent>();
+    spawn_power_monitor(power_tx);
+    // Set while the machine sleeps so the dead child is not respawned.
+    let mut suspended = false;
+
+    // Output device watcher (headphone unplug protection)
+    let (output_tx, mut output_rx) = tokio::sync::mpsc::unbounded_channel::<OutputEvent>();
+    spawn_output_monitor(output_tx.clone());
+
+    // Other MPRIS players: the one playing, shown in the status bar, and
+    // audio focus taken from them while lofi_rs is audible
+    let (others_tx, mut others_rx) = tokio::sync::mpsc::unbounded_channel::<Option<OtherPlayer>>();
+    mpris::spawn_monitor(config.mpris.clone(), others_tx);
+    let mut focus = AudioFocus::new(config.mpris.clone());
+    // Estimated data use against the monthly cap
+    let mut meter = DataMeter::new(config.bandwidth.clone());
+    // Set when playback was paused because the output device went away.
+    let mut auto_paused = false;
+
+    // Playback speed remembered per on-demand source
+    let mut speed_memory: HashMap<String, f64> = HashMap::new();
+
+    // Reconnect supervisor for the current station; `stopped` once it gives up
+    let mut supervisor =
+        ReconnectSupervisor::new(stations[station_index].reconnect_policy(config.reconnect));
+    // Why the stream failed, asked in the background before each attempt
+    let (recheck_tx, mut recheck_rx) = tokio::sync::mpsc::unbounded_channel::<Recheck>();
+    let mut stopped = start_paused;
+    // Started paused and no station played yet
+    let mut waiting_for_play = start_paused;
+
+    // Station switch requested by a key handler, applied after the match
+    let mut switch_to: Option<usize> = None;
+    // Set from sending `Switch` to the player task until it reports the
+    // new player started. Switches requested meanwhile wait in `queued_switch`.
+    let mut pending_switch = false;
+    let mut queued_switch: Option<usize> = None;
+    // Stations to go back to with `u`; a switch to `undo_target` is the undo
+    // itself and pushes nothing
+    let mut undo = UndoStack::from_log(&history::load(), &stations[station_index].name);
+    let mut undo_target: Option<usize> = None;
+    // Backend swap requested by `k` or the palette, applied after the match
+    let mut backend_to: Option<PlayerType> = None;
+    stats.record_play(&stations[station_index].name);
+    let _ = stats.save();
+    let _ = history::record(Some(&stations[station_index].name));
+    let mut station_started = Instant::now();
+    // Last status published for `lofi_rs status`, and when the session
+    // was last written down for crash recovery
+    let mut published_status: Option<Status> = None;
+    let mut snapshot_saved: Option<Instant> = None;
+    let mut summary = SessionSummary::new(config.elapsed_format.clone());
+    summary.station(&stations[station_index].name);
+
+    // ListenAlong publishing, toggled with `p`
+    let mut sharing = false;
+    let mut share_url: Option<String> = None;
+
+    // Settings screen cursor, `Some` while the screen is open
+    let mut settings_view: Option<SettingsView> = None;
+
+    // Hold key currently down and when it was last seen (duck/boost)
+    let mut held_key: Option<(KeyCode, Instant)> = None;
+
+    // Web remote, when enabled
+    let (remote_tx, mut remote_rx) = tokio::sync::mpsc::unbounded_channel::<RemoteCommand>();
+    let remote_view: SharedView = Default::default();
+    if config.remote.enabled {
+        if let Err(e) = remote::spawn_server(&config.remote, remote_view.clone(), remote_tx).await {
+            ui_state.notify(Toast::error(format!("Web remote: {}", e)));
+        }
+    }
+
+    // radio-browser.info lookups for the station detail popup
+    let (info_tx, mut info_rx) = tokio::sync::mpsc::unbounded_channel::<(String, StationInfo)>();
+    let mut info_requested: HashSet<String> = HashSet::new();
+
+    // Opt-in MusicBrainz lookups of the playing track, cached on disk
+    let (mb_tx, mut mb_rx) = tokio::sync::mpsc::unbounded_channel::<(String, Option<Recording>)>();
+    let mut mb_cache = MusicBrainzCache::load();
+    let mut mb_requested: HashSet<String> = HashSet::new();
+    cache::spawn_prune();
+
+    // Scrobbling of the track being heard, once Last.fm or ListenBrainz is
+    // set up. Listens wait in an on-disk queue until a service takes them.
+    let submitter = Submitter::new(&config);
+    let mut listen: Option<Listen> = None;
+    let listen_queue = Arc::new(std::sync::Mutex::new(ListenQueue::load()));
+    let queue_wake = Arc::new(tokio::sync::Notify::new());
+    let (scrobble_tx, mut scrobble_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    if let Some(submitter) = submitter.clone() {
+        spawn_queue(
+            listen_queue.clone(),
+            submitter,
+            queue_wake.clone(),
+            scrobble_tx,
+        );
+    }
+
+    // Stream recording started from the palette
+    let mut recorder: Option<Recorder> = None;
+    let (record_tx, mut record_rx) = tokio::sync::mpsc::unbounded_channel::<RecordEvent>();
+
+    // Opt-in update check, cached for a day
+    let (update_tx, mut update_rx) = tokio::sync::mpsc::unbounded_channel::<Release>();
+    if config.check_updates {
+        spawn_check(update_tx);
+    }
+
+    // Output picker (e), and the device it switched to, whose change
+    // notice must not pause playback
+    let mut output_picker: Option<OutputPicker> = None;
+    let mut recordings_view: Option<RecordingsView> = None;
+    let mut expected_output: Option<String> = None;
+
+    // Ambient noise layers mixed over the stream (n), started with playback
+    let mut ambient = AmbientMixer::new(&app_state.ambient);
+    // Binaural beats (g), also layered over the stream
+    let mut beats = Beats::new(config.beats.clone());
+    if !start_paused {
+        if let Err(e) = ambient.start().and_then(|()| beats.start()) {
+            ui_state.notify(Toast::error(e));
+        }
+    }
+    ui_state.beats = beats.label();
+    // Mixer pane (v) and the second stream it can play
+    let mut mixer = Mixer::new(player_type, config.sandbox_player);
+
+    // Command palette (Ctrl+P), and keys it asks the loop to replay
+    let mut palette: Option<Palette> = None;
+    let mut palette_history: Vec<String> = Vec::new();
+    let mut injected_key: Option<KeyCode> = None;
+    // Commands the palette runs as if typed, one per loop turn, starting
+    // with the `[startup]` actions
+    let mut queued_commands: VecDeque<String> = config.startup.actions.iter().cloned().collect();
+    // Action waiting for a yes in the confirmation dialog
+    let mut confirm: Option<ConfirmAction> = None;
+    // The last run crashed: offer to resume it, without asking when headless
+    if let Some(previous) = Snapshot::left_behind() {
+        if headless {
+            println!("Resuming previous session: {}", previous.describe());
+            queued_commands.extend(previous.commands());
+        } else {
+            ui_state.confirm = Some(ConfirmScreen {
+                title: "Resume session".to_string(),
+                question: format!(
+                    "lofi_rs did not quit normally last time. Resume {}?",
+                    previous.describe()
+                ),
+            });
+            confirm = Some(ConfirmAction::ResumeSession(previous));
+        }
+    }
+    // Notes of the station in the detail popup, while being edited
+    let mut notes_input: Option<TextInput> = None;
+    let mut sleep_at: Option<Instant> = None;
+    // Station rotation playing, and one requested by `w` or the palette
+    let mut rotation: Option<RotationRun> = None;
+    let mut rotation_to: Option<Rotation> = None;
+
+    // Volume key repeat: direction (up), first and latest press
+    let mut volume_repeat: Option<(bool, Instant, Instant)> = None;
+    let mut last_volume_apply = Instant::now()
+        .checked_sub(VOLUME_APPLY_INTERVAL)
+        .unwrap_or_else(Instant::now);
+    let mut volume_pending = false;
+    // The volume slider is being dragged with the mouse
+    let mut dragging_volume = false;
+
+    // UI ticker: elapsed time, sleep timer, stall checks
+    let mut ticker = Ticker::new(Duration::from_millis(config.tick_ms), ui_state.power_saver);
+
+    // Night mode by the `[night]` schedule, or toggled with `z`
+    let mut night = NightMode::new(config.night.clone(), chrono::Local::now().time());
+    // Bitrate variant being played, see `UiState::low_bitrate`
+    let mut low_bitrate = ui_state.low_bitrate();
 
     // ─── Event loop ──────────────────────────────────────────────────────────
     loop {
-        // Shared select arms (platform-independent)
-        let key_future = tokio::task::spawn_blocking(poll_key);
+        // Dismiss expired toasts
+        if ui_state.notifications.expire() {
+            draw_ui(&mut terminal, &ui_state, &stations);
+        }
 
-        // Platform-specific Ctrl+C arm is handled via cfg blocks below.
-        // All other arms are identical — we use a macro-free approach by
-        // extracting the common logic into helper closures/functions above.
+        // Flush a volume change held back while the key was repeating
+        if volume_pending && last_volume_apply.elapsed() >= VOLUME_APPLY_INTERVAL {
+            player.send(PlayerCommand::PushVolume).await?;
+            last_volume_apply = Instant::now();
+            volume_pending = false;
+            ui_state.applied_volume = player.state().applied_volume;
+            draw_ui(&mut terminal, &ui_state, &stations);
+        }
 
-        enum Event_ {
-            TrackChanged,
-            ChildExited,
-            Key(KeyCode, KeyModifiers),
-            Tick,
-            #[cfg(unix)]
-            CtrlC,
+        // Release a duck/boost hold once the key's auto-repeat stops
+        if let Some((_, last_seen)) = held_key {
+            if !key_release_events && last_seen.elapsed() > HOLD_RELEASE_TIMEOUT {
+                held_key = None;
+                player.send(PlayerCommand::Hold(None)).await?;
+            }
         }
 
-        let event = {
-            #[cfg(unix)]
-            {
-                tokio::select! {
-                    _ = track_changed.notified(), if matches!(player_type, PlayerType::Ffplay) => Event_::TrackChanged,
-                    _ = child.wait() => Event_::ChildExited,
-                    _ = ctrl_c.recv() => Event_::CtrlC,
-                    res = key_future => {
-                        if let Ok(Some((code, mods))) = res { Event_::Key(code, mods) } else { continue }
-                    }
-                    _ = ui_tick.tick() => Event_::Tick,
+        // Sleep timer ran out: pause like F8
+        if sleep_at.is_some_and(|at| Instant::now() >= at) {
+            sleep_at = None;
+            ui_state.sleep_left = None;
+            if !waiting_for_play && !player.state().muted {
+                injected_key = Some(KeyCode::F(8));
+            }
+        }
+
+        // Night mode came on or went off, by the clock or by hand
+        let night_on = night.active(chrono::Local::now().time());
+        if night_on != ui_state.night {
+            ui_state.night = night_on;
+            let profile = night.profile();
+            player
+                .send(PlayerCommand::Night(night_on.then_some(profile)))
+                .await?;
+            ui_state.volume = player.state().volume;
+            ui_state.applied_volume = player.state().applied_volume;
+            ui_state.notify(Toast::info(if night_on {
+                format!("Night mode — volume up to {}%", profile.max_volume)
+            } else {
+                "Night mode off".to_string()
+            }));
+            draw_ui(&mut terminal, &ui_state, &stations);
+        }
+
+        // Power saver, data cap or low-data mode moved the station to its
+        // other bitrate variant, if it has one
+        if ui_state.low_bitrate() != low_bitrate {
+            low_bitrate = ui_state.low_bitrate();
+            let station = &stations[station_index];
+            if station.low_bitrate_url.is_some() {
+                stream_url = station.stream_url(low_bitrate).to_string();
+                if !stopped && !suspended {
+                    player.send(PlayerCommand::Play(stream_url.clone())).await?;
                 }
             }
-            #[cfg(not(unix))]
-            {
-                tokio::select! {
-                    _ = track_changed.notified(), if matches!(player_type, PlayerType::Ffplay) => Event_::TrackChanged,
-                    _ = child.wait() => Event_::ChildExited,
-                    res = key_future => {
-                        if let Ok(Some((code, mods))) = res { Event_::Key(code, mods) } else { continue }
-                    }
-                    _ = ui_tick.tick() => Event_::Tick,
+        }
+
+        // Rotation step ran its time: on to the next station
+        if let Some(run) = rotation.as_mut().filter(|r| r.is_due()) {
+            if run.advance() {
+                let (target, toast) = rotation_step(run, &stations);
+                switch_to = target.filter(|&i| i != station_index || stopped);
+                ui_state.notify(toast);
+            } else {
+                ui_state.notify(Toast::info(format!("Rotation {} finished", run.name())));
+                rotation = None;
+            }
+            ui_state.rotation = rotation.as_ref().map(RotationRun::label);
+        }
+
+        enum Event_ {
+            TrackChanged,
+            Player(PlayerEvent),
+            /// The reconnect backoff is over.
+            Retry,
+            Rechecked(Recheck),
+            /// No network for the station at this index at startup.
+            Offline(usize),
+            Power(PowerEvent),
+            Output(OutputEvent),
+            Share(ShareEvent),
+            StationInfo(String, StationInfo),
+            Update(Release),
+            Recording(String, Option<Recording>),
+            ScrobbleFailed(String),
+            Record(RecordEvent),
+            Remote(RemoteCommand),
+            Others(Option<OtherPlayer>),
+            Key(KeyCode, KeyModifiers, KeyEventKind),
+            Mouse(MouseEvent),
+            Paste(String),
+            Tick(Tick),
+            Signal(SignalEvent),
+        }
+
+        let event = if let Some(code) = injected_key.take() {
+            // Key sent by the command palette or the sleep timer
+            Event_::Key(code, KeyModifiers::NONE, KeyEventKind::Press)
+        } else if let Some(command) = (palette.is_none()
+            && confirm.is_none()
+            && ui_state.welcome.is_none()
+            && ui_state.whats_new.is_none())
+        .then(|| queued_commands.pop_front())
+        .flatten()
+        {
+            // Entered into a palette of its own
+            palette = Some(Palette::queued(&command));
+            Event_::Key(KeyCode::Enter, KeyModifiers::NONE, KeyEventKind::Press)
+        } else {
+            let key_future = async {
+                if headless {
+                    std::future::pending().await
+                } else {
+                    tokio::task::spawn_blocking(poll_input).await
                 }
+            };
+            let retry_at = supervisor.retry_at();
+            tokio::select! {
+                _ = track_changed.notified(), if matches!(player_type, PlayerType::Ffplay) => Event_::TrackChanged,
+                Some(ev) = player_rx.recv() => Event_::Player(ev),
+                _ = tokio::time::sleep_until(retry_at.unwrap_or_else(Instant::now).into()), if retry_at.is_some() => Event_::Retry,
+                Some(outcome) = recheck_rx.recv() => Event_::Rechecked(outcome),
+                Some(index) = offline_rx.recv() => Event_::Offline(index),
+                Some(ev) = power_rx.recv() => Event_::Power(ev),
+                Some(ev) = output_rx.recv() => Event_::Output(ev),
+                Some(ev) = share_rx.recv() => Event_::Share(ev),
+                Some((name, info)) = info_rx.recv() => Event_::StationInfo(name, info),
+                Some(release) = update_rx.recv() => Event_::Update(release),
+                Some((key, recording)) = mb_rx.recv() => Event_::Recording(key, recording),
+                Some(e) = scrobble_rx.recv() => Event_::ScrobbleFailed(e),
+                Some(ev) = record_rx.recv() => Event_::Record(ev),
+                Some(command) = remote_rx.recv() => Event_::Remote(command),
+                Some(other) = others_rx.recv() => Event_::Others(other),
+                signal = signals.recv() => Event_::Signal(signal),
+                res = key_future => match res {
+                    Ok(Some(Event::Key(KeyEvent { code, modifiers, kind, .. }))) => Event_::Key(code, modifiers, kind),
+                    Ok(Some(Event::Mouse(mouse))) => Event_::Mouse(mouse),
+                    Ok(Some(Event::Paste(text))) => Event_::Paste(text),
+                    _ => continue,
+                },
+                tick = ticker.tick() => Event_::Tick(tick),
             }
         };
 
         match event {
             // ── ffplay track-boundary workaround ──────────────────────────
             Event_::TrackChanged => {
-                let vol = volume_control.lock().await.volume;
-                child = restart_player(&mut child, &volume_control, stream_url, vol).await?;
+                player.send(PlayerCommand::Play(stream_url.clone())).await?;
             }
 
-            // ── child exited unexpectedly ─────────────────────────────────
-            Event_::ChildExited => {
-                tokio::time::sleep(Duration::from_millis(500)).await;
-                let vol = volume_control.lock().await.volume;
-                child = restart_player(&mut child, &volume_control, stream_url, vol).await?;
+            // ── player exited unexpectedly ────────────────────────────────
+            Event_::Player(PlayerEvent::Exited) if !suspended && !stopped && !pending_switch => {
+                // The attempt is made by `Event_::Retry` once the delay is up
+                if supervisor.on_failure().is_none() {
+                    stopped = true;
+                    let name = &stations[station_index].name;
+                    stats.record_failure(name);
+                    let _ = stats.save();
+                    ui_state.likely_dead = stats.likely_dead();
+                    let hint = if stats.is_likely_dead(name) {
+                        "r to retry, x to remove"
+                    } else {
+                        "r to retry"
+                    };
+                    ui_state.notify(Toast::sticky(format!(
+                        "{} unreachable after {} attempts — {}",
+                        name,
+                        supervisor.failures(),
+                        hint
+                    )));
+                    draw_ui(&mut terminal, &ui_state, &stations);
+                    continue;
+                }
+                ui_state.notify(Toast::warning(format!(
+                    "Reconnecting… (attempt {})",
+                    supervisor.failures()
+                )));
+                draw_ui(&mut terminal, &ui_state, &stations);
             }
+            // Killed on purpose, or gone while a switch replaces it
+            Event_::Player(PlayerEvent::Exited) => {}
 
-            // ── Ctrl+C (unix) ─────────────────────────────────────────────
-            #[cfg(unix)]
-            Event_::CtrlC => {
-                let _ = child.kill().await;
-                break;
+            // ── reconnect attempt after the backoff ───────────────────────
+            Event_::Retry => {
+                supervisor.take_retry();
+                if suspended || stopped || pending_switch {
+                    continue;
+                }
+                // The server and a refresh command may take a while
+                let (station, url) = (stations[station_index].clone(), stream_url.clone());
+                let tx = recheck_tx.clone();
+                tokio::spawn(async move {
+                    let _ = tx.send(recheck(&station, &url).await);
+                });
             }
-
-            // ── 1-second UI tick ──────────────────────────────────────────
-            Event_::Tick => {
-                ui_state.elapsed = start_time.elapsed();
-                ui_state.now_playing = now_playing_state.lock().await.clone();
-                draw_ui(&mut terminal, &ui_state, STATIONS);
+            Event_::Rechecked(outcome) => {
+                if !supervisor.take_check() || suspended || stopped || pending_switch {
+                    continue;
+                }
+                let name = &stations[station_index].name;
+                match outcome {
+                    Recheck::Retry => {}
+                    Recheck::Moved(url) => {
+                        ui_state.notify(Toast::info(format!("{} moved to {}", name, url)));
+                        stream_url = url;
+                    }
+                    Recheck::Refreshed(url) => {
+                        stations[station_index].url = url.clone();
+                        stream_url = url;
+                    }
+                    Recheck::RefreshFailed(e) => {
+                        ui_state.notify(Toast::error(format!("Stream URL refresh failed: {}", e)));
+                    }
+                    Recheck::Refused(status, hint) => {
+                        stopped = true;
+                        ui_state.notify(Toast::sticky(format!(
+                            "{} refused (HTTP {}): {}",
+                            name, status, hint
+                        )));
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                        continue;
+                    }
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+                player.send(PlayerCommand::Play(stream_url.clone())).await?;
+                supervisor.on_spawn();
             }
 
-            // ── Keyboard ──────────────────────────────────────────────────
-            Event_::Key(key_code, modifiers) => {
-                match key_code {
-                    // Volume up
-                    KeyCode::F(11) | KeyCode::Up => {
-                        let vol = {
-                            let mut vc = volume_control.lock().await;
-                            if !vc.muted {
-                                vc.increase_volume();
-                            }
-                            vc.volume
-                        };
-                        let needs_restart = volume_control
-                            .lock()
-                            .await
-                            .apply_volume(&mut child)
-                            .await
-                            .is_err();
-                        if needs_restart {
-                            child =
-                                restart_player(&mut child, &volume_control, stream_url, vol)
-                                    .await?;
-                        }
-                        let vc = volume_control.lock().await;
-                        ui_state.volume = vc.volume;
-                        ui_state.muted = vc.muted;
-                        draw_ui(&mut terminal, &ui_state, STATIONS);
-                    }
+            // ── no network at startup ─────────────────────────────────────
+            Event_::Offline(index) => {
+                // Unless another station was picked, or playback stopped
+                if index != station_index || stopped || pending_switch {
+                    continue;
+                }
+                switch_to = Some(noise::add_stations(&mut stations));
+                ui_state.notify(Toast::info("Offline — playing brown noise"));
+            }
 
-                    // Volume down
-                    KeyCode::F(10) | KeyCode::Down => {
-                        let vol = {
-                            let mut vc = volume_control.lock().await;
-                            if !vc.muted {
-                                vc.decrease_volume();
-                            }
-                            vc.volume
-                        };
-                        let needs_restart = volume_control
-                            .lock()
-                            .await
-                            .apply_volume(&mut child)
-                            .await
-                            .is_err();
-                        if needs_restart {
-                            child =
-                                restart_player(&mut child, &volume_control, stream_url, vol)
-                                    .await?;
-                        }
-                        let vc = volume_control.lock().await;
-                        ui_state.volume = vc.volume;
-                        ui_state.muted = vc.muted;
-                        draw_ui(&mut terminal, &ui_state, STATIONS);
-                    }
+            // ── levels or buffering changed outside lofi_rs (mpv) ─────────
+            Event_::Player(PlayerEvent::Changed) => {
+                let state = player.state();
+                ui_state.volume = state.volume;
+                ui_state.applied_volume = state.applied_volume;
+                ui_state.muted = state.muted;
+                ui_state.buffering = state.buffering;
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            // A new stream title goes through the tick's now-playing handling
+            Event_::Player(PlayerEvent::Title) => ticker.reset_immediately(),
 
-                    // Previous station
-                    KeyCode::F(7) | KeyCode::Left => {
-                        let (vol, is_muted) = {
-                            let vc = volume_control.lock().await;
-                            (vc.volume, vc.muted)
-                        };
-                        station_index = if station_index == 0 {
-                            STATIONS.len() - 1
-                        } else {
-                            station_index - 1
-                        };
-                        stream_url = STATIONS[station_index].url;
-                        let _ = md_tx.send(STATIONS[station_index].metadata_url);
-                        *now_playing_state.lock().await = None;
+            // ── system suspend / resume ───────────────────────────────────
+            Event_::Power(PowerEvent::Sleep) => {
+                if !suspended {
+                    suspended = true;
+                    let _ = player.send(PlayerCommand::Stop).await;
+                    ambient.stop();
+                    beats.stop();
+                    mixer.pause();
+                }
+            }
+            Event_::Power(PowerEvent::Battery(on_battery)) => {
+                let saver = config.power_saver.active(on_battery);
+                if saver == ui_state.power_saver {
+                    continue;
+                }
+                ui_state.power_saver = saver;
+                ticker.set_power_saver(saver);
+                ui_state.notify(Toast::info(if saver {
+                    "Power saver on"
+                } else {
+                    "Power saver off"
+                }));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Power(PowerEvent::Wake) => {
+                suspended = false;
+                if waiting_for_play {
+                    continue;
+                }
+                stopped = false;
+                supervisor.on_spawn();
+                let _ = ambient.start();
+                let _ = beats.start();
+                let _ = mixer.resume().await;
+                // The old connection is dead either way; reconnect from scratch.
+                player.send(PlayerCommand::Play(stream_url.clone())).await?;
+            }
 
-                        child =
-                            restart_player(&mut child, &volume_control, stream_url, vol).await?;
+            // ── output device changed (headphones unplugged, BT drop) ─────
+            Event_::Output(OutputEvent::DeviceChanged { from, to }) => {
+                if !stopped && !suspended {
+                    player.send(PlayerCommand::ReloadOutput).await?;
+                }
+                // Switched from the picker: keep playing
+                if expected_output.take().is_some_and(|id| id == to) {
+                    continue;
+                }
+                if !player.state().muted {
+                    player.send(PlayerCommand::Pause(true)).await?;
+                    auto_paused = true;
+                    ui_state.notify(Toast::sticky(format!(
+                        "Output changed: {} → {} — paused (r to resume)",
+                        from, to
+                    )));
+                    let levels = player.state();
+                    ui_state.volume = levels.volume;
+                    ui_state.applied_volume = levels.applied_volume;
+                    ui_state.muted = levels.muted;
+                    draw_ui(&mut terminal, &ui_state, &stations);
+                }
+            }
 
-                        if is_muted {
-                            volume_control.lock().await.muted = true;
-                            let _ = volume_control
-                                .lock()
-                                .await
-                                .apply_mute(&mut child)
-                                .await;
-                        }
-                        ui_state.station_index = station_index;
-                        draw_ui(&mut terminal, &ui_state, STATIONS);
+            // ── Bluetooth device picked in the output picker connected ────
+            Event_::Output(OutputEvent::BluetoothReady(name, result)) => {
+                let result = match result {
+                    Ok(sink) => set_default_output(&sink).await.map(|()| sink),
+                    Err(e) => Err(e),
+                };
+                ui_state.notify(match result {
+                    Ok(sink) => {
+                        expected_output = Some(sink);
+                        Toast::info(format!("Output: {}", name))
                     }
+                    Err(e) => Toast::error(format!("Bluetooth: {}", e)),
+                });
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
 
-                    // Next station
-                    KeyCode::F(9) | KeyCode::Right => {
-                        let (vol, is_muted) = {
-                            let vc = volume_control.lock().await;
-                            (vc.volume, vc.muted)
-                        };
-                        station_index = (station_index + 1) % STATIONS.len();
-                        stream_url = STATIONS[station_index].url;
-                        let _ = md_tx.send(STATIONS[station_index].metadata_url);
-                        *now_playing_state.lock().await = None;
-
-                        child =
-                            restart_player(&mut child, &volume_control, stream_url, vol).await?;
-
-                        if is_muted {
-                            volume_control.lock().await.muted = true;
-                            let _ = volume_control
-                                .lock()
-                                .await
-                                .apply_mute(&mut child)
-                                .await;
-                        }
-                        ui_state.station_index = station_index;
-                        draw_ui(&mut terminal, &ui_state, STATIONS);
-                    }
+            // ── SIGINT ────────────────────────────────────────────────────
+            Event_::Signal(SignalEvent::Interrupt) => break,
 
-                    // Play/Pause (mute toggle via F8)
-                    KeyCode::F(8) => {
-                        let target_vol = {
-                            let mut vc = volume_control.lock().await;
-                            vc.toggle_mute();
-                            vc.volume
-                        };
-                        let needs_restart = volume_control
-                            .lock()
-                            .await
-                            .apply_mute(&mut child)
-                            .await
-                            .is_err();
-                        if needs_restart {
-                            child = restart_player(
-                                &mut child,
-                                &volume_control,
-                                stream_url,
-                                target_vol,
-                            )
-                            .await?;
-                        }
-                        let vc = volume_control.lock().await;
-                        ui_state.volume = vc.volume;
-                        ui_state.muted = vc.muted;
-                        draw_ui(&mut terminal, &ui_state, STATIONS);
-                    }
+            // ── Ctrl+Z / SIGTSTP ──────────────────────────────────────────
+            Event_::Signal(SignalEvent::Suspend) => suspend_requested = true,
+            Event_::Key(KeyCode::Char('z'), modifiers, KeyEventKind::Press)
+                if CAN_SUSPEND && modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                suspend_requested = true;
+            }
 
-                    // Mute toggle (F12 / m / M)
-                    KeyCode::F(12) | KeyCode::Char('m') | KeyCode::Char('M') => {
-                        let target_vol = {
-                            let mut vc = volume_control.lock().await;
-                            vc.toggle_mute();
-                            vc.volume
-                        };
-                        // Always restart to guarantee mute takes effect
-                        child = restart_player(
-                            &mut child,
-                            &volume_control,
-                            stream_url,
-                            target_vol,
-                        )
-                        .await?;
-                        let vc = volume_control.lock().await;
-                        ui_state.volume = vc.volume;
-                        ui_state.muted = vc.muted;
-                        draw_ui(&mut terminal, &ui_state, STATIONS);
+            // ── UI tick ───────────────────────────────────────────────────
+            Event_::Tick(Tick::Redraw) => {
+                ui_state.elapsed = start_time.elapsed();
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Tick(Tick::Full) => {
+                ui_state.elapsed = start_time.elapsed();
+                ui_state.sleep_left =
+                    sleep_at.map(|at| at.saturating_duration_since(Instant::now()));
+                ui_state.rotation = rotation.as_ref().map(RotationRun::label);
+                ui_state.recorded = recorder.as_ref().map(Recorder::elapsed);
+                // Playing steadily again clears a station's failure streak
+                if !stopped
+                    && supervisor.is_stable()
+                    && stats.record_success(&stations[station_index].name)
+                {
+                    let _ = stats.save();
+                    ui_state.likely_dead = stats.likely_dead();
+                }
+                // Audio focus follows whether anything is audible
+                let audible = !stopped && !waiting_for_play && !ui_state.muted;
+                if audible && !focus.held() {
+                    let taken = focus.take().await;
+                    if !taken.is_empty() {
+                        ui_state.notify(Toast::info(format!(
+                            "{} {}",
+                            match config.mpris.focus {
+                                FocusAction::Duck => "Turned down",
+                                _ => "Paused",
+                            },
+                            taken.join(", ")
+                        )));
                     }
-
-                    // Quit
-                    KeyCode::Char('q') | KeyCode::Char('Q') => {
-                        let _ = child.start_kill();
-                        let _ = tokio::time::timeout(
-                            Duration::from_millis(500),
-                            child.wait(),
-                        )
-                        .await;
-                        break;
+                } else if !audible && focus.held() {
+                    focus.release().await;
+                }
+                let _ = player.send(PlayerCommand::Probe).await;
+                let probe = player.state();
+                ui_state.buffering = probe.buffering;
+                ui_state.timeshift = probe.timeshift;
+                let streams = u32::from(!stopped && !suspended && !waiting_for_play)
+                    + u32::from(recorder.is_some());
+                stats.add_download(meter.sample(probe.bitrate, streams));
+                let used = stats.month_download();
+                match meter.check(used) {
+                    Some(CapLevel::Warning) => ui_state
+                        .notify(Toast::warning(format!("Data: {}", meter.month_label(used)))),
+                    Some(CapLevel::Reached) => {
+                        let _ = stats.save();
+                        ui_state.notify(Toast::sticky(format!(
+                            "Monthly data cap reached: {}{}",
+                            meter.month_label(used),
+                            if meter.capped() {
+                                ", playing low bitrates"
+                            } else {
+                                ""
+                            }
+                        )));
                     }
-
-                    // Ctrl+C (non-unix fallback via keyboard)
-                    KeyCode::Char('c')
-                        if modifiers.contains(KeyModifiers::CONTROL) =>
+                    None => {}
+                }
+                ui_state.data_capped = meter.capped();
+                if let Some(name) = mixer.keep_alive().await {
+                    ui_state.notify(Toast::error(format!("Second stream {} stopped", name)));
+                    ui_state.mixer = ui_state
+                        .mixer
+                        .as_ref()
+                        .map(|_| mixer.screen(&stations[station_index], ui_state.volume));
+                }
+                // Stations without a metadata API fall back to stream tags
+                let parsed = now_playing_state.lock().await.clone().or_else(|| {
+                    let title = probe.stream_title.as_deref()?;
+                    Track::parse(title, &stations[station_index].metadata)
+                });
+                let now_playing = parsed.as_ref().map(Track::to_string);
+                if now_playing != ui_state.now_playing {
+                    submit_listen(
+                        listen.take(),
+                        &config.scrobble,
+                        submitter.as_ref(),
+                        &listen_queue,
+                        &queue_wake,
+                    );
+                    ui_state.recording = None;
+                    if let Some(recording) = recorder
+                        .as_ref()
+                        .filter(|r| r.station == stations[station_index].name)
                     {
-                        let _ = child.start_kill();
-                        let _ = tokio::time::timeout(
-                            Duration::from_millis(500),
-                            child.wait(),
-                        )
-                        .await;
-                        break;
+                        recording.set_track(parsed.clone());
                     }
-
-                    _ => {}
                 }
-            }
+                if now_playing.is_some() && now_playing != ui_state.now_playing {
+                    if let (Some(track), Some(parsed)) = (now_playing.as_deref(), parsed) {
+                        let station = &stations[station_index];
+                        if let Some(submitter) = submitter
+                            .as_ref()
+                            .filter(|_| station.scrobble && config.scrobble.allows(&parsed))
+                        {
+                            let submitter = submitter.clone();
+                            let track = parsed.clone();
+                            tokio::spawn(async move { submitter.now_playing(&track).await });
+                            listen = Some(Listen::new(parsed.clone()));
+                        }
+                        if config.musicbrainz && !ui_state.low_data && parsed.artist.is_some() {
+                            let key = track_key(track);
+                            match mb_cache.get(&key) {
+                                Some(cached) => {
+                                    if let (Some(listen), Some(recording)) =
+                                        (listen.as_mut(), cached)
+                                    {
+                                        listen.use_recording(recording);
+                                    }
+                                    ui_state.recording = cached.cloned();
+                                }
+                                None if mb_requested.insert(key.clone()) => {
+                                    musicbrainz::spawn_lookup(key, parsed, mb_tx.clone())
+                                }
+                                None => {}
+                            }
+                        }
+                        if config.notifications {
+                            desktop_notify(&stations[station_index].name, track);
+                        }
+                        if stats.record_track(track) {
+                            let _ = stats.save();
+                        }
+                        summary.track(track);
+                        ui_state.recent_tracks.insert(0, track.to_string());
+                        ui_state.recent_tracks.truncate(RECENT_TRACKS);
+                    }
+                }
+                // First title after a switch is already covered by the station chime
+                if config.chime
+                    && ui_state.now_playing.is_some()
+                    && now_playing.is_some()
+                    && now_playing != ui_state.now_playing
+                {
+                    player.post(PlayerCommand::Chime(config.chime_volume as f32 / 100.0));
+                }
+                ui_state.liked = now_playing.as_deref().is_some_and(|t| likes.contains(t));
+                ui_state.now_playing = now_playing;
+                draw_ui(&mut terminal, &ui_state, &stations);
+
+                let state = if stopped && !waiting_for_play || suspended {
+                    PlayState::Stopped
+                } else if waiting_for_play || ui_state.muted || auto_paused {
+                    PlayState::Paused
+                } else if ui_state.buffering.is_some() {
+                    PlayState::Buffering
+                } else {
+                    PlayState::Playing
+                };
+                summary.playing(state == PlayState::Playing);
+                let status = Status {
+                    pid: std::process::id(),
+                    station: stations[station_index].name.clone(),
+                    url: stream_url.clone(),
+                    track: ui_state.now_playing.clone(),
+                    volume: ui_state.volume,
+                    state,
+                    elapsed: station_started.elapsed().as_secs(),
+                    bitrate: probe.bitrate,
+                    artwork: ui_state.recording.as_ref().and_then(|r| r.artwork.clone()),
+                    artwork_file: ui_state
+                        .recording
+                        .as_ref()
+                        .and_then(|r| cache::file("artwork", r.artwork.as_deref()?)),
+                };
+                if headless
+                    && !published_status
+                        .as_ref()
+                        .is_some_and(|s| s.same_as(&status))
+                {
+                    println!("{}", status.line());
+                }
+                if published_status.as_ref() != Some(&status) {
+                    status.publish();
+                    if let Ok(mut view) = remote_view.lock() {
+                        view.status = Some(status.clone());
+                        view.stations = stations.iter().map(|s| s.name.clone()).collect();
+                    }
+                    published_status = Some(status);
+                }
+                // Written down for crash recovery, unless the snapshot of
+                // the crashed session is still waiting for an answer
+                let resuming = matches!(confirm, Some(ConfirmAction::ResumeSession(_)));
+                if !resuming && snapshot_saved.is_none_or(|at| at.elapsed() >= SNAPSHOT_INTERVAL) {
+                    Snapshot {
+                        pid: std::process::id(),
+                        saved_at: unix_now(),
+                        station: stations[station_index].name.clone(),
+                        volume: ui_state.volume,
+                        sleep_secs: ui_state.sleep_left.map(|left| left.as_secs()),
+                        rotation: rotation.as_ref().map(|run| run.name().to_string()),
+                        recording: recorder.as_ref().map(|recording| RecordingSnapshot {
+                            station: recording.station.clone(),
+                            format: recording.format.name().to_string(),
+                            remaining_secs: recording.remaining().map(|left| left.as_secs()),
+                        }),
+                    }
+                    .save();
+                    snapshot_saved = Some(Instant::now());
+                }
+
+                // Stall detection: a live child that stops making progress is
+                // killed, which routes it through the reconnect supervisor.
+                if !stopped && !suspended && !pending_switch {
+                    if supervisor.check_stall(probe.progress) {
+                        player.post(PlayerCommand::Kill);
+                    } else if supervisor.check_silence(probe.audible) {
+                        // Some streams die into silence without the player exiting
+                        ui_state.notify(Toast::warning("Stream went silent — reconnecting"));
+                        player.post(PlayerCommand::Kill);
+                    }
+                }
+            }
+
+            // ── ListenAlong ───────────────────────────────────────────────
+            Event_::Share(ShareEvent::Published(Ok((url, created_gist)))) => {
+                if let Some(id) = created_gist {
+                    config.share.gist_id = Some(id);
+                    if let Err(e) = config.save() {
+                        ui_state.notify(Toast::error(format!("Could not save config: {}", e)));
+                    }
+                }
+                if share_url.as_deref() != Some(url.as_str()) {
+                    ui_state.notify(Toast::sticky(format!("Sharing at {}", url)));
+                    share_url = Some(url);
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Share(ShareEvent::Published(Err(e))) => {
+                ui_state.notify(Toast::error(format!("Share failed: {}", e)));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Share(ShareEvent::Update(session)) => {
+                let i = shared_station_index(&mut stations, &session);
+                if i != station_index {
+                    switch_to = Some(i);
+                }
+            }
+
+            // ── player started by a station switch ────────────────────────
+            Event_::Player(PlayerEvent::Started(result)) => {
+                pending_switch = false;
+                ui_state.switching = false;
+                ticker.request(TickClient::Spinner, None);
+                match result {
+                    Ok(()) if suspended => {
+                        let _ = player.send(PlayerCommand::Stop).await;
+                    }
+                    Ok(()) => {
+                        if config.chime && !auto_paused && queued_switch.is_none() {
+                            player.post(PlayerCommand::Chime(config.chime_volume as f32 / 100.0));
+                        }
+                    }
+                    Err(LofiError::SpawnFailed { player, reason }) => {
+                        stopped = true;
+                        ui_state.notify(Toast::error(format!(
+                            "Could not start {} with {}: {} (r to retry, k for another backend)",
+                            stations[station_index].name, player, reason
+                        )));
+                    }
+                    Err(e) => {
+                        stopped = true;
+                        ui_state.notify(Toast::error(format!(
+                            "Could not start {}: {} (r to retry)",
+                            stations[station_index].name, e
+                        )));
+                    }
+                }
+                switch_to = queued_switch.take();
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── other MPRIS players ───────────────────────────────────────
+            Event_::Others(other) => {
+                ui_state.other_player = other.map(|p| p.label());
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── web remote ────────────────────────────────────────────────
+            Event_::Remote(RemoteCommand::Play(i)) => {
+                if i < stations.len() && (i != station_index || stopped) {
+                    switch_to = Some(i);
+                }
+            }
+            Event_::Remote(RemoteCommand::PlayUrl(url)) => {
+                // Like a joined session's unknown station: added for this run only
+                let i = match stations.iter().position(|s| s.url == url) {
+                    Some(i) => i,
+                    None => {
+                        stations.push(Station::new(&import::name_from_url(&url), &url, None));
+                        stations.len() - 1
+                    }
+                };
+                switch_to = Some(i);
+            }
+            Event_::Remote(RemoteCommand::Volume(volume)) => {
+                player.send(PlayerCommand::SetVolume(volume)).await?;
+                ui_state.volume = player.state().volume;
+                ui_state.applied_volume = player.state().applied_volume;
+                ui_state.notify(Toast::info(format!("Volume {}%", ui_state.volume)));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Remote(RemoteCommand::TogglePause) => {
+                if waiting_for_play || stopped {
+                    // Same as F8/r with nothing playing: start the station
+                    if waiting_for_play {
+                        if let Err(e) = ambient.start().and_then(|()| beats.start()) {
+                            ui_state.notify(Toast::error(e));
+                        }
+                    }
+                    waiting_for_play = false;
+                    stopped = false;
+                    supervisor.reset(stations[station_index].reconnect_policy(config.reconnect));
+                    player.send(PlayerCommand::Play(stream_url.clone())).await?;
+                    ui_state.notify(Toast::info(format!("▶ {}", stations[station_index].name)));
+                } else {
+                    let paused = !player.state().muted;
+                    player.send(PlayerCommand::Pause(paused)).await?;
+                    ui_state.muted = player.state().muted;
+                    ui_state.notify(Toast::info(if ui_state.muted {
+                        "Paused"
+                    } else {
+                        "Playing"
+                    }));
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── newer release found by the update check ──────────────────
+            Event_::Update(release) => {
+                ui_state.update = Some((release.version, release.url));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── MusicBrainz data for a track ──────────────────────────────
+            Event_::Recording(key, recording) => {
+                let playing = ui_state.now_playing.as_deref().map(track_key);
+                if playing.as_deref() == Some(key.as_str()) {
+                    if let (Some(listen), Some(recording)) = (listen.as_mut(), &recording) {
+                        listen.use_recording(recording);
+                    }
+                    ui_state.recording = recording.clone();
+                    draw_ui(&mut terminal, &ui_state, &stations);
+                }
+                mb_cache.insert(key, recording);
+                let _ = mb_cache.save();
+            }
+
+            Event_::ScrobbleFailed(e) => {
+                ui_state.notify(Toast::warning(format!("Scrobble failed: {}", e)));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── Recording ─────────────────────────────────────────────────
+            Event_::Record(RecordEvent::Saved(path)) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                ui_state.notify(Toast::info(format!("Saved {}", name)));
+                if let Some(view) = recordings_view.as_mut() {
+                    view.refresh();
+                    ui_state.recordings = Some(view.screen());
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Record(RecordEvent::TranscodeFailed(path, e)) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                ui_state.notify(Toast::warning(format!("Kept {} as recorded: {}", name, e)));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Record(RecordEvent::LoudnessFailed(path, e)) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                ui_state.notify(Toast::warning(format!("Loudness of {}: {}", name, e)));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Record(RecordEvent::TagFailed(path, e)) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                ui_state.notify(Toast::warning(format!("Could not tag {}: {}", name, e)));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Record(RecordEvent::Pruned(count)) => {
+                ui_state.notify(Toast::info(format!(
+                    "Deleted {} old recording(s) to stay under the quota",
+                    count
+                )));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Record(RecordEvent::Stopped(result)) => {
+                recorder = None;
+                ui_state.recorded = None;
+                if let Err(e) = result {
+                    ui_state.notify(Toast::error(format!("Recording stopped: {}", e)));
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── radio-browser.info metadata for a station ─────────────────
+            Event_::StationInfo(name, info) => {
+                if let Some(station) = stations.iter_mut().find(|s| s.name == name) {
+                    station.merge_info(&info);
+                    if let Some(detail) = ui_state.detail.as_mut().filter(|d| d.title == name) {
+                        let playing = (station.url == stream_url).then(|| player.state());
+                        *detail = station_detail(
+                            station,
+                            &stats,
+                            playing.as_ref(),
+                            detail.selected,
+                            notes_input.as_ref(),
+                        );
+                    }
+                }
+                // Keep it, so the lookup happens once per station
+                if let Some(station) = config.stations.iter_mut().find(|s| s.name == name) {
+                    if station.merge_info(&info) {
+                        if let Err(e) = config.save() {
+                            ui_state.notify(Toast::error(format!("Could not save config: {}", e)));
+                        }
+                    }
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── Mouse: click or drag the volume slider ────────────────────
+            Event_::Mouse(mouse) => {
+                let volume = match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left) => {
+                        let volume = ui_state.volume_at(mouse.column, mouse.row);
+                        dragging_volume = volume.is_some();
+                        volume
+                    }
+                    MouseEventKind::Drag(MouseButton::Left) if dragging_volume => {
+                        ui_state.volume_dragged_to(mouse.column)
+                    }
+                    MouseEventKind::Up(MouseButton::Left) if dragging_volume => {
+                        dragging_volume = false;
+                        // Let go: apply what the drag held back
+                        if volume_pending {
+                            player.send(PlayerCommand::PushVolume).await?;
+                            last_volume_apply = Instant::now();
+                            volume_pending = false;
+                            ui_state.applied_volume = player.state().applied_volume;
+                            draw_ui(&mut terminal, &ui_state, &stations);
+                        }
+                        None
+                    }
+                    _ => None,
+                };
+                let Some(volume) = volume.filter(|&v| v != ui_state.volume) else {
+                    continue;
+                };
+                player.send(PlayerCommand::StageVolume(volume)).await?;
+                let levels = player.state();
+                ui_state.volume = levels.volume;
+                ui_state.applied_volume = levels.applied_volume;
+                ui_state.notify(Toast::info(if levels.muted {
+                    "Muted — F12 to unmute".to_string()
+                } else {
+                    format!("Volume {}%", levels.volume)
+                }));
+                if last_volume_apply.elapsed() >= VOLUME_APPLY_INTERVAL {
+                    player.send(PlayerCommand::PushVolume).await?;
+                    last_volume_apply = Instant::now();
+                    volume_pending = false;
+                    ui_state.applied_volume = player.state().applied_volume;
+                } else {
+                    volume_pending = true;
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── Pasted text goes to the open text input ───────────────────
+            Event_::Paste(text) => {
+                if let Some(p) = palette.as_mut() {
+                    p.input.insert(&text);
+                    ui_state.palette = Some(p.screen());
+                } else if let (Some(input), Some(detail)) =
+                    (notes_input.as_mut(), ui_state.detail.as_mut())
+                {
+                    input.insert(&text);
+                    detail.editing = Some((input.text().to_string(), input.cursor()));
+                } else if let Some(view) = recordings_view.as_mut().filter(|v| v.rename.is_some()) {
+                    if let Some(input) = view.rename.as_mut() {
+                        input.insert(&text);
+                    }
+                    ui_state.recordings = Some(view.screen());
+                } else {
+                    continue;
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── Keyboard ──────────────────────────────────────────────────
+            Event_::Key(key_code, _, KeyEventKind::Release) => {
+                if held_key.is_some_and(|(code, _)| code == key_code) {
+                    held_key = None;
+                    player.send(PlayerCommand::Hold(None)).await?;
+                }
+            }
+            // ── First-run cheat sheet: any key dismisses it for good ─────
+            Event_::Key(key_code, modifiers, KeyEventKind::Press) if ui_state.welcome.is_some() => {
+                if key_code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+                    break;
+                }
+                ui_state.welcome = None;
+                app_state.welcomed = true;
+                app_state.last_version = Some(env!("CARGO_PKG_VERSION").to_string());
+                if let Err(e) = app_state.save() {
+                    ui_state.notify(Toast::error(format!("Could not save UI state: {}", e)));
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            // ── Release notes after an upgrade: any key dismisses them ───
+            Event_::Key(key_code, modifiers, KeyEventKind::Press)
+                if ui_state.whats_new.is_some() =>
+            {
+                if key_code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+                    break;
+                }
+                ui_state.whats_new = None;
+                app_state.last_version = Some(env!("CARGO_PKG_VERSION").to_string());
+                if let Err(e) = app_state.save() {
+                    ui_state.notify(Toast::error(format!("Could not save UI state: {}", e)));
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            // ── Confirmation dialog (captures keys while open) ───────────
+            Event_::Key(key_code, modifiers, KeyEventKind::Press) if confirm.is_some() => {
+                match key_code {
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                        ui_state.confirm = None;
+                        match confirm.take() {
+                            Some(ConfirmAction::DeleteRecording(path)) => {
+                                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                                ui_state.notify(match record::delete(&path) {
+                                    Ok(()) => Toast::info(format!("Deleted {}", name)),
+                                    Err(e) => {
+                                        Toast::error(format!("Could not delete {}: {}", name, e))
+                                    }
+                                });
+                                if let Some(view) = recordings_view.as_mut() {
+                                    view.refresh();
+                                    ui_state.recordings = Some(view.screen());
+                                }
+                            }
+                            Some(ConfirmAction::ResumeSession(previous)) => {
+                                queued_commands.extend(previous.commands());
+                            }
+                            Some(ConfirmAction::RemoveStation(name)) => {
+                                let Some(i) = stations.iter().position(|s| s.name == name) else {
+                                    draw_ui(&mut terminal, &ui_state, &stations);
+                                    continue;
+                                };
+                                if config.remove_station(&name) {
+                                    if let Err(e) = config.save() {
+                                        ui_state.notify(Toast::error(format!(
+                                            "Could not save config: {}",
+                                            e
+                                        )));
+                                    } else {
+                                        ui_state.notify(Toast::info(format!("Removed {}", name)));
+                                    }
+                                    stats.stations.remove(&name);
+                                    let _ = stats.save();
+                                    ui_state.likely_dead = stats.likely_dead();
+                                    stations.remove(i);
+                                    if i == station_index {
+                                        station_started = Instant::now();
+                                        station_index = station_index.min(stations.len() - 1);
+                                        switch_to = Some(station_index);
+                                    } else if i < station_index {
+                                        station_index -= 1;
+                                    }
+                                    ui_state.station_index = station_index;
+                                } else {
+                                    ui_state.notify(Toast::error("Cannot remove the only station"));
+                                }
+                            }
+                            None => {}
+                        }
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                        confirm = None;
+                        ui_state.confirm = None;
+                    }
+                    _ => continue,
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            // ── Command palette (captures keys while open) ───────────────
+            Event_::Key(key_code, modifiers, KeyEventKind::Press | KeyEventKind::Repeat)
+                if palette.is_some() =>
+            {
+                let Some(p) = palette.as_mut() else {
+                    continue;
+                };
+                match key_code {
+                    KeyCode::Esc => palette = None,
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Enter => {
+                        let input = p.input.submit();
+                        if !p.queued {
+                            palette_history = p.input.history().to_vec();
+                        }
+                        palette = None;
+                        match parse_command(&input, &stations) {
+                            Err(e) => ui_state.notify(Toast::error(e)),
+                            Ok(PaletteCommand::Play(i)) => {
+                                if i != station_index {
+                                    switch_to = Some(i);
+                                }
+                            }
+                            Ok(PaletteCommand::Volume(v)) => {
+                                player.send(PlayerCommand::SetVolume(v)).await?;
+                                ui_state.volume = player.state().volume;
+                                ui_state.applied_volume = player.state().applied_volume;
+                                ui_state
+                                    .notify(Toast::info(format!("Volume {}%", ui_state.volume)));
+                            }
+                            Ok(PaletteCommand::Sleep(duration)) => {
+                                sleep_at = duration.and_then(|d| Instant::now().checked_add(d));
+                                ui_state.sleep_left = duration;
+                                ui_state.notify(Toast::info(match duration {
+                                    Some(d) => {
+                                        format!("Pausing in {} min", d.as_secs().div_ceil(60))
+                                    }
+                                    None => "Sleep timer off".to_string(),
+                                }));
+                            }
+                            Ok(PaletteCommand::Theme(name)) => {
+                                ui_state.theme = theme_by_name(name);
+                                ui_state.layout.theme = Some(name.to_string());
+                                config.theme = name.to_string();
+                                ui_state.notify(Toast::info(format!("Theme: {}", name)));
+                                save_layout(&mut app_state, &mut ui_state);
+                            }
+                            Ok(PaletteCommand::Sort(sort)) => {
+                                let current = stations[station_index].name.clone();
+                                app_state.station_sort = sort;
+                                sort_stations(&mut stations, sort, &stats, &config.stations);
+                                station_index =
+                                    stations.iter().position(|s| s.name == current).unwrap_or(0);
+                                ui_state.station_index = station_index;
+                                ui_state.station_sort = sort;
+                                ui_state.notify(Toast::info(format!("Sort: {}", sort.label())));
+                                if let Err(e) = app_state.save() {
+                                    ui_state.notify(Toast::error(format!(
+                                        "Could not save UI state: {}",
+                                        e
+                                    )));
+                                }
+                            }
+                            // The rest are plain key bindings
+                            Ok(PaletteCommand::Mute) => injected_key = Some(KeyCode::F(12)),
+                            Ok(PaletteCommand::Pause) => injected_key = Some(KeyCode::F(8)),
+                            Ok(PaletteCommand::Next) => injected_key = Some(KeyCode::F(9)),
+                            Ok(PaletteCommand::Prev) => injected_key = Some(KeyCode::F(7)),
+                            Ok(PaletteCommand::Like) => injected_key = Some(KeyCode::Char('l')),
+                            Ok(PaletteCommand::Stats) => injected_key = Some(KeyCode::Char('h')),
+                            Ok(PaletteCommand::History) => injected_key = Some(KeyCode::Char('y')),
+                            Ok(PaletteCommand::Output) => injected_key = Some(KeyCode::Char('e')),
+                            Ok(PaletteCommand::Noise) => injected_key = Some(KeyCode::Char('n')),
+                            Ok(PaletteCommand::Mixer) => injected_key = Some(KeyCode::Char('v')),
+                            Ok(PaletteCommand::Beats(preset)) => {
+                                let result = match preset {
+                                    Some(p) => beats.set_preset(Some(p)),
+                                    None => {
+                                        beats.disable();
+                                        Ok(())
+                                    }
+                                };
+                                ui_state.notify(match result {
+                                    Ok(()) => beats_toast(&beats),
+                                    Err(e) => Toast::error(e),
+                                });
+                                ui_state.beats = beats.label();
+                            }
+                            Ok(PaletteCommand::Settings) => injected_key = Some(KeyCode::Char('s')),
+                            Ok(PaletteCommand::Share) => injected_key = Some(KeyCode::Char('p')),
+                            Ok(PaletteCommand::Record(..)) if recorder.is_some() => {
+                                ui_state.notify(Toast::warning("Already recording"));
+                            }
+                            Ok(PaletteCommand::Record(duration, format)) => {
+                                let station = &stations[station_index];
+                                if NoiseKind::from_url(&station.url).is_some() {
+                                    ui_state
+                                        .notify(Toast::error("Generated noise cannot be recorded"));
+                                } else {
+                                    let track =
+                                        now_playing_state.lock().await.clone().or_else(|| {
+                                            let title = player.state().stream_title?;
+                                            Track::parse(&title, &station.metadata)
+                                        });
+                                    match Recorder::start(
+                                        &config.recording,
+                                        &station.name,
+                                        &stream_url,
+                                        track,
+                                        duration,
+                                        format.unwrap_or(config.recording.format),
+                                        record_tx.clone(),
+                                    ) {
+                                        Ok(started) => {
+                                            ui_state.notify(Toast::info(match duration {
+                                                Some(d) => format!(
+                                                    "Recording {} for {} min",
+                                                    station.name,
+                                                    d.as_secs().div_ceil(60)
+                                                ),
+                                                None => format!("Recording {}", station.name),
+                                            }));
+                                            ui_state.recorded = Some(Duration::ZERO);
+                                            recorder = Some(started);
+                                        }
+                                        Err(e) => ui_state.notify(Toast::error(e)),
+                                    }
+                                }
+                            }
+                            Ok(PaletteCommand::StopRecording) => match &recorder {
+                                Some(recording) => {
+                                    recording.stop();
+                                    ui_state.notify(Toast::info(format!(
+                                        "Stopped recording {}",
+                                        recording.station
+                                    )));
+                                }
+                                None => ui_state.notify(Toast::warning("Not recording")),
+                            },
+                            Ok(PaletteCommand::Recordings) => {
+                                injected_key = Some(KeyCode::Char('f'))
+                            }
+                            Ok(PaletteCommand::Undo) => injected_key = Some(KeyCode::Char('u')),
+                            Ok(PaletteCommand::Night) => injected_key = Some(KeyCode::Char('z')),
+                            Ok(PaletteCommand::LowData) => {
+                                ui_state.low_data = !ui_state.low_data;
+                                player
+                                    .send(PlayerCommand::LowData(ui_state.low_data))
+                                    .await?;
+                                ui_state.notify(Toast::info(if ui_state.low_data {
+                                    "Low data on — low bitrates, no artwork or info lookups"
+                                } else {
+                                    "Low data off"
+                                }));
+                            }
+                            Ok(PaletteCommand::Rotate(None)) => {
+                                injected_key = Some(KeyCode::Char('w'))
+                            }
+                            Ok(PaletteCommand::Rotate(Some(name))) => {
+                                match best_match(
+                                    &name,
+                                    config.rotations.iter().map(|r| r.name.as_str()),
+                                ) {
+                                    Some(i) => rotation_to = Some(config.rotations[i].clone()),
+                                    None => ui_state.notify(Toast::warning(format!(
+                                        "No rotation matches \"{}\"",
+                                        name
+                                    ))),
+                                }
+                            }
+                            Ok(PaletteCommand::Macro(name)) => {
+                                match macros::find(&config.macros, &name) {
+                                    Some(found) => {
+                                        if found.actions.iter().any(|a| macros::is_macro(a)) {
+                                            ui_state.notify(Toast::warning(format!(
+                                                "Macro {}: macros cannot run other macros",
+                                                found.name
+                                            )));
+                                        }
+                                        queued_commands.extend(
+                                            found
+                                                .actions
+                                                .iter()
+                                                .filter(|a| !macros::is_macro(a))
+                                                .cloned(),
+                                        );
+                                    }
+                                    None => ui_state.notify(Toast::warning(format!(
+                                        "No macro matches \"{}\"",
+                                        name
+                                    ))),
+                                }
+                            }
+                            Ok(PaletteCommand::StopRotation) => match rotation.take() {
+                                Some(run) => {
+                                    ui_state.rotation = None;
+                                    ui_state.notify(Toast::info(format!(
+                                        "Rotation {} stopped",
+                                        run.name()
+                                    )));
+                                }
+                                None => ui_state.notify(Toast::warning("No rotation playing")),
+                            },
+                            Ok(PaletteCommand::Backend(None)) => {
+                                injected_key = Some(KeyCode::Char('k'))
+                            }
+                            Ok(PaletteCommand::Backend(Some(p))) if p == player_type => {
+                                ui_state.notify(Toast::info(format!("Already using {}", p.name())));
+                            }
+                            Ok(PaletteCommand::Backend(target)) => backend_to = target,
+                            Ok(PaletteCommand::Quit) => injected_key = Some(KeyCode::Char('q')),
+                        }
+                    }
+                    _ => {
+                        if !p.input.handle_key(key_code, modifiers) {
+                            continue;
+                        }
+                    }
+                }
+                ui_state.palette = palette.as_ref().map(Palette::screen);
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── Output picker (captures keys while open) ─────────────────
+            Event_::Key(key_code, modifiers, _) if output_picker.is_some() => {
+                let Some(picker) = output_picker.as_mut() else {
+                    continue;
+                };
+                match key_code {
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Up => picker.move_selection(-1),
+                    KeyCode::Down => picker.move_selection(1),
+                    KeyCode::Char('r') | KeyCode::Char('R') => *picker = OutputPicker::open().await,
+                    KeyCode::Enter => {
+                        if let Some(bt) = picker.selected().and_then(|d| d.bluetooth.clone()) {
+                            ui_state.notify(Toast::info(format!("Connecting {}…", bt.name)));
+                            spawn_connect(bt, output_tx.clone());
+                        } else if let Some(device) = picker.selected().cloned() {
+                            match set_default_output(&device.id).await {
+                                Ok(()) => {
+                                    expected_output = Some(device.id);
+                                    ui_state
+                                        .notify(Toast::info(format!("Output: {}", device.name)));
+                                }
+                                Err(e) => {
+                                    ui_state.notify(Toast::error(format!(
+                                        "Could not switch output: {}",
+                                        e
+                                    )));
+                                }
+                            }
+                        }
+                        output_picker = None;
+                    }
+                    KeyCode::Esc | KeyCode::Char('e') | KeyCode::Char('E') | KeyCode::Char('q') => {
+                        output_picker = None;
+                    }
+                    _ => continue,
+                }
+                ui_state.output = output_picker.as_ref().map(OutputPicker::screen);
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── Ambient mixer (captures keys while open) ─────────────────
+            Event_::Key(key_code, modifiers, _) if ui_state.ambient.is_some() => {
+                let result = match key_code {
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Up => {
+                        ambient.move_selection(-1);
+                        Ok(())
+                    }
+                    KeyCode::Down => {
+                        ambient.move_selection(1);
+                        Ok(())
+                    }
+                    KeyCode::Left | KeyCode::Char('-') => ambient.adjust(-1),
+                    KeyCode::Right | KeyCode::Char('+') | KeyCode::Char('=') => ambient.adjust(1),
+                    KeyCode::Char('0') => {
+                        ambient.mute_selected();
+                        Ok(())
+                    }
+                    KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Char('q') => {
+                        ui_state.ambient = None;
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if let Err(e) = result {
+                    ui_state.notify(Toast::error(e));
+                }
+                if app_state.ambient != ambient.levels() {
+                    app_state.ambient = ambient.levels();
+                    let _ = app_state.save();
+                }
+                ui_state.ambient = Some(ambient.screen());
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── Mixer pane (captures keys while open) ────────────────────
+            Event_::Key(key_code, modifiers, _) if ui_state.mixer.is_some() => {
+                let result = match key_code {
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Up => {
+                        mixer.move_selection(-1);
+                        Ok(())
+                    }
+                    KeyCode::Down => {
+                        mixer.move_selection(1);
+                        Ok(())
+                    }
+                    KeyCode::Left | KeyCode::Right | KeyCode::Char('-') | KeyCode::Char('+')
+                        if mixer.selected == 0 =>
+                    {
+                        let up = matches!(key_code, KeyCode::Right | KeyCode::Char('+'));
+                        player
+                            .send(PlayerCommand::StepVolume(if up { 1 } else { -1 }))
+                            .await?;
+                        player.send(PlayerCommand::PushVolume).await?;
+                        ui_state.volume = player.state().volume;
+                        ui_state.applied_volume = player.state().applied_volume;
+                        Ok(())
+                    }
+                    KeyCode::Left | KeyCode::Char('-') => mixer.adjust_side(-1).await,
+                    KeyCode::Right | KeyCode::Char('+') => mixer.adjust_side(1).await,
+                    // Cycle the second stream through the other stations
+                    KeyCode::Char('<')
+                    | KeyCode::Char('>')
+                    | KeyCode::PageUp
+                    | KeyCode::PageDown => {
+                        mixer.selected = 1;
+                        let forward = matches!(key_code, KeyCode::Char('>') | KeyCode::PageDown);
+                        let len = stations.len();
+                        let from = mixer
+                            .side_station()
+                            .and_then(|s| stations.iter().position(|t| t.name == s.name))
+                            .unwrap_or(station_index);
+                        let next = (1..len)
+                            .map(|offset| {
+                                if forward {
+                                    (from + offset) % len
+                                } else {
+                                    (from + len - offset) % len
+                                }
+                            })
+                            .find(|&i| i != station_index);
+                        match next {
+                            Some(i) => mixer.set_side(Some(&stations[i])).await,
+                            None => Err("No other station to mix in".to_string()),
+                        }
+                    }
+                    KeyCode::Char('0') | KeyCode::Backspace | KeyCode::Delete => {
+                        mixer.set_side(None).await
+                    }
+                    KeyCode::Esc | KeyCode::Char('v') | KeyCode::Char('V') | KeyCode::Char('q') => {
+                        ui_state.mixer = None;
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if let Err(e) = result {
+                    ui_state.notify(Toast::error(e));
+                }
+                ui_state.mixer = Some(mixer.screen(&stations[station_index], ui_state.volume));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── Station detail popup (captures keys while open) ──────────
+            Event_::Key(key_code, modifiers, KeyEventKind::Press | KeyEventKind::Repeat)
+                if ui_state.detail.is_some() =>
+            {
+                let Some(detail) = ui_state.detail.as_ref() else {
+                    continue;
+                };
+                let name = detail.title.clone();
+                let mut selected = detail.selected;
+                let Some(index) = stations.iter().position(|s| s.name == name) else {
+                    ui_state.detail = None;
+                    notes_input = None;
+                    draw_ui(&mut terminal, &ui_state, &stations);
+                    continue;
+                };
+                if let Some(input) = notes_input.as_mut() {
+                    match key_code {
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                        KeyCode::Esc => notes_input = None,
+                        KeyCode::Enter => {
+                            let notes =
+                                Some(input.submit().trim().to_string()).filter(|n| !n.is_empty());
+                            notes_input = None;
+                            stations[index].notes = notes.clone();
+                            if let Some(s) = config.stations.iter_mut().find(|s| s.name == name) {
+                                s.notes = notes;
+                                ui_state.notify(match config.save() {
+                                    Ok(()) => Toast::info("Notes saved"),
+                                    Err(e) => Toast::error(format!("Could not save config: {}", e)),
+                                });
+                            }
+                        }
+                        _ => {
+                            if !input.handle_key(key_code, modifiers) {
+                                continue;
+                            }
+                        }
+                    }
+                } else {
+                    let n = DETAIL_ACTIONS.len();
+                    match key_code {
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                        KeyCode::Left | KeyCode::BackTab => selected = (selected + n - 1) % n,
+                        KeyCode::Right | KeyCode::Tab => selected = (selected + 1) % n,
+                        KeyCode::Enter => match DETAIL_ACTIONS[selected] {
+                            DetailAction::Play => {
+                                ui_state.detail = None;
+                                if index != ui_state.station_index {
+                                    switch_to = Some(index);
+                                }
+                                draw_ui(&mut terminal, &ui_state, &stations);
+                                continue;
+                            }
+                            DetailAction::EditNotes => {
+                                let mut input = TextInput::default();
+                                input.insert(stations[index].notes.as_deref().unwrap_or(""));
+                                notes_input = Some(input);
+                            }
+                            DetailAction::Favorite => {
+                                let favorite = !stations[index].favorite;
+                                stations[index].favorite = favorite;
+                                if let Some(s) = config.stations.iter_mut().find(|s| s.name == name)
+                                {
+                                    s.favorite = favorite;
+                                    if let Err(e) = config.save() {
+                                        ui_state.notify(Toast::error(format!(
+                                            "Could not save config: {}",
+                                            e
+                                        )));
+                                    }
+                                }
+                            }
+                            DetailAction::Delete => {
+                                ui_state.detail = None;
+                                ui_state.confirm = Some(ConfirmScreen {
+                                    title: "Remove station".to_string(),
+                                    question: format!("Remove {} from the config?", name),
+                                });
+                                confirm = Some(ConfirmAction::RemoveStation(name));
+                                draw_ui(&mut terminal, &ui_state, &stations);
+                                continue;
+                            }
+                        },
+                        KeyCode::Char('o') | KeyCode::Char('O') => {
+                            let link = link_for(
+                                &stations[index],
+                                ui_state.now_playing.as_deref(),
+                                key_code == KeyCode::Char('O'),
+                            );
+                            open_link(link, &mut ui_state);
+                        }
+                        KeyCode::Esc
+                        | KeyCode::Char('i')
+                        | KeyCode::Char('I')
+                        | KeyCode::Char('q') => {
+                            ui_state.detail = None;
+                            draw_ui(&mut terminal, &ui_state, &stations);
+                            continue;
+                        }
+                        _ => continue,
+                    }
+                }
+                let playing = (index == station_index).then(|| player.state());
+                ui_state.detail = Some(station_detail(
+                    &stations[index],
+                    &stats,
+                    playing.as_ref(),
+                    selected,
+                    notes_input.as_ref(),
+                ));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+
+            // ── Stats view (captures keys while open) ────────────────────
+            Event_::Key(key_code, modifiers, _) if ui_state.stats.is_some() => match key_code {
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                KeyCode::Esc | KeyCode::Char('h') | KeyCode::Char('H') | KeyCode::Char('q') => {
+                    ui_state.stats = None;
+                    draw_ui(&mut terminal, &ui_state, &stations);
+                }
+                _ => {}
+            },
+            // ── Timeline view (captures keys while open) ─────────────────
+            Event_::Key(key_code, modifiers, _) if ui_state.timeline.is_some() => {
+                let Some(screen) = ui_state.timeline.as_mut() else {
+                    continue;
+                };
+                match key_code {
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Esc | KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Char('q') => {
+                        ui_state.timeline = None;
+                    }
+                    KeyCode::Up => screen.scroll = screen.scroll.saturating_sub(1),
+                    KeyCode::Down => screen.scroll = screen.scroll.saturating_add(1),
+                    KeyCode::PageUp => screen.scroll = screen.scroll.saturating_sub(10),
+                    KeyCode::PageDown => screen.scroll = screen.scroll.saturating_add(10),
+                    KeyCode::Home => screen.scroll = 0,
+                    _ => continue,
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            // ── Recordings view (captures keys while open) ──────────────
+            Event_::Key(key_code, modifiers, _) if recordings_view.is_some() => {
+                let Some(view) = recordings_view.as_mut() else {
+                    continue;
+                };
+                if let Some(input) = view.rename.as_mut() {
+                    match key_code {
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                        KeyCode::Esc => view.rename = None,
+                        KeyCode::Enter => match view.finish_rename() {
+                            Ok(name) => {
+                                ui_state.notify(Toast::info(format!("Renamed to {}", name)))
+                            }
+                            Err(e) => ui_state.notify(Toast::error(e)),
+                        },
+                        _ => {
+                            if !input.handle_key(key_code, modifiers) {
+                                continue;
+                            }
+                        }
+                    }
+                } else {
+                    match key_code {
+                        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                        KeyCode::Esc
+                        | KeyCode::Char('f')
+                        | KeyCode::Char('F')
+                        | KeyCode::Char('q') => {
+                            recordings_view = None;
+                        }
+                        KeyCode::Up => view.move_selection(-1),
+                        KeyCode::Down => view.move_selection(1),
+                        KeyCode::Char('r') | KeyCode::Char('R') => view.start_rename(),
+                        KeyCode::Enter => {
+                            // Played like a remote's URL: a station for this run only
+                            if let Some(file) = view.selected() {
+                                let url = file.path.to_string_lossy().into_owned();
+                                let i = match stations.iter().position(|s| s.url == url) {
+                                    Some(i) => i,
+                                    None => {
+                                        let name = format!("Recording: {}", file.name());
+                                        let mut station = Station::new(&name, &url, None);
+                                        station.live = false;
+                                        station.scrobble = false;
+                                        stations.push(station);
+                                        stations.len() - 1
+                                    }
+                                };
+                                if i != station_index || stopped {
+                                    switch_to = Some(i);
+                                }
+                                recordings_view = None;
+                            }
+                        }
+                        KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete => {
+                            if let Some(file) = view.selected() {
+                                ui_state.confirm = Some(ConfirmScreen {
+                                    title: "Delete recording".to_string(),
+                                    question: format!("Delete {}?", file.name()),
+                                });
+                                confirm = Some(ConfirmAction::DeleteRecording(file.path.clone()));
+                            }
+                        }
+                        _ => continue,
+                    }
+                }
+                ui_state.recordings = recordings_view.as_ref().map(RecordingsView::screen);
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            // ── Notification log (captures keys while open) ─────────────
+            Event_::Key(key_code, modifiers, _) if ui_state.log_scroll.is_some() => {
+                let Some(scroll) = ui_state.log_scroll.as_mut() else {
+                    continue;
+                };
+                match key_code {
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Esc | KeyCode::Char('j') | KeyCode::Char('J') | KeyCode::Char('q') => {
+                        ui_state.log_scroll = None;
+                    }
+                    KeyCode::Up => *scroll = scroll.saturating_sub(1),
+                    KeyCode::Down => *scroll = scroll.saturating_add(1),
+                    KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+                    KeyCode::PageDown => *scroll = scroll.saturating_add(10),
+                    KeyCode::Home => *scroll = 0,
+                    _ => continue,
+                }
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            // ── Settings screen (captures keys while open) ───────────────
+            Event_::Key(key_code, modifiers, _) if settings_view.is_some() => {
+                let Some(view) = settings_view.as_mut() else {
+                    continue;
+                };
+                let dir = match key_code {
+                    KeyCode::Esc | KeyCode::Char('s') | KeyCode::Char('S') => {
+                        settings_view = None;
+                        ui_state.settings = None;
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                        continue;
+                    }
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Tab => {
+                        view.next_pane(1);
+                        0
+                    }
+                    KeyCode::BackTab => {
+                        view.next_pane(-1);
+                        0
+                    }
+                    KeyCode::Up => {
+                        view.move_row(-1);
+                        0
+                    }
+                    KeyCode::Down => {
+                        view.move_row(1);
+                        0
+                    }
+                    KeyCode::Left => -1,
+                    KeyCode::Right | KeyCode::Enter | KeyCode::Char(' ') => 1,
+                    _ => 0,
+                };
+                if dir != 0 {
+                    let setting = view.selected();
+                    setting.adjust(&mut config, dir);
+                    if let Err(e) = config.save() {
+                        ui_state.notify(Toast::error(format!("Could not save config: {}", e)));
+                    }
+                    player
+                        .send(PlayerCommand::Configure(Box::new(config.clone())))
+                        .await?;
+                    if setting == Setting::Theme {
+                        ui_state.layout.theme = Some(config.theme.clone());
+                        ui_state.theme = theme_by_name(&config.theme);
+                        save_layout(&mut app_state, &mut ui_state);
+                    }
+                    if setting == Setting::Icons {
+                        ui_state.icons = icon_set(config.icons);
+                    }
+
+                    if setting.affects_filters() {
+                        player.send(PlayerCommand::ApplyFilters).await?;
+                    } else if setting.affects_volume() {
+                        player.send(PlayerCommand::PushVolume).await?;
+                    }
+                    let levels = player.state();
+                    ui_state.volume = levels.volume;
+                    ui_state.applied_volume = levels.applied_volume;
+                    ui_state.muted = levels.muted;
+                    ui_state.capabilities = levels.capabilities;
+                }
+                ui_state.settings = Some(view.screen(&config));
+                draw_ui(&mut terminal, &ui_state, &stations);
+            }
+            Event_::Key(key_code, modifiers, _) => {
+                match key_code {
+                    // Letters in the focused station list move its cursor to
+                    // the next station starting with them; Esc leaves the list
+                    KeyCode::Char(ch)
+                        if ui_state.focus == Focus::Stations
+                            && ch.is_alphanumeric()
+                            && !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                    {
+                        let cursor = ui_state.station_cursor.min(stations.len() - 1);
+                        if let Some(i) = next_station_with_letter(&stations, cursor, ch) {
+                            ui_state.station_cursor = i;
+                            draw_ui(&mut terminal, &ui_state, &stations);
+                        }
+                    }
+                    KeyCode::Esc if ui_state.focus == Focus::Stations => {
+                        ui_state.focus = Focus::Status;
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Pane focus: the arrow keys act on the focused pane
+                    KeyCode::Tab | KeyCode::BackTab => {
+                        let history_shown =
+                            player_layout(terminal.size()?, &ui_state.layout, stations.len())
+                                .history
+                                .is_some();
+                        ui_state.focus = ui_state
+                            .focus
+                            .cycle(key_code == KeyCode::Tab, history_shown);
+                        ui_state.station_cursor = ui_state.station_index;
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+                    KeyCode::Up | KeyCode::Down if ui_state.focus == Focus::Stations => {
+                        let n = stations.len();
+                        let cursor = ui_state.station_cursor.min(n - 1);
+                        ui_state.station_cursor = if key_code == KeyCode::Up {
+                            (cursor + n - 1) % n
+                        } else {
+                            (cursor + 1) % n
+                        };
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+                    KeyCode::Enter if ui_state.focus == Focus::Stations => {
+                        injected_key = Some(KeyCode::Char('i'));
+                    }
+                    KeyCode::Up
+                    | KeyCode::Down
+                    | KeyCode::PageUp
+                    | KeyCode::PageDown
+                    | KeyCode::Home
+                        if ui_state.focus == Focus::History =>
+                    {
+                        let scroll = ui_state.history_scroll;
+                        ui_state.history_scroll = match key_code {
+                            KeyCode::Up => scroll.saturating_sub(1),
+                            KeyCode::Down => scroll.saturating_add(1),
+                            KeyCode::PageUp => scroll.saturating_sub(10),
+                            KeyCode::PageDown => scroll.saturating_add(10),
+                            _ => 0,
+                        }
+                        .min(ui_state.recent_tracks.len().saturating_sub(1) as u16);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Layout: compact mode, controls pane, station pane size
+                    KeyCode::Char('c') | KeyCode::Char('C')
+                        if !modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        ui_state.layout.compact = !ui_state.layout.compact;
+                        save_layout(&mut app_state, &mut ui_state);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+                    KeyCode::Char('?') => {
+                        ui_state.layout.show_controls = !ui_state.layout.show_controls;
+                        save_layout(&mut app_state, &mut ui_state);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+                    KeyCode::Char('{') | KeyCode::Char('}') => {
+                        let full = stations.len() as u16 + 2;
+                        let current = ui_state.layout.list_height.unwrap_or(full);
+                        let height = if key_code == KeyCode::Char('}') {
+                            current + 1
+                        } else {
+                            current.saturating_sub(1).max(MIN_LIST_HEIGHT)
+                        };
+                        ui_state.layout.list_height = (height != full).then_some(height);
+                        save_layout(&mut app_state, &mut ui_state);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Cycle color theme
+                    KeyCode::Char('t') | KeyCode::Char('T') => {
+                        let i = THEMES
+                            .iter()
+                            .position(|t| t.name == ui_state.theme.name)
+                            .unwrap_or(0);
+                        ui_state.theme = THEMES[(i + 1) % THEMES.len()];
+                        ui_state.layout.theme = Some(ui_state.theme.name.to_string());
+                        config.theme = ui_state.theme.name.to_string();
+                        ui_state.notify(Toast::info(format!("Theme: {}", ui_state.theme.name)));
+                        save_layout(&mut app_state, &mut ui_state);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Open the settings screen (a: straight to the Audio pane)
+                    KeyCode::Char('s')
+                    | KeyCode::Char('S')
+                    | KeyCode::Char('a')
+                    | KeyCode::Char('A') => {
+                        let pane = if matches!(key_code, KeyCode::Char('a') | KeyCode::Char('A')) {
+                            AUDIO_PANE
+                        } else {
+                            0
+                        };
+                        let view = SettingsView::new(pane);
+                        ui_state.settings = Some(view.screen(&config));
+                        settings_view = Some(view);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Command palette
+                    KeyCode::Char('p') | KeyCode::Char('P')
+                        if modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        let p = Palette::new(palette_history.clone());
+                        ui_state.palette = Some(p.screen());
+                        palette = Some(p);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Notification log
+                    KeyCode::Char('j') | KeyCode::Char('J') => {
+                        ui_state.log_scroll = Some(0);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Like / unlike the current track
+                    KeyCode::Char('l') | KeyCode::Char('L') => {
+                        if let Some(track) = ui_state.now_playing.clone() {
+                            ui_state.liked = likes.toggle(&track, &stations[station_index].name);
+                            ui_state.notify(match likes.save() {
+                                Err(e) => Toast::error(format!("Could not save likes: {}", e)),
+                                Ok(()) if ui_state.liked => Toast::info("♥ Liked"),
+                                Ok(()) => Toast::info("Unliked"),
+                            });
+                        } else {
+                            ui_state.notify(Toast::error("No track info to like"));
+                        }
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // ListenAlong: publish this session for `lofi_rs join`
+                    KeyCode::Char('p') | KeyCode::Char('P')
+                        if !modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        if !config.share.is_configured() {
+                            ui_state.notify(Toast::error(
+                                "Set [share] endpoint or gist_token in the config",
+                            ));
+                        } else if sharing {
+                            sharing = false;
+                            ui_state.notify(Toast::info("Stopped sharing"));
+                        } else {
+                            sharing = true;
+                            spawn_publish(
+                                config.share.clone(),
+                                SharedSession::new(&stations[station_index], unix_now()),
+                                share_tx.clone(),
+                            );
+                            ui_state.notify(Toast::info("Publishing session…"));
+                        }
+                        ui_state.sharing = sharing;
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Open the homepage (o) or a search for the track (O)
+                    KeyCode::Char('o') | KeyCode::Char('O') => {
+                        let link = link_for(
+                            &stations[station_index],
+                            ui_state.now_playing.as_deref(),
+                            key_code == KeyCode::Char('O'),
+                        );
+                        open_link(link, &mut ui_state);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Station details and notes (of the station under the
+                    // cursor while the list has focus)
+                    KeyCode::Char('i') | KeyCode::Char('I') => {
+                        let index = if ui_state.focus == Focus::Stations {
+                            ui_state.station_cursor.min(stations.len() - 1)
+                        } else {
+                            station_index
+                        };
+                        let station = &stations[index];
+                        if station.lacks_info()
+                            && !ui_state.low_data
+                            && info_requested.insert(station.name.clone())
+                        {
+                            spawn_lookup(
+                                station.name.clone(),
+                                station.url.clone(),
+                                info_tx.clone(),
+                            );
+                        }
+                        let playing = (index == station_index).then(|| player.state());
+                        ui_state.detail =
+                            Some(station_detail(station, &stats, playing.as_ref(), 0, None));
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Listening stats and most heard tracks
+                    KeyCode::Char('h') | KeyCode::Char('H') => {
+                        stats.add_listen_time(
+                            &stations[station_index].name,
+                            station_started.elapsed(),
+                        );
+                        station_started = Instant::now();
+                        let mut screen = stats.screen();
+                        if let Ok(queue) = listen_queue.lock() {
+                            screen.queued_listens = queue.len();
+                            screen.queue_error = queue.last_error().map(str::to_string);
+                        }
+                        screen.goal = stats.goal_progress(config.daily_goal_minutes);
+                        screen.data = format!(
+                            "~{} this session, {}",
+                            format_bytes(meter.session()),
+                            meter.month_label(stats.month_download())
+                        );
+                        ui_state.stats = Some(screen);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Output device picker
+                    KeyCode::Char('e') | KeyCode::Char('E') => {
+                        if config.airplay {
+                            enable_airplay_discovery().await;
+                        }
+                        let picker = OutputPicker::open().await;
+                        ui_state.output = Some(picker.screen());
+                        output_picker = Some(picker);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Ambient noise mixer
+                    KeyCode::Char('n') | KeyCode::Char('N') => {
+                        ui_state.ambient = Some(ambient.screen());
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Mixer pane
+                    KeyCode::Char('v') | KeyCode::Char('V') => {
+                        ui_state.mixer =
+                            Some(mixer.screen(&stations[station_index], ui_state.volume));
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Beats on/off, next preset
+                    KeyCode::Char('g') | KeyCode::Char('G') => {
+                        let result = if key_code == KeyCode::Char('G') {
+                            beats.set_preset(None)
+                        } else {
+                            beats.toggle()
+                        };
+                        ui_state.notify(match result {
+                            Ok(()) => beats_toast(&beats),
+                            Err(e) => Toast::error(e),
+                        });
+                        ui_state.beats = beats.label();
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Saved recordings
+                    KeyCode::Char('f') | KeyCode::Char('F') => {
+                        let view = RecordingsView::open(&config.recording);
+                        ui_state.recordings = Some(view.screen());
+                        recordings_view = Some(view);
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Night mode on or off by hand, until the schedule changes
+                    KeyCode::Char('z') | KeyCode::Char('Z') => {
+                        night.toggle(chrono::Local::now().time());
+                    }
+
+                    // Start the first configured rotation, or stop the one playing
+                    KeyCode::Char('w') | KeyCode::Char('W') => {
+                        if let Some(run) = rotation.take() {
+                            ui_state.rotation = None;
+                            ui_state
+                                .notify(Toast::info(format!("Rotation {} stopped", run.name())));
+                        } else if let Some(first) = config.rotations.first() {
+                            rotation_to = Some(first.clone());
+                        } else {
+                            ui_state.notify(Toast::warning(
+                                "No rotations — add [[rotations]] to the config",
+                            ));
+                        }
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Next installed player backend
+                    KeyCode::Char('k') | KeyCode::Char('K') => match next_player(player_type) {
+                        Some(next) => backend_to = Some(next),
+                        None => {
+                            ui_state.notify(Toast::warning(format!(
+                                "No other backend installed than {}",
+                                player_type.name()
+                            )));
+                            draw_ui(&mut terminal, &ui_state, &stations);
+                        }
+                    },
+
+                    // Timeline of station switches
+                    KeyCode::Char('y') | KeyCode::Char('Y') => {
+                        ui_state.timeline =
+                            Some(history::timeline_screen(&history::load(), unix_now()));
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Push-to-duck / momentary boost (hold)
+                    code if hold_level_for(code).is_some() => {
+                        let level = hold_level_for(code);
+                        held_key = Some((code, Instant::now()));
+                        player.send(PlayerCommand::Hold(level)).await?;
+                    }
+
+                    // Volume up / down; held keys speed up and the backend
+                    // is updated at most every VOLUME_APPLY_INTERVAL
+                    KeyCode::F(11) | KeyCode::Up | KeyCode::F(10) | KeyCode::Down => {
+                        let up = matches!(key_code, KeyCode::F(11) | KeyCode::Up);
+                        let now = Instant::now();
+                        let held_since = match volume_repeat {
+                            Some((was_up, since, last))
+                                if was_up == up && now - last < HOLD_RELEASE_TIMEOUT =>
+                            {
+                                since
+                            }
+                            _ => now,
+                        };
+                        volume_repeat = Some((up, held_since, now));
+                        let steps = if now - held_since >= VOLUME_ACCEL_AFTER {
+                            2
+                        } else {
+                            1
+                        };
+                        player
+                            .send(PlayerCommand::StepVolume(if up { steps } else { -steps }))
+                            .await?;
+                        let levels = player.state();
+                        ui_state.volume = levels.volume;
+                        ui_state.applied_volume = levels.applied_volume;
+                        ui_state.muted = levels.muted;
+                        ui_state.notify(Toast::info(if levels.capabilities.runtime_volume {
+                            format!("Volume {}%", levels.volume)
+                        } else {
+                            format!(
+                                "Volume {}% — {} takes it on the next start",
+                                levels.volume,
+                                player_type.name()
+                            )
+                        }));
+                        if last_volume_apply.elapsed() >= VOLUME_APPLY_INTERVAL {
+                            player.send(PlayerCommand::PushVolume).await?;
+                            last_volume_apply = Instant::now();
+                            volume_pending = false;
+                            ui_state.applied_volume = player.state().applied_volume;
+                        } else {
+                            volume_pending = true;
+                        }
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Timeshift: ←/→ seek while there is a buffer, End goes live
+                    KeyCode::Left | KeyCode::Right | KeyCode::End
+                        if ui_state.timeshift.is_some() =>
+                    {
+                        let secs = match key_code {
+                            KeyCode::Left => Some(-SEEK_STEP),
+                            KeyCode::Right => Some(SEEK_STEP),
+                            _ => None,
+                        };
+                        if let Err(e) = player.send(PlayerCommand::Seek(secs)).await {
+                            ui_state.notify(Toast::error(format!("Seek failed: {}", e)));
+                        }
+                        ui_state.timeshift = player.state().timeshift;
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Previous station (from the one shown, which may still be starting)
+                    KeyCode::F(7) | KeyCode::Left => {
+                        let shown = ui_state.station_index;
+                        switch_to = Some(if shown == 0 {
+                            stations.len() - 1
+                        } else {
+                            shown - 1
+                        });
+                    }
+
+                    // Next station
+                    KeyCode::F(9) | KeyCode::Right => {
+                        switch_to = Some((ui_state.station_index + 1) % stations.len());
+                    }
+
+                    // Back to the station played before; again goes further back
+                    KeyCode::Char('u') | KeyCode::Char('U') => {
+                        let shown = ui_state.station_index;
+                        let back = undo.back(|name| {
+                            stations
+                                .iter()
+                                .position(|s| s.name == name)
+                                .filter(|&i| i != shown)
+                        });
+                        match back {
+                            Some(i) => {
+                                switch_to = Some(i);
+                                undo_target = Some(i);
+                            }
+                            None => {
+                                ui_state.notify(Toast::info("No earlier station to go back to"));
+                                draw_ui(&mut terminal, &ui_state, &stations);
+                            }
+                        }
+                    }
+
+                    // Cycle station list sort mode
+                    KeyCode::F(6) => {
+                        let current = stations[station_index].name.clone();
+                        app_state.station_sort = app_state.station_sort.next();
+                        sort_stations(
+                            &mut stations,
+                            app_state.station_sort,
+                            &stats,
+                            &config.stations,
+                        );
+                        station_index =
+                            stations.iter().position(|s| s.name == current).unwrap_or(0);
+                        ui_state.station_index = station_index;
+                        ui_state.station_sort = app_state.station_sort;
+                        ui_state.notify(Toast::info(format!(
+                            "Sort: {}",
+                            app_state.station_sort.label()
+                        )));
+                        if let Err(e) = app_state.save() {
+                            ui_state
+                                .notify(Toast::error(format!("Could not save UI state: {}", e)));
+                        }
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // First play after starting paused
+                    KeyCode::F(8) if waiting_for_play => {
+                        waiting_for_play = false;
+                        stopped = false;
+                        supervisor
+                            .reset(stations[station_index].reconnect_policy(config.reconnect));
+                        player.send(PlayerCommand::Play(stream_url.clone())).await?;
+                        ui_state.notify(Toast::info(format!("▶ {}", stations[station_index].name)));
+                        if let Err(e) = ambient.start().and_then(|()| beats.start()) {
+                            ui_state.notify(Toast::error(e));
+                        }
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Play/Pause (mute toggle via F8)
+                    KeyCode::F(8) => {
+                        let paused = !player.state().muted;
+                        player.send(PlayerCommand::Pause(paused)).await?;
+                        auto_paused = false;
+                        let levels = player.state();
+                        ui_state.volume = levels.volume;
+                        ui_state.applied_volume = levels.applied_volume;
+                        ui_state.muted = levels.muted;
+                        ui_state.notify(Toast::info(match levels.muted {
+                            true if levels.capabilities.pause => "Paused".to_string(),
+                            true => format!("Muted — {} cannot pause", player_type.name()),
+                            false => "Playing".to_string(),
+                        }));
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Retry after the reconnect supervisor gave up
+                    KeyCode::Char('r') | KeyCode::Char('R') if stopped => {
+                        stopped = false;
+                        waiting_for_play = false;
+                        supervisor
+                            .reset(stations[station_index].reconnect_policy(config.reconnect));
+                        ui_state.notify(Toast::info(format!(
+                            "Retrying {}…",
+                            stations[station_index].name
+                        )));
+                        player.send(PlayerCommand::Play(stream_url.clone())).await?;
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Remove a station flagged as likely dead
+                    KeyCode::Char('x') | KeyCode::Char('X')
+                        if ui_state.likely_dead.contains(&stations[station_index].name) =>
+                    {
+                        let name = stations[station_index].name.clone();
+                        ui_state.confirm = Some(ConfirmScreen {
+                            title: "Remove station".to_string(),
+                            question: format!("Remove {} from the config?", name),
+                        });
+                        confirm = Some(ConfirmAction::RemoveStation(name));
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Resume after an output-device change paused playback
+                    KeyCode::Char('r') | KeyCode::Char('R') if auto_paused => {
+                        player.send(PlayerCommand::Pause(false)).await?;
+                        auto_paused = false;
+                        let levels = player.state();
+                        ui_state.volume = levels.volume;
+                        ui_state.applied_volume = levels.applied_volume;
+                        ui_state.muted = levels.muted;
+                        ui_state.notify(Toast::info("Resumed"));
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Mute toggle (F12 / m / M)
+                    KeyCode::F(12) | KeyCode::Char('m') | KeyCode::Char('M') => {
+                        let muted = !player.state().muted;
+                        player.send(PlayerCommand::Mute(muted)).await?;
+                        let levels = player.state();
+                        ui_state.volume = levels.volume;
+                        ui_state.applied_volume = levels.applied_volume;
+                        ui_state.muted = levels.muted;
+                        ui_state.notify(Toast::info(if levels.muted {
+                            "Muted"
+                        } else {
+                            "Unmuted"
+                        }));
+                        draw_ui(&mut terminal, &ui_state, &stations);
+                    }
+
+                    // Playback speed (on-demand sources only)
+                    KeyCode::Char('[') | KeyCode::Char(']') => {
+                        let on_demand = !stations[station_index].live;
+                        if on_demand && player.state().capabilities.speed {
+                            player
+                                .send(PlayerCommand::StepSpeed(key_code == KeyCode::Char(']')))
+                                .await?;
+                            let speed = player.state().speed;
+                            speed_memory.insert(stream_url.clone(), speed);
+                            ui_state.speed = Some(speed);
+                            ui_state.notify(Toast::info(format!("Speed {:.2}×", speed)));
+                            draw_ui(&mut terminal, &ui_state, &stations);
+                        }
+                    }
+
+                    // Quit
+                    KeyCode::Char('q') | KeyCode::Char('Q') => break,
+
+                    // Ctrl+C (non-unix fallback via keyboard)
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+
+                    _ => {}
+                }
+            }
+        }
+
+        // ── Suspend requested above: pause, stop, resume on `fg` ─────────
+        if std::mem::take(&mut suspend_requested) {
+            let _ = player.send(PlayerCommand::Stop).await;
+            if headless {
+                suspend_headless();
+            } else {
+                suspend_to_shell(key_release_events)?;
+                terminal.clear()?;
+            }
+            if !stopped && !suspended {
+                player.send(PlayerCommand::Play(stream_url.clone())).await?;
+                supervisor.on_spawn();
+            }
+            draw_ui(&mut terminal, &ui_state, &stations);
         }
+
+        // ── Rotation start requested above: its first station plays now ─
+        if let Some(requested) = rotation_to.take() {
+            match RotationRun::start(&requested) {
+                Some(run) => {
+                    let (target, toast) = rotation_step(&run, &stations);
+                    switch_to = target.filter(|&i| i != station_index || stopped);
+                    ui_state.notify(toast);
+                    ui_state.rotation = Some(run.label());
+                    rotation = Some(run);
+                }
+                None => ui_state.notify(Toast::warning(format!(
+                    "Rotation {} has no steps",
+                    requested.name
+                ))),
+            }
+            draw_ui(&mut terminal, &ui_state, &stations);
+        }
+
+        // ── Backend swap requested above: same station and levels ───────
+        if let Some(target) = backend_to.take() {
+            if !player_available(target) {
+                ui_state.notify(Toast::error(format!("{} is not installed", target.name())));
+            } else {
+                match player.send(PlayerCommand::SetBackend(target)).await {
+                    Ok(()) => {
+                        player_type = target;
+                        mixer.set_player_type(target);
+                        supervisor.on_spawn();
+                        ui_state.speed = ui_state.speed.map(|_| player.state().speed);
+                        ui_state.backend = target.name();
+                        ui_state.capabilities = player.state().capabilities;
+                        ui_state.notify(Toast::info(format!("Backend: {}", target.name())));
+                    }
+                    Err(LofiError::SpawnFailed { reason, .. }) => {
+                        ui_state.notify(Toast::error(format!(
+                            "Could not start {}, staying on {}: {}",
+                            target.name(),
+                            player_type.name(),
+                            reason
+                        )))
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            draw_ui(&mut terminal, &ui_state, &stations);
+        }
+
+        // ── Station switch requested above ───────────────────────────────
+        if pending_switch && switch_to.is_some() {
+            // The previous switch is still starting its player: show the
+            // choice now, start it when that one is done
+            queued_switch = switch_to.take();
+            ui_state.station_index = queued_switch.unwrap_or(station_index);
+            draw_ui(&mut terminal, &ui_state, &stations);
+        } else if let Some(target) = switch_to.take() {
+            stats.add_listen_time(&stations[station_index].name, station_started.elapsed());
+            submit_listen(
+                listen.take(),
+                &config.scrobble,
+                submitter.as_ref(),
+                &listen_queue,
+                &queue_wake,
+            );
+            if undo_target.take() != Some(target) {
+                undo.push(&stations[station_index].name);
+            }
+            station_index = target;
+            stream_url = stations[station_index]
+                .stream_url(ui_state.low_bitrate())
+                .to_string();
+            let _ = md_tx.send(metadata_source(&stations[station_index]));
+            *now_playing_state.lock().await = None;
+            let speed = remembered_speed(&speed_memory, &stations[station_index]);
+            ui_state.speed = (!stations[station_index].live).then_some(speed);
+
+            // The player task swaps the players while the UI keeps running;
+            // `PlayerEvent::Started` reports when it is done
+            player.post(PlayerCommand::Switch {
+                url: stream_url.clone(),
+                speed,
+            });
+            pending_switch = true;
+            ui_state.switching = true;
+            ticker.request(TickClient::Spinner, Some(SPINNER_FRAME));
+            stopped = false;
+            waiting_for_play = false;
+            if !auto_paused {
+                ui_state.notify(Toast::info(format!("▶ {}", stations[station_index].name)));
+            }
+            supervisor.reset(stations[station_index].reconnect_policy(config.reconnect));
+            stats.record_play(&stations[station_index].name);
+            station_started = Instant::now();
+            let _ = stats.save();
+            let _ = history::record(Some(&stations[station_index].name));
+            summary.station(&stations[station_index].name);
+            if sharing {
+                spawn_publish(
+                    config.share.clone(),
+                    SharedSession::new(&stations[station_index], unix_now()),
+                    share_tx.clone(),
+                );
+            }
+            ui_state.station_index = station_index;
+            draw_ui(&mut terminal, &ui_state, &stations);
+        }
+    }
+
+    // Queued behind a pending switch, so its player goes as well
+    let _ = player.send(PlayerCommand::Stop).await;
+    focus.release().await;
+    if let Some(recording) = &recorder {
+        recording.stop();
+    }
+
+    // Record listening time of the last station; its track waits in the
+    // queue for the next run
+    stats.add_listen_time(&stations[station_index].name, station_started.elapsed());
+    submit_listen(
+        listen.take(),
+        &config.scrobble,
+        submitter.as_ref(),
+        &listen_queue,
+        &queue_wake,
+    );
+    let streams =
+        u32::from(!stopped && !suspended && !waiting_for_play) + u32::from(recorder.is_some());
+    stats.add_download(meter.sample(player.state().bitrate, streams));
+    summary.downloaded = meter.session();
+    let _ = stats.save();
+    let _ = history::record(None);
+    if let Some(path) = &chime_wav {
+        let _ = std::fs::remove_file(path);
+    }
+    ambient.stop();
+    beats.disable();
+    mixer.pause();
+    noise::remove_loop_wavs();
+    beats::remove_wav();
+    status::clear();
+    snapshot::clear();
+    if config.remote.enabled && config.remote.socket {
+        let _ = std::fs::remove_file(remote::socket_path());
     }
 
     // Restore terminal
-    terminal.clear()?;
-    disable_raw_mode()?;
-    {
+    if key_release_events {
+        let _ = execute!(std::io::stdout(), PopKeyboardEnhancementFlags);
+    }
+    if !headless {
+        terminal.clear()?;
+        disable_raw_mode()?;
         let mut stdout = std::io::stdout();
-        let _ = execute!(stdout, Show);
+        let _ = execute!(stdout, DisableBracketedPaste, DisableMouseCapture, Show);
     }
-    println!();
+    println!("{}", summary);
 
     Ok(())
 }
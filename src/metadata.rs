@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Separators tried, in order, between artist and title in stream titles.
+const SEPARATORS: [&str; 5] = [" — ", " – ", " - ", " | ", " / "];
+
+/// Which side of the separator the artist is on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FieldOrder {
+    /// "Artist - Title", the usual ICY convention.
+    #[default]
+    ArtistTitle,
+    /// "Title | Artist".
+    TitleArtist,
+}
+
+/// Per-station rules for reading artist and title out of its metadata,
+/// under `[stations.metadata]`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MetadataRules {
+    /// Separator between the two fields; the common ones (" - ", " – ",
+    /// " — ", " | ", " / ") are tried when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separator: Option<String>,
+    pub order: FieldOrder,
+    /// Text removed wherever it appears, such as a station tag the stream
+    /// appends to every title.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub strip: Vec<String>,
+}
+
+impl MetadataRules {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A track as far as the metadata tells: artist when it can be told apart.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Track {
+    pub artist: Option<String>,
+    pub title: String,
+}
+
+impl fmt::Display for Track {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.artist {
+            Some(artist) => write!(f, "{} — {}", artist, self.title),
+            None => f.write_str(&self.title),
+        }
+    }
+}
+
+impl Track {
+    /// A track from separate artist and title fields (metadata APIs).
+    /// `None` when both are empty.
+    pub fn from_parts(artist: &str, title: &str, rules: &MetadataRules) -> Option<Track> {
+        let artist = Some(clean(artist, rules)).filter(|a| !a.is_empty());
+        let title = clean(title, rules);
+        match (artist, title.is_empty()) {
+            (None, true) => None,
+            (Some(artist), true) => Some(Track {
+                artist: None,
+                title: artist,
+            }),
+            (artist, false) => Some(Track { artist, title }),
+        }
+    }
+
+    /// Split a stream title into artist and title after `rules`. A title
+    /// without a separator is kept whole. `None` when nothing is left.
+    pub fn parse(raw: &str, rules: &MetadataRules) -> Option<Track> {
+        let text = clean(raw, rules);
+        if text.is_empty() {
+            return None;
+        }
+        let split = match rules.separator.as_deref() {
+            Some(sep) => text.split_once(sep),
+            None => SEPARATORS.iter().find_map(|sep| text.split_once(sep)),
+        };
+        let Some((first, second)) = split else {
+            return Some(Track {
+                artist: None,
+                title: text,
+            });
+        };
+        let (artist, title) = match rules.order {
+            FieldOrder::ArtistTitle => (first, second),
+            FieldOrder::TitleArtist => (second, first),
+        };
+        Track::from_parts(artist, title, &MetadataRules::default())
+    }
+}
+
+/// Decode entities, normalize characters and whitespace, apply `strip`.
+fn clean(text: &str, rules: &MetadataRules) -> String {
+    let mut text = normalize_chars(&decode_entities(text));
+    for strip in &rules.strip {
+        if !strip.is_empty() {
+            text = text.replace(strip.as_str(), " ");
+        }
+    }
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    text.trim_matches(|c: char| c == '-' || c == '|' || c == '/' || c.is_whitespace())
+        .to_string()
+}
+
+/// Decode the HTML entities stream metadata is often escaped with.
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                entity => {
+                    let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Base letters and their precomposed forms, per combining mark.
+const COMPOSITIONS: [(char, &str, &str); 7] = [
+    ('\u{300}', "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+    ('\u{301}', "aeiouyAEIOUY", "áéíóúýÁÉÍÓÚÝ"),
+    ('\u{302}', "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+    ('\u{303}', "anoANO", "ãñõÃÑÕ"),
+    ('\u{308}', "aeiouyAEIOU", "äëïöüÿÄËÏÖÜ"),
+    ('\u{30A}', "aA", "åÅ"),
+    ('\u{327}', "cC", "çÇ"),
+];
+
+/// Map look-alike characters to one form: precomposed accents (the common
+/// Latin cases of NFC), plain spaces and hyphens, no zero-width or control
+/// characters.
+fn normalize_chars(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        let composed =
+            COMPOSITIONS
+                .iter()
+                .find(|(mark, ..)| *mark == c)
+                .and_then(|(_, bases, composed)| {
+                    let base = out.chars().last()?;
+                    let i = bases.chars().position(|b| b == base)?;
+                    composed.chars().nth(i)
+                });
+        if let Some(composed) = composed {
+            out.pop();
+            out.push(composed);
+            continue;
+        }
+        match c {
+            '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' => {}
+            '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2212}' => out.push('-'),
+            c if c.is_whitespace() => out.push(' '),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
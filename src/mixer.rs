@@ -0,0 +1,183 @@
+use std::time::{Duration, Instant};
+
+use crate::backend::PlayerBackend;
+use crate::config::Station;
+use crate::player::{PlayerType, VolumeControl};
+use crate::ui::MixerScreen;
+
+/// Level a second stream starts at, quiet under the main one.
+const SIDE_DEFAULT_VOLUME: u32 = 30;
+/// A second stream that exits sooner than this after starting is dropped
+/// instead of restarted.
+const SIDE_MIN_UPTIME: Duration = Duration::from_secs(10);
+
+/// A second station played under the main one, in its own player with its
+/// own volume (e.g. a talk stream quietly under lofi).
+pub struct SideStream {
+    pub station: Station,
+    control: VolumeControl,
+    player: Option<Box<dyn PlayerBackend>>,
+    started: Instant,
+}
+
+impl SideStream {
+    fn kill(&mut self) {
+        if let Some(mut player) = self.player.take() {
+            player.start_kill();
+        }
+    }
+
+    async fn spawn(&mut self) -> Result<(), String> {
+        self.kill();
+        let player = self
+            .control
+            .start(&self.station.url, self.control.volume)
+            .await
+            .map_err(|e| format!("Could not start {}: {}", self.station.name, e))?;
+        self.player = Some(player);
+        self.started = Instant::now();
+        Ok(())
+    }
+}
+
+impl Drop for SideStream {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+/// The Mixer pane (v): the main stream's level and an optional second one.
+pub struct Mixer {
+    player_type: PlayerType,
+    /// Start the second player sandboxed too (`sandbox_player`).
+    sandbox: bool,
+    side: Option<SideStream>,
+    /// Row under the cursor: 0 main, 1 second stream.
+    pub selected: usize,
+}
+
+impl Mixer {
+    pub fn new(player_type: PlayerType, sandbox: bool) -> Self {
+        Self {
+            player_type,
+            sandbox,
+            side: None,
+            selected: 0,
+        }
+    }
+
+    /// Start second streams with `player_type` from now on; one already
+    /// playing keeps its player.
+    pub fn set_player_type(&mut self, player_type: PlayerType) {
+        self.player_type = player_type;
+    }
+
+    pub fn side_station(&self) -> Option<&Station> {
+        self.side.as_ref().map(|s| &s.station)
+    }
+
+    pub fn move_selection(&mut self, delta: i32) {
+        self.selected = (self.selected as i32 + delta).clamp(0, 1) as usize;
+    }
+
+    /// Play `station` as the second stream, or stop it with `None`.
+    pub async fn set_side(&mut self, station: Option<&Station>) -> Result<(), String> {
+        let volume = self
+            .side
+            .as_ref()
+            .map(|s| s.control.volume)
+            .unwrap_or(SIDE_DEFAULT_VOLUME);
+        self.side = None;
+        let Some(station) = station else {
+            return Ok(());
+        };
+        let mut control = VolumeControl::new(self.player_type);
+        control.instance = "lofi_side";
+        control.sandbox = self.sandbox;
+        control.volume = volume;
+        let mut side = SideStream {
+            station: station.clone(),
+            control,
+            player: None,
+            started: Instant::now(),
+        };
+        side.spawn().await?;
+        self.side = Some(side);
+        Ok(())
+    }
+
+    /// Raise or lower the second stream by `steps` volume steps.
+    pub async fn adjust_side(&mut self, steps: i32) -> Result<(), String> {
+        let Some(side) = self.side.as_mut() else {
+            return Ok(());
+        };
+        // afplay only has the system-wide volume, which would move both streams
+        if matches!(self.player_type, PlayerType::Afplay) {
+            return Err("afplay cannot set the second stream's own volume".to_string());
+        }
+        if steps > 0 {
+            side.control.increase_volume(steps as u32);
+        } else {
+            side.control.decrease_volume(steps.unsigned_abs());
+        }
+        let applied = match side.player.as_mut() {
+            Some(player) => side.control.apply_volume(player.as_mut()).await.is_ok(),
+            None => false,
+        };
+        if !applied {
+            side.spawn().await?;
+        }
+        Ok(())
+    }
+
+    /// Stop the second stream's player, keeping it selected (system sleep).
+    pub fn pause(&mut self) {
+        if let Some(side) = self.side.as_mut() {
+            side.kill();
+        }
+    }
+
+    /// Restart the second stream after `pause`.
+    pub async fn resume(&mut self) -> Result<(), String> {
+        match self.side.as_mut() {
+            Some(side) => side.spawn().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Restart a second stream whose player exited. One that keeps failing
+    /// right after starting is dropped; its name is returned.
+    pub async fn keep_alive(&mut self) -> Option<String> {
+        let side = self.side.as_mut()?;
+        let exited = match side.player.as_mut() {
+            Some(player) => player.has_ended(),
+            None => false,
+        };
+        if !exited {
+            return None;
+        }
+        if side.started.elapsed() < SIDE_MIN_UPTIME || side.spawn().await.is_err() {
+            let name = side.station.name.clone();
+            self.side = None;
+            return Some(name);
+        }
+        None
+    }
+
+    /// Snapshot handed to the UI.
+    pub fn screen(&self, main: &Station, main_volume: u32) -> MixerScreen {
+        let mut rows = vec![("Main".to_string(), main.name.clone(), Some(main_volume))];
+        rows.push(match &self.side {
+            Some(side) => (
+                "Second".to_string(),
+                side.station.name.clone(),
+                Some(side.control.volume),
+            ),
+            None => ("Second".to_string(), "off".to_string(), None),
+        });
+        MixerScreen {
+            rows,
+            selected: self.selected,
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Interval of the other-players poller.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// What `playerctl` prints per player: instance, name, status, volume,
+/// artist, title.
+const FORMAT: &str =
+    "{{playerInstance}}\t{{playerName}}\t{{status}}\t{{volume}}\t{{artist}}\t{{title}}";
+
+/// What other players do while lofi_rs plays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusAction {
+    /// Leave them alone.
+    #[default]
+    Off,
+    /// Pause them, and resume them when lofi_rs stops.
+    Pause,
+    /// Turn them down to `duck_volume`, and back up when lofi_rs stops.
+    Duck,
+}
+
+/// Other media players on the desktop, under `[mpris]`. Linux only: they
+/// are found and controlled over MPRIS with `playerctl`.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MprisConfig {
+    /// What happens to players that are playing when lofi_rs starts.
+    pub focus: FocusAction,
+    /// Their volume while ducked, 0-100.
+    pub duck_volume: u32,
+    /// Show what another player is playing in the status bar.
+    pub show_others: bool,
+    /// Player names (as `playerctl -l` lists them, without the instance)
+    /// never touched or shown. mpv with the MPRIS plugin is lofi_rs itself.
+    pub ignore: Vec<String>,
+}
+
+impl Default for MprisConfig {
+    fn default() -> Self {
+        Self {
+            focus: FocusAction::Off,
+            duck_volume: 20,
+            show_others: false,
+            ignore: vec!["mpv".to_string()],
+        }
+    }
+}
+
+impl MprisConfig {
+    fn ignores(&self, player: &OtherPlayer) -> bool {
+        self.ignore
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&player.name))
+    }
+}
+
+/// Another MPRIS player.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OtherPlayer {
+    /// Bus name suffix used to control it, e.g. "firefox.instance_1_23".
+    instance: String,
+    /// Application name, e.g. "spotify".
+    pub name: String,
+    pub playing: bool,
+    paused: bool,
+    volume: Option<f64>,
+    pub artist: String,
+    pub title: String,
+}
+
+impl OtherPlayer {
+    /// Status bar label: "spotify: Artist — Title".
+    pub fn label(&self) -> String {
+        match (self.artist.is_empty(), self.title.is_empty()) {
+            (_, true) => self.name.clone(),
+            (true, false) => format!("{}: {}", self.name, self.title),
+            (false, false) => format!("{}: {} — {}", self.name, self.artist, self.title),
+        }
+    }
+}
+
+/// Every MPRIS player but the ignored ones; none when `playerctl` is missing.
+async fn players(config: &MprisConfig) -> Vec<OtherPlayer> {
+    let Ok(output) = TokioCommand::new("playerctl")
+        .args(["--all-players", "metadata", "--format", FORMAT])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let (instance, name, status) = (fields.next()?, fields.next()?, fields.next()?);
+            Some(OtherPlayer {
+                instance: instance.to_string(),
+                name: name.to_string(),
+                playing: status == "Playing",
+                paused: status == "Paused",
+                volume: fields.next()?.parse().ok(),
+                artist: fields.next().unwrap_or_default().to_string(),
+                title: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .filter(|p| !p.instance.is_empty() && !config.ignores(p))
+        .collect()
+}
+
+async fn playerctl(instance: &str, args: &[&str]) -> bool {
+    TokioCommand::new("playerctl")
+        .arg("--player")
+        .arg(instance)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|s| s.success())
+}
+
+/// Report the first other player that is playing through `tx` every few
+/// seconds, when it changes; `None` once nothing else plays.
+pub fn spawn_monitor(config: MprisConfig, tx: UnboundedSender<Option<OtherPlayer>>) {
+    if !config.show_others {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut last = None;
+        loop {
+            let playing = players(&config).await.into_iter().find(|p| p.playing);
+            if playing != last {
+                if tx.send(playing.clone()).is_err() {
+                    break;
+                }
+                last = playing;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+/// Audio focus over the other players: taken while lofi_rs plays, given
+/// back when it stops.
+pub struct AudioFocus {
+    config: MprisConfig,
+    /// Players paused or ducked, with the volume to give back when ducked.
+    taken: Vec<(String, Option<f64>)>,
+    held: bool,
+}
+
+impl AudioFocus {
+    pub fn new(config: MprisConfig) -> Self {
+        Self {
+            config,
+            taken: Vec::new(),
+            held: false,
+        }
+    }
+
+    pub fn held(&self) -> bool {
+        self.held
+    }
+
+    /// Pause or duck every other player that is playing; returns their
+    /// names.
+    pub async fn take(&mut self) -> Vec<String> {
+        self.held = true;
+        if self.config.focus == FocusAction::Off {
+            return Vec::new();
+        }
+        let mut names = Vec::new();
+        for player in players(&self.config).await {
+            if !player.playing || self.taken.iter().any(|(i, _)| *i == player.instance) {
+                continue;
+            }
+            let done = match self.config.focus {
+                FocusAction::Pause => playerctl(&player.instance, &["pause"]).await,
+                FocusAction::Duck => {
+                    let level = format!("{:.2}", self.config.duck_volume.min(100) as f64 / 100.0);
+                    player.volume.is_some()
+                        && playerctl(&player.instance, &["volume", &level]).await
+                }
+                FocusAction::Off => false,
+            };
+            if done {
+                let restore = match self.config.focus {
+                    FocusAction::Duck => player.volume,
+                    _ => None,
+                };
+                self.taken.push((player.instance, restore));
+                names.push(player.name);
+            }
+        }
+        names
+    }
+
+    /// Resume what `take` paused, unless it was played or stopped by hand
+    /// since, and turn ducked players back up.
+    pub async fn release(&mut self) {
+        self.held = false;
+        let taken = std::mem::take(&mut self.taken);
+        if taken.is_empty() {
+            return;
+        }
+        let current = players(&self.config).await;
+        for (instance, restore) in taken {
+            let Some(player) = current.iter().find(|p| p.instance == instance) else {
+                continue;
+            };
+            match restore {
+                Some(volume) => {
+                    playerctl(&instance, &["volume", &format!("{:.2}", volume)]).await;
+                }
+                None if player.paused => {
+                    playerctl(&instance, &["play"]).await;
+                }
+                None => {}
+            }
+        }
+    }
+}
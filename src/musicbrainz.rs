@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::cache;
+use crate::metadata::Track;
+use crate::net;
+use crate::state::{cache_dir, state_dir};
+use crate::stats::unix_now;
+
+const SEARCH_URL: &str = "https://musicbrainz.org/ws/2/recording";
+const COVER_ART_URL: &str = "https://coverartarchive.org/release";
+/// MusicBrainz allows one request per second per client.
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+/// Search score (0-100) below which a hit is taken for a different song.
+const MIN_SCORE: u32 = 90;
+/// Tracks not found are looked up again after a week.
+const MISS_RETRY_SECS: u64 = 7 * 24 * 60 * 60;
+/// Tracks found are looked up again after half a year, for new cover art.
+const HIT_REFRESH_SECS: u64 = 182 * 24 * 60 * 60;
+/// Lookups kept in the cache; the oldest go first.
+const CACHE_LEN: usize = 5000;
+
+/// Canonical data for a track, as MusicBrainz has it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Recording {
+    pub artist: String,
+    pub title: String,
+    /// First release the recording appeared on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<String>,
+    /// Year of that release.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<String>,
+    /// Front cover on the Cover Art Archive, when it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artwork: Option<String>,
+}
+
+impl Recording {
+    /// "Release (year)" for the Now Playing title.
+    pub fn release_label(&self) -> Option<String> {
+        let release = self.release.as_deref()?;
+        Some(match &self.year {
+            Some(year) => format!("{} ({})", release, year),
+            None => release.to_string(),
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedLookup {
+    looked_up_at: u64,
+    /// `None` when MusicBrainz had no good match.
+    recording: Option<Recording>,
+}
+
+/// Lookups by [`track_key`](crate::stats::track_key), kept as JSON in the
+/// cache directory so a track is looked up once in a while.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MusicBrainzCache {
+    tracks: HashMap<String, CachedLookup>,
+}
+
+impl MusicBrainzCache {
+    /// Load the cache; a missing or corrupt file starts empty.
+    pub fn load() -> Self {
+        cache_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .or_else(|| std::fs::read_to_string(old_cache_path()?).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = cache_path().ok_or("cannot locate cache directory (HOME unset)")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string(self)?)?;
+        if let Some(old) = old_cache_path() {
+            let _ = std::fs::remove_file(old);
+        }
+        Ok(())
+    }
+
+    /// The cached lookup for `key`: `Some(None)` for a known miss, `None`
+    /// when it has to be looked up (again).
+    pub fn get(&self, key: &str) -> Option<Option<&Recording>> {
+        let cached = self.tracks.get(key)?;
+        let age = unix_now().saturating_sub(cached.looked_up_at);
+        match &cached.recording {
+            Some(recording) if age < HIT_REFRESH_SECS => Some(Some(recording)),
+            None if age < MISS_RETRY_SECS => Some(None),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, key: String, recording: Option<Recording>) {
+        if self.tracks.len() >= CACHE_LEN && !self.tracks.contains_key(&key) {
+            let oldest = self
+                .tracks
+                .iter()
+                .min_by_key(|(_, cached)| cached.looked_up_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.tracks.remove(&oldest);
+            }
+        }
+        self.tracks.insert(
+            key,
+            CachedLookup {
+                looked_up_at: unix_now(),
+                recording,
+            },
+        );
+    }
+}
+
+fn cache_path() -> Option<PathBuf> {
+    Some(cache_dir()?.join("musicbrainz.json"))
+}
+
+/// Where the cache was kept before it moved to the cache directory.
+fn old_cache_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("musicbrainz.json"))
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    recordings: Vec<SearchRecording>,
+}
+
+#[derive(Deserialize)]
+struct SearchRecording {
+    #[serde(default)]
+    score: u32,
+    title: String,
+    #[serde(rename = "artist-credit", default)]
+    artist_credit: Vec<ArtistCredit>,
+    #[serde(default)]
+    releases: Vec<SearchRelease>,
+}
+
+#[derive(Deserialize)]
+struct ArtistCredit {
+    name: String,
+    #[serde(default)]
+    joinphrase: String,
+}
+
+#[derive(Deserialize)]
+struct SearchRelease {
+    id: String,
+    title: String,
+    #[serde(default)]
+    date: Option<String>,
+}
+
+fn client() -> reqwest::Result<reqwest::Client> {
+    // MusicBrainz turns away requests without a descriptive User-Agent
+    net::client_builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent(concat!(
+            "lofi_rs/",
+            env!("CARGO_PKG_VERSION"),
+            " ( https://github.com/xsa-dev/lofi_rs )"
+        ))
+        .build()
+}
+
+/// Wait until the next request is allowed.
+async fn throttle() {
+    static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+    let wait = {
+        let Ok(mut last) = LAST_REQUEST.lock() else {
+            return;
+        };
+        let now = Instant::now();
+        let next = last.map_or(now, |at| (at + MIN_REQUEST_INTERVAL).max(now));
+        *last = Some(next);
+        next - now
+    };
+    tokio::time::sleep(wait).await;
+}
+
+/// Lucene phrase for the search query.
+fn phrase(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Look `track` up; `Ok(None)` when there is no good match.
+pub async fn lookup(track: &Track) -> Result<Option<Recording>, Box<dyn std::error::Error>> {
+    let Some(artist) = track.artist.as_deref() else {
+        return Ok(None);
+    };
+    let query = format!(
+        "recording:{} AND artist:{}",
+        phrase(&track.title),
+        phrase(artist)
+    );
+    let client = client()?;
+    throttle().await;
+    let found: SearchResponse = client
+        .get(SEARCH_URL)
+        .query(&[("query", query.as_str()), ("limit", "1"), ("fmt", "json")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let Some(hit) = found.recordings.into_iter().find(|r| r.score >= MIN_SCORE) else {
+        return Ok(None);
+    };
+    let artist = hit
+        .artist_credit
+        .iter()
+        .map(|credit| format!("{}{}", credit.name, credit.joinphrase))
+        .collect::<String>();
+    let release = hit.releases.into_iter().next();
+    let mut artwork = None;
+    if let Some(release) = &release {
+        // Fetched rather than just checked, so there is a copy on disk
+        let url = format!("{}/{}/front-250", COVER_ART_URL, release.id);
+        if let Ok(response) = client.get(&url).send().await {
+            if response.status().is_success() {
+                if let Ok(bytes) = response.bytes().await {
+                    cache::put_file("artwork", &url, &bytes);
+                }
+                artwork = Some(url);
+            }
+        }
+    }
+    Ok(Some(Recording {
+        artist: if artist.is_empty() {
+            track.artist.clone().unwrap_or_default()
+        } else {
+            artist
+        },
+        title: hit.title,
+        year: release
+            .as_ref()
+            .and_then(|r| r.date.as_deref())
+            .and_then(|date| date.get(..4))
+            .map(str::to_string),
+        release: release.map(|r| r.title),
+        artwork,
+    }))
+}
+
+/// Look `track` up in the background and send the result, tagged with
+/// `key`, through `tx`. Network failures send nothing, so the track is
+/// tried again next time it plays.
+pub fn spawn_lookup(key: String, track: Track, tx: UnboundedSender<(String, Option<Recording>)>) {
+    tokio::spawn(async move {
+        if let Ok(recording) = lookup(&track).await {
+            let _ = tx.send((key, recording));
+        }
+    });
+}
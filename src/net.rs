@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+
+/// Head start of each connection attempt over the next one (RFC 8305
+/// recommends 250 ms).
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+static IP_FAMILY: OnceLock<IpFamily> = OnceLock::new();
+
+/// Longest stream URL taken from outside the config.
+const MAX_URL_LEN: usize = 2048;
+
+/// Characters a shell would act on; a stream URL needs none of them
+/// unescaped.
+const SHELL_CHARS: &[char] = &['\'', '"', '`', '$', '\\'];
+
+/// A plain http(s) URL, for stream URLs that come from elsewhere than the
+/// user's own config (shared sessions, imports, the remote): nothing a
+/// player could read as an option or a local file, and nothing a shell
+/// would expand.
+pub fn is_stream_url(url: &str) -> bool {
+    url.len() <= MAX_URL_LEN
+        && (url.starts_with("http://") || url.starts_with("https://"))
+        && !url
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || SHELL_CHARS.contains(&c))
+}
+
+/// Address family lofi_rs's own connections use, `ip_family` in the config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    /// Race IPv6 and IPv4 and keep whichever connects first.
+    #[default]
+    Auto,
+    Ipv4,
+    Ipv6,
+}
+
+impl IpFamily {
+    fn allows(self, addr: &SocketAddr) -> bool {
+        match self {
+            IpFamily::Auto => true,
+            IpFamily::Ipv4 => addr.is_ipv4(),
+            IpFamily::Ipv6 => addr.is_ipv6(),
+        }
+    }
+}
+
+/// Fix the address family from the config. Later calls have no effect.
+pub fn init(family: IpFamily) {
+    let _ = IP_FAMILY.set(family);
+}
+
+fn family() -> IpFamily {
+    IP_FAMILY.get().copied().unwrap_or_default()
+}
+
+/// `reqwest::Client::builder()` bound to the configured family. Left to
+/// both, reqwest already races them (happy eyeballs, 300 ms head start).
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder();
+    match family() {
+        IpFamily::Auto => builder,
+        IpFamily::Ipv4 => builder.local_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        IpFamily::Ipv6 => builder.local_address(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+    }
+}
+
+/// Connect to `host`, trying its addresses of the configured family in
+/// turn, alternating families, each `ATTEMPT_DELAY` after the previous
+/// one; the first to connect wins. A broken IPv6 route then costs 250 ms
+/// instead of a full connect timeout.
+pub async fn connect(host: &str, port: u16) -> std::io::Result<TcpStream> {
+    let (mut v6, mut v4): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        tokio::net::lookup_host((host, port))
+            .await?
+            .filter(|addr| family().allows(addr))
+            .partition(SocketAddr::is_ipv6);
+    if v6.is_empty() && v4.is_empty() {
+        return Err(std::io::Error::new(
+            ErrorKind::AddrNotAvailable,
+            format!("{} has no address of the configured family", host),
+        ));
+    }
+    // IPv6 first, as resolvers order it on dual-stack hosts
+    v6.reverse();
+    v4.reverse();
+    let mut addrs = Vec::new();
+    while let Some(addr) = v6.pop().or_else(|| v4.pop()) {
+        addrs.push(addr);
+        if let Some(addr) = v4.pop() {
+            addrs.push(addr);
+        }
+    }
+
+    let mut attempts = JoinSet::new();
+    for (i, addr) in addrs.into_iter().enumerate() {
+        attempts.spawn(async move {
+            tokio::time::sleep(ATTEMPT_DELAY * i as u32).await;
+            TcpStream::connect(addr).await
+        });
+    }
+    let mut last_error = None;
+    while let Some(attempt) = attempts.join_next().await {
+        match attempt {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(e)) => last_error = Some(e),
+            Err(_) => {}
+        }
+    }
+    Err(last_error.unwrap_or_else(|| ErrorKind::ConnectionRefused.into()))
+}
@@ -0,0 +1,127 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+/// Quieter evening listening, under `[night]`: between `start` and `end`
+/// the volume is capped lower, bass is cut and loud passages compressed.
+/// `z` turns it on or off by hand until the next scheduled change.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NightConfig {
+    /// Switch on and off by the clock; off, only `z` turns it on.
+    pub enabled: bool,
+    /// Local time it starts, "HH:MM".
+    pub start: String,
+    /// Local time it ends, "HH:MM"; before `start` means the next morning.
+    pub end: String,
+    /// Volume cap while on, 0-100, below the global `max_volume`.
+    pub max_volume: u32,
+    /// Bass cut in dB (low shelf around 150 Hz); 0 leaves bass alone.
+    pub bass_cut_db: f64,
+    /// Compress the dynamic range, so quiet passages need no turning up.
+    pub compress: bool,
+}
+
+impl Default for NightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+            max_volume: 40,
+            bass_cut_db: 6.0,
+            compress: true,
+        }
+    }
+}
+
+impl NightConfig {
+    /// `start` and `end` as times; `None` when either does not parse.
+    pub fn window(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let parse = |text: &str| NaiveTime::parse_from_str(text.trim(), "%H:%M").ok();
+        Some((parse(&self.start)?, parse(&self.end)?))
+    }
+
+    /// Whether the schedule has it on at `now`.
+    fn scheduled(&self, now: NaiveTime) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        match self.window() {
+            Some((start, end)) if start <= end => start <= now && now < end,
+            // Overnight window, e.g. 22:00-07:00
+            Some((start, end)) => now >= start || now < end,
+            None => false,
+        }
+    }
+
+    pub fn profile(&self) -> NightProfile {
+        NightProfile {
+            max_volume: self.max_volume.min(100),
+            bass_cut_db: self.bass_cut_db.clamp(0.0, 24.0),
+            compress: self.compress,
+        }
+    }
+}
+
+/// What the player applies while night mode is on.
+#[derive(Clone, Copy, PartialEq)]
+pub struct NightProfile {
+    pub max_volume: u32,
+    pub bass_cut_db: f64,
+    pub compress: bool,
+}
+
+impl NightProfile {
+    /// lavfi filters for the bass cut and compression.
+    pub fn lavfi_filters(&self) -> Vec<String> {
+        let mut filters = Vec::new();
+        if self.bass_cut_db > 0.0 {
+            filters.push(format!("bass=g=-{:.1}:f=150", self.bass_cut_db));
+        }
+        if self.compress {
+            filters
+                .push("acompressor=threshold=0.0625:ratio=4:attack=20:release=250:makeup=2".into());
+        }
+        filters
+    }
+}
+
+/// Night mode by the clock, with the manual override of `z`.
+pub struct NightMode {
+    config: NightConfig,
+    /// Set by hand; holds until the schedule next changes its mind.
+    manual: Option<bool>,
+    /// What the schedule said last time it was asked.
+    scheduled: bool,
+}
+
+impl NightMode {
+    pub fn new(config: NightConfig, now: NaiveTime) -> Self {
+        Self {
+            scheduled: config.scheduled(now),
+            config,
+            manual: None,
+        }
+    }
+
+    /// Whether it should be on at `now`.
+    pub fn active(&mut self, now: NaiveTime) -> bool {
+        let scheduled = self.config.scheduled(now);
+        if scheduled != self.scheduled {
+            self.scheduled = scheduled;
+            self.manual = None;
+        }
+        self.manual.unwrap_or(scheduled)
+    }
+
+    /// Turn it on if off and the other way round; returns the new state.
+    pub fn toggle(&mut self, now: NaiveTime) -> bool {
+        let on = !self.active(now);
+        self.manual = Some(on);
+        on
+    }
+
+    pub fn profile(&self) -> NightProfile {
+        self.config.profile()
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use tokio::process::{Child, Command as TokioCommand};
+
+use crate::chime::{wav_bytes, CHIME_RATE};
+use crate::config::{runtime_dir, Station};
+use crate::ui::AmbientScreen;
+
+/// URL scheme of the generated stations, e.g. `noise:brown`.
+pub const NOISE_SCHEME: &str = "noise:";
+
+/// Percentage points per ambient mixer key press.
+const AMBIENT_STEP: u32 = 5;
+
+/// Seconds in the WAV loop played by the process backends.
+const LOOP_SECS: usize = 30;
+/// Crossfade between the end and the start of the loop, in seconds.
+const LOOP_FADE_SECS: f32 = 1.0;
+
+/// Procedurally generated ambience.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NoiseKind {
+    White,
+    Pink,
+    Brown,
+    /// Brown noise bed with scattered droplets.
+    Rain,
+}
+
+impl NoiseKind {
+    pub const ALL: [NoiseKind; 4] = [
+        NoiseKind::White,
+        NoiseKind::Pink,
+        NoiseKind::Brown,
+        NoiseKind::Rain,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NoiseKind::White => "white",
+            NoiseKind::Pink => "pink",
+            NoiseKind::Brown => "brown",
+            NoiseKind::Rain => "rain",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.name() == name)
+    }
+
+    /// The kind a `noise:` station URL plays.
+    pub fn from_url(url: &str) -> Option<Self> {
+        Self::parse(url.strip_prefix(NOISE_SCHEME)?)
+    }
+
+    pub fn station(self) -> Station {
+        let name = match self {
+            NoiseKind::White => "White noise",
+            NoiseKind::Pink => "Pink noise",
+            NoiseKind::Brown => "Brown noise",
+            NoiseKind::Rain => "Rain",
+        };
+        Station::new(name, &format!("{}{}", NOISE_SCHEME, self.name()), None)
+    }
+}
+
+/// Append the generated stations missing from `stations` and return the
+/// index of the brown noise one.
+pub fn add_stations(stations: &mut Vec<Station>) -> usize {
+    for kind in NoiseKind::ALL {
+        if !stations
+            .iter()
+            .any(|s| NoiseKind::from_url(&s.url) == Some(kind))
+        {
+            stations.push(kind.station());
+        }
+    }
+    stations
+        .iter()
+        .position(|s| NoiseKind::from_url(&s.url) == Some(NoiseKind::Brown))
+        .unwrap_or(0)
+}
+
+/// Endless noise of one kind as interleaved stereo s16 at `CHIME_RATE`.
+pub struct Noise {
+    kind: NoiseKind,
+    /// xorshift state
+    seed: u32,
+    /// Pink filter taps (Paul Kellet's economy filter).
+    pink: [f32; 3],
+    brown: f32,
+    /// Current droplet: remaining samples and its amplitude.
+    drop: (u32, f32),
+}
+
+impl Noise {
+    pub fn new(kind: NoiseKind) -> Self {
+        Self {
+            kind,
+            seed: 0x9e37_79b9,
+            pink: [0.0; 3],
+            brown: 0.0,
+            drop: (0, 0.0),
+        }
+    }
+
+    /// Uniform sample in -1.0..1.0.
+    fn white(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        let w = self.white();
+        match self.kind {
+            NoiseKind::White => w * 0.25,
+            NoiseKind::Pink => {
+                self.pink[0] = 0.99765 * self.pink[0] + w * 0.0990460;
+                self.pink[1] = 0.96300 * self.pink[1] + w * 0.2965164;
+                self.pink[2] = 0.57000 * self.pink[2] + w * 1.0526913;
+                (self.pink.iter().sum::<f32>() + w * 0.1848) * 0.08
+            }
+            NoiseKind::Brown => {
+                self.brown = (self.brown + w * 0.02) * 0.998;
+                self.brown * 1.5
+            }
+            NoiseKind::Rain => {
+                self.brown = (self.brown + w * 0.02) * 0.998;
+                if self.drop.0 == 0 && self.white() > 0.9995 {
+                    self.drop = (CHIME_RATE / 100, self.white().abs() * 0.3);
+                }
+                let mut v = self.brown;
+                if self.drop.0 > 0 {
+                    self.drop.0 -= 1;
+                    let env = self.drop.0 as f32 / (CHIME_RATE / 100) as f32;
+                    v += w * self.drop.1 * env;
+                }
+                v
+            }
+        }
+    }
+
+    /// Fill `out` with interleaved stereo samples at `gain` (0.0-1.0).
+    pub fn fill(&mut self, out: &mut [i16], gain: f32) {
+        for frame in out.chunks_exact_mut(2) {
+            let v = (self.next_sample() * gain).clamp(-1.0, 1.0);
+            let s = (v * i16::MAX as f32) as i16;
+            frame[0] = s;
+            frame[1] = s;
+        }
+    }
+}
+
+/// A seamless loop of `kind`: the tail is crossfaded into the head.
+fn loop_samples(kind: NoiseKind) -> Vec<i16> {
+    let fade = (CHIME_RATE as f32 * LOOP_FADE_SECS) as usize;
+    let frames = CHIME_RATE as usize * LOOP_SECS;
+    let mut samples = vec![0i16; (frames + fade) * 2];
+    Noise::new(kind).fill(&mut samples, 1.0);
+    let (body, tail) = samples.split_at_mut(frames * 2);
+    for (i, (head, extra)) in body.iter_mut().zip(tail.iter()).enumerate() {
+        let t = (i / 2) as f32 / fade as f32;
+        *head = (*head as f32 * t + *extra as f32 * (1.0 - t)) as i16;
+    }
+    samples.truncate(frames * 2);
+    samples
+}
+
+/// WAV loop of `kind` in the runtime directory, written on first use, for
+/// backends that can only play files.
+pub fn loop_wav(kind: NoiseKind) -> Option<PathBuf> {
+    let path = runtime_dir().join(format!(
+        "lofi_rs_noise_{}_{}.wav",
+        kind.name(),
+        std::process::id()
+    ));
+    if !path.exists() {
+        std::fs::write(&path, wav_bytes(&loop_samples(kind))).ok()?;
+    }
+    Some(path)
+}
+
+/// Remove the WAV loops written by `loop_wav`.
+pub fn remove_loop_wavs() {
+    for kind in NoiseKind::ALL {
+        let path = runtime_dir().join(format!(
+            "lofi_rs_noise_{}_{}.wav",
+            kind.name(),
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// First raw PCM player found: PipeWire, PulseAudio, then ALSA.
+pub fn pcm_output_command() -> Option<(&'static str, Vec<String>)> {
+    let rate = CHIME_RATE.to_string();
+    let candidates: [(&str, Vec<String>); 3] = [
+        (
+            "pw-cat",
+            vec![
+                "--playback".into(),
+                "--format".into(),
+                "s16".into(),
+                "--rate".into(),
+                rate.clone(),
+                "--channels".into(),
+                "2".into(),
+                "-".into(),
+            ],
+        ),
+        (
+            "pacat",
+            vec![
+                "--playback".into(),
+                "--format=s16le".into(),
+                format!("--rate={}", rate),
+                "--channels=2".into(),
+            ],
+        ),
+        (
+            "aplay",
+            vec![
+                "-q".into(),
+                "-f".into(),
+                "S16_LE".into(),
+                "-r".into(),
+                rate,
+                "-c".into(),
+                "2".into(),
+            ],
+        ),
+    ];
+    candidates
+        .into_iter()
+        .find(|(cmd, _)| Command::new(cmd).arg("--version").output().is_ok())
+}
+
+/// `lofi_rs noise KIND`: generate `kind` at `volume` (0-100) into the first
+/// raw PCM player found, until it goes away. This is the built-in backend's
+/// player process, and works on its own from a shell too.
+pub fn play(kind: NoiseKind, volume: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let mut noise = Noise::new(kind);
+    let gain = volume.min(100) as f32 / 100.0;
+    play_pcm(|samples| noise.fill(samples, gain))
+}
+
+/// Feed interleaved stereo samples from `fill` into the first raw PCM
+/// player found until it exits.
+pub fn play_pcm(mut fill: impl FnMut(&mut [i16])) -> Result<(), Box<dyn std::error::Error>> {
+    let (cmd, args) = pcm_output_command().ok_or("no PCM output found (pw-cat, pacat or aplay)")?;
+    let mut sink = Command::new(cmd)
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = sink.stdin.take().ok_or("PCM output has no stdin")?;
+    let mut samples = vec![0i16; CHIME_RATE as usize / 10 * 2];
+    let mut bytes = Vec::with_capacity(samples.len() * 2);
+    loop {
+        fill(&mut samples);
+        bytes.clear();
+        for s in &samples {
+            bytes.extend_from_slice(&s.to_le_bytes());
+        }
+        if stdin.write_all(&bytes).is_err() {
+            break;
+        }
+    }
+    drop(stdin);
+    let _ = sink.wait();
+    Ok(())
+}
+
+/// How generated layers (ambient noise, beats) are played next to the stream.
+#[derive(Clone, Copy)]
+pub enum AmbientBackend {
+    /// A subcommand of this binary writing into a raw PCM player.
+    Builtin,
+    /// mpv or ffplay looping the WAV rendering.
+    Mpv,
+    Ffplay,
+}
+
+impl AmbientBackend {
+    pub fn detect() -> Option<Self> {
+        let has = |cmd: &str, flag: &str| Command::new(cmd).arg(flag).output().is_ok();
+        if pcm_output_command().is_some() {
+            Some(AmbientBackend::Builtin)
+        } else if has("mpv", "--version") {
+            Some(AmbientBackend::Mpv)
+        } else if has("ffplay", "-version") {
+            Some(AmbientBackend::Ffplay)
+        } else {
+            None
+        }
+    }
+
+    /// Start a layer at `level`: `lofi_rs <subcommand...> --volume level`
+    /// for the built-in backend, else a loop of the WAV `wav` writes.
+    pub fn spawn(
+        self,
+        subcommand: &[String],
+        wav: impl FnOnce() -> Option<PathBuf>,
+        level: u32,
+    ) -> std::io::Result<Child> {
+        let (cmd, args) = match self {
+            AmbientBackend::Builtin => {
+                let mut args = subcommand.to_vec();
+                args.extend(["--volume".to_string(), level.to_string()]);
+                (std::env::current_exe()?.display().to_string(), args)
+            }
+            AmbientBackend::Mpv | AmbientBackend::Ffplay => {
+                let wav = wav().ok_or(std::io::ErrorKind::NotFound)?;
+                let wav = wav.display().to_string();
+                match self {
+                    AmbientBackend::Mpv => (
+                        "mpv".to_string(),
+                        vec![
+                            "--no-video".to_string(),
+                            "--no-terminal".to_string(),
+                            "--loop-file=inf".to_string(),
+                            format!("--volume={}", level),
+                            wav,
+                        ],
+                    ),
+                    _ => (
+                        "ffplay".to_string(),
+                        vec![
+                            "-nodisp".to_string(),
+                            "-loglevel".to_string(),
+                            "quiet".to_string(),
+                            "-loop".to_string(),
+                            "0".to_string(),
+                            "-volume".to_string(),
+                            level.to_string(),
+                            wav,
+                        ],
+                    ),
+                }
+            }
+        };
+        TokioCommand::new(cmd)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+    }
+}
+
+/// Noise layers played alongside the stream, each in its own process with
+/// its own level. Changing a level restarts that layer.
+pub struct AmbientMixer {
+    levels: [u32; 4],
+    players: [Option<Child>; 4],
+    /// Detected on first use; `Some(None)` when nothing can play the layers.
+    backend: Option<Option<AmbientBackend>>,
+    selected: usize,
+}
+
+impl AmbientMixer {
+    /// A stopped mixer with the levels remembered in the state file.
+    pub fn new(levels: &BTreeMap<String, u32>) -> Self {
+        let mut mixer = Self {
+            levels: [0; 4],
+            players: Default::default(),
+            backend: None,
+            selected: 0,
+        };
+        for (i, kind) in NoiseKind::ALL.into_iter().enumerate() {
+            mixer.levels[i] = levels.get(kind.name()).copied().unwrap_or(0).min(100);
+        }
+        mixer
+    }
+
+    /// Levels to remember, without the layers that are off.
+    pub fn levels(&self) -> BTreeMap<String, u32> {
+        NoiseKind::ALL
+            .into_iter()
+            .zip(self.levels)
+            .filter(|&(_, level)| level > 0)
+            .map(|(kind, level)| (kind.name().to_string(), level))
+            .collect()
+    }
+
+    /// Start every layer that is turned up.
+    pub fn start(&mut self) -> Result<(), String> {
+        for i in 0..NoiseKind::ALL.len() {
+            self.respawn(i)?;
+        }
+        Ok(())
+    }
+
+    /// Silence every layer, keeping the levels.
+    pub fn stop(&mut self) {
+        self.players = Default::default();
+    }
+
+    pub fn move_selection(&mut self, delta: i32) {
+        let len = NoiseKind::ALL.len() as i32;
+        self.selected = (self.selected as i32 + delta).rem_euclid(len) as usize;
+    }
+
+    /// Raise or lower the selected layer by `steps` mixer steps.
+    pub fn adjust(&mut self, steps: i32) -> Result<(), String> {
+        let level = self.levels[self.selected] as i32 + steps * AMBIENT_STEP as i32;
+        self.levels[self.selected] = level.clamp(0, 100) as u32;
+        self.respawn(self.selected)
+    }
+
+    /// Turn the selected layer off.
+    pub fn mute_selected(&mut self) {
+        self.levels[self.selected] = 0;
+        self.players[self.selected] = None;
+    }
+
+    fn respawn(&mut self, i: usize) -> Result<(), String> {
+        self.players[i] = None;
+        let level = self.levels[i];
+        if level == 0 {
+            return Ok(());
+        }
+        let backend = *self.backend.get_or_insert_with(AmbientBackend::detect);
+        let backend = backend.ok_or("Ambient noise needs pw-cat, pacat, aplay, mpv or ffplay")?;
+        let kind = NoiseKind::ALL[i];
+        let subcommand = ["noise".to_string(), kind.name().to_string()];
+        let child = backend
+            .spawn(&subcommand, || loop_wav(kind), level)
+            .map_err(|e| format!("Could not start {} noise: {}", kind.name(), e))?;
+        self.players[i] = Some(child);
+        Ok(())
+    }
+
+    /// Snapshot handed to the UI.
+    pub fn screen(&self) -> AmbientScreen {
+        AmbientScreen {
+            layers: NoiseKind::ALL
+                .into_iter()
+                .map(|kind| kind.station().name)
+                .zip(self.levels)
+                .collect(),
+            selected: self.selected,
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::process::Stdio;
+use tokio::process::Command as TokioCommand;
+
+/// Show a desktop notification via `notify-send` (Linux) or `osascript`
+/// (macOS). Fire-and-forget: a missing tool is silently ignored.
+pub fn desktop_notify(summary: &str, body: &str) {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let script = format!("display notification {:?} with title {:?}", body, summary);
+        let mut cmd = TokioCommand::new("osascript");
+        cmd.arg("-e").arg(script);
+        cmd
+    } else {
+        let mut cmd = TokioCommand::new("notify-send");
+        cmd.arg("--app-name=lofi_rs").arg(summary).arg(body);
+        cmd
+    };
+    if let Ok(mut child) = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        tokio::spawn(async move {
+            let _ = child.wait().await;
+        });
+    }
+}
+
+/// Open `url` in the default browser via `open` (macOS) or `xdg-open`.
+/// Returns whether the opener could be started.
+pub fn open_url(url: &str) -> bool {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    match TokioCommand::new(opener)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                let _ = child.wait().await;
+            });
+            true
+        }
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,226 @@
+use std::time::Duration;
+use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::bluetooth::{paired_audio_devices, BluetoothDevice};
+use crate::ui::OutputScreen;
+
+/// Change of the system's default audio output.
+#[derive(Clone, Debug)]
+pub enum OutputEvent {
+    /// The default output switched away from `from` (headphones unplugged,
+    /// Bluetooth dropped, dock detached, ...).
+    DeviceChanged { from: String, to: String },
+    /// A Bluetooth device picked in the output picker finished connecting:
+    /// its name and sink, or why it failed.
+    BluetoothReady(String, Result<String, String>),
+}
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Poll the default output device and report changes through `tx`.
+///
+/// When the platform tool is missing (no `pactl` on Linux, no
+/// `SwitchAudioSource` on macOS) the monitor quietly does nothing.
+pub fn spawn_output_monitor(tx: UnboundedSender<OutputEvent>) {
+    tokio::spawn(async move {
+        let Some(mut current) = default_output_device().await else {
+            return;
+        };
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let Some(device) = default_output_device().await else {
+                continue;
+            };
+            if device != current {
+                let from = std::mem::replace(&mut current, device.clone());
+                if tx
+                    .send(OutputEvent::DeviceChanged { from, to: device })
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Name of the current default output device, if it can be determined.
+pub async fn default_output_device() -> Option<String> {
+    let (cmd, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("SwitchAudioSource", &["-c", "-t", "output"])
+    } else {
+        ("pactl", &["get-default-sink"])
+    };
+    let out = TokioCommand::new(cmd).args(args).output().await.ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let name = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+// ─── Output picker ────────────────────────────────────────────────────────────
+
+/// An audio output the system can route to.
+#[derive(Clone)]
+pub struct OutputDevice {
+    /// Name the platform tool knows it by (PulseAudio sink, macOS device).
+    pub id: String,
+    /// Human-readable description.
+    pub name: String,
+    /// AirPlay (RAOP) speaker.
+    pub airplay: bool,
+    /// Paired Bluetooth device that is not connected yet; `id` is empty
+    /// until connecting creates its sink.
+    pub bluetooth: Option<BluetoothDevice>,
+}
+
+async fn run(cmd: &str, args: &[&str]) -> Option<String> {
+    let out = TokioCommand::new(cmd).args(args).output().await.ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Every output device, AirPlay speakers included once discovered.
+pub async fn list_output_devices() -> Vec<OutputDevice> {
+    if cfg!(target_os = "macos") {
+        let Some(text) = run("SwitchAudioSource", &["-a", "-t", "output"]).await else {
+            return Vec::new();
+        };
+        return text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(|name| OutputDevice {
+                id: name.to_string(),
+                name: name.to_string(),
+                airplay: name.contains("AirPlay"),
+                bluetooth: None,
+            })
+            .collect();
+    }
+    let Some(text) = run("pactl", &["list", "sinks"]).await else {
+        return Vec::new();
+    };
+    // "Sink #N" blocks with indented "Name:" and "Description:" lines
+    let mut devices: Vec<OutputDevice> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(id) = line.strip_prefix("Name: ") {
+            devices.push(OutputDevice {
+                id: id.to_string(),
+                name: id.to_string(),
+                airplay: id.starts_with("raop"),
+                bluetooth: None,
+            });
+        } else if let Some(desc) = line.strip_prefix("Description: ") {
+            if let Some(d) = devices.last_mut() {
+                d.name = desc.to_string();
+            }
+        }
+    }
+    devices
+}
+
+/// Make `id` the system's default output.
+pub async fn set_default_output(id: &str) -> Result<(), String> {
+    let (cmd, args): (&str, Vec<&str>) = if cfg!(target_os = "macos") {
+        ("SwitchAudioSource", vec!["-t", "output", "-s", id])
+    } else {
+        ("pactl", vec!["set-default-sink", id])
+    };
+    match TokioCommand::new(cmd).args(&args).output().await {
+        Ok(out) if out.status.success() => Ok(()),
+        Ok(out) => Err(String::from_utf8_lossy(&out.stderr).trim().to_string()),
+        Err(e) => Err(format!("{}: {}", cmd, e)),
+    }
+}
+
+/// Have PulseAudio/PipeWire look for AirPlay speakers on the network; they
+/// then show up as `raop` sinks. macOS lists AirPlay outputs by itself.
+pub async fn enable_airplay_discovery() {
+    if cfg!(target_os = "macos") {
+        return;
+    }
+    let loaded = run("pactl", &["list", "short", "modules"])
+        .await
+        .is_some_and(|m| m.contains("module-raop-discover"));
+    if !loaded {
+        let _ = run("pactl", &["load-module", "module-raop-discover"]).await;
+    }
+}
+
+/// Output device popup state.
+pub struct OutputPicker {
+    pub devices: Vec<OutputDevice>,
+    /// Id of the current default output.
+    pub current: Option<String>,
+    pub selected: usize,
+}
+
+impl OutputPicker {
+    /// Scan the devices, starting with the current one selected. Paired
+    /// Bluetooth devices that are not connected come last.
+    pub async fn open() -> Self {
+        let mut devices = list_output_devices().await;
+        devices.extend(
+            paired_audio_devices()
+                .await
+                .into_iter()
+                .filter(|b| !b.connected)
+                .map(|b| OutputDevice {
+                    id: String::new(),
+                    name: b.name.clone(),
+                    airplay: false,
+                    bluetooth: Some(b),
+                }),
+        );
+        let current = default_output_device().await;
+        let selected = devices
+            .iter()
+            .position(|d| Some(&d.id) == current.as_ref())
+            .unwrap_or(0);
+        Self {
+            devices,
+            current,
+            selected,
+        }
+    }
+
+    pub fn move_selection(&mut self, dir: i32) {
+        let n = self.devices.len() as i32;
+        if n > 0 {
+            self.selected = (self.selected as i32 + dir).rem_euclid(n) as usize;
+        }
+    }
+
+    pub fn selected(&self) -> Option<&OutputDevice> {
+        self.devices.get(self.selected)
+    }
+
+    pub fn screen(&self) -> OutputScreen {
+        OutputScreen {
+            devices: self
+                .devices
+                .iter()
+                .map(|d| {
+                    let label = if d.airplay {
+                        format!("{} (AirPlay)", d.name)
+                    } else if d.bluetooth.is_some() {
+                        format!("{} (Bluetooth, connect)", d.name)
+                    } else {
+                        d.name.clone()
+                    };
+                    (label, Some(&d.id) == self.current.as_ref())
+                })
+                .collect(),
+            selected: self.selected,
+        }
+    }
+}
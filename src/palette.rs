@@ -0,0 +1,377 @@
+use std::time::Duration;
+
+use crate::beats::BeatPreset;
+use crate::config::Station;
+use crate::input::TextInput;
+use crate::player::PlayerType;
+use crate::record::RecordingFormat;
+use crate::stats::StationSort;
+use crate::ui::{PaletteScreen, THEMES};
+
+/// Commands understood by the palette, with the usage shown as a hint.
+const COMMANDS: &[(&str, &str)] = &[
+    ("play", "play <station>"),
+    ("volume", "volume <0-100>"),
+    ("mute", "mute"),
+    ("pause", "pause"),
+    ("next", "next"),
+    ("prev", "prev"),
+    ("undo", "undo"),
+    ("rotate", "rotate [name] | rotate off"),
+    ("macro", "macro <name>"),
+    ("night", "night"),
+    ("low-data", "low-data"),
+    ("sleep", "sleep <minutes> | sleep off"),
+    ("theme", "theme <name>"),
+    ("sort", "sort <manual|a-z|recent|most>"),
+    ("like", "like"),
+    ("stats", "stats"),
+    ("history", "history"),
+    ("settings", "settings"),
+    ("output", "output"),
+    ("noise", "noise"),
+    ("mixer", "mixer"),
+    ("beats", "beats <focus|relax|sleep|off>"),
+    ("share", "share"),
+    (
+        "record",
+        "record [duration] [copy|ogg|opus|flac] | record stop",
+    ),
+    ("recordings", "recordings"),
+    ("backend", "backend [mpv|ffplay|afplay|vlc|gstreamer]"),
+    ("quit", "quit"),
+];
+
+/// A parsed palette command.
+pub enum PaletteCommand {
+    Play(usize),
+    Volume(u32),
+    Mute,
+    Pause,
+    Next,
+    Prev,
+    /// Back to the station played before.
+    Undo,
+    /// Start the rotation best matching the name; `None` starts the first
+    /// one, or stops the one playing.
+    Rotate(Option<String>),
+    StopRotation,
+    /// Run the `[[macros]]` entry best matching the name.
+    Macro(String),
+    /// Night mode on or off by hand.
+    Night,
+    /// Low-data mode on or off.
+    LowData,
+    /// Pause after the duration; `None` cancels the timer.
+    Sleep(Option<Duration>),
+    Theme(&'static str),
+    Sort(StationSort),
+    Like,
+    Stats,
+    History,
+    Settings,
+    Output,
+    Noise,
+    Mixer,
+    /// Beats with a preset; `None` turns them off.
+    Beats(Option<BeatPreset>),
+    Share,
+    /// Record the current station, for a while or until stopped, in a
+    /// format other than the configured one.
+    Record(Option<Duration>, Option<RecordingFormat>),
+    StopRecording,
+    Recordings,
+    /// Switch the player backend; `None` moves to the next installed one.
+    Backend(Option<PlayerType>),
+    Quit,
+}
+
+/// Score `needle` as a case-insensitive subsequence of `haystack`; higher is
+/// better. Consecutive and word-start matches score extra, so "jc" prefers
+/// "Jazz Cafe" over "Just Chill".
+pub fn fuzzy_score(needle: &str, haystack: &str) -> Option<i32> {
+    let hay: Vec<char> = haystack.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut prev_match: Option<usize> = None;
+    for c in needle.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = pos + hay[pos..].iter().position(|&h| h == c)?;
+        score += 1;
+        if prev_match.is_some_and(|p| p + 1 == i) {
+            score += 3;
+        }
+        if i == 0 || !hay[i - 1].is_alphanumeric() {
+            score += 2;
+        }
+        prev_match = Some(i);
+        pos = i + 1;
+    }
+    // Prefer shorter names among equal matches
+    Some(score * 100 - hay.len() as i32)
+}
+
+/// Best fuzzy match of `query` among `candidates`.
+pub fn best_match<'a, I: IntoIterator<Item = &'a str>>(
+    query: &str,
+    candidates: I,
+) -> Option<usize> {
+    candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, c)| fuzzy_score(query, c).map(|s| (i, s)))
+        .max_by_key(|&(_, s)| s)
+        .map(|(i, _)| i)
+}
+
+/// Longest duration the palette takes, for timers and recordings.
+const MAX_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Parse a duration such as "45" (minutes), "45m", "1h" or "90s", up to
+/// [`MAX_DURATION`].
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => text.split_at(i),
+        None => (text, "m"),
+    };
+    let n: u64 = number.parse().ok()?;
+    let secs = match unit.trim() {
+        "s" | "sec" => Some(n),
+        "m" | "min" => n.checked_mul(60),
+        "h" => n.checked_mul(3600),
+        _ => return None,
+    }?;
+    Some(Duration::from_secs(secs)).filter(|&d| d <= MAX_DURATION)
+}
+
+pub fn parse_command(input: &str, stations: &[Station]) -> Result<PaletteCommand, String> {
+    let input = input.trim();
+    let (word, arg) = match input.split_once(char::is_whitespace) {
+        Some((w, a)) => (w, a.trim()),
+        None => (input, ""),
+    };
+    if word.is_empty() {
+        return Err("Type a command".to_string());
+    }
+    // Command names match by prefix first ("vol"), then fuzzily ("vlm")
+    let name = COMMANDS
+        .iter()
+        .map(|(name, _)| *name)
+        .find(|name| name.starts_with(&word.to_lowercase()))
+        .or_else(|| best_match(word, COMMANDS.iter().map(|(n, _)| *n)).map(|i| COMMANDS[i].0));
+    let Some(name) = name else {
+        // A bare station name plays it
+        return match best_match(input, stations.iter().map(|s| s.name.as_str())) {
+            Some(i) => Ok(PaletteCommand::Play(i)),
+            None => Err(format!("Unknown command: {}", word)),
+        };
+    };
+    match name {
+        "play" if arg.is_empty() => Err("Usage: play <station>".to_string()),
+        "play" => best_match(arg, stations.iter().map(|s| s.name.as_str()))
+            .map(PaletteCommand::Play)
+            .ok_or_else(|| format!("No station matches \"{}\"", arg)),
+        "volume" => arg
+            .trim_end_matches('%')
+            .parse::<u32>()
+            .map(|v| PaletteCommand::Volume(v.min(100)))
+            .map_err(|_| "Usage: volume <0-100>".to_string()),
+        "mute" => Ok(PaletteCommand::Mute),
+        "pause" => Ok(PaletteCommand::Pause),
+        "next" => Ok(PaletteCommand::Next),
+        "prev" => Ok(PaletteCommand::Prev),
+        "undo" => Ok(PaletteCommand::Undo),
+        "night" => Ok(PaletteCommand::Night),
+        "low-data" | "lowdata" => Ok(PaletteCommand::LowData),
+        "rotate" if arg == "off" || arg == "stop" => Ok(PaletteCommand::StopRotation),
+        "rotate" if arg.is_empty() => Ok(PaletteCommand::Rotate(None)),
+        "rotate" => Ok(PaletteCommand::Rotate(Some(arg.to_string()))),
+        "macro" if arg.is_empty() => Err("Usage: macro <name>".to_string()),
+        "macro" => Ok(PaletteCommand::Macro(arg.to_string())),
+        "sleep" if arg == "off" || arg == "cancel" => Ok(PaletteCommand::Sleep(None)),
+        "sleep" => parse_duration(arg)
+            .map(|d| PaletteCommand::Sleep(Some(d)))
+            .ok_or_else(|| "Usage: sleep <minutes> | sleep off (up to 24h)".to_string()),
+        "theme" => best_match(arg, THEMES.iter().map(|t| t.name))
+            .map(|i| PaletteCommand::Theme(THEMES[i].name))
+            .ok_or_else(|| format!("No theme matches \"{}\"", arg)),
+        "sort" => {
+            let modes = [
+                StationSort::Manual,
+                StationSort::Alphabetical,
+                StationSort::RecentlyPlayed,
+                StationSort::MostPlayed,
+            ];
+            best_match(arg, modes.iter().map(|m| m.label()))
+                .map(|i| PaletteCommand::Sort(modes[i]))
+                .ok_or_else(|| "Usage: sort <manual|a-z|recent|most>".to_string())
+        }
+        "like" => Ok(PaletteCommand::Like),
+        "stats" => Ok(PaletteCommand::Stats),
+        "history" => Ok(PaletteCommand::History),
+        "settings" => Ok(PaletteCommand::Settings),
+        "output" => Ok(PaletteCommand::Output),
+        "noise" => Ok(PaletteCommand::Noise),
+        "mixer" => Ok(PaletteCommand::Mixer),
+        "beats" if arg == "off" => Ok(PaletteCommand::Beats(None)),
+        "beats" => BeatPreset::ALL
+            .into_iter()
+            .find(|p| !arg.is_empty() && p.name().starts_with(arg))
+            .map(|p| PaletteCommand::Beats(Some(p)))
+            .ok_or_else(|| "Usage: beats <focus|relax|sleep|off>".to_string()),
+        "share" => Ok(PaletteCommand::Share),
+        "record" if arg == "stop" || arg == "off" => Ok(PaletteCommand::StopRecording),
+        "record" => {
+            let usage =
+                || "Usage: record [duration] [copy|ogg|opus|flac] | record stop".to_string();
+            // Duration and format may come in either order
+            let (mut duration, mut format) = (None, None);
+            for word in arg.split_whitespace() {
+                match RecordingFormat::parse(&word.to_lowercase()) {
+                    Some(f) if format.is_none() => format = Some(f),
+                    None if duration.is_none() => {
+                        duration = Some(parse_duration(word).ok_or_else(usage)?)
+                    }
+                    _ => return Err(usage()),
+                }
+            }
+            Ok(PaletteCommand::Record(duration, format))
+        }
+        "recordings" => Ok(PaletteCommand::Recordings),
+        "backend" if arg.is_empty() => Ok(PaletteCommand::Backend(None)),
+        "backend" => PlayerType::parse(&arg.to_lowercase())
+            .map(|p| PaletteCommand::Backend(Some(p)))
+            .ok_or_else(|| "Usage: backend [mpv|ffplay|afplay|vlc|gstreamer]".to_string()),
+        "quit" => Ok(PaletteCommand::Quit),
+        other => Err(format!("Unknown command: {}", other)),
+    }
+}
+
+/// Text typed into the palette (Ctrl+P).
+pub struct Palette {
+    pub input: TextInput,
+    /// Holds a command run on the user's behalf, kept out of the history.
+    pub queued: bool,
+}
+
+impl Palette {
+    /// An empty palette; ↑/↓ recall `history`, oldest first.
+    pub fn new(history: Vec<String>) -> Self {
+        Self {
+            input: TextInput::with_history(history),
+            queued: false,
+        }
+    }
+
+    /// A palette with `command` typed in, to be entered right away.
+    pub fn queued(command: &str) -> Self {
+        let mut input = TextInput::default();
+        input.insert(command);
+        Self {
+            input,
+            queued: true,
+        }
+    }
+
+    /// Snapshot handed to the UI: the input and the usage of matching commands.
+    pub fn screen(&self) -> PaletteScreen {
+        let word = self.input.text().split_whitespace().next().unwrap_or("");
+        let mut hints: Vec<(i32, &str)> = COMMANDS
+            .iter()
+            .filter_map(|(name, usage)| {
+                if word.is_empty() {
+                    Some((0, *usage))
+                } else {
+                    fuzzy_score(word, name).map(|s| (s, *usage))
+                }
+            })
+            .collect();
+        hints.sort_by_key(|&(s, _)| std::cmp::Reverse(s));
+        PaletteScreen {
+            input: self.input.text().to_string(),
+            cursor: self.input.cursor(),
+            hints: hints.into_iter().map(|(_, usage)| usage).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stations() -> Vec<Station> {
+        ["Jazz Cafe", "Just Chill", "Deep Focus"]
+            .iter()
+            .map(|name| Station::new(name, "http://example.com/s", None))
+            .collect()
+    }
+
+    #[test]
+    fn parse_duration_takes_units_up_to_a_day() {
+        assert_eq!(parse_duration("45"), Some(Duration::from_secs(45 * 60)));
+        assert_eq!(parse_duration(" 45m "), Some(Duration::from_secs(45 * 60)));
+        assert_eq!(parse_duration("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("24h"), Some(MAX_DURATION));
+        assert_eq!(parse_duration("25h"), None);
+        assert_eq!(parse_duration("10x"), None);
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("-5"), None);
+    }
+
+    #[test]
+    fn parse_duration_rejects_overflowing_values() {
+        assert_eq!(parse_duration("18446744073709551615h"), None);
+        assert_eq!(parse_duration("18446744073709551615m"), None);
+        assert_eq!(parse_duration("18446744073709551615"), None);
+        assert_eq!(parse_duration("99999999999999999999999s"), None);
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts_and_short_names() {
+        assert!(fuzzy_score("jc", "Jazz Cafe") > fuzzy_score("jc", "Just Chill"));
+        assert!(fuzzy_score("jazz", "Jazz") > fuzzy_score("jazz", "Jazz Cafe"));
+        assert!(fuzzy_score("JAZZ", "jazz cafe").is_some());
+        assert_eq!(fuzzy_score("zj", "Jazz"), None);
+        assert_eq!(fuzzy_score("x", ""), None);
+    }
+
+    #[test]
+    fn parse_command_matches_commands_and_stations() {
+        let stations = stations();
+        assert!(matches!(
+            parse_command("vol 150%", &stations),
+            Ok(PaletteCommand::Volume(100))
+        ));
+        assert!(matches!(
+            parse_command("play jc", &stations),
+            Ok(PaletteCommand::Play(0))
+        ));
+        assert!(matches!(
+            parse_command("deep focus", &stations),
+            Ok(PaletteCommand::Play(2))
+        ));
+        assert!(matches!(
+            parse_command("sleep 1h", &stations),
+            Ok(PaletteCommand::Sleep(Some(d))) if d == Duration::from_secs(3600)
+        ));
+        assert!(matches!(
+            parse_command("sleep off", &stations),
+            Ok(PaletteCommand::Sleep(None))
+        ));
+        assert!(matches!(
+            parse_command("record 30m", &stations),
+            Ok(PaletteCommand::Record(Some(_), None))
+        ));
+        assert!(parse_command("  ", &stations).is_err());
+        assert!(parse_command("volume loud", &stations).is_err());
+    }
+
+    #[test]
+    fn parse_command_rejects_huge_durations() {
+        let stations = stations();
+        assert!(parse_command("sleep 18446744073709551615h", &stations).is_err());
+        assert!(parse_command("sleep 48h", &stations).is_err());
+        assert!(parse_command("record 18446744073709551615m", &stations).is_err());
+    }
+}
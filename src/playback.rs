@@ -0,0 +1,585 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{oneshot, watch};
+
+use crate::backend::{ChangeSender, PlayerBackend, PlayerChange};
+use crate::config::Config;
+use crate::error::LofiError;
+use crate::night::NightProfile;
+use crate::player::{Capabilities, HoldLevel, PlayerType, VolumeControl};
+use crate::ui::Timeshift;
+
+/// How long a killed player gets to exit before the next one starts.
+const KILL_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// mpv volume changes this soon after a command are taken as its echo, not
+/// as someone else changing the volume.
+const ECHO_WINDOW: Duration = Duration::from_secs(1);
+
+/// What the UI loop asks of the player task. Level changes that the backend
+/// cannot follow at runtime restart the player, except volume on backends
+/// without [`Capabilities::runtime_volume`], which waits for the next start;
+/// with nothing playing they only take effect on the next `Play`.
+pub enum PlayerCommand {
+    /// (Re)start playback of `url`, keeping volume, mute and speed.
+    Play(String),
+    /// Like `Play` at `speed`, answered with [`PlayerEvent::Started`] so a
+    /// station switch does not hold up the UI.
+    Switch { url: String, speed: f64 },
+    /// Kill the player; nothing plays until the next `Play`.
+    Stop,
+    /// Kill the player as if it had died, so its exit is reported.
+    Kill,
+    /// Set the volume, capped at the configured maximum.
+    SetVolume(u32),
+    /// Set the volume without telling the backend yet (see `PushVolume`).
+    /// Ignored while muted.
+    StageVolume(u32),
+    /// Move the volume by this many volume steps without telling the
+    /// backend yet (see `PushVolume`). Ignored while muted.
+    StepVolume(i32),
+    /// Send the current volume to the backend.
+    PushVolume,
+    /// Mute or unmute. The PCM pipeline mutes at runtime; other backends
+    /// restart to guarantee mute takes effect.
+    Mute(bool),
+    /// Pause or resume: mpv and VLC pause, signal-driven backends are
+    /// stopped.
+    Pause(bool),
+    /// Switch the momentary hold level, ramping the output to it.
+    Hold(Option<HoldLevel>),
+    /// One speed step faster (`true`) or slower.
+    StepSpeed(bool),
+    /// Seek this many seconds in the timeshift buffer; `None` goes live.
+    Seek(Option<f64>),
+    /// Move playback to the current default output device.
+    ReloadOutput,
+    /// Swap to another backend, restarting the current stream on it with
+    /// the same volume, mute and speed. Falls back to the previous backend
+    /// when the new one cannot be started.
+    SetBackend(PlayerType),
+    /// Copy the player-related values of a changed config.
+    Configure(Box<Config>),
+    /// Apply mono/balance after a `Configure`.
+    ApplyFilters,
+    /// Turn night mode on with this profile, or off: its filters and
+    /// volume cap.
+    Night(Option<NightProfile>),
+    /// Low-data mode on or off; the cache it sets takes effect with the
+    /// next `Play`.
+    LowData(bool),
+    /// Play the track-change chime at this fraction of the volume.
+    Chime(f32),
+    /// Refresh the backend readings in [`PlayerState`].
+    Probe,
+}
+
+/// Sent by the player task without being asked.
+pub enum PlayerEvent {
+    /// A `Switch` finished: the new player runs, or could not be started.
+    Started(Result<(), LofiError>),
+    /// The player exited without being told to.
+    Exited,
+    /// Levels or buffering changed outside a command, e.g. mpv paused or
+    /// its volume set from playerctl.
+    Changed,
+    /// The player reported a new stream title.
+    Title,
+}
+
+/// Levels as of the last command, and the backend readings of the last
+/// `Probe`.
+#[derive(Clone, Default)]
+pub struct PlayerState {
+    pub volume: u32,
+    /// Volume the backend last took; trails `volume` while a change is
+    /// staged or being applied.
+    pub applied_volume: u32,
+    pub muted: bool,
+    pub speed: f64,
+    /// What the current backend can do without a restart.
+    pub capabilities: Capabilities,
+    pub stream_title: Option<String>,
+    pub buffering: Option<u32>,
+    pub timeshift: Option<Timeshift>,
+    pub bitrate: Option<u32>,
+    pub codec: Option<String>,
+    /// Playback progress for stall detection; `None` while muted.
+    pub progress: Option<f64>,
+    /// Audible progress for silence detection; `None` while muted.
+    pub audible: Option<f64>,
+}
+
+type Reply = oneshot::Sender<Result<(), LofiError>>;
+
+/// The UI loop's end of the player task.
+pub struct PlayerHandle {
+    commands: UnboundedSender<(PlayerCommand, Option<Reply>)>,
+    state: watch::Receiver<PlayerState>,
+}
+
+impl PlayerHandle {
+    /// Send `command` and wait until it was carried out. Fails when the
+    /// player had to be restarted and could not be.
+    pub async fn send(&self, command: PlayerCommand) -> Result<(), LofiError> {
+        let (reply, done) = oneshot::channel();
+        self.commands
+            .send((command, Some(reply)))
+            .map_err(|_| LofiError::PlayerStopped)?;
+        done.await.unwrap_or(Err(LofiError::PlayerStopped))
+    }
+
+    /// Send `command` without waiting for it.
+    pub fn post(&self, command: PlayerCommand) {
+        let _ = self.commands.send((command, None));
+    }
+
+    pub fn state(&self) -> PlayerState {
+        self.state.borrow().clone()
+    }
+}
+
+/// Start the task owning `control` and the player process. Nothing plays
+/// until the first `Play`.
+pub fn spawn_supervisor(
+    control: VolumeControl,
+    chime_pcm: Vec<i16>,
+    chime_wav: Option<PathBuf>,
+) -> (PlayerHandle, UnboundedReceiver<PlayerEvent>) {
+    let (command_tx, command_rx) = unbounded_channel();
+    let (event_tx, event_rx) = unbounded_channel();
+    let (change_tx, change_rx) = unbounded_channel();
+    let (state_tx, state_rx) = watch::channel(PlayerState {
+        capabilities: control.capabilities(),
+        ..Default::default()
+    });
+    let applied_volume = control.volume;
+    let supervisor = PlayerSupervisor {
+        control,
+        player: None,
+        url: String::new(),
+        chime_pcm,
+        chime_wav,
+        events: event_tx,
+        state: state_tx,
+        generation: 0,
+        changes: change_tx,
+        observed: Observed::default(),
+        last_command: Instant::now(),
+        applied_volume,
+    };
+    supervisor.publish();
+    tokio::spawn(supervisor.run(command_rx, change_rx));
+    (
+        PlayerHandle {
+            commands: command_tx,
+            state: state_rx,
+        },
+        event_rx,
+    )
+}
+
+/// Copy the player-related config values into `vc`.
+pub fn apply_config(vc: &mut VolumeControl, config: &Config) {
+    vc.volume_step = config.volume_step;
+    vc.cache_secs = config.cache_secs;
+    vc.sandbox = config.sandbox_player;
+    vc.timeshift_mins = config.timeshift_minutes;
+    vc.curve = config.volume_curve;
+    vc.mono = config.mono;
+    vc.balance = config.balance;
+    vc.set_limits(
+        config.max_volume,
+        config.limiter.then_some(config.limiter_ceiling),
+    );
+}
+
+/// Owns the player: the only place it is started, waited for and stopped.
+struct PlayerSupervisor {
+    control: VolumeControl,
+    player: Option<Box<dyn PlayerBackend>>,
+    /// Stream of the last `Play`, used when a change needs a restart.
+    url: String,
+    chime_pcm: Vec<i16>,
+    chime_wav: Option<PathBuf>,
+    events: UnboundedSender<PlayerEvent>,
+    state: watch::Sender<PlayerState>,
+    /// Bumped whenever the player goes, so changes observed on an earlier
+    /// one are dropped.
+    generation: u64,
+    changes: ChangeSender,
+    observed: Observed,
+    /// When the last command that may have changed the output was done.
+    last_command: Instant,
+    /// See [`PlayerState::applied_volume`].
+    applied_volume: u32,
+}
+
+/// Last values the player reported by itself.
+#[derive(Default)]
+struct Observed {
+    volume: Option<f64>,
+    paused: Option<bool>,
+    title: Option<String>,
+    buffering: Option<u32>,
+}
+
+enum Next {
+    Command(PlayerCommand, Option<Reply>),
+    Change(u64, PlayerChange),
+    Exited,
+}
+
+impl PlayerSupervisor {
+    async fn run(
+        mut self,
+        mut commands: UnboundedReceiver<(PlayerCommand, Option<Reply>)>,
+        mut changes: UnboundedReceiver<(u64, PlayerChange)>,
+    ) {
+        loop {
+            let exited = async {
+                match self.player.as_mut() {
+                    Some(player) => player.wait().await,
+                    None => std::future::pending().await,
+                }
+            };
+            let next = tokio::select! {
+                request = commands.recv() => match request {
+                    Some((command, reply)) => Next::Command(command, reply),
+                    None => break,
+                },
+                Some((generation, change)) = changes.recv() => Next::Change(generation, change),
+                () = exited => Next::Exited,
+            };
+            match next {
+                Next::Command(command, reply) => {
+                    let switch = matches!(command, PlayerCommand::Switch { .. });
+                    let quiet = matches!(command, PlayerCommand::Probe | PlayerCommand::Chime(_));
+                    let result = self.handle(command).await;
+                    if !quiet {
+                        self.last_command = Instant::now();
+                    }
+                    self.publish();
+                    if switch {
+                        let _ = self.events.send(PlayerEvent::Started(result));
+                    } else if let Some(reply) = reply {
+                        let _ = reply.send(result);
+                    }
+                }
+                Next::Change(generation, change) if generation == self.generation => {
+                    if let Some(event) = self.observed(change).await {
+                        self.publish();
+                        let _ = self.events.send(event);
+                    }
+                }
+                Next::Change(..) => {}
+                Next::Exited => {
+                    self.player = None;
+                    let _ = self.events.send(PlayerEvent::Exited);
+                }
+            }
+        }
+        self.stop().await;
+    }
+
+    async fn handle(&mut self, command: PlayerCommand) -> Result<(), LofiError> {
+        match command {
+            PlayerCommand::Play(url) => self.play(url).await,
+            PlayerCommand::Switch { url, speed } => {
+                self.control.speed = speed;
+                self.play(url).await
+            }
+            PlayerCommand::Stop => {
+                self.stop().await;
+                Ok(())
+            }
+            PlayerCommand::Kill => {
+                if let Some(player) = self.player.as_mut() {
+                    player.start_kill();
+                }
+                Ok(())
+            }
+            PlayerCommand::SetVolume(volume) => {
+                self.control.volume = volume.min(self.control.volume_cap());
+                self.push_volume().await
+            }
+            PlayerCommand::StepVolume(steps) => {
+                if !self.control.muted {
+                    if steps > 0 {
+                        self.control.increase_volume(steps.unsigned_abs());
+                    } else {
+                        self.control.decrease_volume(steps.unsigned_abs());
+                    }
+                }
+                Ok(())
+            }
+            PlayerCommand::StageVolume(volume) => {
+                if !self.control.muted {
+                    self.control.volume = volume.min(self.control.volume_cap());
+                }
+                Ok(())
+            }
+            PlayerCommand::PushVolume => self.push_volume().await,
+            PlayerCommand::Mute(muted) => {
+                if self.control.muted == muted {
+                    return Ok(());
+                }
+                self.control.toggle_mute();
+                let applied = matches!(self.control.player_type, PlayerType::Ffplay)
+                    && match self.player.as_mut() {
+                        Some(player) => self.control.apply_volume(player.as_mut()).await.is_ok(),
+                        None => true,
+                    };
+                self.restart_unless(applied).await
+            }
+            PlayerCommand::Pause(paused) => {
+                if self.control.muted == paused {
+                    return Ok(());
+                }
+                self.control.toggle_mute();
+                let applied = match self.player.as_mut() {
+                    Some(player) => self.control.apply_mute(player.as_mut()).await.is_ok(),
+                    None => true,
+                };
+                self.restart_unless(applied).await
+            }
+            PlayerCommand::Hold(hold) => {
+                if self.control.hold == hold {
+                    return Ok(());
+                }
+                let from = self.control.effective_volume();
+                self.control.hold = hold;
+                if !self.control.capabilities().runtime_volume && self.player.is_some() {
+                    return Ok(());
+                }
+                let applied = match self.player.as_mut() {
+                    Some(player) => self
+                        .control
+                        .ramp_volume(player.as_mut(), from)
+                        .await
+                        .is_ok(),
+                    None => true,
+                };
+                self.restart_unless(applied).await
+            }
+            PlayerCommand::StepSpeed(faster) => {
+                if faster {
+                    self.control.increase_speed();
+                } else {
+                    self.control.decrease_speed();
+                }
+                let applied = match self.player.as_mut() {
+                    Some(player) => player.set_speed(self.control.speed).await.is_ok(),
+                    None => true,
+                };
+                self.restart_unless(applied).await
+            }
+            PlayerCommand::Seek(secs) => {
+                let result = match secs {
+                    Some(secs) => self.control.seek(secs).await,
+                    None => self.control.seek_live().await,
+                }
+                .map_err(|e| LofiError::IpcFailed {
+                    player: self.control.player_type.name(),
+                    reason: e.to_string(),
+                });
+                let timeshift = self.control.timeshift().await;
+                self.state.send_modify(|s| s.timeshift = timeshift);
+                result
+            }
+            PlayerCommand::ReloadOutput => {
+                let applied = self.control.reload_output().await.is_ok();
+                self.restart_unless(applied).await
+            }
+            PlayerCommand::SetBackend(player_type) => {
+                let previous = self.control.player_type;
+                let playing = self.player.is_some();
+                self.stop().await;
+                self.control.set_player_type(player_type);
+                if !playing {
+                    return Ok(());
+                }
+                let result = self.play(self.url.clone()).await;
+                if result.is_err() {
+                    self.control.set_player_type(previous);
+                    let _ = self.play(self.url.clone()).await;
+                }
+                result
+            }
+            PlayerCommand::Configure(config) => {
+                apply_config(&mut self.control, &config);
+                Ok(())
+            }
+            PlayerCommand::ApplyFilters => {
+                let applied = self.control.apply_audio_filters().await.is_ok();
+                self.restart_unless(applied).await
+            }
+            PlayerCommand::Night(profile) => {
+                if self.control.night == profile {
+                    return Ok(());
+                }
+                self.control.set_night(profile);
+                let applied = self.control.apply_audio_filters().await.is_ok();
+                self.restart_unless(applied).await?;
+                self.push_volume().await
+            }
+            PlayerCommand::LowData(on) => {
+                self.control.low_data = on;
+                Ok(())
+            }
+            PlayerCommand::Chime(level) => {
+                self.control
+                    .play_chime(&self.chime_pcm, self.chime_wav.as_deref(), level);
+                Ok(())
+            }
+            PlayerCommand::Probe => {
+                let (progress, audible) = match self.player.as_mut() {
+                    Some(player) if !self.control.muted => {
+                        (player.progress().await, self.control.audible_progress())
+                    }
+                    _ => (None, None),
+                };
+                let stream_title = self.observed.title.clone();
+                let buffering = self.observed.buffering;
+                let timeshift = self.control.timeshift().await;
+                let bitrate = self.control.bitrate().await;
+                let codec = self.control.codec().await;
+                self.state.send_modify(|s| {
+                    s.stream_title = stream_title;
+                    s.buffering = buffering;
+                    s.timeshift = timeshift;
+                    s.bitrate = bitrate;
+                    s.codec = codec;
+                    s.progress = progress;
+                    s.audible = audible;
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Kill the running player and start one for `url` at the current
+    /// volume, paused again if muted.
+    async fn play(&mut self, url: String) -> Result<(), LofiError> {
+        self.stop().await;
+        self.url = url;
+        let volume = self.control.effective_volume();
+        let mut player =
+            self.control
+                .start(&self.url, volume)
+                .await
+                .map_err(|e| LofiError::SpawnFailed {
+                    player: self.control.player_type.name(),
+                    reason: e.to_string(),
+                })?;
+        if self.control.muted {
+            let _ = self.control.apply_mute(player.as_mut()).await;
+        }
+        player.observe(self.generation, self.changes.clone());
+        self.player = Some(player);
+        self.applied_volume = self.control.volume;
+        Ok(())
+    }
+
+    async fn stop(&mut self) {
+        self.generation += 1;
+        self.observed = Observed::default();
+        if let Some(mut player) = self.player.take() {
+            player.start_kill();
+            let _ = tokio::time::timeout(KILL_TIMEOUT, player.wait()).await;
+        }
+    }
+
+    /// Restart a running player whose backend could not apply a change.
+    async fn restart_unless(&mut self, applied: bool) -> Result<(), LofiError> {
+        if applied || self.player.is_none() {
+            return Ok(());
+        }
+        self.play(self.url.clone()).await
+    }
+
+    /// Send the current volume to the backend, restarting if it could not
+    /// take it. Backends without runtime volume keep it staged until the
+    /// next start instead.
+    async fn push_volume(&mut self) -> Result<(), LofiError> {
+        if !self.control.capabilities().runtime_volume && self.player.is_some() {
+            return Ok(());
+        }
+        let applied = match self.player.as_mut() {
+            Some(player) => self.control.apply_volume(player.as_mut()).await.is_ok(),
+            None => true,
+        };
+        self.restart_unless(applied).await?;
+        self.applied_volume = self.control.volume;
+        Ok(())
+    }
+
+    /// Follow a change the player reported. Volume and pause changes nobody
+    /// asked for (playerctl, mpv's own keys) become the task's levels.
+    async fn observed(&mut self, change: PlayerChange) -> Option<PlayerEvent> {
+        match change {
+            PlayerChange::Volume(level) => {
+                let first = self.observed.volume.replace(level).is_none();
+                let expected = self.control.curve.apply(self.control.effective_volume());
+                if first
+                    || self.last_command.elapsed() < ECHO_WINDOW
+                    || self.control.muted
+                    || self.control.hold.is_some()
+                    || (level - expected).abs() < 0.5
+                {
+                    return None;
+                }
+                self.control.volume = self
+                    .control
+                    .curve
+                    .invert(level)
+                    .min(self.control.volume_cap());
+                self.applied_volume = self.control.volume;
+                Some(PlayerEvent::Changed)
+            }
+            PlayerChange::Pause(paused) => {
+                let previous = self.observed.paused.replace(paused);
+                if previous.is_none_or(|p| p == paused) || paused == self.control.muted {
+                    return None;
+                }
+                self.control.toggle_mute();
+                if !paused {
+                    // Muting may have left mpv at volume 0
+                    let _ = self.push_volume().await;
+                }
+                Some(PlayerEvent::Changed)
+            }
+            PlayerChange::Title(title) => {
+                // Without tags mpv falls back to the file name
+                let title =
+                    Some(title).filter(|t| !t.is_empty() && !self.url.ends_with(t.as_str()));
+                if title == self.observed.title {
+                    return None;
+                }
+                self.observed.title = title.clone();
+                self.state.send_modify(|s| s.stream_title = title);
+                Some(PlayerEvent::Title)
+            }
+            PlayerChange::Buffering(buffering) => {
+                if buffering == self.observed.buffering {
+                    return None;
+                }
+                self.observed.buffering = buffering;
+                self.state.send_modify(|s| s.buffering = buffering);
+                Some(PlayerEvent::Changed)
+            }
+        }
+    }
+
+    fn publish(&self) {
+        self.state.send_modify(|s| {
+            s.volume = self.control.volume;
+            s.applied_volume = self.applied_volume;
+            s.muted = self.control.muted;
+            s.speed = self.control.speed;
+            s.capabilities = self.control.capabilities();
+        });
+    }
+}
@@ -1,18 +1,305 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
 use std::process::{Command, Stdio};
-use tokio::process::Command as TokioCommand;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command as TokioCommand};
+use tokio::sync::Mutex;
 
+use crate::backend::{unsupported, BackendFuture, ChangeSender, PlayerBackend, PlayerChange};
+use crate::config::{runtime_dir, timeshift_dir};
+use crate::night::NightProfile;
+use crate::noise::{self, NoiseKind};
+use crate::state::instance;
+use crate::tls;
+use crate::ui::Timeshift;
+
+/// Level used while the duck key is held.
+pub const DUCK_VOLUME: u32 = 20;
+/// Extra volume added while the boost key is held.
+pub const BOOST_STEP: u32 = 20;
+
+const RAMP_STEPS: i64 = 8;
+const RAMP_STEP_DELAY: Duration = Duration::from_millis(25);
+
+/// Momentary volume override active while a hold key is down.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HoldLevel {
+    Duck,
+    Boost,
+}
+
+/// Slowest and fastest playback speed for on-demand sources.
+pub const MIN_SPEED: f64 = 0.75;
+pub const MAX_SPEED: f64 = 2.0;
+const SPEED_STEP: f64 = 0.25;
+
+/// Seconds moved by one timeshift seek.
+pub const SEEK_STEP: f64 = 30.0;
+/// Back buffer mpv keeps per timeshift minute, sized for 192 kbit/s streams.
+const TIMESHIFT_BYTES_PER_MIN: u32 = 24_000 * 60;
+
+/// Mapping from the 0-100 volume shown in the UI to the level sent to the
+/// backend. The non-linear curves spend more of the range on quiet levels,
+/// where loudness perception is most sensitive.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeCurve {
+    #[default]
+    Linear,
+    /// 40 dB exponential taper.
+    Log,
+    Cubic,
+}
+
+impl VolumeCurve {
+    /// Map a UI level (0-100) to a backend level (0.0-100.0).
+    pub fn apply(self, level: u32) -> f64 {
+        let x = level.min(100) as f64 / 100.0;
+        let y = match self {
+            VolumeCurve::Linear => x,
+            VolumeCurve::Log if x == 0.0 => 0.0,
+            VolumeCurve::Log => 10f64.powf(2.0 * (x - 1.0)),
+            VolumeCurve::Cubic => x * x * x,
+        };
+        y * 100.0
+    }
+
+    /// The level whose output `apply` comes closest to `output`.
+    pub fn invert(self, output: f64) -> u32 {
+        (0..=100)
+            .min_by(|&a, &b| {
+                (self.apply(a) - output)
+                    .abs()
+                    .total_cmp(&(self.apply(b) - output).abs())
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// Step used when nudging the left/right balance.
+pub const BALANCE_STEP: i32 = 10;
+/// Read-ahead cache in low-data mode, in seconds.
+const LOW_DATA_CACHE_SECS: u32 = 60;
+
+/// Settings baked into the player's command line at spawn time.
 #[derive(Clone, Copy)]
+pub struct PlaybackOptions {
+    pub volume: u32,
+    pub curve: VolumeCurve,
+    pub cache_secs: u32,
+    pub speed: f64,
+    pub mono: bool,
+    pub balance: i32,
+    /// Peak limiter ceiling (linear amplitude), if enabled.
+    pub limiter: Option<f64>,
+    /// Night mode bass cut and compression, while on.
+    pub night: Option<NightProfile>,
+    /// Names the IPC socket, so a second player gets its own.
+    pub instance: &'static str,
+    /// Minutes of played stream to keep for seeking back (mpv); 0 is off.
+    pub timeshift_mins: u32,
+}
+
+impl PlaybackOptions {
+    /// lavfi filters (without `atempo`) implementing the audio options.
+    pub fn lavfi_filters(&self) -> Vec<String> {
+        let mut filters = self
+            .night
+            .map(|night| night.lavfi_filters())
+            .unwrap_or_default();
+        if self.mono || self.balance != 0 {
+            let left = if self.balance > 0 {
+                1.0 - self.balance as f64 / 100.0
+            } else {
+                1.0
+            };
+            let right = if self.balance < 0 {
+                1.0 + self.balance as f64 / 100.0
+            } else {
+                1.0
+            };
+            let pan = if self.mono {
+                format!(
+                    "pan=stereo|c0={l:.2}*c0+{l:.2}*c1|c1={r:.2}*c0+{r:.2}*c1",
+                    l = left / 2.0,
+                    r = right / 2.0
+                )
+            } else {
+                format!("pan=stereo|c0={:.2}*c0|c1={:.2}*c1", left, right)
+            };
+            filters.push(pan);
+        }
+        if let Some(ceiling) = self.limiter {
+            filters.push(format!("alimiter=limit={:.3}:level=disabled", ceiling));
+        }
+        filters
+    }
+}
+
+/// Which player to use, as chosen in the config.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayerPreference {
+    /// First available of mpv → ffplay → afplay → VLC → GStreamer.
+    #[default]
+    Auto,
+    Mpv,
+    Ffplay,
+    Afplay,
+    Vlc,
+    Gstreamer,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum PlayerType {
+    /// `ffmpeg` decoding to raw PCM, piped into a persistent `ffplay` sink.
     Ffplay,
     Mpv,
     Afplay,
+    /// libvlc playing in this process, only with the `vlc` cargo feature.
+    /// Titles and buffering come from its events; mono/balance/limiter are
+    /// not applied and it is never sandboxed.
+    Vlc,
+    /// A GStreamer `playbin` in this process, only with the `gstreamer`
+    /// cargo feature. Titles and buffering come from its bus; F8 mutes it,
+    /// mono/balance/limiter are not applied and it is never sandboxed.
+    GStreamer,
+    /// This binary's own `noise` subcommand, when nothing else is installed:
+    /// plays only the generated `noise:` stations, volume changes restart it.
+    Builtin,
+}
+
+/// What a backend can do to a running player. Anything else needs a
+/// restart, or is not available at all.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Volume (and duck/boost) changes apply while it plays.
+    pub runtime_volume: bool,
+    /// F8 pauses it; otherwise F8 only silences it.
+    pub pause: bool,
+    /// It reads track titles from the stream's own tags.
+    pub metadata: bool,
+    /// ←/→ seek in a timeshift buffer.
+    pub seek: bool,
+    /// Playback speed can be changed, with pitch correction.
+    pub speed: bool,
+}
+
+impl PlayerType {
+    /// Backends that play streams, in the order they are tried and cycled.
+    pub const STREAMING: [PlayerType; 5] = [
+        PlayerType::Mpv,
+        PlayerType::Ffplay,
+        PlayerType::Afplay,
+        PlayerType::Vlc,
+        PlayerType::GStreamer,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PlayerType::Ffplay => "ffplay",
+            PlayerType::Mpv => "mpv",
+            PlayerType::Afplay => "afplay",
+            PlayerType::Vlc => "vlc",
+            PlayerType::GStreamer => "gstreamer",
+            PlayerType::Builtin => "built-in",
+        }
+    }
+
+    pub fn capabilities(self) -> Capabilities {
+        // Signal-driven backends are paused with SIGSTOP
+        let signal_pause = cfg!(unix);
+        match self {
+            PlayerType::Mpv => Capabilities {
+                runtime_volume: true,
+                pause: true,
+                metadata: true,
+                seek: true,
+                speed: true,
+            },
+            PlayerType::Ffplay => Capabilities {
+                runtime_volume: true,
+                speed: true,
+                ..Default::default()
+            },
+            PlayerType::Vlc => Capabilities {
+                runtime_volume: true,
+                pause: true,
+                metadata: true,
+                speed: true,
+                ..Default::default()
+            },
+            // Live streams have no pause; F8 mutes the playbin
+            PlayerType::GStreamer => Capabilities {
+                runtime_volume: true,
+                metadata: true,
+                ..Default::default()
+            },
+            // afplay only has the system-wide volume
+            PlayerType::Afplay => Capabilities {
+                runtime_volume: true,
+                pause: signal_pause,
+                ..Default::default()
+            },
+            PlayerType::Builtin => Capabilities {
+                pause: signal_pause,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// A streaming backend by name, as typed in the palette.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::STREAMING.into_iter().find(|p| p.name() == name)
+    }
 }
 
 pub struct VolumeControl {
     pub volume: u32, // 0-100
     pub player_type: PlayerType,
-    pub mpv_socket: Option<String>,
+    /// mpv's JSON IPC socket.
+    pub ipc_socket: Option<String>,
     pub muted: bool,
+    pub hold: Option<HoldLevel>,
+    /// Playback speed; always 1.0 for live sources.
+    pub speed: f64,
+    /// Downmix to mono on both channels (single earbud listening).
+    pub mono: bool,
+    /// Left/right balance, -100 (left only) to 100 (right only).
+    pub balance: i32,
+    /// Hard volume cap from the config.
+    pub max_volume: u32,
+    /// Peak limiter ceiling from the config, if enabled.
+    pub limiter: Option<f64>,
+    /// Night mode profile while it is on; its cap applies below `max_volume`.
+    pub night: Option<NightProfile>,
+    pub curve: VolumeCurve,
+    /// Percentage points per volume key press.
+    pub volume_step: u32,
+    /// Seconds of read-ahead cache (mpv); 0 keeps the backend default.
+    pub cache_secs: u32,
+    /// Low-data mode: at least `LOW_DATA_CACHE_SECS` of cache, so a slow
+    /// link stutters less.
+    pub low_data: bool,
+    /// Names this player's IPC socket; "lofi" for the main player.
+    pub instance: &'static str,
+    /// Minutes of played stream kept for seeking back (mpv); 0 is off.
+    pub timeshift_mins: u32,
+    /// Start players with reduced privileges (Linux, see `player_command`).
+    pub sandbox: bool,
+    /// Output gain of the ffmpeg pipeline, stored as `f32` bits.
+    pcm_gain: Arc<AtomicU32>,
+    /// Bytes of PCM pumped by the ffmpeg pipeline, used for stall detection.
+    pcm_bytes: Arc<AtomicU64>,
+    /// Bytes of those that were not silence, used for silence detection.
+    pcm_audible_bytes: Arc<AtomicU64>,
+    /// Samples mixed on top of the stream by the PCM pump (chimes).
+    pcm_overlay: Arc<std::sync::Mutex<VecDeque<i16>>>,
+    pcm_sink: Option<PcmSink>,
     volume_before_mute: u32,
 }
 
@@ -21,18 +308,354 @@ impl VolumeControl {
         Self {
             volume: 70,
             player_type,
-            mpv_socket: None,
+            ipc_socket: None,
             muted: false,
+            hold: None,
+            speed: 1.0,
+            mono: false,
+            balance: 0,
+            max_volume: 100,
+            limiter: None,
+            night: None,
+            curve: VolumeCurve::Linear,
+            volume_step: 5,
+            cache_secs: 0,
+            low_data: false,
+            instance: "lofi",
+            timeshift_mins: 0,
+            sandbox: false,
+            pcm_gain: Arc::new(AtomicU32::new(0.7f32.to_bits())),
+            pcm_bytes: Arc::new(AtomicU64::new(0)),
+            pcm_audible_bytes: Arc::new(AtomicU64::new(0)),
+            pcm_overlay: Arc::new(std::sync::Mutex::new(VecDeque::new())),
+            pcm_sink: None,
             volume_before_mute: 70,
         }
     }
 
-    pub fn increase_volume(&mut self) {
-        self.volume = (self.volume + 5).min(100);
+    /// Start playing `url` at `volume`. For the ffmpeg pipeline the
+    /// decoder output is pumped into the (re)used sink process.
+    pub async fn start(
+        &mut self,
+        url: &str,
+        volume: u32,
+    ) -> Result<Box<dyn PlayerBackend>, std::io::Error> {
+        let (cmd, args, socket) =
+            build_player_args(self.player_type, url, self.playback_options(volume));
+        self.ipc_socket = socket.clone();
+        match self.player_type {
+            PlayerType::Mpv => {
+                let child = spawn_player(&cmd, &args, self.sandbox).await?;
+                Ok(Box::new(MpvProcess { child, socket }))
+            }
+            PlayerType::Ffplay => self.start_pcm(&cmd, &args, volume),
+            PlayerType::Afplay | PlayerType::Builtin => {
+                let child = spawn_player(&cmd, &args, self.sandbox).await?;
+                Ok(Box::new(SignalProcess {
+                    child,
+                    system_volume: self.player_type == PlayerType::Afplay,
+                }))
+            }
+            #[cfg(feature = "vlc")]
+            PlayerType::Vlc => Ok(Box::new(crate::vlc::VlcPlayer::start(
+                &cmd,
+                &args,
+                self.curve.apply(volume),
+            )?)),
+            #[cfg(not(feature = "vlc"))]
+            PlayerType::Vlc => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Built without the vlc feature",
+            )),
+            #[cfg(feature = "gstreamer")]
+            PlayerType::GStreamer => Ok(Box::new(crate::gst::GstPlayer::start(
+                &cmd,
+                &args,
+                self.curve.apply(volume),
+            )?)),
+            #[cfg(not(feature = "gstreamer"))]
+            PlayerType::GStreamer => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Built without the gstreamer feature",
+            )),
+        }
+    }
+
+    fn start_pcm(
+        &mut self,
+        cmd: &str,
+        args: &[String],
+        volume: u32,
+    ) -> Result<Box<dyn PlayerBackend>, std::io::Error> {
+        self.set_pcm_gain(volume);
+        let sink_alive = match self.pcm_sink.as_mut() {
+            Some(sink) => matches!(sink.child.try_wait(), Ok(None)),
+            None => false,
+        };
+        if !sink_alive {
+            self.pcm_sink = Some(PcmSink::spawn()?);
+        }
+        let sink_stdin = match self.pcm_sink.as_ref() {
+            Some(sink) => sink.stdin.clone(),
+            None => return Err(std::io::ErrorKind::NotConnected.into()),
+        };
+        let mut child = player_command(cmd, args, self.sandbox)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(pump_pcm(
+                stdout,
+                sink_stdin,
+                self.pcm_gain.clone(),
+                self.pcm_bytes.clone(),
+                self.pcm_audible_bytes.clone(),
+                self.pcm_overlay.clone(),
+            ));
+        }
+        Ok(Box::new(PcmDecoder {
+            child,
+            gain: self.pcm_gain.clone(),
+            pumped: self.pcm_bytes.clone(),
+        }))
+    }
+
+    fn set_pcm_gain(&self, level: u32) {
+        let gain = (self.curve.apply(level) / 100.0) as f32;
+        self.pcm_gain.store(gain.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Play a notification chime at `level` (0.0-1.0) relative to the current
+    /// volume. The PCM pipeline mixes `samples` into the stream; other backends
+    /// play `wav` in a short-lived process.
+    pub fn play_chime(&self, samples: &[i16], wav: Option<&Path>, level: f32) {
+        if self.muted {
+            return;
+        }
+        match self.player_type {
+            PlayerType::Ffplay => {
+                if let Ok(mut overlay) = self.pcm_overlay.lock() {
+                    overlay.clear();
+                    overlay.extend(samples.iter().map(|&s| (s as f32 * level) as i16));
+                }
+            }
+            PlayerType::Mpv => {
+                let Some(wav) = wav else { return };
+                let volume = self.curve.apply(self.effective_volume()) * level as f64;
+                let _ = spawn_detached(
+                    "mpv",
+                    &[
+                        "--no-video".to_string(),
+                        "--no-terminal".to_string(),
+                        format!("--volume={:.2}", volume),
+                        wav.display().to_string(),
+                    ],
+                );
+            }
+            #[cfg(feature = "vlc")]
+            PlayerType::Vlc => {
+                let Some(wav) = wav else { return };
+                let volume = self.curve.apply(self.effective_volume()) * level as f64;
+                crate::vlc::play_chime(wav, volume);
+            }
+            #[cfg(not(feature = "vlc"))]
+            PlayerType::Vlc => {}
+            #[cfg(feature = "gstreamer")]
+            PlayerType::GStreamer => {
+                let Some(wav) = wav else { return };
+                let volume = self.curve.apply(self.effective_volume()) * level as f64;
+                crate::gst::play_chime(wav, volume);
+            }
+            #[cfg(not(feature = "gstreamer"))]
+            PlayerType::GStreamer => {}
+            PlayerType::Builtin => {}
+            PlayerType::Afplay => {
+                let Some(wav) = wav else { return };
+                let _ = spawn_detached(
+                    "afplay",
+                    &[
+                        "-v".to_string(),
+                        format!("{:.2}", level),
+                        wav.display().to_string(),
+                    ],
+                );
+            }
+        }
+    }
+
+    /// A reading that only increases while the stream is audible, for
+    /// telling a dead-silent stream from a quiet one. Only the ffmpeg
+    /// pipeline sees the samples; `None` elsewhere.
+    pub fn audible_progress(&self) -> Option<f64> {
+        match self.player_type {
+            PlayerType::Ffplay => Some(self.pcm_audible_bytes.load(Ordering::Relaxed) as f64),
+            _ => None,
+        }
+    }
+
+    /// Apply the configured hard cap and limiter, clamping the current level.
+    pub fn set_limits(&mut self, max_volume: u32, limiter: Option<f64>) {
+        self.max_volume = max_volume.min(100);
+        self.limiter = limiter;
+        self.clamp_volume();
+    }
+
+    /// Turn night mode on with `profile`, or off. Its lower cap clamps the
+    /// current level; turning it off does not raise it again.
+    pub fn set_night(&mut self, profile: Option<NightProfile>) {
+        self.night = profile;
+        self.clamp_volume();
+    }
+
+    /// Highest volume allowed: `max_volume`, or night mode's cap below it.
+    pub fn volume_cap(&self) -> u32 {
+        match self.night {
+            Some(night) => self.max_volume.min(night.max_volume),
+            None => self.max_volume,
+        }
+    }
+
+    fn clamp_volume(&mut self) {
+        self.volume = self.volume.min(self.volume_cap());
+        self.volume_before_mute = self.volume_before_mute.min(self.volume_cap());
+    }
+
+    /// Raise the volume by `steps` volume steps, up to the cap.
+    pub fn increase_volume(&mut self, steps: u32) {
+        self.volume = (self.volume + self.volume_step * steps).min(self.volume_cap());
+    }
+
+    pub fn decrease_volume(&mut self, steps: u32) {
+        self.volume = self.volume.saturating_sub(self.volume_step * steps);
+    }
+
+    pub fn playback_options(&self, volume: u32) -> PlaybackOptions {
+        PlaybackOptions {
+            volume,
+            curve: self.curve,
+            cache_secs: if self.low_data {
+                self.cache_secs.max(LOW_DATA_CACHE_SECS)
+            } else {
+                self.cache_secs
+            },
+            speed: self.speed,
+            mono: self.mono,
+            balance: self.balance,
+            limiter: self.limiter,
+            night: self.night,
+            instance: self.instance,
+            timeshift_mins: self.timeshift_mins,
+        }
+    }
+
+    /// Whether ←/→ can seek in a timeshift buffer.
+    pub fn supports_timeshift(&self) -> bool {
+        self.player_type.capabilities().seek && self.timeshift_mins > 0
+    }
+
+    /// Seek `secs` back (negative) or forward within the timeshift buffer.
+    pub async fn seek(&self, secs: f64) -> Result<(), Box<dyn std::error::Error>> {
+        match (self.player_type, self.ipc_socket.as_deref()) {
+            (PlayerType::Mpv, Some(socket)) if self.timeshift_mins > 0 => {
+                send_ipc_command(socket, &format!("seek {} relative\n", secs)).await?;
+                Ok(())
+            }
+            _ => Err("Timeshift needs mpv".into()),
+        }
+    }
+
+    /// Jump back to the live edge of the timeshift buffer.
+    pub async fn seek_live(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let window = self.timeshift().await.ok_or("Timeshift needs mpv")?;
+        let socket = self.ipc_socket.as_deref().ok_or("Timeshift needs mpv")?;
+        send_ipc_command(socket, &format!("seek {} absolute\n", window.end)).await?;
+        Ok(())
+    }
+
+    /// Stream bitrate in kbit/s, when the backend reports it (mpv).
+    pub async fn bitrate(&self) -> Option<u32> {
+        if !matches!(self.player_type, PlayerType::Mpv) {
+            return None;
+        }
+        let socket = self.ipc_socket.as_deref()?;
+        let bits = mpv_get_property(socket, "audio-bitrate").await?.as_f64()?;
+        Some((bits / 1000.0).round() as u32)
+    }
+
+    /// Codec of the audio stream, when the backend reports it (mpv).
+    pub async fn codec(&self) -> Option<String> {
+        if !matches!(self.player_type, PlayerType::Mpv) {
+            return None;
+        }
+        let socket = self.ipc_socket.as_deref()?;
+        let codec = mpv_get_property(socket, "audio-codec-name").await?;
+        codec.as_str().map(str::to_string)
+    }
+
+    /// The buffered window around the playback position, if seekable.
+    pub async fn timeshift(&self) -> Option<Timeshift> {
+        if !self.supports_timeshift() {
+            return None;
+        }
+        let socket = self.ipc_socket.as_deref()?;
+        let pos = mpv_get_property(socket, "time-pos").await?.as_f64()?;
+        let cache = mpv_get_property(socket, "demuxer-cache-state").await?;
+        let range = cache
+            .get("seekable-ranges")?
+            .as_array()?
+            .iter()
+            .filter_map(|r| Some((r.get("start")?.as_f64()?, r.get("end")?.as_f64()?)))
+            .find(|&(start, end)| start <= pos && pos <= end + 1.0)?;
+        Some(Timeshift {
+            buffered: (range.1 - range.0).max(0.0),
+            behind: (range.1 - pos).max(0.0),
+            end: range.1,
+        })
+    }
+
+    /// Spawn `player_type` from now on. What belonged to the previous
+    /// backend (PCM sink, IPC socket) is dropped, and speed goes back to
+    /// normal where the new one cannot change it.
+    pub fn set_player_type(&mut self, player_type: PlayerType) {
+        self.pcm_sink = None;
+        self.ipc_socket = None;
+        self.player_type = player_type;
+        if !self.supports_speed() {
+            self.speed = 1.0;
+        }
+    }
+
+    /// The backend's capabilities; seeking also needs a timeshift buffer.
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            seek: self.supports_timeshift(),
+            ..self.player_type.capabilities()
+        }
+    }
+
+    /// Whether the backend can change speed (with pitch correction).
+    pub fn supports_speed(&self) -> bool {
+        self.player_type.capabilities().speed
+    }
+
+    pub fn increase_speed(&mut self) {
+        self.speed = (self.speed + SPEED_STEP).min(MAX_SPEED);
     }
 
-    pub fn decrease_volume(&mut self) {
-        self.volume = self.volume.saturating_sub(5);
+    pub fn decrease_speed(&mut self) {
+        self.speed = (self.speed - SPEED_STEP).max(MIN_SPEED);
+    }
+
+    /// Level actually sent to the backend, taking hold keys into account.
+    pub fn effective_volume(&self) -> u32 {
+        match self.hold {
+            Some(HoldLevel::Duck) => self.volume.min(DUCK_VOLUME),
+            Some(HoldLevel::Boost) if !self.muted => {
+                (self.volume + BOOST_STEP).min(self.volume_cap())
+            }
+            _ => self.volume,
+        }
     }
 
     pub fn toggle_mute(&mut self) {
@@ -46,108 +669,518 @@ impl VolumeControl {
         }
     }
 
+    /// Pause or resume to match `muted`, or silence the player where it
+    /// cannot pause.
     pub async fn apply_mute(
         &self,
-        child: &mut tokio::process::Child,
+        player: &mut dyn PlayerBackend,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        match self.player_type {
-            PlayerType::Mpv => {
-                if let Some(ref socket) = self.mpv_socket {
-                    let cmd = if self.muted {
-                        "set pause yes\n"
-                    } else {
-                        "set pause no\n"
-                    };
-                    if let Ok(mut stream) = tokio::net::UnixStream::connect(socket).await {
-                        use tokio::io::AsyncWriteExt;
-                        stream.write_all(cmd.as_bytes()).await?;
-                        return Ok(());
-                    }
-                }
-                self.apply_volume(child).await
+        if player.set_paused(self.muted).await.is_ok() {
+            return Ok(());
+        }
+        self.apply_volume(player).await
+    }
+
+    /// Reopen the audio output so playback moves to the current default
+    /// device. mpv does it in place; otherwise this errors and the player
+    /// must be restarted. The PCM sink is dropped first, because it stays on
+    /// the device it opened and would take the restarted decoder with it.
+    pub async fn reload_output(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.pcm_sink = None;
+        match (self.player_type, self.ipc_socket.as_deref()) {
+            (PlayerType::Mpv, Some(socket)) => {
+                send_ipc_command(socket, "ao-reload\n").await?;
+                Ok(())
             }
-            PlayerType::Ffplay => self.apply_volume(child).await,
-            PlayerType::Afplay => {
-                #[cfg(unix)]
-                {
-                    use nix::sys::signal;
-                    use nix::unistd::Pid;
-
-                    if let Some(pid) = child.id() {
-                        let sig = if self.muted {
-                            signal::Signal::SIGSTOP
-                        } else {
-                            signal::Signal::SIGCONT
-                        };
-                        let _ = signal::kill(Pid::from_raw(pid as i32), sig);
-                        return Ok(());
-                    }
-                }
-                self.apply_volume(child).await
+            _ => Err("Output reload not available, restart needed".into()),
+        }
+    }
+
+    /// Apply mono/balance at runtime. Errors when a restart is needed.
+    pub async fn apply_audio_filters(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match (self.player_type, self.ipc_socket.as_deref()) {
+            (PlayerType::Mpv, Some(socket)) => {
+                let filters = self.playback_options(self.volume).lavfi_filters();
+                let cmd = if filters.is_empty() {
+                    "af clr \"\"\n".to_string()
+                } else {
+                    format!("af set \"lavfi=[{}]\"\n", filters.join(","))
+                };
+                send_ipc_command(socket, &cmd).await?;
+                Ok(())
             }
+            _ => Err("Runtime audio filters not available, restart needed".into()),
         }
     }
 
     pub async fn apply_volume(
         &self,
-        _child: &mut tokio::process::Child,
+        player: &mut dyn PlayerBackend,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        match self.player_type {
-            PlayerType::Mpv => {
-                if let Some(ref socket) = self.mpv_socket {
-                    let volume_cmd = format!("set volume {}%\n", self.volume);
-                    if let Ok(mut stream) = tokio::net::UnixStream::connect(socket).await {
-                        use tokio::io::AsyncWriteExt;
-                        stream.write_all(volume_cmd.as_bytes()).await?;
-                    }
+        self.set_output_volume(player, self.effective_volume())
+            .await
+    }
+
+    /// Smoothly move the output from `from` to the current effective volume.
+    /// Fails without side effects when the backend has no runtime volume.
+    pub async fn ramp_volume(
+        &self,
+        player: &mut dyn PlayerBackend,
+        from: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let target = self.effective_volume() as i64;
+        let from = from as i64;
+        for step in 1..=RAMP_STEPS {
+            let level = from + (target - from) * step / RAMP_STEPS;
+            self.set_output_volume(player, level as u32).await?;
+            if step < RAMP_STEPS {
+                tokio::time::sleep(RAMP_STEP_DELAY).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_output_volume(
+        &self,
+        player: &mut dyn PlayerBackend,
+        level: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        player.set_volume(self.curve.apply(level)).await?;
+        Ok(())
+    }
+}
+
+/// mpv, controlled over its JSON IPC socket.
+struct MpvProcess {
+    child: Child,
+    socket: Option<String>,
+}
+
+impl MpvProcess {
+    fn socket(&self) -> std::io::Result<String> {
+        self.socket.clone().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "MPV IPC not available, restart needed",
+            )
+        })
+    }
+}
+
+impl PlayerBackend for MpvProcess {
+    fn wait(&mut self) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let _ = self.child.wait().await;
+        })
+    }
+
+    fn has_ended(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
+    }
+
+    fn start_kill(&mut self) {
+        let _ = self.child.start_kill();
+    }
+
+    fn set_volume(&mut self, level: f64) -> BackendFuture<'_, std::io::Result<()>> {
+        let socket = self.socket();
+        Box::pin(async move {
+            let volume_cmd = format!("set volume {:.2}\n", level);
+            let _ = send_ipc_command(&socket?, &volume_cmd).await;
+            Ok(())
+        })
+    }
+
+    fn set_paused(&mut self, paused: bool) -> BackendFuture<'_, std::io::Result<()>> {
+        let socket = self.socket();
+        Box::pin(async move {
+            let cmd = if paused {
+                "set pause yes\n"
+            } else {
+                "set pause no\n"
+            };
+            send_ipc_command(&socket?, cmd).await
+        })
+    }
+
+    fn set_speed(&mut self, speed: f64) -> BackendFuture<'_, std::io::Result<()>> {
+        let socket = self.socket();
+        Box::pin(async move { send_ipc_command(&socket?, &format!("set speed {}\n", speed)).await })
+    }
+
+    fn progress(&mut self) -> BackendFuture<'_, Option<f64>> {
+        let socket = self.socket.clone();
+        Box::pin(async move {
+            mpv_get_property(socket.as_deref()?, "time-pos")
+                .await?
+                .as_f64()
+        })
+    }
+
+    fn observe(&mut self, generation: u64, changes: ChangeSender) {
+        if let Some(socket) = self.socket.clone() {
+            tokio::spawn(observe_mpv(socket, generation, changes));
+        }
+    }
+}
+
+/// The ffmpeg decoder of the PCM pipeline. Its volume is the gain the
+/// pump applies, and its progress the bytes pumped.
+struct PcmDecoder {
+    child: Child,
+    gain: Arc<AtomicU32>,
+    pumped: Arc<AtomicU64>,
+}
+
+impl PlayerBackend for PcmDecoder {
+    fn wait(&mut self) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let _ = self.child.wait().await;
+        })
+    }
+
+    fn has_ended(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
+    }
+
+    fn start_kill(&mut self) {
+        let _ = self.child.start_kill();
+    }
+
+    fn set_volume(&mut self, level: f64) -> BackendFuture<'_, std::io::Result<()>> {
+        self.gain
+            .store(((level / 100.0) as f32).to_bits(), Ordering::Relaxed);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn progress(&mut self) -> BackendFuture<'_, Option<f64>> {
+        let pumped = self.pumped.load(Ordering::Relaxed);
+        Box::pin(async move { Some(pumped as f64) })
+    }
+}
+
+/// afplay or the built-in noise player, paused by stopping the process.
+/// Only afplay follows the volume, through the system-wide output volume.
+struct SignalProcess {
+    child: Child,
+    system_volume: bool,
+}
+
+impl PlayerBackend for SignalProcess {
+    fn wait(&mut self) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let _ = self.child.wait().await;
+        })
+    }
+
+    fn has_ended(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
+    }
+
+    fn start_kill(&mut self) {
+        let _ = self.child.start_kill();
+    }
+
+    fn set_volume(&mut self, level: f64) -> BackendFuture<'_, std::io::Result<()>> {
+        if !self.system_volume {
+            return unsupported("volume");
+        }
+        if cfg!(target_os = "macos") {
+            let script = format!("set volume output volume {}", level.round());
+            let _ = Command::new("osascript").arg("-e").arg(script).output();
+        }
+        Box::pin(async { Ok(()) })
+    }
+
+    fn set_paused(&mut self, paused: bool) -> BackendFuture<'_, std::io::Result<()>> {
+        #[cfg(unix)]
+        {
+            use nix::sys::signal;
+            use nix::unistd::Pid;
+
+            if let Some(pid) = self.child.id() {
+                let sig = if paused {
+                    signal::Signal::SIGSTOP
                 } else {
-                    return Err("MPV IPC not available, restart needed".into());
-                }
+                    signal::Signal::SIGCONT
+                };
+                let _ = signal::kill(Pid::from_raw(pid as i32), sig);
+                return Box::pin(async { Ok(()) });
             }
-            PlayerType::Ffplay => {
-                return Err("FFplay restart needed".into());
+        }
+        #[cfg(not(unix))]
+        let _ = paused;
+        unsupported("pause")
+    }
+}
+
+/// Write a raw input command to the player's IPC socket.
+async fn send_ipc_command(socket: &str, cmd: &str) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+    let mut stream = tokio::net::UnixStream::connect(socket).await?;
+    stream.write_all(cmd.as_bytes()).await
+}
+
+// ─── ffmpeg → ffplay PCM pipeline ────────────────────────────────────────────
+
+const PCM_RATE: u32 = 48_000;
+const PCM_CHANNELS: u32 = 2;
+/// Sample RMS (of 32767) below which decoded audio counts as silence, about -70 dBFS.
+const SILENCE_RMS: f64 = 10.0;
+
+/// Long-lived `ffplay` reading raw s16le PCM from stdin. Decoders come and go
+/// (station switches, reconnects) while the sink keeps the device open.
+struct PcmSink {
+    child: tokio::process::Child,
+    stdin: Arc<Mutex<ChildStdin>>,
+}
+
+impl PcmSink {
+    fn spawn() -> Result<Self, std::io::Error> {
+        let mut child = TokioCommand::new("ffplay")
+            .args([
+                "-nodisp",
+                "-loglevel",
+                "quiet",
+                "-f",
+                "s16le",
+                "-ar",
+                &PCM_RATE.to_string(),
+                "-ch_layout",
+                "stereo",
+                "-i",
+                "pipe:0",
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or(std::io::ErrorKind::BrokenPipe)?;
+        Ok(Self {
+            child,
+            stdin: Arc::new(Mutex::new(stdin)),
+        })
+    }
+}
+
+/// Copy decoded PCM into the sink, scaling samples by the current gain.
+/// Returns when the decoder exits or the sink goes away; dropping the
+/// decoder's stdout then makes it exit too, which triggers a reconnect.
+async fn pump_pcm(
+    mut decoder: ChildStdout,
+    sink: Arc<Mutex<ChildStdin>>,
+    gain: Arc<AtomicU32>,
+    pumped: Arc<AtomicU64>,
+    audible: Arc<AtomicU64>,
+    overlay: Arc<std::sync::Mutex<VecDeque<i16>>>,
+) {
+    let mut buf = vec![0u8; 8192];
+    let mut filled = 0;
+    loop {
+        let n = match decoder.read(&mut buf[filled..]).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        filled += n;
+        // Only whole 16-bit samples are scaled; a trailing odd byte waits.
+        let whole = filled & !1;
+        let g = f32::from_bits(gain.load(Ordering::Relaxed));
+        let mixed: Vec<i16> = match overlay.lock() {
+            Ok(mut o) if !o.is_empty() => {
+                let n = (whole / 2).min(o.len());
+                o.drain(..n).collect()
             }
-            PlayerType::Afplay => {
-                if cfg!(target_os = "macos") {
-                    let script =
-                        format!("set volume output volume {}", self.volume);
-                    let _ = Command::new("osascript")
-                        .arg("-e")
-                        .arg(script)
-                        .output();
-                }
+            _ => Vec::new(),
+        };
+        let mut energy = 0.0f64;
+        for (i, sample) in buf[..whole].chunks_exact_mut(2).enumerate() {
+            let extra = mixed.get(i).copied().unwrap_or(0) as f32;
+            let decoded = i16::from_le_bytes([sample[0], sample[1]]) as f32;
+            energy += (decoded as f64).powi(2);
+            let v = (decoded + extra) * g;
+            let v = v.clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+            sample.copy_from_slice(&v.to_le_bytes());
+        }
+        if sink.lock().await.write_all(&buf[..whole]).await.is_err() {
+            return;
+        }
+        pumped.fetch_add(whole as u64, Ordering::Relaxed);
+        // RMS of the stream itself, before volume and chimes
+        if whole > 0 && (energy / (whole / 2) as f64).sqrt() > SILENCE_RMS {
+            audible.fetch_add(whole as u64, Ordering::Relaxed);
+        }
+        buf.copy_within(whole..filled, 0);
+        filled -= whole;
+    }
+}
+
+/// With less than this buffered ahead, mpv counts as buffering.
+const LOW_CACHE_SECS: f64 = 2.0;
+
+const OBSERVED_PROPERTIES: [&str; 4] = ["volume", "pause", "media-title", "demuxer-cache-duration"];
+
+/// Subscribe to property changes over mpv's JSON IPC and forward them,
+/// tagged with `generation`, until mpv exits. mpv creates the socket a
+/// moment after it starts, so connecting is retried for a few seconds.
+async fn observe_mpv(socket: String, generation: u64, tx: ChangeSender) {
+    use tokio::io::AsyncBufReadExt;
+
+    let mut stream = None;
+    for _ in 0..50 {
+        if let Ok(s) = tokio::net::UnixStream::connect(&socket).await {
+            stream = Some(s);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let Some(mut stream) = stream else { return };
+    for (id, name) in OBSERVED_PROPERTIES.iter().enumerate() {
+        let request = serde_json::json!({ "command": ["observe_property", id + 1, name] });
+        if stream
+            .write_all(format!("{}\n", request).as_bytes())
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+    let mut lines = tokio::io::BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        if message.get("event").and_then(|e| e.as_str()) != Some("property-change") {
+            continue;
+        }
+        let data = message.get("data");
+        let change = match message.get("name").and_then(|n| n.as_str()) {
+            Some("volume") => data.and_then(|d| d.as_f64()).map(PlayerChange::Volume),
+            Some("pause") => data.and_then(|d| d.as_bool()).map(PlayerChange::Pause),
+            Some("media-title") => data
+                .and_then(|d| d.as_str())
+                .map(|t| PlayerChange::Title(t.to_string())),
+            Some("demuxer-cache-duration") => data.and_then(|d| d.as_f64()).map(|secs| {
+                PlayerChange::Buffering(
+                    (secs < LOW_CACHE_SECS).then(|| (secs / LOW_CACHE_SECS * 100.0) as u32),
+                )
+            }),
+            _ => None,
+        };
+        if let Some(change) = change {
+            if tx.send((generation, change)).is_err() {
+                return;
             }
         }
-        Ok(())
     }
 }
 
-pub fn detect_player() -> Option<PlayerType> {
-    if Command::new("mpv").arg("--version").output().is_ok() {
-        Some(PlayerType::Mpv)
-    } else if Command::new("ffplay").arg("-version").output().is_ok() {
-        Some(PlayerType::Ffplay)
-    } else if cfg!(target_os = "macos")
-        && Command::new("afplay").arg("--help").output().is_ok()
-        && Command::new("curl").arg("--version").output().is_ok()
-    {
-        Some(PlayerType::Afplay)
-    } else {
+/// Query a property over mpv's JSON IPC. Returns `None` on any failure,
+/// including the property being unavailable.
+async fn mpv_get_property(socket: &str, name: &str) -> Option<serde_json::Value> {
+    use tokio::io::AsyncBufReadExt;
+
+    let query = async {
+        let mut stream = tokio::net::UnixStream::connect(socket).await.ok()?;
+        let request = serde_json::json!({ "command": ["get_property", name], "request_id": 1 });
+        stream
+            .write_all(format!("{}\n", request).as_bytes())
+            .await
+            .ok()?;
+        let mut lines = tokio::io::BufReader::new(stream).lines();
+        // Skip asynchronous events until our reply arrives.
+        while let Some(line) = lines.next_line().await.ok()? {
+            let reply: serde_json::Value = serde_json::from_str(&line).ok()?;
+            if reply.get("request_id").and_then(|id| id.as_u64()) == Some(1) {
+                if reply.get("error").and_then(|e| e.as_str()) != Some("success") {
+                    return None;
+                }
+                return reply.get("data").cloned();
+            }
+        }
         None
+    };
+    tokio::time::timeout(Duration::from_millis(500), query)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Pick the preferred player if installed, otherwise fall back to the first
+/// available one.
+pub fn detect_player(preference: PlayerPreference) -> Option<PlayerType> {
+    let preferred = match preference {
+        PlayerPreference::Auto => None,
+        PlayerPreference::Mpv => Some(PlayerType::Mpv),
+        PlayerPreference::Ffplay => Some(PlayerType::Ffplay),
+        PlayerPreference::Afplay => Some(PlayerType::Afplay),
+        PlayerPreference::Vlc => Some(PlayerType::Vlc),
+        PlayerPreference::Gstreamer => Some(PlayerType::GStreamer),
+    };
+    if let Some(p) = preferred.filter(|&p| player_available(p)) {
+        return Some(p);
     }
+    PlayerType::STREAMING
+        .into_iter()
+        .find(|&p| player_available(p))
 }
 
-/// Returns `(command, args, optional_ipc_socket_path)`.
+/// The next installed streaming backend after `current`, wrapping around.
+pub fn next_player(current: PlayerType) -> Option<PlayerType> {
+    let all = PlayerType::STREAMING;
+    let start = all.iter().position(|&p| p == current).map_or(0, |i| i + 1);
+    (0..all.len())
+        .map(|i| all[(start + i) % all.len()])
+        .find(|&p| p != current && player_available(p))
+}
+
+pub fn player_available(player_type: PlayerType) -> bool {
+    match player_type {
+        PlayerType::Mpv => Command::new("mpv").arg("--version").output().is_ok(),
+        PlayerType::Ffplay => {
+            Command::new("ffplay").arg("-version").output().is_ok()
+                && Command::new("ffmpeg").arg("-version").output().is_ok()
+        }
+        PlayerType::Afplay => {
+            cfg!(target_os = "macos")
+                && Command::new("afplay").arg("--help").output().is_ok()
+                && Command::new("curl").arg("--version").output().is_ok()
+        }
+        #[cfg(feature = "vlc")]
+        PlayerType::Vlc => crate::vlc::available(),
+        #[cfg(not(feature = "vlc"))]
+        PlayerType::Vlc => false,
+        #[cfg(feature = "gstreamer")]
+        PlayerType::GStreamer => crate::gst::available(),
+        #[cfg(not(feature = "gstreamer"))]
+        PlayerType::GStreamer => false,
+        PlayerType::Builtin => noise::pcm_output_command().is_some(),
+    }
+}
+
+/// Returns `(command, args, optional_ipc_socket_path)`. libvlc and
+/// GStreamer play in this process, so for them they are what it opens and
+/// its media options or playbin properties.
 pub fn build_player_args(
     player_type: PlayerType,
     stream_url: &str,
-    volume: u32,
+    opts: PlaybackOptions,
 ) -> (String, Vec<String>, Option<String>) {
+    let volume = opts.curve.apply(opts.volume);
+    // Generated stations: the built-in player makes the noise itself, the
+    // others loop a WAV rendering of it
+    let noise = NoiseKind::from_url(stream_url);
+    let noise_wav = match player_type {
+        PlayerType::Builtin => None,
+        _ => noise.and_then(noise::loop_wav),
+    };
+    let looped = noise_wav.is_some();
+    let noise_path = noise_wav.map(|p| p.display().to_string());
+    let stream_url = noise_path.as_deref().unwrap_or(stream_url);
     match player_type {
-        PlayerType::Ffplay => (
-            "ffplay".to_string(),
-            vec![
-                "-nodisp".to_string(),
+        PlayerType::Ffplay => {
+            // Volume is applied by the PCM pump, not by ffmpeg.
+            let mut args = vec![
                 "-loglevel".to_string(),
                 "quiet".to_string(),
                 "-reconnect".to_string(),
@@ -156,46 +1189,245 @@ pub fn build_player_args(
                 "1".to_string(),
                 "-reconnect_delay_max".to_string(),
                 "5".to_string(),
-                "-volume".to_string(),
-                volume.to_string(),
-                stream_url.to_string(),
-            ],
-            None,
-        ),
+            ];
+            if looped {
+                args.extend(["-stream_loop".to_string(), "-1".to_string()]);
+            }
+            if let Some(ca_file) = tls::ca_file(stream_url) {
+                args.extend(["-tls_verify".to_string(), "1".to_string()]);
+                args.extend(["-ca_file".to_string(), ca_file.display().to_string()]);
+            }
+            args.extend(["-i".to_string(), stream_url.to_string(), "-vn".to_string()]);
+            let mut filters = opts.lavfi_filters();
+            if opts.speed != 1.0 {
+                filters.push(format!("atempo={}", opts.speed));
+            }
+            if !filters.is_empty() {
+                args.push("-af".to_string());
+                args.push(filters.join(","));
+            }
+            args.extend([
+                "-f".to_string(),
+                "s16le".to_string(),
+                "-ar".to_string(),
+                PCM_RATE.to_string(),
+                "-ac".to_string(),
+                PCM_CHANNELS.to_string(),
+                "pipe:1".to_string(),
+            ]);
+            ("ffmpeg".to_string(), args, None)
+        }
         PlayerType::Mpv => {
-            let socket_path = format!("/tmp/mpv_lofi_{}.sock", std::process::id());
-            (
-                "mpv".to_string(),
-                vec![
-                    "--no-video".to_string(),
-                    "--no-terminal".to_string(),
-                    "--quiet".to_string(),
-                    "--stream-lavf-o=reconnect=1,reconnect_streamed=1,reconnect_delay_max=5"
-                        .to_string(),
-                    format!("--input-ipc-server={}", socket_path),
-                    format!("--volume={}", volume),
-                    stream_url.to_string(),
-                ],
-                Some(socket_path),
-            )
+            let socket_path = runtime_dir()
+                .join(format!("mpv_{}_{}.sock", opts.instance, std::process::id()))
+                .to_string_lossy()
+                .into_owned();
+            let filters = opts.lavfi_filters();
+            let mut args = vec![
+                "--no-video".to_string(),
+                "--no-terminal".to_string(),
+                "--quiet".to_string(),
+                "--stream-lavf-o=reconnect=1,reconnect_streamed=1,reconnect_delay_max=5"
+                    .to_string(),
+                format!("--input-ipc-server={}", socket_path),
+                format!("--volume={:.2}", volume),
+                format!("--speed={}", opts.speed),
+            ];
+            if opts.cache_secs > 0 {
+                args.push("--cache=yes".to_string());
+                args.push(format!("--cache-secs={}", opts.cache_secs));
+            }
+            if let Some(name) = instance() {
+                // Tells the instances apart in the sound server's mixer
+                args.push(format!("--audio-client-name=lofi_rs ({})", name));
+            }
+            if opts.timeshift_mins > 0 {
+                // Keep what was played so the live stream can be seeked back
+                args.push("--cache=yes".to_string());
+                args.push("--demuxer-seekable-cache=yes".to_string());
+                args.push("--cache-on-disk=yes".to_string());
+                args.push(format!("--demuxer-cache-dir={}", timeshift_dir().display()));
+                args.push(format!(
+                    "--demuxer-max-back-bytes={}",
+                    opts.timeshift_mins * TIMESHIFT_BYTES_PER_MIN
+                ));
+            }
+            if !filters.is_empty() {
+                args.push(format!("--af=lavfi=[{}]", filters.join(",")));
+            }
+            if looped {
+                args.push("--loop-file=inf".to_string());
+            }
+            if let Some(ca_file) = tls::ca_file(stream_url) {
+                args.push("--tls-verify=yes".to_string());
+                args.push(format!("--tls-ca-file={}", ca_file.display()));
+            }
+            // Nothing after `--` is read as an option
+            args.push("--".to_string());
+            args.push(stream_url.to_string());
+            ("mpv".to_string(), args, Some(socket_path))
+        }
+        // The URL goes to sh as `$1`, never into the script itself
+        PlayerType::Afplay if looped => {
+            let loop_cmd = r#"while true; do afplay "$1"; done"#;
+            ("sh".to_string(), sh_args(loop_cmd, stream_url), None)
         }
         PlayerType::Afplay => {
-            let curl_cmd = format!(
-                "while true; do curl -fsSL --retry 5 --retry-delay 1 '{}' | afplay -; sleep 1; done",
-                stream_url
-            );
-            ("sh".to_string(), vec!["-c".to_string(), curl_cmd], None)
+            let curl_cmd = r#"while true; do curl -fsSL --retry 5 --retry-delay 1 -- "$1" | afplay -; sleep 1; done"#;
+            ("sh".to_string(), sh_args(curl_cmd, stream_url), None)
+        }
+        PlayerType::Vlc => {
+            let mut options = vec![":no-video".to_string(), format!(":rate={}", opts.speed)];
+            if opts.cache_secs > 0 {
+                options.push(format!(":network-caching={}", opts.cache_secs * 1000));
+            }
+            if looped {
+                options.push(":input-repeat=65535".to_string());
+            }
+            (stream_url.to_string(), options, None)
+        }
+        PlayerType::GStreamer => {
+            let mut options = Vec::new();
+            if opts.cache_secs > 0 {
+                let nanos = Duration::from_secs(opts.cache_secs as u64).as_nanos();
+                options.push(format!("buffer-duration={}", nanos));
+            }
+            if looped {
+                options.push("loop".to_string());
+            }
+            (stream_url.to_string(), options, None)
+        }
+        PlayerType::Builtin => {
+            let exe = std::env::current_exe()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|_| "lofi_rs".to_string());
+            let kind = noise.unwrap_or(NoiseKind::Brown);
+            let args = vec![
+                "noise".to_string(),
+                kind.name().to_string(),
+                "--volume".to_string(),
+                (volume.round() as u32).to_string(),
+            ];
+            (exe, args, None)
         }
     }
 }
 
+/// `sh` arguments running `script` with `arg` as its `$1`.
+fn sh_args(script: &str, arg: &str) -> Vec<String> {
+    ["-c", script, "sh", arg]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Spawn a short-lived helper process and reap it in the background.
+fn spawn_detached(cmd: &str, args: &[String]) -> Result<(), std::io::Error> {
+    let mut child = TokioCommand::new(cmd)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    tokio::spawn(async move {
+        let _ = child.wait().await;
+    });
+    Ok(())
+}
+
+/// Whether bubblewrap is installed, checked once.
+#[cfg(target_os = "linux")]
+fn bwrap_available() -> bool {
+    static AVAILABLE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *AVAILABLE.get_or_init(|| Command::new("bwrap").arg("--version").output().is_ok())
+}
+
+/// Existing files named in a player's `args`, as plain paths, `--opt=PATH`
+/// values or `file://` URLs: a local file being played, a station's CA file.
+#[cfg(target_os = "linux")]
+fn files_in_args(args: &[String]) -> Vec<String> {
+    args.iter()
+        .map(|arg| match arg.strip_prefix("file://") {
+            Some(path) => path,
+            None if arg.starts_with("--") => arg.split_once('=').map_or("", |(_, v)| v),
+            None => arg,
+        })
+        .filter(|path| Path::new(path).is_absolute() && Path::new(path).is_file())
+        .map(str::to_string)
+        .collect()
+}
+
+/// bubblewrap options for a player: the system read-only, home hidden
+/// (except the PulseAudio cookie and the files in `player_args`), a private
+/// /tmp, and only the runtime and timeshift directories writable.
+#[cfg(target_os = "linux")]
+fn bwrap_args(player_args: &[String]) -> Vec<String> {
+    let mut args: Vec<String> = ["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    args.extend(["--tmpfs".to_string(), "/tmp".to_string()]);
+    if let Some(home) = std::env::var_os("HOME") {
+        let home = home.to_string_lossy().into_owned();
+        let cookie = format!("{}/.config/pulse", home);
+        args.extend(["--tmpfs".to_string(), home]);
+        args.extend(["--ro-bind-try".to_string(), cookie.clone(), cookie]);
+    }
+    // After the tmpfs mounts, which could otherwise cover them
+    for dir in [runtime_dir(), timeshift_dir()] {
+        let dir = dir.to_string_lossy().into_owned();
+        args.extend(["--bind".to_string(), dir.clone(), dir]);
+    }
+    for file in files_in_args(player_args) {
+        args.extend(["--ro-bind-try".to_string(), file.clone(), file]);
+    }
+    for flag in [
+        "--unshare-pid",
+        "--unshare-uts",
+        "--unshare-cgroup-try",
+        "--new-session",
+        "--die-with-parent",
+    ] {
+        args.push(flag.to_string());
+    }
+    args
+}
+
+/// Command for a player process. With `sandbox` on Linux it cannot gain
+/// privileges (no setuid helpers) and, when bubblewrap is installed, runs
+/// in a read-only view of the system, since it is handed URLs from imports
+/// and station directories.
+fn player_command(cmd: &str, args: &[String], sandbox: bool) -> TokioCommand {
+    #[cfg(target_os = "linux")]
+    if sandbox {
+        let mut command = if bwrap_available() {
+            let mut command = TokioCommand::new("bwrap");
+            command.args(bwrap_args(args)).arg("--").arg(cmd);
+            command
+        } else {
+            TokioCommand::new(cmd)
+        };
+        command.args(args);
+        // SAFETY: prctl is async-signal-safe and touches no parent state
+        unsafe {
+            command.pre_exec(|| nix::sys::prctl::set_no_new_privs().map_err(std::io::Error::from));
+        }
+        return command;
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = sandbox;
+    let mut command = TokioCommand::new(cmd);
+    command.args(args);
+    command
+}
+
 /// Spawn a player child process with all stdio suppressed.
-pub async fn spawn_player(
+async fn spawn_player(
     cmd: &str,
     args: &[String],
+    sandbox: bool,
 ) -> Result<tokio::process::Child, std::io::Error> {
-    TokioCommand::new(cmd)
-        .args(args)
+    player_command(cmd, args, sandbox)
         .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::null())
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// System power transitions the player cares about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerEvent {
+    /// The machine is about to suspend.
+    Sleep,
+    /// The machine has resumed from suspend.
+    Wake,
+    /// Switched to (`true`) or off battery power.
+    Battery(bool),
+}
+
+/// When the power saver (slower UI refresh, low-bitrate streams) is on.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerSaverMode {
+    #[default]
+    Off,
+    /// While running on battery.
+    Battery,
+    Always,
+}
+
+impl PowerSaverMode {
+    pub fn active(self, on_battery: bool) -> bool {
+        match self {
+            PowerSaverMode::Off => false,
+            PowerSaverMode::Battery => on_battery,
+            PowerSaverMode::Always => true,
+        }
+    }
+}
+
+/// Interval of the clock-jump detector.
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Wall-clock time that must vanish from the monotonic clock before we
+/// consider the machine to have been asleep.
+const CLOCK_JUMP_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// Interval of the battery poller.
+const BATTERY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Spawn background tasks that report suspend/resume through `tx`.
+///
+/// On Linux the logind `PrepareForSleep` signal is followed via `dbus-monitor`,
+/// which gives us a chance to stop playback before the machine goes down.
+/// On every platform a clock-jump detector reports `Wake`: the monotonic clock
+/// does not advance while suspended, so a large gap against the wall clock
+/// means we just woke up. This also covers macOS, where the IOKit power
+/// notifications are not reachable without native bindings.
+///
+/// Battery power is polled and reported as `Battery` once at startup and on
+/// every change; machines without a battery never report it.
+pub fn spawn_power_monitor(tx: UnboundedSender<PowerEvent>) {
+    #[cfg(target_os = "linux")]
+    {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let _ = watch_logind(tx).await;
+        });
+    }
+
+    {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let mut last = None;
+            loop {
+                let now = tokio::task::spawn_blocking(on_battery).await.ok().flatten();
+                if let Some(battery) = now.filter(|&b| last != Some(b)) {
+                    if tx.send(PowerEvent::Battery(battery)).is_err() {
+                        break;
+                    }
+                    last = Some(battery);
+                }
+                tokio::time::sleep(BATTERY_CHECK_INTERVAL).await;
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut last_mono = Instant::now();
+        let mut last_wall = SystemTime::now();
+        loop {
+            tokio::time::sleep(CLOCK_CHECK_INTERVAL).await;
+            let mono = last_mono.elapsed();
+            let wall = last_wall.elapsed().unwrap_or(mono);
+            last_mono = Instant::now();
+            last_wall = SystemTime::now();
+            if wall > mono + CLOCK_JUMP_THRESHOLD && tx.send(PowerEvent::Wake).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Whether the machine runs on battery; `None` when it cannot tell (or has
+/// no battery). Linux reads `/sys/class/power_supply`: a mains adapter's
+/// `online` flag when there is one, else whether a battery is discharging.
+#[cfg(target_os = "linux")]
+fn on_battery() -> Option<bool> {
+    let read = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|s| s.trim().to_string())
+    };
+    let mut battery = None;
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let dir = entry.path();
+        match read(dir.join("type")).as_deref() {
+            Some("Mains") => {
+                if let Some(online) = read(dir.join("online")) {
+                    return Some(online == "0");
+                }
+            }
+            Some("Battery") => {
+                let discharging = read(dir.join("status")).as_deref() == Some("Discharging");
+                battery = Some(battery.unwrap_or(false) || discharging);
+            }
+            _ => {}
+        }
+    }
+    battery
+}
+
+/// macOS: `pmset -g batt` starts with "Now drawing from 'Battery Power'".
+#[cfg(target_os = "macos")]
+fn on_battery() -> Option<bool> {
+    let out = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let first = text.lines().next()?;
+    if first.contains("'Battery Power'") {
+        Some(true)
+    } else if first.contains("'AC Power'") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn on_battery() -> Option<bool> {
+    None
+}
+
+/// Follow logind's `PrepareForSleep(bool)` signal on the system bus.
+#[cfg(target_os = "linux")]
+async fn watch_logind(tx: UnboundedSender<PowerEvent>) -> std::io::Result<()> {
+    use std::process::Stdio;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::process::Command as TokioCommand;
+
+    let mut child = TokioCommand::new("dbus-monitor")
+        .arg("--system")
+        .arg("type='signal',interface='org.freedesktop.login1.Manager',member='PrepareForSleep'")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let stdout = child.stdout.take().ok_or(std::io::ErrorKind::BrokenPipe)?;
+    let mut lines = BufReader::new(stdout).lines();
+    let mut in_signal = false;
+    while let Some(line) = lines.next_line().await? {
+        let line = line.trim();
+        if line.starts_with("signal ") {
+            in_signal = line.contains("member=PrepareForSleep");
+            continue;
+        }
+        if !in_signal {
+            continue;
+        }
+        let event = match line {
+            "boolean true" => PowerEvent::Sleep,
+            "boolean false" => PowerEvent::Wake,
+            _ => continue,
+        };
+        in_signal = false;
+        if tx.send(event).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,245 @@
+use std::time::{Duration, Instant};
+use tokio::process::Command as TokioCommand;
+
+use crate::config::Config;
+use crate::net;
+use crate::noise::NoiseKind;
+use crate::refresh::refusal_hint;
+use crate::tls;
+
+/// How long the stream gets to answer, and ffprobe to read it.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+/// Response headers worth showing besides the ICY ones.
+const HEADERS: [&str; 3] = ["content-type", "server", "content-length"];
+
+/// What the HTTP side of a stream looked like.
+struct HttpProbe {
+    status: reqwest::StatusCode,
+    /// Where redirects ended up, if anywhere else.
+    final_url: Option<String>,
+    headers: Vec<(String, String)>,
+    icy: Vec<(String, String)>,
+    /// Until the response headers arrived.
+    headers_after: Duration,
+    /// Until the first bytes of audio arrived, if any did.
+    audio_after: Option<Duration>,
+}
+
+async fn probe_http(url: &str) -> Result<HttpProbe, String> {
+    let client = tls::client_builder(url)
+        .connect_timeout(PROBE_TIMEOUT)
+        .user_agent(concat!("lofi_rs/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let mut response = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        client.get(url).header("Icy-MetaData", "1").send(),
+    )
+    .await
+    .map_err(|_| "no answer within 15 s".to_string())?
+    .map_err(|e| error_chain(&e))?;
+    let headers_after = started.elapsed();
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+    };
+    let headers = HEADERS
+        .iter()
+        .filter_map(|&name| Some((name.to_string(), header(name)?)))
+        .collect();
+    let mut icy: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .filter(|(name, _)| name.as_str().starts_with("icy-"))
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.as_str().to_string(), value)
+        })
+        .collect();
+    icy.sort();
+    let final_url = Some(response.url().to_string()).filter(|u| u != url);
+    let status = response.status();
+    let audio_after = match tokio::time::timeout(PROBE_TIMEOUT, response.chunk()).await {
+        Ok(Ok(Some(_))) => Some(started.elapsed()),
+        _ => None,
+    };
+    Ok(HttpProbe {
+        status,
+        final_url,
+        headers,
+        icy,
+        headers_after,
+        audio_after,
+    })
+}
+
+/// Codec details of the first audio stream, as read by ffprobe.
+async fn probe_stream(url: &str) -> Result<Vec<(&'static str, String)>, String> {
+    let output = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        TokioCommand::new("ffprobe")
+            .args([
+                "-v",
+                "error",
+                "-select_streams",
+                "a:0",
+                "-show_entries",
+                "stream=codec_name,codec_long_name,profile,sample_rate,channels,channel_layout,bit_rate:format=format_name,bit_rate",
+                "-of",
+                "json",
+                "-i",
+                url,
+            ])
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| "ffprobe read nothing within 15 s".to_string())?
+    .map_err(|e| format!("cannot run ffprobe: {} (it comes with ffmpeg)", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match stderr.lines().rev().find(|l| !l.trim().is_empty()) {
+            Some(line) => format!("ffprobe: {}", line.trim()),
+            None => format!("ffprobe exited with {}", output.status),
+        });
+    }
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).map_err(|e| format!("ffprobe output: {}", e))?;
+    let stream = json
+        .get("streams")
+        .and_then(|s| s.get(0))
+        .ok_or("no audio stream found")?;
+    let format = json.get("format");
+    let text = |value: Option<&serde_json::Value>| match value? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+    let mut details = Vec::new();
+    if let Some(codec) = text(stream.get("codec_name")) {
+        let long = text(stream.get("codec_long_name"));
+        let profile = text(stream.get("profile"));
+        let mut line = codec;
+        if let Some(long) = long {
+            line.push_str(&format!(" ({})", long));
+        }
+        if let Some(profile) = profile {
+            line.push_str(&format!(", {} profile", profile));
+        }
+        details.push(("Codec", line));
+    }
+    // Streams often leave the bitrate to the container
+    let bitrate = text(stream.get("bit_rate"))
+        .or_else(|| text(format.and_then(|f| f.get("bit_rate"))))
+        .and_then(|b| b.parse::<u64>().ok());
+    if let Some(bits) = bitrate {
+        details.push(("Bitrate", format!("{} kbit/s", bits / 1000)));
+    }
+    if let Some(rate) = text(stream.get("sample_rate")) {
+        details.push(("Sample rate", format!("{} Hz", rate)));
+    }
+    if let Some(channels) = text(stream.get("channels")) {
+        details.push((
+            "Channels",
+            match text(stream.get("channel_layout")) {
+                Some(layout) => format!("{} ({})", channels, layout),
+                None => channels,
+            },
+        ));
+    }
+    if let Some(container) = text(format.and_then(|f| f.get("format_name"))) {
+        details.push(("Container", container));
+    }
+    Ok(details)
+}
+
+/// `e` with its causes, which say what actually failed (DNS, TLS, refused).
+fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut text = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        text.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    text
+}
+
+fn millis(d: Duration) -> String {
+    format!("{} ms", d.as_millis())
+}
+
+/// `lofi_rs probe`: print what `target` (a URL or a configured station's
+/// name) serves and how fast, for finding out why a station won't play.
+pub async fn run(target: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load().ok();
+    net::init(config.as_ref().map(|c| c.ip_family).unwrap_or_default());
+    tls::init(config.as_ref().map_or(&[], |c| c.stations.as_slice()));
+    let station = config.and_then(|c| {
+        c.stations
+            .into_iter()
+            .find(|s| s.name.eq_ignore_ascii_case(target))
+    });
+    let url = station.as_ref().map_or(target, |s| s.url.as_str());
+    if NoiseKind::from_url(url).is_some() {
+        println!("{} is generated noise; there is no stream to probe", url);
+        return Ok(());
+    }
+    match &station {
+        Some(station) => println!("Probing {} ({})\n", station.name, url),
+        None => println!("Probing {}\n", url),
+    }
+
+    let network = url.starts_with("http://") || url.starts_with("https://");
+    if network {
+        let http = probe_http(url)
+            .await
+            .map_err(|e| format!("cannot connect: {}", e))?;
+        println!("HTTP");
+        println!("  Status:        {}", http.status);
+        if let Some(final_url) = &http.final_url {
+            println!("  Redirected to: {}", final_url);
+        }
+        for (name, value) in &http.headers {
+            println!("  {:<15}{}", format!("{}:", name), value);
+        }
+        println!("  Headers after: {}", millis(http.headers_after));
+        match http.audio_after {
+            Some(after) => println!("  Audio after:   {}", millis(after)),
+            None => println!("  Audio after:   no data received"),
+        }
+        println!("\nICY headers");
+        if http.icy.is_empty() {
+            println!("  None (no in-stream track titles)");
+        }
+        for (name, value) in &http.icy {
+            println!("  {:<16} {}", format!("{}:", name), value);
+        }
+        if !http.status.is_success() {
+            return Err(match refusal_hint(http.status.as_u16()) {
+                Some(hint) => format!("the server answered {}: {}", http.status, hint),
+                None => format!("the server answered {}", http.status),
+            }
+            .into());
+        }
+    }
+
+    println!("\nStream");
+    match probe_stream(url).await {
+        Ok(details) => {
+            for (name, value) in details {
+                println!("  {:<12} {}", format!("{}:", name), value);
+            }
+        }
+        Err(e) => {
+            println!("  {}", e);
+            if !network {
+                return Err(e.into());
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::cache;
+use crate::config::Station;
+use crate::net;
+
+/// radio-browser.info mirror; `all.api` resolves to any live server.
+const API_URL: &str = "https://all.api.radio-browser.info/json";
+/// Answers are asked for again after a day; older ones still serve while
+/// radio-browser.info cannot be reached.
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Station metadata from radio-browser.info. Empty strings mean unknown.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StationInfo {
+    pub name: String,
+    pub url: String,
+    /// Stream URL with playlists and redirects already resolved.
+    pub url_resolved: String,
+    pub homepage: String,
+    pub country: String,
+    pub language: String,
+    pub tags: String,
+}
+
+fn client() -> reqwest::Result<reqwest::Client> {
+    net::client_builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent(concat!("lofi_rs/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+/// The cached answer for `key`, or `fetch`'s, which is cached in turn.
+async fn cached<T, F>(key: &str, fetch: F) -> Result<T, Box<dyn std::error::Error>>
+where
+    T: DeserializeOwned + Serialize,
+    F: Future<Output = Result<T, Box<dyn std::error::Error>>>,
+{
+    if let Some(value) = cache::get("radiobrowser", key, Some(CACHE_TTL)) {
+        return Ok(value);
+    }
+    match fetch.await {
+        Ok(value) => {
+            cache::put("radiobrowser", key, &value);
+            Ok(value)
+        }
+        Err(e) => cache::get("radiobrowser", key, None).ok_or(e),
+    }
+}
+
+/// Search stations by name, most voted first.
+pub async fn search(
+    query: &str,
+    limit: u32,
+) -> Result<Vec<StationInfo>, Box<dyn std::error::Error>> {
+    let key = format!("search {} {}", limit, query);
+    cached(&key, fetch_search(query, limit)).await
+}
+
+async fn fetch_search(
+    query: &str,
+    limit: u32,
+) -> Result<Vec<StationInfo>, Box<dyn std::error::Error>> {
+    Ok(client()?
+        .get(format!("{}/stations/search", API_URL))
+        .query(&[
+            ("name", query),
+            ("limit", &limit.to_string()),
+            ("order", "votes"),
+            ("reverse", "true"),
+            ("hidebroken", "true"),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+/// Look up a station by its stream URL.
+pub async fn lookup_by_url(url: &str) -> Result<Option<StationInfo>, Box<dyn std::error::Error>> {
+    cached(&format!("byurl {}", url), fetch_by_url(url)).await
+}
+
+async fn fetch_by_url(url: &str) -> Result<Option<StationInfo>, Box<dyn std::error::Error>> {
+    let found: Vec<StationInfo> = client()?
+        .get(format!("{}/stations/byurl", API_URL))
+        .query(&[("url", url)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(found.into_iter().next())
+}
+
+impl StationInfo {
+    /// A config station for this search result.
+    pub fn to_station(&self) -> Station {
+        let url = if self.url_resolved.is_empty() {
+            &self.url
+        } else {
+            &self.url_resolved
+        };
+        let mut station = Station::new(self.name.trim(), url, None);
+        station.merge_info(self);
+        station
+    }
+}
+
+/// Look up `url` in the background and send what was found, tagged with
+/// `station` (its name), through `tx`. Failures and misses send nothing.
+pub fn spawn_lookup(station: String, url: String, tx: UnboundedSender<(String, StationInfo)>) {
+    tokio::spawn(async move {
+        if let Ok(Some(info)) = lookup_by_url(&url).await {
+            let _ = tx.send((station, info));
+        }
+    });
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// A player that stays up this long counts as healthy and resets the
+/// failure counter.
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+
+/// How hard to try reconnecting a station. Set globally under `[reconnect]`
+/// and overridden per station with a `reconnect` table.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReconnectPolicy {
+    /// Consecutive failed attempts before giving up; 0 retries forever.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on every further failure.
+    pub backoff_ms: u64,
+    /// Upper bound for the retry delay.
+    pub max_backoff_ms: u64,
+    /// Restart when playback makes no progress for this long; 0 disables.
+    pub stall_timeout_secs: u64,
+    /// Restart when the stream plays nothing but silence for this long
+    /// (ffmpeg pipeline only, which sees the samples); 0 disables.
+    pub silence_timeout_secs: u64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 10,
+            backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            stall_timeout_secs: 20,
+            silence_timeout_secs: 60,
+        }
+    }
+}
+
+/// Per-station overrides of the global [`ReconnectPolicy`].
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+pub struct ReconnectOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backoff_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_backoff_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stall_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub silence_timeout_secs: Option<u64>,
+}
+
+impl ReconnectPolicy {
+    pub fn with_overrides(self, overrides: Option<&ReconnectOverrides>) -> Self {
+        let Some(o) = overrides else {
+            return self;
+        };
+        Self {
+            max_retries: o.max_retries.unwrap_or(self.max_retries),
+            backoff_ms: o.backoff_ms.unwrap_or(self.backoff_ms),
+            max_backoff_ms: o.max_backoff_ms.unwrap_or(self.max_backoff_ms),
+            stall_timeout_secs: o.stall_timeout_secs.unwrap_or(self.stall_timeout_secs),
+            silence_timeout_secs: o.silence_timeout_secs.unwrap_or(self.silence_timeout_secs),
+        }
+    }
+}
+
+/// Decides when (and whether) a dead or stalled player is restarted.
+pub struct ReconnectSupervisor {
+    policy: ReconnectPolicy,
+    failures: u32,
+    spawned_at: Instant,
+    progress: Option<f64>,
+    progress_at: Instant,
+    audible: Option<f64>,
+    audible_at: Instant,
+    /// When the attempt after a failure is due; waited for by the event
+    /// loop, so switching away or quitting meanwhile cancels it.
+    retry_at: Option<Instant>,
+    /// The stream is being checked in the background before the attempt.
+    checking: bool,
+}
+
+impl ReconnectSupervisor {
+    pub fn new(policy: ReconnectPolicy) -> Self {
+        let now = Instant::now();
+        Self {
+            policy,
+            failures: 0,
+            spawned_at: now,
+            progress: None,
+            progress_at: now,
+            audible: None,
+            audible_at: now,
+            retry_at: None,
+            checking: false,
+        }
+    }
+
+    /// Switch to another station's policy, forgetting past failures.
+    pub fn reset(&mut self, policy: ReconnectPolicy) {
+        *self = Self::new(policy);
+    }
+
+    /// Record that a fresh player was spawned.
+    pub fn on_spawn(&mut self) {
+        self.spawned_at = Instant::now();
+        self.progress = None;
+        self.progress_at = self.spawned_at;
+        self.audible = None;
+        self.audible_at = self.spawned_at;
+        self.retry_at = None;
+        self.checking = false;
+    }
+
+    /// The player died or stalled. Returns the delay before the next attempt,
+    /// due at [`retry_at`](Self::retry_at), or `None` once `max_retries`
+    /// consecutive attempts have failed.
+    pub fn on_failure(&mut self) -> Option<Duration> {
+        if self.spawned_at.elapsed() >= STABLE_AFTER {
+            self.failures = 0;
+        }
+        if self.policy.max_retries > 0 && self.failures >= self.policy.max_retries {
+            return None;
+        }
+        let exp = self.failures.min(16);
+        self.failures += 1;
+        let delay = self
+            .policy
+            .backoff_ms
+            .saturating_mul(1 << exp)
+            .min(self.policy.max_backoff_ms);
+        let delay = Duration::from_millis(delay);
+        self.retry_at = Instant::now().checked_add(delay);
+        Some(delay)
+    }
+
+    /// When the pending attempt is due, if one is.
+    pub fn retry_at(&self) -> Option<Instant> {
+        self.retry_at
+    }
+
+    /// The pending attempt is being made: its stream is checked first.
+    pub fn take_retry(&mut self) {
+        self.retry_at = None;
+        self.checking = true;
+    }
+
+    /// The check of [`take_retry`](Self::take_retry) is back; `false` when
+    /// a player was spawned or the station switched meanwhile, so it is
+    /// stale.
+    pub fn take_check(&mut self) -> bool {
+        std::mem::take(&mut self.checking)
+    }
+
+    /// Whether the current player has been up long enough to count as healthy.
+    pub fn is_stable(&self) -> bool {
+        self.spawned_at.elapsed() >= STABLE_AFTER
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Feed a playback progress reading (position or bytes decoded).
+    /// Returns `true` when it has not moved for longer than the stall timeout.
+    pub fn check_stall(&mut self, progress: Option<f64>) -> bool {
+        if self.policy.stall_timeout_secs == 0 {
+            return false;
+        }
+        let Some(value) = progress else {
+            return false;
+        };
+        if self.progress != Some(value) {
+            self.progress = Some(value);
+            self.progress_at = Instant::now();
+            return false;
+        }
+        self.progress_at.elapsed() >= Duration::from_secs(self.policy.stall_timeout_secs)
+    }
+
+    /// Feed an audible-progress reading (see `VolumeControl::audible_progress`).
+    /// Returns `true` when the stream has been silent for longer than the
+    /// silence timeout.
+    pub fn check_silence(&mut self, audible: Option<f64>) -> bool {
+        if self.policy.silence_timeout_secs == 0 {
+            return false;
+        }
+        let Some(value) = audible else {
+            return false;
+        };
+        if self.audible != Some(value) {
+            self.audible = Some(value);
+            self.audible_at = Instant::now();
+            return false;
+        }
+        self.audible_at.elapsed() >= Duration::from_secs(self.policy.silence_timeout_secs)
+    }
+}
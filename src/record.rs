@@ -0,0 +1,949 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::watch;
+
+use crate::input::TextInput;
+use crate::loudness::{
+    ffmpeg_error, normalized_copy, Loudness, LoudnessMode, REPLAYGAIN_REFERENCE_LUFS,
+};
+use crate::metadata::Track;
+use crate::state::state_dir;
+use crate::tls;
+use crate::ui::RecordingsScreen;
+
+const MB: u64 = 1024 * 1024;
+/// Bytes written between free-space checks while recording.
+const SPACE_CHECK_BYTES: u64 = 16 * MB;
+/// Extensions of the files in the recordings directory that count as
+/// recordings, for the quota.
+const EXTENSIONS: [&str; 6] = ["mp3", "aac", "ogg", "opus", "flac", "audio"];
+
+/// What recorded files are saved as.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingFormat {
+    /// The stream as it arrives (usually MP3 or AAC).
+    #[default]
+    Copy,
+    /// Ogg Vorbis.
+    Ogg,
+    Opus,
+    Flac,
+}
+
+impl RecordingFormat {
+    pub const ALL: [RecordingFormat; 4] = [
+        RecordingFormat::Copy,
+        RecordingFormat::Ogg,
+        RecordingFormat::Opus,
+        RecordingFormat::Flac,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordingFormat::Copy => "copy",
+            RecordingFormat::Ogg => "ogg",
+            RecordingFormat::Opus => "opus",
+            RecordingFormat::Flac => "flac",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// File extension and ffmpeg encoder; `None` for a stream copy.
+    fn encoder(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            RecordingFormat::Copy => None,
+            RecordingFormat::Ogg => Some(("ogg", "libvorbis")),
+            RecordingFormat::Opus => Some(("opus", "libopus")),
+            RecordingFormat::Flac => Some(("flac", "flac")),
+        }
+    }
+}
+
+/// Where and how streams are recorded, under `[recording]`.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RecordingConfig {
+    /// Directory recordings go to; `~/Music/lofi_rs` when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<PathBuf>,
+    /// Start a new file once one reaches this many MB; 0 for no limit.
+    pub max_file_mb: u64,
+    /// Start a new file once one is this many minutes long; 0 for no limit.
+    pub max_file_minutes: u64,
+    /// MB to leave free on the disk: recording does not start with less
+    /// and stops when it gets there.
+    pub min_free_mb: u64,
+    /// MB all recordings together may take; the oldest are deleted to stay
+    /// under it. 0 keeps everything.
+    pub quota_mb: u64,
+    /// Format files are saved in unless `record` names another: "copy"
+    /// keeps the stream as is, "ogg", "opus" and "flac" transcode each
+    /// finished file with ffmpeg.
+    pub format: RecordingFormat,
+    /// Bitrate for ogg and opus, in kbit/s.
+    pub bitrate_kbps: u32,
+    /// Measure each saved file's loudness: "tag" writes ReplayGain tags,
+    /// "normalize" saves a copy at `target_lufs` next to it. Needs ffmpeg.
+    pub loudness: LoudnessMode,
+    /// Loudness of normalized copies, in LUFS. ReplayGain tags always use
+    /// the standard -18 LUFS reference.
+    pub target_lufs: f64,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            max_file_mb: 0,
+            max_file_minutes: 60,
+            min_free_mb: 500,
+            quota_mb: 0,
+            format: RecordingFormat::Copy,
+            bitrate_kbps: 160,
+            loudness: LoudnessMode::Off,
+            target_lufs: REPLAYGAIN_REFERENCE_LUFS,
+        }
+    }
+}
+
+impl RecordingConfig {
+    /// The recordings directory, with a leading `~` expanded.
+    pub fn directory(&self) -> Option<PathBuf> {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        match &self.directory {
+            Some(dir) => match dir.strip_prefix("~") {
+                Ok(rest) => Some(home?.join(rest)),
+                Err(_) => Some(dir.clone()),
+            },
+            None => Some(home?.join("Music/lofi_rs")),
+        }
+    }
+
+    /// Whether the file being written should make way for a new one.
+    fn rotate(&self, written: u64, age: Duration) -> bool {
+        (self.max_file_mb > 0 && written >= self.max_file_mb * MB)
+            || (self.max_file_minutes > 0 && age.as_secs() >= self.max_file_minutes * 60)
+    }
+}
+
+/// Results of the background recording, delivered to the event loop.
+pub enum RecordEvent {
+    /// A file was finished.
+    Saved(PathBuf),
+    /// A file could not be transcoded and was kept as recorded.
+    TranscodeFailed(PathBuf, String),
+    /// A file was saved without tags.
+    TagFailed(PathBuf, String),
+    /// A file's loudness could not be measured or its normalized copy made.
+    LoudnessFailed(PathBuf, String),
+    /// Old recordings deleted to stay under the quota.
+    Pruned(usize),
+    /// Recording ended: `Ok` when it was stopped or its time was up.
+    Stopped(Result<(), String>),
+}
+
+/// Lengths of finished recordings in seconds, by path, kept as JSON in the
+/// state directory: most recorded formats do not say how long they are.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct RecordingIndex {
+    lengths: HashMap<String, u64>,
+}
+
+impl RecordingIndex {
+    /// Load the index; a missing or corrupt file starts empty.
+    fn load() -> Self {
+        index_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save, forgetting files that are gone.
+    fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.lengths.retain(|path, _| Path::new(path).exists());
+        let path = index_path().ok_or("cannot locate state directory (HOME unset)")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn key(path: &Path) -> String {
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Note the length of the recording at `path`.
+    fn record(path: &Path, length: Duration) {
+        let mut index = Self::load();
+        index.lengths.insert(Self::key(path), length.as_secs());
+        let _ = index.save();
+    }
+}
+
+fn index_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("recordings.json"))
+}
+
+/// Bytes free for unprivileged users on the disk holding `dir`.
+fn free_space(dir: &Path) -> Option<u64> {
+    let stat = nix::sys::statvfs::statvfs(dir).ok()?;
+    Some(stat.blocks_available() as u64 * stat.fragment_size() as u64)
+}
+
+/// Fail when the disk holding `dir` has less than `min_free_mb` free.
+fn check_space(dir: &Path, min_free_mb: u64) -> Result<(), String> {
+    match free_space(dir) {
+        Some(free) if free < min_free_mb * MB => Err(format!(
+            "Only {} MB free in {} (min_free_mb is {})",
+            free / MB,
+            dir.display(),
+            min_free_mb
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Recordings still being written, transcoded or tagged, by path without
+/// extension; `prune` leaves them alone.
+static UNFINISHED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Mark the recording at `path` (any of its formats) as unfinished or not.
+fn set_unfinished(path: &Path, unfinished: bool) {
+    let key = path.with_extension("");
+    let mut paths = UNFINISHED.lock().unwrap_or_else(|e| e.into_inner());
+    paths.retain(|p| *p != key);
+    if unfinished {
+        paths.push(key);
+    }
+}
+
+fn is_unfinished(path: &Path) -> bool {
+    let key = path.with_extension("");
+    UNFINISHED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&key)
+}
+
+/// A file name as `Job::open` makes them: "{stem} YYYY-MM-DD HH-MM-SS",
+/// maybe with " (2)" and " (normalized)" after it.
+fn is_recording_name(stem: &str) -> bool {
+    let stem = stem.strip_suffix(" (normalized)").unwrap_or(stem);
+    let stem = match stem.strip_suffix(')').and_then(|s| s.rsplit_once(" (")) {
+        Some((rest, n)) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => rest,
+        _ => stem,
+    };
+    let Some(split) = stem.len().checked_sub(20) else {
+        return false;
+    };
+    stem.get(split..).is_some_and(|stamp| {
+        stamp.starts_with(' ')
+            && chrono::NaiveDateTime::parse_from_str(&stamp[1..], "%Y-%m-%d %H-%M-%S").is_ok()
+    })
+}
+
+/// Delete the oldest recordings in `dir` until they fit in `quota_mb`;
+/// returns how many went. Only files this app recorded count: those in the
+/// index or named like `Job::open` names them. Unfinished ones are kept.
+pub fn prune(dir: &Path, quota_mb: u64) -> usize {
+    if quota_mb == 0 {
+        return 0;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let index = RecordingIndex::load();
+    let mut files: Vec<(std::time::SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| EXTENSIONS.contains(&ext))
+        })
+        .filter(|path| {
+            index.lengths.contains_key(&RecordingIndex::key(path))
+                || path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(is_recording_name)
+        })
+        .filter_map(|path| {
+            let meta = std::fs::metadata(&path).ok().filter(|m| m.is_file())?;
+            Some((meta.modified().ok()?, meta.len(), path))
+        })
+        .collect();
+    files.sort();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    let mut deleted = 0;
+    for (_, len, path) in files {
+        if total <= quota_mb * MB {
+            break;
+        }
+        if is_unfinished(&path) {
+            continue;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= len;
+            deleted += 1;
+        }
+    }
+    deleted
+}
+
+/// Tags written to a finished recording, so music libraries can file it.
+struct Tags {
+    artist: Option<String>,
+    title: String,
+    /// The station.
+    album: String,
+    /// Day recording started, "2024-05-17".
+    date: String,
+    /// Measured loudness, written as ReplayGain tags.
+    replaygain: Option<Loudness>,
+}
+
+impl Tags {
+    /// Tags for a file of `station` started at `started`. A file holding a
+    /// single track is named after it; otherwise after station and time.
+    fn new(station: &str, started: DateTime<Local>, tracks: &[Track]) -> Self {
+        let (artist, title) = match tracks {
+            [track] => (track.artist.clone(), track.title.clone()),
+            _ => (
+                None,
+                format!("{} {}", station, started.format("%Y-%m-%d %H:%M")),
+            ),
+        };
+        Self {
+            artist,
+            title,
+            album: station.to_string(),
+            date: started.format("%Y-%m-%d").to_string(),
+            replaygain: None,
+        }
+    }
+
+    /// ffmpeg `-metadata` arguments; these become Vorbis comments in Ogg,
+    /// Opus and FLAC.
+    fn ffmpeg_args(&self) -> Vec<String> {
+        let mut pairs = vec![
+            ("title", self.title.as_str()),
+            ("album", self.album.as_str()),
+            ("date", self.date.as_str()),
+        ];
+        if let Some(artist) = &self.artist {
+            pairs.push(("artist", artist));
+        }
+        let replaygain = self.replaygain.map(|l| l.replaygain_tags());
+        for (key, value) in replaygain.iter().flatten() {
+            pairs.push((key, value));
+        }
+        pairs
+            .into_iter()
+            .flat_map(|(key, value)| ["-metadata".to_string(), format!("{}={}", key, value)])
+            .collect()
+    }
+
+    /// An ID3v2.3 tag with UTF-16 text frames, which nearly every player
+    /// reads in MP3 and ADTS AAC files.
+    fn id3v2(&self) -> Vec<u8> {
+        let mut frames = Vec::new();
+        let year = self.date.get(..4).unwrap_or_default();
+        let fields = [
+            ("TIT2", Some(self.title.as_str())),
+            ("TPE1", self.artist.as_deref()),
+            ("TALB", Some(self.album.as_str())),
+            ("TYER", Some(year)),
+        ];
+        for (id, text) in fields {
+            let Some(text) = text.filter(|t| !t.is_empty()) else {
+                continue;
+            };
+            // Encoding 1: UTF-16 with a byte order mark
+            let mut data = vec![1, 0xFF, 0xFE];
+            data.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            frames.extend_from_slice(id.as_bytes());
+            frames.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            frames.extend_from_slice(&[0, 0]);
+            frames.extend(data);
+        }
+        // User text frames: encoding 0 (Latin-1), description, value
+        for (key, value) in self.replaygain.iter().flat_map(|l| l.replaygain_tags()) {
+            let data = [&[0], key.as_bytes(), &[0], value.as_bytes()].concat();
+            frames.extend_from_slice(b"TXXX");
+            frames.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            frames.extend_from_slice(&[0, 0]);
+            frames.extend(data);
+        }
+        // The tag size is "syncsafe": seven bits per byte
+        let size = frames.len() as u32;
+        let mut tag = b"ID3\x03\x00\x00".to_vec();
+        tag.extend([21, 14, 7, 0].map(|shift| ((size >> shift) & 0x7F) as u8));
+        tag.extend(frames);
+        tag
+    }
+}
+
+/// Put an ID3v2 tag in front of the MP3 or AAC file at `path`.
+fn write_id3(path: &Path, tags: &Tags) -> std::io::Result<()> {
+    let tagged = path.with_extension("tagging");
+    let written = (|| {
+        let mut out = std::io::BufWriter::new(File::create(&tagged)?);
+        out.write_all(&tags.id3v2())?;
+        std::io::copy(&mut File::open(path)?, &mut out)?;
+        out.flush()
+    })();
+    match written {
+        Ok(()) => std::fs::rename(&tagged, path),
+        Err(e) => {
+            let _ = std::fs::remove_file(&tagged);
+            Err(e)
+        }
+    }
+}
+
+/// Transcode `path` to `format` with ffmpeg, next to it and tagged with
+/// `tags`, and delete the original; returns the new file.
+async fn transcode(
+    path: &Path,
+    format: RecordingFormat,
+    bitrate_kbps: u32,
+    tags: &Tags,
+) -> Result<PathBuf, String> {
+    let Some((ext, encoder)) = format.encoder() else {
+        return Ok(path.to_path_buf());
+    };
+    // Streams already in the format are kept as they are
+    if path.extension().is_some_and(|e| e == ext) {
+        return Ok(path.to_path_buf());
+    }
+    let out = path.with_extension(ext);
+    let mut cmd = TokioCommand::new("ffmpeg");
+    cmd.args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(path)
+        .args(["-vn", "-c:a", encoder]);
+    if format != RecordingFormat::Flac {
+        cmd.arg("-b:a").arg(format!("{}k", bitrate_kbps));
+    }
+    let output = cmd
+        .args(tags.ffmpeg_args())
+        .arg(&out)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("cannot run ffmpeg: {}", e))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&out);
+        return Err(ffmpeg_error(&output));
+    }
+    let _ = std::fs::remove_file(path);
+    Ok(out)
+}
+
+/// File extension for a stream's Content-Type.
+fn extension(content_type: &str) -> &'static str {
+    match content_type.split(';').next().unwrap_or("").trim() {
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/aac" | "audio/aacp" | "audio/x-aac" => "aac",
+        "audio/ogg" | "application/ogg" => "ogg",
+        "audio/opus" => "opus",
+        "audio/flac" | "audio/x-flac" => "flac",
+        _ => "audio",
+    }
+}
+
+/// Station names as file names: no path separators or control characters.
+fn file_stem(station: &str) -> String {
+    let stem: String = station
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    stem.trim_matches(|c: char| c == '.' || c.is_whitespace())
+        .to_string()
+}
+
+/// A stream being recorded in the background.
+pub struct Recorder {
+    pub station: String,
+    pub format: RecordingFormat,
+    started: Instant,
+    deadline: Option<Instant>,
+    stop: watch::Sender<bool>,
+    track: watch::Sender<Option<Track>>,
+}
+
+impl Recorder {
+    /// Record `url` of `station`, now playing `track`, into the recordings
+    /// directory, saved as `format`, until stopped or, with `duration`,
+    /// until it is up. Fails when the directory cannot be made or the disk
+    /// is too full.
+    pub fn start(
+        config: &RecordingConfig,
+        station: &str,
+        url: &str,
+        track: Option<Track>,
+        duration: Option<Duration>,
+        format: RecordingFormat,
+        tx: UnboundedSender<RecordEvent>,
+    ) -> Result<Self, String> {
+        let dir = config
+            .directory()
+            .ok_or("Cannot locate the recordings directory (HOME unset)")?;
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+        let pruned = prune(&dir, config.quota_mb);
+        if pruned > 0 {
+            let _ = tx.send(RecordEvent::Pruned(pruned));
+        }
+        check_space(&dir, config.min_free_mb)?;
+
+        let (stop, stopped) = watch::channel(false);
+        let (track, playing) = watch::channel(track);
+        let deadline = duration.and_then(|d| Instant::now().checked_add(d));
+        let job = Job {
+            config: config.clone(),
+            dir,
+            station: station.to_string(),
+            stem: file_stem(station),
+            url: url.to_string(),
+            format,
+            playing,
+            tx: tx.clone(),
+        };
+        tokio::spawn(async move {
+            let result = job.run(deadline, stopped).await;
+            let _ = tx.send(RecordEvent::Stopped(result));
+        });
+        Ok(Self {
+            station: station.to_string(),
+            format,
+            started: Instant::now(),
+            deadline,
+            stop,
+            track,
+        })
+    }
+
+    /// The station moved on to `track`, for the tags of the file.
+    pub fn set_track(&self, track: Option<Track>) {
+        let _ = self.track.send(track);
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Time left of a recording started with a duration.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Finish the current file and end the recording.
+    pub fn stop(&self) {
+        let _ = self.stop.send(true);
+    }
+}
+
+/// The file being written.
+struct Part {
+    file: File,
+    path: PathBuf,
+    opened: Instant,
+    started: DateTime<Local>,
+    written: u64,
+    /// Tracks heard in it, in order.
+    tracks: Vec<Track>,
+}
+
+struct Job {
+    config: RecordingConfig,
+    dir: PathBuf,
+    station: String,
+    stem: String,
+    url: String,
+    format: RecordingFormat,
+    playing: watch::Receiver<Option<Track>>,
+    tx: UnboundedSender<RecordEvent>,
+}
+
+impl Job {
+    fn open(&self, ext: &str) -> Result<Part, String> {
+        let started = Local::now();
+        let stamp = started.format("%Y-%m-%d %H-%M-%S");
+        let mut path = self.dir.join(format!("{} {}.{}", self.stem, stamp, ext));
+        let mut n = 2;
+        while path.exists() {
+            path = self
+                .dir
+                .join(format!("{} {} ({}).{}", self.stem, stamp, n, ext));
+            n += 1;
+        }
+        let file =
+            File::create(&path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+        set_unfinished(&path, true);
+        Ok(Part {
+            file,
+            path,
+            opened: Instant::now(),
+            started,
+            written: 0,
+            tracks: Vec::new(),
+        })
+    }
+
+    /// Close `part`, transcode and tag it in the background while
+    /// recording goes on, report it and make room under the quota.
+    fn finish(&self, part: Part) {
+        let Part {
+            mut file,
+            path,
+            opened,
+            started,
+            tracks,
+            ..
+        } = part;
+        let length = opened.elapsed();
+        let _ = file.flush();
+        drop(file);
+        let mut tags = Tags::new(&self.station, started, &tracks);
+        let (format, bitrate) = (self.format, self.config.bitrate_kbps);
+        let (mode, target) = (self.config.loudness, self.config.target_lufs);
+        let (dir, quota_mb, tx) = (self.dir.clone(), self.config.quota_mb, self.tx.clone());
+        tokio::spawn(async move {
+            let part_path = path.clone();
+            // Measured before transcoding, so the tags go in with the rest
+            let loudness = match mode {
+                LoudnessMode::Off => None,
+                _ => match Loudness::measure(&path).await {
+                    Ok(loudness) => Some(loudness),
+                    Err(e) => {
+                        let _ = tx.send(RecordEvent::LoudnessFailed(path.clone(), e));
+                        None
+                    }
+                },
+            };
+            if mode == LoudnessMode::Tag {
+                tags.replaygain = loudness;
+            }
+            let saved = match transcode(&path, format, bitrate, &tags).await {
+                Ok(saved) => saved,
+                Err(e) => {
+                    let _ = tx.send(RecordEvent::TranscodeFailed(path.clone(), e));
+                    path
+                }
+            };
+            // Files from ffmpeg carry their tags already
+            if saved.extension().is_some_and(|e| e == "mp3" || e == "aac") {
+                let tagging = saved.clone();
+                let tagged = tokio::task::spawn_blocking(move || write_id3(&tagging, &tags)).await;
+                if let Ok(Err(e)) = tagged {
+                    let _ = tx.send(RecordEvent::TagFailed(saved.clone(), e.to_string()));
+                }
+            }
+            RecordingIndex::record(&saved, length);
+            if let (LoudnessMode::Normalize, Some(loudness)) = (mode, loudness) {
+                match normalized_copy(&saved, loudness, target, bitrate).await {
+                    Ok(copy) => {
+                        RecordingIndex::record(&copy, length);
+                        let _ = tx.send(RecordEvent::Saved(copy));
+                    }
+                    Err(e) => {
+                        let _ = tx.send(RecordEvent::LoudnessFailed(saved.clone(), e));
+                    }
+                }
+            }
+            let _ = tx.send(RecordEvent::Saved(saved));
+            set_unfinished(&part_path, false);
+            let pruned = prune(&dir, quota_mb);
+            if pruned > 0 {
+                let _ = tx.send(RecordEvent::Pruned(pruned));
+            }
+        });
+    }
+
+    async fn run(
+        &self,
+        deadline: Option<Instant>,
+        mut stopped: watch::Receiver<bool>,
+    ) -> Result<(), String> {
+        let client = tls::client_builder(&self.url)
+            .connect_timeout(Duration::from_secs(10))
+            .user_agent(concat!("lofi_rs/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let mut response = client
+            .get(&self.url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| e.to_string())?;
+        let ext = extension(
+            response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or(""),
+        );
+
+        let mut part: Option<Part> = None;
+        let mut unchecked: u64 = 0;
+        let result = loop {
+            let time_up = async {
+                match deadline {
+                    Some(at) => tokio::time::sleep_until(at.into()).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let chunk = tokio::select! {
+                chunk = response.chunk() => chunk,
+                _ = stopped.changed() => break Ok(()),
+                _ = time_up => break Ok(()),
+            };
+            let bytes = match chunk {
+                Ok(Some(bytes)) => bytes,
+                Ok(None) => break Err("The stream ended".to_string()),
+                Err(e) => break Err(e.to_string()),
+            };
+            if part
+                .as_ref()
+                .is_some_and(|p| self.config.rotate(p.written, p.opened.elapsed()))
+            {
+                if let Some(done) = part.take() {
+                    self.finish(done);
+                }
+            }
+            let current = match &mut part {
+                Some(current) => current,
+                None => part.insert(self.open(ext)?),
+            };
+            if let Err(e) = current.file.write_all(&bytes) {
+                break Err(format!("Cannot write {}: {}", current.path.display(), e));
+            }
+            current.written += bytes.len() as u64;
+            if let Some(track) = self.playing.borrow().as_ref() {
+                if current.tracks.last() != Some(track) {
+                    current.tracks.push(track.clone());
+                }
+            }
+            unchecked += bytes.len() as u64;
+            if unchecked >= SPACE_CHECK_BYTES {
+                unchecked = 0;
+                if let Err(e) = check_space(&self.dir, self.config.min_free_mb) {
+                    break Err(e);
+                }
+            }
+        };
+        if let Some(done) = part {
+            self.finish(done);
+        }
+        result
+    }
+}
+
+/// A finished recording, as the recordings view lists it.
+pub struct RecordingFile {
+    pub path: PathBuf,
+    pub size: u64,
+    modified: SystemTime,
+    /// Length in seconds, when it was recorded here.
+    pub length: Option<u64>,
+}
+
+impl RecordingFile {
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Saved recordings, newest first, with one selected and maybe being
+/// renamed.
+pub struct RecordingsView {
+    dir: Option<PathBuf>,
+    pub files: Vec<RecordingFile>,
+    pub selected: usize,
+    /// New name of the selected file, without its extension.
+    pub rename: Option<TextInput>,
+}
+
+impl RecordingsView {
+    pub fn open(config: &RecordingConfig) -> Self {
+        let mut view = Self {
+            dir: config.directory(),
+            files: Vec::new(),
+            selected: 0,
+            rename: None,
+        };
+        view.refresh();
+        view
+    }
+
+    /// Read the directory again, keeping the selection in range.
+    pub fn refresh(&mut self) {
+        let index = RecordingIndex::load();
+        let entries = self
+            .dir
+            .as_deref()
+            .and_then(|dir| std::fs::read_dir(dir).ok());
+        self.files = entries
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|e| {
+                e.path()
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| EXTENSIONS.contains(&ext))
+            })
+            .filter_map(|e| {
+                let meta = e.metadata().ok().filter(|m| m.is_file())?;
+                let path = e.path();
+                Some(RecordingFile {
+                    length: index.lengths.get(&RecordingIndex::key(&path)).copied(),
+                    size: meta.len(),
+                    modified: meta.modified().ok()?,
+                    path,
+                })
+            })
+            .collect();
+        self.files.sort_by_key(|f| std::cmp::Reverse(f.modified));
+        self.selected = self.selected.min(self.files.len().saturating_sub(1));
+    }
+
+    pub fn move_selection(&mut self, dir: i32) {
+        let n = self.files.len() as i32;
+        if n > 0 {
+            self.selected = (self.selected as i32 + dir).rem_euclid(n) as usize;
+        }
+    }
+
+    pub fn selected(&self) -> Option<&RecordingFile> {
+        self.files.get(self.selected)
+    }
+
+    /// Start renaming the selected file.
+    pub fn start_rename(&mut self) {
+        if let Some(file) = self.selected() {
+            let mut input = TextInput::default();
+            input.insert(&file.path.file_stem().unwrap_or_default().to_string_lossy());
+            self.rename = Some(input);
+        }
+    }
+
+    /// Give the selected file the name typed; its extension stays.
+    pub fn finish_rename(&mut self) -> Result<String, String> {
+        let Some(mut input) = self.rename.take() else {
+            return Err("Nothing to rename".to_string());
+        };
+        let Some(file) = self.selected() else {
+            return Err("Nothing to rename".to_string());
+        };
+        let stem = input.submit().trim().to_string();
+        if stem.is_empty() || stem.contains(['/', '\\']) || stem.starts_with('.') {
+            return Err(format!("\"{}\" is not a usable file name", stem));
+        }
+        let mut name = stem;
+        if let Some(ext) = file.path.extension() {
+            name = format!("{}.{}", name, ext.to_string_lossy());
+        }
+        let to = file.path.with_file_name(&name);
+        if to == file.path {
+            return Ok(name);
+        }
+        if to.exists() {
+            return Err(format!("{} already exists", name));
+        }
+        std::fs::rename(&file.path, &to).map_err(|e| e.to_string())?;
+        let mut index = RecordingIndex::load();
+        if let Some(length) = index.lengths.remove(&RecordingIndex::key(&file.path)) {
+            index.lengths.insert(RecordingIndex::key(&to), length);
+        }
+        let _ = index.save();
+        self.refresh();
+        if let Some(i) = self.files.iter().position(|f| f.path == to) {
+            self.selected = i;
+        }
+        Ok(name)
+    }
+
+    /// Snapshot handed to the UI.
+    pub fn screen(&self) -> RecordingsScreen {
+        RecordingsScreen {
+            directory: self
+                .dir
+                .as_ref()
+                .map(|d| d.display().to_string())
+                .unwrap_or_default(),
+            rows: self
+                .files
+                .iter()
+                .map(|f| {
+                    let length = f.length.map_or("—".to_string(), |secs| {
+                        format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+                    });
+                    (
+                        f.name(),
+                        format!("{:.1} MB", f.size as f64 / MB as f64),
+                        length,
+                    )
+                })
+                .collect(),
+            selected: self.selected,
+            renaming: self
+                .rename
+                .as_ref()
+                .map(|input| (input.text().to_string(), input.cursor())),
+        }
+    }
+}
+
+/// Delete the recording at `path`.
+pub fn delete(path: &Path) -> std::io::Result<()> {
+    std::fs::remove_file(path)?;
+    let _ = RecordingIndex::load().save();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_names_match_what_open_makes() {
+        assert!(is_recording_name("Jazz Cafe 2024-05-17 21-04-09"));
+        assert!(is_recording_name("Jazz Cafe 2024-05-17 21-04-09 (2)"));
+        assert!(is_recording_name(
+            "Jazz Cafe 2024-05-17 21-04-09 (normalized)"
+        ));
+        assert!(is_recording_name(
+            "Ünïcødé 2024-05-17 21-04-09 (3) (normalized)"
+        ));
+        assert!(!is_recording_name("Jazz Cafe"));
+        assert!(!is_recording_name("holiday mix 2024"));
+        assert!(!is_recording_name("Jazz Cafe 2024-05-17 21:04:09"));
+        assert!(!is_recording_name("Jazz Cafe 2024-05-17 21-04-09 (x)"));
+        assert!(!is_recording_name("ÜÜÜÜÜÜÜÜÜÜÜ"));
+    }
+}
@@ -0,0 +1,153 @@
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::process::Command as TokioCommand;
+
+use crate::config::Station;
+use crate::net;
+use crate::tls;
+
+/// How long a `refresh_command` may run before it is killed.
+const REFRESH_COMMAND_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Deserialize)]
+struct RefreshResponse {
+    url: String,
+}
+
+/// What a stream URL answers, for telling why the player gave up on it.
+pub enum StreamCheck {
+    /// Served where it was asked for, or not reachable over HTTP at all.
+    Fine,
+    /// Served from this URL after redirects. Players may not follow them,
+    /// e.g. ffmpeg refuses an https → http redirect.
+    Redirected(String),
+    /// Refused with this HTTP status.
+    Refused(u16),
+}
+
+impl StreamCheck {
+    /// A refusal that may point at an expired token (401 Unauthorized,
+    /// 403 Forbidden or 410 Gone), worth a `refresh_command`.
+    pub fn refreshable(&self) -> bool {
+        matches!(self, StreamCheck::Refused(401 | 403 | 410))
+    }
+
+    /// A refusal retrying will not get past: the status and what to try.
+    pub fn refusal(&self) -> Option<(u16, &'static str)> {
+        match *self {
+            StreamCheck::Refused(status) => Some((status, refusal_hint(status)?)),
+            _ => None,
+        }
+    }
+}
+
+/// Ask the server for `url`, following redirects, and see how it answers.
+pub async fn check_stream(url: &str) -> StreamCheck {
+    let Ok(client) = tls::client_builder(url)
+        .timeout(Duration::from_secs(5))
+        .user_agent(concat!("lofi_rs/", env!("CARGO_PKG_VERSION")))
+        .build()
+    else {
+        return StreamCheck::Fine;
+    };
+    match client.get(url).send().await {
+        Ok(resp) if resp.status().is_client_error() => StreamCheck::Refused(resp.status().as_u16()),
+        Ok(resp) if reqwest::Url::parse(url).ok().as_ref() != Some(resp.url()) => {
+            StreamCheck::Redirected(resp.url().to_string())
+        }
+        _ => StreamCheck::Fine,
+    }
+}
+
+/// What to do about a stream the player gave up on, found by [`recheck`].
+pub enum Recheck {
+    /// Nothing found; play it again.
+    Retry,
+    /// Play it from where it redirects to.
+    Moved(String),
+    /// Play this fresh URL from the station's refresh source.
+    Refreshed(String),
+    /// Refreshing failed; play the old URL again.
+    RefreshFailed(String),
+    /// Give up: refused with this status, and what to try.
+    Refused(u16, &'static str),
+}
+
+/// Ask the server why `url` of `station` stopped playing: tokenised URLs
+/// expire and are re-resolved, redirects are followed for the player, and
+/// refusals retrying cannot fix are explained instead.
+pub async fn recheck(station: &Station, url: &str) -> Recheck {
+    let check = check_stream(url).await;
+    if let StreamCheck::Redirected(url) = check {
+        return Recheck::Moved(url);
+    }
+    if station.can_refresh() && check.refreshable() {
+        return match resolve_stream_url(station).await {
+            Ok(url) => Recheck::Refreshed(url),
+            Err(e) => Recheck::RefreshFailed(e.to_string()),
+        };
+    }
+    match check.refusal() {
+        Some((status, hint)) => Recheck::Refused(status, hint),
+        None => Recheck::Retry,
+    }
+}
+
+/// Why a server answering `status` keeps refusing, and what to try.
+pub fn refusal_hint(status: u16) -> Option<&'static str> {
+    match status {
+        401 => Some("it wants a login or token — set refresh_command or refresh_url"),
+        403 => Some("blocked in your region, or missing headers (User-Agent, Referer) — try a VPN"),
+        404 => Some("the stream is gone — look for its current URL"),
+        410 => Some("the link expired — set refresh_command or refresh_url"),
+        451 => Some("blocked in your region for legal reasons — try a VPN"),
+        _ => None,
+    }
+}
+
+/// Obtain a fresh stream URL for `station` from its `refresh_command` or
+/// `refresh_url`.
+pub async fn resolve_stream_url(station: &Station) -> Result<String, Box<dyn std::error::Error>> {
+    let text = if let Some(cmd) = station.refresh_command.as_deref() {
+        let output = TokioCommand::new("sh")
+            .arg("-c")
+            .arg(cmd)
+            .kill_on_drop(true)
+            .output();
+        let out = tokio::time::timeout(REFRESH_COMMAND_TIMEOUT, output)
+            .await
+            .map_err(|_| {
+                format!(
+                    "refresh command timed out after {}s",
+                    REFRESH_COMMAND_TIMEOUT.as_secs()
+                )
+            })??;
+        if !out.status.success() {
+            return Err(format!("refresh command exited with {}", out.status).into());
+        }
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    } else if let Some(endpoint) = station.refresh_url.as_deref() {
+        let client = net::client_builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let body = client
+            .get(endpoint)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        match serde_json::from_str::<RefreshResponse>(&body) {
+            Ok(resp) => resp.url,
+            Err(_) => body,
+        }
+    } else {
+        return Err("no refresh_command or refresh_url configured".into());
+    };
+
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| "refresh returned no URL".into())
+}
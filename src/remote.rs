@@ -0,0 +1,542 @@
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_rustls::rustls;
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::runtime_dir;
+use crate::net::is_stream_url;
+use crate::status::Status;
+
+/// The single-page remote served at `/`.
+const PAGE: &str = include_str!("remote.html");
+/// Requests larger than this are refused; commands are a few bytes.
+const MAX_REQUEST: usize = 16 * 1024;
+/// A client that sends nothing for this long is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Station switches closer together than this are refused, so a stuck
+/// client cannot keep the player reconnecting.
+const MIN_SWITCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// `[remote]` in the config: a web remote for phones on the same network.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    pub enabled: bool,
+    /// Address and port to listen on; empty listens on `socket` only.
+    pub listen: String,
+    /// Also listen on `remote.sock` in the runtime directory, reachable
+    /// from elsewhere through `ssh -L` without opening a port. Plain HTTP:
+    /// the ssh connection encrypts it.
+    pub socket: bool,
+    /// Secret the API requires, as `Authorization: Bearer TOKEN` or
+    /// `?token=TOKEN`; open the remote as `http://HOST:PORT/?token=TOKEN`.
+    /// Required unless `listen` is a loopback address.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// PEM certificate chain and private key; with both set the remote is
+    /// served over HTTPS.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_cert: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_key: Option<PathBuf>,
+    /// Accept stream URLs in play requests, not just station numbers.
+    pub allow_urls: bool,
+}
+
+impl RemoteConfig {
+    /// Listening beyond this machine without a token would let anyone on
+    /// the network control the player, so the port is not opened.
+    fn is_exposed(&self) -> bool {
+        self.token.as_deref().is_none_or(str::is_empty)
+            && !matches!(self.listen_host(), "" | "127.0.0.1" | "localhost" | "[::1]")
+    }
+
+    /// `listen` without its port.
+    fn listen_host(&self) -> &str {
+        self.listen
+            .rsplit_once(':')
+            .map_or(self.listen.as_str(), |(h, _)| h)
+    }
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: "0.0.0.0:8737".to_string(),
+            socket: false,
+            token: None,
+            tls_cert: None,
+            tls_key: None,
+            allow_urls: false,
+        }
+    }
+}
+
+/// A request from the web remote, carried out by the event loop.
+pub enum RemoteCommand {
+    /// Switch to the station at this index.
+    Play(usize),
+    /// Play a stream URL, for this run only (`allow_urls`).
+    PlayUrl(String),
+    Volume(u32),
+    TogglePause,
+}
+
+/// What the remote shows, refreshed by the event loop.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct RemoteView {
+    pub status: Option<Status>,
+    pub stations: Vec<String>,
+}
+
+pub type SharedView = Arc<Mutex<RemoteView>>;
+
+/// Body of `POST /api/play`: a station number or, with `allow_urls`, a URL.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlayRequest {
+    station: Option<usize>,
+    url: Option<String>,
+}
+
+/// Body of `POST /api/volume`; values above 100 are clamped.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VolumeRequest {
+    volume: u32,
+}
+
+/// Body of commands without arguments: empty, or `{}`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EmptyRequest {}
+
+/// What every connection of one server shares.
+struct Server {
+    token: Option<String>,
+    /// Host part of `listen`, accepted in `Host` headers.
+    listen_host: String,
+    allow_urls: bool,
+    view: SharedView,
+    tx: UnboundedSender<RemoteCommand>,
+    last_switch: Mutex<Option<Instant>>,
+}
+
+type Reply = Result<(&'static str, &'static str, String), (&'static str, String)>;
+
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a [u8]) -> Result<T, (&'static str, String)> {
+    serde_json::from_slice(body).map_err(|e| ("400 Bad Request", e.to_string()))
+}
+
+struct Request {
+    method: String,
+    path: String,
+    /// Token from the `Authorization: Bearer` header or the `token` query
+    /// parameter.
+    token: Option<String>,
+    /// `Host` and `Origin` headers, checked against DNS rebinding and
+    /// cross-site requests.
+    host: Option<String>,
+    origin: Option<String>,
+    /// The body is declared as JSON. Requiring it keeps plain cross-site
+    /// form posts from reaching the commands.
+    json: bool,
+    body: Vec<u8>,
+}
+
+/// Compare in time independent of where the inputs differ, so the token
+/// cannot be guessed byte by byte from response times.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Read one HTTP/1.1 request; `None` on malformed or oversized input.
+async fn read_request<S: AsyncRead + Unpin>(stream: &mut S) -> Option<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 2048];
+    let header_end = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        if buf.len() > MAX_REQUEST {
+            return None;
+        }
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let head = std::str::from_utf8(&buf[..header_end]).ok()?;
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: Vec<(&str, &str)> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    let header = |wanted: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+            .map(|(_, value)| *value)
+    };
+    let length = header("content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let token = header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| {
+            let query = path.split_once('?')?.1;
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("token="))
+        })
+        .map(str::to_string);
+    let json = header("content-type").is_some_and(|value| value.starts_with("application/json"));
+    let host = header("host").map(str::to_string);
+    let origin = header("origin").map(str::to_string);
+    // A huge Content-Length must not wrap around the limit
+    if header_end.checked_add(length)? > MAX_REQUEST {
+        return None;
+    }
+    let mut body = buf[header_end..].to_vec();
+    while body.len() < length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(length);
+    Some(Request {
+        method,
+        path,
+        token,
+        host,
+        origin,
+        json,
+        body,
+    })
+}
+
+async fn respond<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) {
+    let challenge = if status.starts_with("401") {
+        "WWW-Authenticate: Bearer\r\n"
+    } else if status.starts_with("429") {
+        "Retry-After: 2\r\n"
+    } else {
+        ""
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\n{}Content-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        challenge,
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Whether `host` (a `Host` header, maybe with a port) names this server:
+/// an IP address, localhost or the `listen` host. Other names may have been
+/// pointed at it by a page rebinding its DNS to reach the API.
+fn is_own_host(host: &str, listen_host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split_once(']').map_or("", |(ip, _)| ip),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    !name.is_empty()
+        && (name.eq_ignore_ascii_case("localhost")
+            || name.parse::<std::net::IpAddr>().is_ok()
+            || name.eq_ignore_ascii_case(listen_host.trim_matches(['[', ']'])))
+}
+
+/// Route a request: the page, the status, or a command for the event loop.
+fn handle(request: &Request, server: &Server) -> Reply {
+    let path = request.path.split('?').next().unwrap_or("");
+    let host = request.host.as_deref().unwrap_or("");
+    if !is_own_host(host, &server.listen_host) {
+        return Err(("403 Forbidden", "unexpected Host".to_string()));
+    }
+    // Browsers name the page making the request; only our own may
+    if let Some(origin) = &request.origin {
+        let own = ["http://", "https://"]
+            .iter()
+            .any(|scheme| origin.strip_prefix(scheme) == Some(host));
+        if !own {
+            return Err(("403 Forbidden", "cross-origin request".to_string()));
+        }
+    }
+    // The page holds no data; it passes its own `?token=` on to the API
+    if (request.method.as_str(), path) == ("GET", "/") {
+        return Ok(("200 OK", "text/html; charset=utf-8", PAGE.to_string()));
+    }
+    let expected = server.token.as_deref().filter(|t| !t.is_empty());
+    if let Some(expected) = expected {
+        let given = request.token.as_deref().unwrap_or("");
+        if !constant_time_eq(given.as_bytes(), expected.as_bytes()) {
+            return Err(("401 Unauthorized", "missing or wrong token".to_string()));
+        }
+    }
+    if (request.method.as_str(), path) == ("GET", "/api/status") {
+        let view = server
+            .view
+            .lock()
+            .map_err(|_| ("500 Internal Server Error", String::new()))?;
+        let json = serde_json::to_string(&*view).unwrap_or_default();
+        return Ok(("200 OK", "application/json", json));
+    }
+    if request.method != "POST" || !matches!(path, "/api/play" | "/api/volume" | "/api/toggle") {
+        return Err(("404 Not Found", "not found".to_string()));
+    }
+    let body: &[u8] = if request.body.is_empty() {
+        b"{}"
+    } else {
+        &request.body
+    };
+    // Without a token, only a JSON request (which a cross-site form cannot
+    // send) may run a command, even with an empty body
+    if !request.json && expected.is_none() {
+        return Err((
+            "415 Unsupported Media Type",
+            "send application/json".to_string(),
+        ));
+    }
+    let command = match path {
+        "/api/play" => play_command(parse_body(body)?, server)?,
+        "/api/volume" => {
+            let req: VolumeRequest = parse_body(body)?;
+            RemoteCommand::Volume(req.volume.min(100))
+        }
+        _ => {
+            let _: EmptyRequest = parse_body(body)?;
+            RemoteCommand::TogglePause
+        }
+    };
+    server.tx.send(command).map_err(|_| {
+        (
+            "503 Service Unavailable",
+            "player is shutting down".to_string(),
+        )
+    })?;
+    Ok(("200 OK", "application/json", "{}".to_string()))
+}
+
+/// Check a play request against the station list, `allow_urls` and the
+/// switch rate limit.
+fn play_command(
+    req: PlayRequest,
+    server: &Server,
+) -> Result<RemoteCommand, (&'static str, String)> {
+    let command = match (req.station, req.url) {
+        (Some(i), None) => {
+            let count = server.view.lock().map(|v| v.stations.len()).unwrap_or(0);
+            if i >= count {
+                return Err(("400 Bad Request", format!("no station {}", i)));
+            }
+            RemoteCommand::Play(i)
+        }
+        (None, Some(_)) if !server.allow_urls => {
+            return Err((
+                "403 Forbidden",
+                "URLs are not allowed; set [remote] allow_urls".to_string(),
+            ));
+        }
+        (None, Some(url)) if !is_stream_url(&url) => {
+            return Err(("400 Bad Request", "not an http(s) stream URL".to_string()));
+        }
+        (None, Some(url)) => RemoteCommand::PlayUrl(url),
+        _ => return Err(("400 Bad Request", "give either station or url".to_string())),
+    };
+    let mut last = server
+        .last_switch
+        .lock()
+        .map_err(|_| ("500 Internal Server Error", String::new()))?;
+    if last.is_some_and(|at| at.elapsed() < MIN_SWITCH_INTERVAL) {
+        return Err((
+            "429 Too Many Requests",
+            "switching stations too fast".to_string(),
+        ));
+    }
+    *last = Some(Instant::now());
+    Ok(command)
+}
+
+async fn serve_client<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, server: Arc<Server>) {
+    let Ok(Some(request)) = tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await
+    else {
+        respond(&mut stream, "400 Bad Request", "text/plain", "bad request").await;
+        return;
+    };
+    match handle(&request, &server) {
+        Ok((status, content_type, body)) => respond(&mut stream, status, content_type, &body).await,
+        Err((status, message)) => respond(&mut stream, status, "text/plain", &message).await,
+    }
+}
+
+/// TLS setup from the PEM files named in the config.
+fn tls_acceptor(cert: &Path, key: &Path) -> Result<TlsAcceptor, Box<dyn std::error::Error>> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("{}: {}", cert.display(), e))?;
+    let key = PrivateKeyDer::from_pem_file(key).map_err(|e| format!("{}: {}", key.display(), e))?;
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Where the remote listens with `socket` on.
+pub fn socket_path() -> PathBuf {
+    runtime_dir().join("remote.sock")
+}
+
+/// Start serving the web remote; commands arrive through `tx`. Fails when
+/// the address or socket cannot be bound or the TLS files cannot be loaded.
+pub async fn spawn_server(
+    config: &RemoteConfig,
+    view: SharedView,
+    tx: UnboundedSender<RemoteCommand>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tls = match (&config.tls_cert, &config.tls_key) {
+        (Some(cert), Some(key)) => Some(tls_acceptor(cert, key)?),
+        (None, None) => None,
+        _ => return Err("tls_cert and tls_key must be set together".into()),
+    };
+    let server = Arc::new(Server {
+        token: config.token.clone(),
+        listen_host: config.listen_host().to_string(),
+        allow_urls: config.allow_urls,
+        view,
+        tx,
+        last_switch: Mutex::new(None),
+    });
+    if config.socket {
+        // Left behind by a player that did not exit cleanly
+        let path = socket_path();
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)
+            .map_err(|e| format!("cannot listen on {}: {}", path.display(), e))?;
+        let server = server.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_client(stream, server.clone()));
+            }
+        });
+    }
+    if config.listen.is_empty() {
+        return Ok(());
+    }
+    if config.is_exposed() {
+        return Err(format!(
+            "listening on {} needs a [remote] token, or listen on 127.0.0.1",
+            config.listen
+        )
+        .into());
+    }
+    let listener = TcpListener::bind(&config.listen)
+        .await
+        .map_err(|e| format!("cannot listen on {}: {}", config.listen, e))?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let server = server.clone();
+            match tls.clone() {
+                Some(tls) => {
+                    tokio::spawn(async move {
+                        let handshake =
+                            tokio::time::timeout(READ_TIMEOUT, tls.accept(stream)).await;
+                        if let Ok(Ok(stream)) = handshake {
+                            serve_client(stream, server).await;
+                        }
+                    });
+                }
+                None => {
+                    tokio::spawn(serve_client(stream, server));
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(host: &str, origin: Option<&str>, json: bool) -> Request {
+        Request {
+            method: "POST".to_string(),
+            path: "/api/toggle".to_string(),
+            token: None,
+            host: Some(host.to_string()),
+            origin: origin.map(str::to_string),
+            json,
+            body: Vec::new(),
+        }
+    }
+
+    fn server() -> (Server, tokio::sync::mpsc::UnboundedReceiver<RemoteCommand>) {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let server = Server {
+            token: None,
+            listen_host: "127.0.0.1".to_string(),
+            allow_urls: false,
+            view: SharedView::default(),
+            tx,
+            last_switch: Mutex::new(None),
+        };
+        (server, rx)
+    }
+
+    #[test]
+    fn own_hosts_are_addresses_localhost_and_the_listen_host() {
+        assert!(is_own_host("127.0.0.1:8737", "127.0.0.1"));
+        assert!(is_own_host("192.168.1.20:8737", "0.0.0.0"));
+        assert!(is_own_host("[::1]:8737", "127.0.0.1"));
+        assert!(is_own_host("LOCALHOST:8737", "127.0.0.1"));
+        assert!(is_own_host("radio.lan:8737", "radio.lan"));
+        assert!(!is_own_host("attacker.example:8737", "127.0.0.1"));
+        assert!(!is_own_host("127.0.0.1.attacker.example", "127.0.0.1"));
+        assert!(!is_own_host("", "127.0.0.1"));
+    }
+
+    #[test]
+    fn commands_need_json_and_a_matching_origin() {
+        let (server, mut rx) = server();
+        let err = |reply: Reply| reply.err().map(|(status, _)| status);
+
+        let rebound = request("attacker.example:8737", None, true);
+        assert_eq!(err(handle(&rebound, &server)), Some("403 Forbidden"));
+        let cross_site = request("127.0.0.1:8737", Some("http://attacker.example"), true);
+        assert_eq!(err(handle(&cross_site, &server)), Some("403 Forbidden"));
+        let form = request("127.0.0.1:8737", None, false);
+        assert_eq!(
+            err(handle(&form, &server)),
+            Some("415 Unsupported Media Type")
+        );
+        assert!(rx.try_recv().is_err());
+
+        let own = request("127.0.0.1:8737", Some("http://127.0.0.1:8737"), true);
+        assert!(handle(&own, &server).is_ok());
+        assert!(matches!(rx.try_recv(), Ok(RemoteCommand::TogglePause)));
+    }
+}
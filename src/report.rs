@@ -0,0 +1,298 @@
+use chrono::{Duration as Days, Local, TimeZone};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::history::{self, HistoryEntry};
+use crate::stats::{unix_now, Stats};
+use crate::timefmt::format_elapsed;
+
+/// Stations and tracks listed in a report.
+const TOP_ENTRIES: usize = 10;
+/// How listening time is written in reports.
+const TIME_FORMAT: &str = "%hh %Mm";
+
+/// Span a report covers, ending now.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportPeriod {
+    /// Since midnight.
+    Day,
+    /// Today and the six days before.
+    Week,
+}
+
+/// Format of the report file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// Format for a file name: `.md` and `.html` are recognized.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("md") | Some("markdown") => ReportFormat::Markdown,
+            Some("html") | Some("htm") => ReportFormat::Html,
+            _ => ReportFormat::Text,
+        }
+    }
+}
+
+/// Listening over one period, from the session log and the stats store.
+struct Report {
+    title: String,
+    /// Seconds listened in total.
+    listened: u64,
+    /// Stations by seconds listened, most first.
+    stations: Vec<(String, u64)>,
+    /// Tracks by plays in the period, most first.
+    tracks: Vec<(String, usize)>,
+    /// Start and length of the longest session.
+    longest: Option<(u64, u64)>,
+    sessions: usize,
+}
+
+/// Local midnight `days_back` days ago, as Unix time.
+fn midnight(days_back: i64) -> u64 {
+    let today = Local::now().date_naive() - Days::days(days_back);
+    today
+        .and_hms_opt(0, 0, 0)
+        .and_then(|t| Local.from_local_datetime(&t).earliest())
+        .map_or(0, |t| t.timestamp().max(0) as u64)
+}
+
+fn build(period: ReportPeriod, entries: &[HistoryEntry], stats: &Stats, now: u64) -> Report {
+    let (since, title) = match period {
+        ReportPeriod::Day => (midnight(0), "Today"),
+        ReportPeriod::Week => (midnight(6), "Last 7 days"),
+    };
+    let date = |at: u64| {
+        Local
+            .timestamp_opt(at as i64, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    let title = format!("{} ({} – {})", title, date(since), date(now));
+
+    // Each entry plays until the next one; sessions end where the player quit
+    let mut by_station: HashMap<&str, u64> = HashMap::new();
+    let mut sessions: Vec<(u64, u64)> = Vec::new();
+    let mut session_start: Option<u64> = None;
+    for (i, entry) in entries.iter().enumerate() {
+        let until = entries.get(i + 1).map_or(now, |next| next.at);
+        let Some(station) = &entry.station else {
+            if let Some(start) = session_start.take() {
+                sessions.push((start, entry.at));
+            }
+            continue;
+        };
+        session_start.get_or_insert(entry.at);
+        let (from, to) = (entry.at.max(since), until.min(now));
+        if to > from {
+            *by_station.entry(station).or_default() += to - from;
+        }
+    }
+    if let Some(start) = session_start {
+        sessions.push((start, now));
+    }
+    let sessions: Vec<(u64, u64)> = sessions
+        .into_iter()
+        .filter(|&(_, end)| end > since)
+        .map(|(start, end)| (start.max(since), end.saturating_sub(start.max(since))))
+        .collect();
+
+    let mut stations: Vec<(String, u64)> = by_station
+        .into_iter()
+        .map(|(name, secs)| (name.to_string(), secs))
+        .collect();
+    stations.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let listened = stations.iter().map(|(_, secs)| secs).sum();
+    stations.truncate(TOP_ENTRIES);
+
+    let mut tracks: Vec<(String, usize)> = stats
+        .tracks
+        .values()
+        .map(|t| {
+            let plays = t.play_times.iter().filter(|&&at| at >= since).count();
+            (t.title.clone(), plays)
+        })
+        .filter(|(_, plays)| *plays > 0)
+        .collect();
+    tracks.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    tracks.truncate(TOP_ENTRIES);
+
+    Report {
+        title,
+        listened,
+        stations,
+        tracks,
+        longest: sessions.iter().copied().max_by_key(|&(_, secs)| secs),
+        sessions: sessions.len(),
+    }
+}
+
+fn time(secs: u64) -> String {
+    format_elapsed(Duration::from_secs(secs), TIME_FORMAT)
+}
+
+/// Start of a session, as shown in reports.
+fn started(at: u64) -> String {
+    Local
+        .timestamp_opt(at as i64, 0)
+        .single()
+        .map(|t| t.format("%a %Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+impl Report {
+    fn text(&self) -> String {
+        let mut out = format!("Listening report — {}\n\n", self.title);
+        out.push_str(&format!(
+            "  Listened:         {} in {} session(s)\n",
+            time(self.listened),
+            self.sessions
+        ));
+        if let Some((start, secs)) = self.longest {
+            out.push_str(&format!(
+                "  Longest session:  {} from {}\n",
+                time(secs),
+                started(start)
+            ));
+        }
+        out.push_str("\nTop stations\n");
+        if self.stations.is_empty() {
+            out.push_str("  Nothing played\n");
+        }
+        let width = self
+            .stations
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .max()
+            .unwrap_or(0);
+        for (i, (name, secs)) in self.stations.iter().enumerate() {
+            out.push_str(&format!(
+                "  {:>2}. {:<width$}  {:>8}\n",
+                i + 1,
+                name,
+                time(*secs),
+                width = width
+            ));
+        }
+        out.push_str("\nTop tracks\n");
+        if self.tracks.is_empty() {
+            out.push_str("  No track metadata seen\n");
+        }
+        for (i, (title, plays)) in self.tracks.iter().enumerate() {
+            out.push_str(&format!("  {:>2}. {:>3}×  {}\n", i + 1, plays, title));
+        }
+        out
+    }
+
+    fn markdown(&self) -> String {
+        // Table cells cannot hold a bare pipe
+        let cell = |text: &str| text.replace('|', "\\|");
+        let mut out = format!("# Listening report — {}\n\n", self.title);
+        out.push_str(&format!(
+            "- **Listened:** {} in {} session(s)\n",
+            time(self.listened),
+            self.sessions
+        ));
+        if let Some((start, secs)) = self.longest {
+            out.push_str(&format!(
+                "- **Longest session:** {} from {}\n",
+                time(secs),
+                started(start)
+            ));
+        }
+        out.push_str("\n## Top stations\n\n| # | Station | Time |\n|---|---|---|\n");
+        for (i, (name, secs)) in self.stations.iter().enumerate() {
+            out.push_str(&format!(
+                "| {} | {} | {} |\n",
+                i + 1,
+                cell(name),
+                time(*secs)
+            ));
+        }
+        out.push_str("\n## Top tracks\n\n| # | Track | Plays |\n|---|---|---|\n");
+        for (i, (title, plays)) in self.tracks.iter().enumerate() {
+            out.push_str(&format!("| {} | {} | {} |\n", i + 1, cell(title), plays));
+        }
+        out
+    }
+
+    fn html(&self) -> String {
+        let title = html_escape(&self.title);
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Listening report — {}</title>\n\
+             <style>body{{font-family:sans-serif;max-width:40em;margin:2em auto}}\
+             td,th{{padding:.2em .8em;text-align:left}}</style>\n\
+             </head>\n<body>\n<h1>Listening report — {}</h1>\n<ul>\n",
+            title, title
+        );
+        out.push_str(&format!(
+            "<li><b>Listened:</b> {} in {} session(s)</li>\n",
+            time(self.listened),
+            self.sessions
+        ));
+        if let Some((start, secs)) = self.longest {
+            out.push_str(&format!(
+                "<li><b>Longest session:</b> {} from {}</li>\n",
+                time(secs),
+                started(start)
+            ));
+        }
+        out.push_str("</ul>\n<h2>Top stations</h2>\n<table>\n");
+        out.push_str("<tr><th>#</th><th>Station</th><th>Time</th></tr>\n");
+        for (i, (name, secs)) in self.stations.iter().enumerate() {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                i + 1,
+                html_escape(name),
+                time(*secs)
+            ));
+        }
+        out.push_str("</table>\n<h2>Top tracks</h2>\n<table>\n");
+        out.push_str("<tr><th>#</th><th>Track</th><th>Plays</th></tr>\n");
+        for (i, (title, plays)) in self.tracks.iter().enumerate() {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                i + 1,
+                html_escape(title),
+                plays
+            ));
+        }
+        out.push_str("</table>\n</body>\n</html>\n");
+        out
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `lofi_rs report`: print the report for `period`, and write it to
+/// `output` in `format` when given.
+pub fn run(
+    period: ReportPeriod,
+    output: Option<(&Path, ReportFormat)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let report = build(period, &history::load(), &Stats::load(), unix_now());
+    print!("{}", report.text());
+    if let Some((path, format)) = output {
+        let text = match format {
+            ReportFormat::Text => report.text(),
+            ReportFormat::Markdown => report.markdown(),
+            ReportFormat::Html => report.html(),
+        };
+        std::fs::write(path, text)?;
+        println!("\nWritten to {}", path.display());
+    }
+    Ok(())
+}
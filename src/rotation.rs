@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::config::Station;
+use crate::palette::best_match;
+
+/// A station rotation, under `[[rotations]]`: each step plays its station
+/// for a while, then the next one takes over, like pomodoro phases with
+/// their own soundscapes.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Rotation {
+    pub name: String,
+    pub steps: Vec<RotationStep>,
+    /// Start over after the last step; otherwise the rotation ends there
+    /// and its station keeps playing.
+    #[serde(default = "default_true")]
+    pub repeat: bool,
+}
+
+/// One phase of a [`Rotation`].
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RotationStep {
+    /// Station to play, matched fuzzily by name.
+    pub station: String,
+    pub minutes: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl RotationStep {
+    /// Index of the station this step plays.
+    pub fn station_index(&self, stations: &[Station]) -> Option<usize> {
+        best_match(&self.station, stations.iter().map(|s| s.name.as_str()))
+    }
+}
+
+/// A rotation being played: the current step and when it ends (`None` for
+/// a step too long to end).
+pub struct RotationRun {
+    rotation: Rotation,
+    step: usize,
+    until: Option<Instant>,
+}
+
+impl RotationRun {
+    /// Start `rotation` at its first step; `None` when it has no steps.
+    pub fn start(rotation: &Rotation) -> Option<Self> {
+        let first = rotation.steps.first()?;
+        Some(Self {
+            until: step_end(first),
+            rotation: rotation.clone(),
+            step: 0,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.rotation.name
+    }
+
+    pub fn step(&self) -> &RotationStep {
+        &self.rotation.steps[self.step]
+    }
+
+    /// The current step has run its time.
+    pub fn is_due(&self) -> bool {
+        self.until.is_some_and(|until| Instant::now() >= until)
+    }
+
+    /// Move to the next step, wrapping around when the rotation repeats.
+    /// Returns `false` once a non-repeating rotation is over.
+    pub fn advance(&mut self) -> bool {
+        let next = self.step + 1;
+        if next >= self.rotation.steps.len() && !self.rotation.repeat {
+            return false;
+        }
+        self.step = next % self.rotation.steps.len();
+        self.until = step_end(self.step());
+        true
+    }
+
+    /// Status bar label: "Focus 1/2 12m".
+    pub fn label(&self) -> String {
+        let label = format!(
+            "{} {}/{}",
+            self.rotation.name,
+            self.step + 1,
+            self.rotation.steps.len()
+        );
+        match self.until {
+            Some(until) => format!(
+                "{} {}m",
+                label,
+                until
+                    .saturating_duration_since(Instant::now())
+                    .as_secs()
+                    .div_ceil(60)
+            ),
+            None => label,
+        }
+    }
+}
+
+/// Steps last at least a minute, so a `minutes = 0` typo cannot spin.
+fn step_length(step: &RotationStep) -> Duration {
+    Duration::from_secs(step.minutes.max(1).saturating_mul(60))
+}
+
+/// When `step` ends if it starts now.
+fn step_end(step: &RotationStep) -> Option<Instant> {
+    Instant::now().checked_add(step_length(step))
+}
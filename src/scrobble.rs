@@ -0,0 +1,342 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Notify;
+
+use crate::config::Config;
+use crate::lastfm::Scrobbler;
+use crate::listenbrainz::ListenBrainz;
+use crate::metadata::Track;
+use crate::musicbrainz::Recording;
+use crate::state::state_dir;
+use crate::stats::unix_now;
+
+/// First retry of a failed submission; each further failure doubles it.
+const FIRST_RETRY_SECS: u64 = 60;
+/// Longest wait between retries.
+const MAX_RETRY_SECS: u64 = 60 * 60;
+/// Listens kept waiting; beyond this the oldest are dropped.
+const QUEUE_LEN: usize = 10_000;
+
+/// What gets scrobbled, under `[scrobble]`. Stations can opt out with
+/// `scrobble = false`.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScrobbleRules {
+    /// Seconds a track has to play before it counts. Stream tracks have no
+    /// known length, so Last.fm's "half the track" rule cannot apply.
+    pub min_listen_secs: u64,
+    /// Tracks whose artist or title contains any of these (ignoring case)
+    /// are never scrobbled: jingles, idents, ads, "Unknown Artist".
+    pub ignore: Vec<String>,
+}
+
+impl Default for ScrobbleRules {
+    fn default() -> Self {
+        Self {
+            min_listen_secs: 60,
+            ignore: [
+                "unknown artist",
+                "jingle",
+                "station id",
+                "advert",
+                "commercial",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        }
+    }
+}
+
+impl ScrobbleRules {
+    /// Whether `track` may be scrobbled at all. Tracks without an artist
+    /// are left out: scrobble services need one.
+    pub fn allows(&self, track: &Track) -> bool {
+        let Some(artist) = track.artist.as_deref() else {
+            return false;
+        };
+        let artist = artist.to_lowercase();
+        let title = track.title.to_lowercase();
+        !self.ignore.iter().map(|i| i.to_lowercase()).any(|ignored| {
+            !ignored.is_empty() && (artist.contains(&ignored) || title.contains(&ignored))
+        })
+    }
+}
+
+/// A track being listened to that may be scrobbled once it ends.
+pub struct Listen {
+    pub track: Track,
+    /// Unix time it started, which is what gets submitted.
+    pub started_at: u64,
+    started: Instant,
+}
+
+impl Listen {
+    pub fn new(track: Track) -> Self {
+        Self {
+            track,
+            started_at: unix_now(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Submit MusicBrainz's spelling of the track instead of the stream's.
+    pub fn use_recording(&mut self, recording: &Recording) {
+        self.track = Track {
+            artist: Some(recording.artist.clone()),
+            title: recording.title.clone(),
+        };
+    }
+
+    /// Whether it played long enough under `rules` to be scrobbled.
+    pub fn counts(&self, rules: &ScrobbleRules) -> bool {
+        self.started.elapsed().as_secs() >= rules.min_listen_secs
+    }
+}
+
+/// Why a submission failed.
+pub enum SubmitError {
+    /// Offline, rate limited or a server error: try again later.
+    Retry(String),
+    /// The service refused the listen; retrying would not help.
+    Rejected(String),
+}
+
+/// Where listens are submitted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Service {
+    Lastfm,
+    Listenbrainz,
+}
+
+/// A listen waiting to be submitted to one service.
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QueuedListen {
+    service: Service,
+    artist: String,
+    title: String,
+    started_at: u64,
+    /// Failed attempts so far.
+    #[serde(default)]
+    attempts: u32,
+    /// Unix time of the next attempt.
+    #[serde(default)]
+    next_try: u64,
+}
+
+impl QueuedListen {
+    fn track(&self) -> Track {
+        Track {
+            artist: Some(self.artist.clone()),
+            title: self.title.clone(),
+        }
+    }
+}
+
+/// Listens not submitted yet, kept as JSON in the state directory so they
+/// survive being offline and restarts.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ListenQueue {
+    listens: Vec<QueuedListen>,
+    /// Why the last attempt has to be retried; cleared by a success.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
+}
+
+impl ListenQueue {
+    /// Load the queue; a missing or corrupt file starts empty.
+    pub fn load() -> Self {
+        queue_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = queue_path().ok_or("cannot locate state directory (HOME unset)")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.listens.len()
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Queue `listen` for each of `services`, to be tried right away.
+    pub fn push(&mut self, listen: &Listen, services: &[Service]) {
+        for &service in services {
+            self.listens.push(QueuedListen {
+                service,
+                artist: listen.track.artist.clone().unwrap_or_default(),
+                title: listen.track.title.clone(),
+                started_at: listen.started_at,
+                attempts: 0,
+                next_try: 0,
+            });
+        }
+        let excess = self.listens.len().saturating_sub(QUEUE_LEN);
+        self.listens.drain(..excess);
+    }
+
+    fn due(&self, now: u64) -> Vec<QueuedListen> {
+        self.listens
+            .iter()
+            .filter(|l| l.next_try <= now)
+            .cloned()
+            .collect()
+    }
+
+    /// Seconds until the next listen is due, if any is waiting.
+    fn next_due_in(&self, now: u64) -> Option<u64> {
+        self.listens
+            .iter()
+            .map(|l| l.next_try.saturating_sub(now))
+            .min()
+    }
+
+    fn remove(&mut self, listen: &QueuedListen) {
+        if let Some(i) = self.listens.iter().position(|l| l == listen) {
+            self.listens.remove(i);
+        }
+    }
+
+    /// Put `listen` back for a later attempt, backing off exponentially.
+    fn postpone(&mut self, listen: &QueuedListen, now: u64) {
+        if let Some(queued) = self.listens.iter_mut().find(|l| *l == listen) {
+            queued.attempts += 1;
+            let wait = FIRST_RETRY_SECS
+                .saturating_mul(1 << queued.attempts.min(16).saturating_sub(1))
+                .min(MAX_RETRY_SECS);
+            queued.next_try = now + wait;
+        }
+    }
+}
+
+fn queue_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("scrobble_queue.json"))
+}
+
+/// The scrobble services with an account configured.
+#[derive(Clone)]
+pub struct Submitter {
+    lastfm: Option<Scrobbler>,
+    listenbrainz: Option<ListenBrainz>,
+}
+
+impl Submitter {
+    /// `None` when no service is set up.
+    pub fn new(config: &Config) -> Option<Self> {
+        let submitter = Self {
+            lastfm: Scrobbler::new(&config.lastfm),
+            listenbrainz: ListenBrainz::new(&config.listenbrainz),
+        };
+        (submitter.lastfm.is_some() || submitter.listenbrainz.is_some()).then_some(submitter)
+    }
+
+    pub fn services(&self) -> Vec<Service> {
+        let mut services = Vec::new();
+        if self.lastfm.is_some() {
+            services.push(Service::Lastfm);
+        }
+        if self.listenbrainz.is_some() {
+            services.push(Service::Listenbrainz);
+        }
+        services
+    }
+
+    /// Show `track` as playing now on every service; failures are ignored.
+    pub async fn now_playing(&self, track: &Track) {
+        if let Some(lastfm) = &self.lastfm {
+            let _ = lastfm.now_playing(track).await;
+        }
+        if let Some(listenbrainz) = &self.listenbrainz {
+            let _ = listenbrainz.playing_now(track).await;
+        }
+    }
+
+    async fn submit(&self, listen: &QueuedListen) -> Result<(), SubmitError> {
+        let track = listen.track();
+        match listen.service {
+            Service::Lastfm => match &self.lastfm {
+                Some(lastfm) => lastfm.scrobble(&track, listen.started_at).await,
+                None => Err(SubmitError::Retry("Last.fm is not set up".to_string())),
+            },
+            Service::Listenbrainz => match &self.listenbrainz {
+                Some(listenbrainz) => listenbrainz.submit(&track, listen.started_at).await,
+                None => Err(SubmitError::Retry("ListenBrainz is not set up".to_string())),
+            },
+        }
+    }
+}
+
+/// Submit queued listens in the background: due ones whenever `wake` is
+/// notified or a retry comes due. Listens a service refuses are dropped and
+/// reported through `errors`.
+pub fn spawn_queue(
+    queue: Arc<Mutex<ListenQueue>>,
+    submitter: Submitter,
+    wake: Arc<Notify>,
+    errors: UnboundedSender<String>,
+) {
+    tokio::spawn(async move {
+        loop {
+            let due = match queue.lock() {
+                Ok(queue) => queue.due(unix_now()),
+                Err(_) => return,
+            };
+            let mut offline = false;
+            for listen in due {
+                // One failure to connect postpones the rest as well
+                let result = if offline {
+                    None
+                } else {
+                    Some(submitter.submit(&listen).await)
+                };
+                let Ok(mut queue) = queue.lock() else {
+                    return;
+                };
+                match result {
+                    None => queue.postpone(&listen, unix_now()),
+                    Some(Ok(())) => {
+                        queue.remove(&listen);
+                        queue.last_error = None;
+                    }
+                    Some(Err(SubmitError::Retry(e))) => {
+                        offline = true;
+                        queue.postpone(&listen, unix_now());
+                        queue.last_error = Some(e);
+                    }
+                    Some(Err(SubmitError::Rejected(e))) => {
+                        queue.remove(&listen);
+                        let _ = errors.send(e);
+                    }
+                }
+                let _ = queue.save();
+            }
+            let wait = match queue.lock() {
+                Ok(queue) => queue.next_due_in(unix_now()),
+                Err(_) => return,
+            };
+            match wait {
+                Some(secs) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_secs(secs.max(1))) => {}
+                        _ = wake.notified() => {}
+                    }
+                }
+                None => wake.notified().await,
+            }
+        }
+    });
+}
@@ -0,0 +1,239 @@
+use crate::config::Config;
+use crate::player::{PlayerPreference, VolumeCurve, BALANCE_STEP};
+use crate::ui::{IconStyle, SettingsScreen, THEMES};
+
+/// Tabs of the settings screen, switched with Tab / Shift+Tab.
+pub const PANES: &[&str] = &["General", "Playback", "Audio"];
+pub const AUDIO_PANE: usize = 2;
+
+/// A config value editable from the settings screen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    VolumeStep,
+    Theme,
+    Icons,
+    Notifications,
+    Chime,
+    Backend,
+    CacheSecs,
+    Timeshift,
+    VolumeCurve,
+    MaxVolume,
+    Limiter,
+    Mono,
+    Balance,
+}
+
+fn pane_settings(pane: usize) -> &'static [Setting] {
+    match pane {
+        0 => &[
+            Setting::VolumeStep,
+            Setting::Theme,
+            Setting::Icons,
+            Setting::Notifications,
+            Setting::Chime,
+        ],
+        1 => &[Setting::Backend, Setting::CacheSecs, Setting::Timeshift],
+        _ => &[
+            Setting::VolumeCurve,
+            Setting::MaxVolume,
+            Setting::Limiter,
+            Setting::Mono,
+            Setting::Balance,
+        ],
+    }
+}
+
+fn on_off(b: bool) -> String {
+    if b { "On" } else { "Off" }.to_string()
+}
+
+/// Cycle `current` through `options` by `dir` steps, wrapping around.
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, dir: i32) -> T {
+    let i = options.iter().position(|&o| o == current).unwrap_or(0) as i32;
+    let n = options.len() as i32;
+    options[(i + dir).rem_euclid(n) as usize]
+}
+
+impl Setting {
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::VolumeStep => "Volume step",
+            Setting::Theme => "Theme",
+            Setting::Icons => "Icons",
+            Setting::Notifications => "Track notifications",
+            Setting::Chime => "Track-change chime",
+            Setting::Backend => "Backend (on restart)",
+            Setting::CacheSecs => "Buffer ahead (mpv)",
+            Setting::Timeshift => "Timeshift buffer (mpv)",
+            Setting::VolumeCurve => "Volume curve",
+            Setting::MaxVolume => "Max volume",
+            Setting::Limiter => "Peak limiter",
+            Setting::Mono => "Mono downmix",
+            Setting::Balance => "Balance",
+        }
+    }
+
+    pub fn value(self, config: &Config) -> String {
+        match self {
+            Setting::VolumeStep => format!("{}%", config.volume_step),
+            Setting::Theme => config.theme.clone(),
+            Setting::Icons => match config.icons {
+                IconStyle::Off => "off",
+                IconStyle::Unicode => "unicode",
+                IconStyle::Nerd => "nerd font",
+            }
+            .to_string(),
+            Setting::Notifications => on_off(config.notifications),
+            Setting::Chime if !config.chime => on_off(false),
+            Setting::Chime => format!("{}%", config.chime_volume),
+            Setting::Backend => match config.backend {
+                PlayerPreference::Auto => "auto",
+                PlayerPreference::Mpv => "mpv",
+                PlayerPreference::Ffplay => "ffplay",
+                PlayerPreference::Afplay => "afplay",
+                PlayerPreference::Vlc => "vlc",
+                PlayerPreference::Gstreamer => "gstreamer",
+            }
+            .to_string(),
+            Setting::CacheSecs if config.cache_secs == 0 => "default".to_string(),
+            Setting::CacheSecs => format!("{} s", config.cache_secs),
+            Setting::Timeshift if config.timeshift_minutes == 0 => on_off(false),
+            Setting::Timeshift => format!("{} min", config.timeshift_minutes),
+            Setting::VolumeCurve => match config.volume_curve {
+                VolumeCurve::Linear => "linear",
+                VolumeCurve::Log => "log",
+                VolumeCurve::Cubic => "cubic",
+            }
+            .to_string(),
+            Setting::MaxVolume => format!("{}%", config.max_volume),
+            Setting::Limiter => on_off(config.limiter),
+            Setting::Mono => on_off(config.mono),
+            Setting::Balance => match config.balance {
+                0 => "Center".to_string(),
+                b if b < 0 => format!("L {}", -b),
+                b => format!("R {}", b),
+            },
+        }
+    }
+
+    /// Step the value once in direction `dir` (-1 or 1).
+    pub fn adjust(self, config: &mut Config, dir: i32) {
+        match self {
+            Setting::VolumeStep => {
+                config.volume_step = (config.volume_step as i32 + dir).clamp(1, 25) as u32;
+            }
+            Setting::Theme => {
+                let names: Vec<&str> = THEMES.iter().map(|t| t.name).collect();
+                config.theme = cycle(&names, config.theme.as_str(), dir).to_string();
+            }
+            Setting::Icons => {
+                config.icons = cycle(
+                    &[IconStyle::Off, IconStyle::Unicode, IconStyle::Nerd],
+                    config.icons,
+                    dir,
+                );
+            }
+            Setting::Notifications => config.notifications = !config.notifications,
+            Setting::Chime => {
+                // Off → 10% … 100% → off
+                let level = if config.chime {
+                    config.chime_volume as i32
+                } else {
+                    0
+                };
+                let level = (level + 10 * dir).clamp(0, 100);
+                config.chime = level > 0;
+                if level > 0 {
+                    config.chime_volume = level as u32;
+                }
+            }
+            Setting::Backend => {
+                config.backend = cycle(
+                    &[
+                        PlayerPreference::Auto,
+                        PlayerPreference::Mpv,
+                        PlayerPreference::Ffplay,
+                        PlayerPreference::Afplay,
+                        PlayerPreference::Vlc,
+                        PlayerPreference::Gstreamer,
+                    ],
+                    config.backend,
+                    dir,
+                );
+            }
+            Setting::CacheSecs => {
+                config.cache_secs = (config.cache_secs as i32 + 5 * dir).clamp(0, 300) as u32;
+            }
+            Setting::Timeshift => {
+                config.timeshift_minutes =
+                    (config.timeshift_minutes as i32 + 15 * dir).clamp(0, 240) as u32;
+            }
+            Setting::VolumeCurve => {
+                config.volume_curve = cycle(
+                    &[VolumeCurve::Linear, VolumeCurve::Log, VolumeCurve::Cubic],
+                    config.volume_curve,
+                    dir,
+                );
+            }
+            Setting::MaxVolume => {
+                config.max_volume = (config.max_volume as i32 + 5 * dir).clamp(5, 100) as u32;
+            }
+            Setting::Limiter => config.limiter = !config.limiter,
+            Setting::Mono => config.mono = !config.mono,
+            Setting::Balance => {
+                config.balance = (config.balance + BALANCE_STEP * dir).clamp(-100, 100);
+            }
+        }
+    }
+
+    /// Whether the change must be pushed to the audio filter chain.
+    pub fn affects_filters(self) -> bool {
+        matches!(self, Setting::Limiter | Setting::Mono | Setting::Balance)
+    }
+
+    /// Whether the change alters the level sent to the backend.
+    pub fn affects_volume(self) -> bool {
+        matches!(self, Setting::VolumeCurve | Setting::MaxVolume)
+    }
+}
+
+/// Cursor position on the settings screen.
+#[derive(Clone, Copy)]
+pub struct SettingsView {
+    pub pane: usize,
+    pub row: usize,
+}
+
+impl SettingsView {
+    pub fn new(pane: usize) -> Self {
+        Self { pane, row: 0 }
+    }
+
+    pub fn selected(&self) -> Setting {
+        pane_settings(self.pane)[self.row]
+    }
+
+    pub fn next_pane(&mut self, dir: i32) {
+        self.pane = (self.pane as i32 + dir).rem_euclid(PANES.len() as i32) as usize;
+        self.row = 0;
+    }
+
+    pub fn move_row(&mut self, dir: i32) {
+        let n = pane_settings(self.pane).len() as i32;
+        self.row = (self.row as i32 + dir).rem_euclid(n) as usize;
+    }
+
+    /// Snapshot handed to the UI for drawing.
+    pub fn screen(&self, config: &Config) -> SettingsScreen {
+        SettingsScreen {
+            tabs: PANES,
+            pane: self.pane,
+            row: self.row,
+            rows: pane_settings(self.pane)
+                .iter()
+                .map(|s| (s.label(), s.value(config)))
+                .collect(),
+        }
+    }
+}
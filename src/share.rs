@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::config::Station;
+use crate::net;
+use crate::stats::unix_now;
+
+/// File name of the session blob inside a gist.
+const GIST_FILE: &str = "lofi_rs_session.json";
+/// How often `lofi_rs join` re-reads the shared session.
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(30);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the ListenAlong session is published, under `[share]`. With
+/// `endpoint` set the session is `PUT` there; otherwise `gist_token` publishes
+/// it to a secret GitHub gist.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ShareConfig {
+    /// Self-hosted URL accepting a JSON `PUT` and serving it back on `GET`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// GitHub token with the `gist` scope.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gist_token: Option<String>,
+    /// Gist updated on every publish; filled in when the first one is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gist_id: Option<String>,
+}
+
+impl ShareConfig {
+    pub fn is_configured(&self) -> bool {
+        self.endpoint.is_some() || self.gist_token.is_some()
+    }
+}
+
+/// The blob a friend's `lofi_rs join <url>` reads.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SharedSession {
+    pub station: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_url: Option<String>,
+    #[serde(default = "default_true")]
+    pub live: bool,
+    /// Unix time the station was started by the publisher.
+    pub started_at: u64,
+    /// Unix time of this publish.
+    pub published_at: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl SharedSession {
+    pub fn new(station: &Station, started_at: u64) -> Self {
+        Self {
+            station: station.name.clone(),
+            url: station.url.clone(),
+            metadata_url: station.metadata_url.clone(),
+            live: station.live,
+            started_at,
+            published_at: unix_now(),
+        }
+    }
+
+    /// Station to play for this session when the joiner has no matching entry.
+    pub fn to_station(&self) -> Station {
+        let mut station = Station::new(&self.station, &self.url, self.metadata_url.as_deref());
+        station.live = self.live;
+        station
+    }
+}
+
+/// Results of background share work, delivered to the event loop.
+pub enum ShareEvent {
+    /// A publish finished: the share URL and, when a gist had to be
+    /// created, its id (to be saved in the config).
+    Published(Result<(String, Option<String>), String>),
+    /// The followed session switched station.
+    Update(SharedSession),
+}
+
+fn client() -> reqwest::Result<reqwest::Client> {
+    net::client_builder()
+        .timeout(HTTP_TIMEOUT)
+        .user_agent(concat!("lofi_rs/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+#[derive(Deserialize)]
+struct GistResponse {
+    id: String,
+    html_url: String,
+    #[serde(default)]
+    files: std::collections::HashMap<String, GistFile>,
+}
+
+#[derive(Deserialize)]
+struct GistFile {
+    #[serde(default)]
+    content: String,
+}
+
+async fn publish(
+    share: &ShareConfig,
+    session: &SharedSession,
+) -> Result<(String, Option<String>), Box<dyn std::error::Error>> {
+    let body = serde_json::to_string_pretty(session)?;
+    let client = client()?;
+    if let Some(endpoint) = share.endpoint.as_deref() {
+        client
+            .put(endpoint)
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        return Ok((endpoint.to_string(), None));
+    }
+    let token = share
+        .gist_token
+        .as_deref()
+        .ok_or("set [share] endpoint or gist_token in the config")?;
+    let payload = serde_json::json!({
+        "description": "lofi_rs ListenAlong session",
+        "public": false,
+        "files": { GIST_FILE: { "content": body } },
+    });
+    let request = match share.gist_id.as_deref() {
+        Some(id) => client.patch(format!("https://api.github.com/gists/{}", id)),
+        None => client.post("https://api.github.com/gists"),
+    };
+    let gist: GistResponse = request
+        .bearer_auth(token)
+        .header("Accept", "application/vnd.github+json")
+        .json(&payload)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let created = share.gist_id.is_none().then_some(gist.id);
+    Ok((gist.html_url, created))
+}
+
+/// Publish `session` in the background; the outcome arrives as
+/// [`ShareEvent::Published`].
+pub fn spawn_publish(share: ShareConfig, session: SharedSession, tx: UnboundedSender<ShareEvent>) {
+    tokio::spawn(async move {
+        let result = publish(&share, &session).await.map_err(|e| e.to_string());
+        let _ = tx.send(ShareEvent::Published(result));
+    });
+}
+
+/// Read a shared session from a share URL (an endpoint or a gist page).
+pub async fn fetch_session(url: &str) -> Result<SharedSession, Box<dyn std::error::Error>> {
+    let client = client()?;
+    if url.contains("gist.github.com/") {
+        let id = url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .ok_or("gist URL without an id")?;
+        let gist: GistResponse = client
+            .get(format!("https://api.github.com/gists/{}", id))
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let file = gist
+            .files
+            .get(GIST_FILE)
+            .ok_or("gist does not contain a lofi_rs session")?;
+        return checked(serde_json::from_str(&file.content)?);
+    }
+    checked(
+        client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?,
+    )
+}
+
+/// Refuse a session whose stream is not a plain http(s) URL: it is played
+/// as given, so it must not be a local file or read as a player option.
+fn checked(session: SharedSession) -> Result<SharedSession, Box<dyn std::error::Error>> {
+    if !net::is_stream_url(&session.url) {
+        return Err("the shared session's stream is not an http(s) URL".into());
+    }
+    Ok(session)
+}
+
+/// Keep re-reading the session at `url` and report station changes.
+pub fn spawn_follower(url: String, mut current: SharedSession, tx: UnboundedSender<ShareEvent>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(FOLLOW_INTERVAL).await;
+            let Ok(session) = fetch_session(&url).await else {
+                continue;
+            };
+            if session.url != current.url {
+                current = session.clone();
+                if tx.send(ShareEvent::Update(session)).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+}
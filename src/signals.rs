@@ -0,0 +1,100 @@
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
+
+/// Whether Ctrl+Z can hand the terminal back to the shell.
+pub const CAN_SUSPEND: bool = cfg!(unix);
+
+pub enum SignalEvent {
+    /// SIGINT, or Ctrl+C on the console where there are no signals.
+    Interrupt,
+    /// SIGTSTP from outside (`kill -TSTP`); Ctrl+Z itself arrives as a key
+    /// because raw mode turns off the terminal's signal keys.
+    Suspend,
+}
+
+/// The signals the event loop reacts to.
+pub struct Signals {
+    #[cfg(unix)]
+    interrupt: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    suspend: tokio::signal::unix::Signal,
+}
+
+impl Signals {
+    pub fn new() -> std::io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            Ok(Self {
+                interrupt: signal(SignalKind::interrupt())?,
+                suspend: signal(SignalKind::from_raw(nix::libc::SIGTSTP))?,
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            Ok(Self {})
+        }
+    }
+
+    pub async fn recv(&mut self) -> SignalEvent {
+        #[cfg(unix)]
+        {
+            tokio::select! {
+                _ = self.interrupt.recv() => SignalEvent::Interrupt,
+                _ = self.suspend.recv() => SignalEvent::Suspend,
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = tokio::signal::ctrl_c().await;
+            SignalEvent::Interrupt
+        }
+    }
+}
+
+/// Give the terminal back to the shell and stop the process, as Ctrl+Z
+/// would without raw mode. Returns once continued, with raw mode restored.
+/// Does nothing where there is no job control (see [`CAN_SUSPEND`]).
+/// Stop the process like Ctrl+Z without touching the terminal, for headless
+/// runs; it continues on `fg` or SIGCONT.
+pub fn suspend_headless() {
+    #[cfg(unix)]
+    if CAN_SUSPEND {
+        let _ = nix::sys::signal::raise(nix::sys::signal::Signal::SIGSTOP);
+    }
+}
+
+pub fn suspend_to_shell(key_release_events: bool) -> std::io::Result<()> {
+    if !CAN_SUSPEND {
+        return Ok(());
+    }
+    let mut stdout = std::io::stdout();
+    if key_release_events {
+        let _ = execute!(stdout, PopKeyboardEnhancementFlags);
+    }
+    execute!(
+        stdout,
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        Clear(ClearType::All),
+        MoveTo(0, 0),
+        Show
+    )?;
+    disable_raw_mode()?;
+    #[cfg(unix)]
+    let _ = nix::sys::signal::raise(nix::sys::signal::Signal::SIGSTOP);
+    enable_raw_mode()?;
+    execute!(stdout, Hide, EnableMouseCapture, EnableBracketedPaste)?;
+    if key_release_events {
+        let _ = execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::write_private;
+use crate::state::instance_path;
+use crate::stats::unix_now;
+
+/// How often the running session is written down.
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Snapshots older than this are not worth resuming.
+const RESUME_WITHIN: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What was going on in a session, kept in the state directory while it
+/// runs so the next launch can pick it up again after a crash.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Snapshot {
+    /// Process id of the player that wrote it.
+    pub pid: u32,
+    /// Unix time it was written.
+    pub saved_at: u64,
+    pub station: String,
+    pub volume: u32,
+    /// Seconds left on the sleep timer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sleep_secs: Option<u64>,
+    /// Rotation playing, by name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording: Option<RecordingSnapshot>,
+}
+
+/// A recording that was running.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RecordingSnapshot {
+    pub station: String,
+    /// Format name, as the palette takes it.
+    pub format: String,
+    /// Seconds left of a timed recording.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_secs: Option<u64>,
+}
+
+fn snapshot_path() -> Option<PathBuf> {
+    instance_path("session.json")
+}
+
+impl Snapshot {
+    /// Write the snapshot; failures are ignored.
+    pub fn save(&self) {
+        let Some(path) = snapshot_path() else {
+            return;
+        };
+        let Ok(json) = serde_json::to_string(self) else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        // Written aside and renamed, so a crash mid-write keeps the previous one
+        let _ = write_private(&path, json.as_bytes());
+    }
+
+    /// The snapshot of a session that ended without quitting: its player is
+    /// gone but did not clear it. Recent ones only.
+    pub fn left_behind() -> Option<Snapshot> {
+        let text = std::fs::read_to_string(snapshot_path()?).ok()?;
+        let snapshot: Snapshot = serde_json::from_str(&text).ok()?;
+        let pid = nix::unistd::Pid::from_raw(snapshot.pid as i32);
+        if snapshot.pid == std::process::id() || nix::sys::signal::kill(pid, None).is_ok() {
+            return None;
+        }
+        (unix_now().saturating_sub(snapshot.saved_at) < RESUME_WITHIN.as_secs()).then_some(snapshot)
+    }
+
+    /// "Jazz Cafe at 40%, sleep timer 12 min left, recording", for the
+    /// resume question.
+    pub fn describe(&self) -> String {
+        let mut text = match &self.rotation {
+            Some(rotation) => format!("Rotation {} at {}%", rotation, self.volume),
+            None => format!("{} at {}%", self.station, self.volume),
+        };
+        if let Some(secs) = self.sleep_secs {
+            text.push_str(&format!(", sleep timer {} min left", secs.div_ceil(60)));
+        }
+        if let Some(recording) = &self.recording {
+            text.push_str(&format!(", recording {}", recording.station));
+        }
+        text
+    }
+
+    /// Palette commands that bring the session back, run in order.
+    pub fn commands(&self) -> Vec<String> {
+        let mut commands = vec![match &self.rotation {
+            Some(rotation) => format!("rotate {}", rotation),
+            None => format!("play {}", self.station),
+        }];
+        commands.push(format!("volume {}", self.volume));
+        if let Some(secs) = self.sleep_secs {
+            commands.push(format!("sleep {}s", secs.max(1)));
+        }
+        // `record` takes the station playing, so one of another station
+        // than the one resumed is not picked up again
+        if let Some(recording) = self
+            .recording
+            .as_ref()
+            .filter(|r| r.station == self.station)
+        {
+            commands.push(match recording.remaining_secs {
+                Some(secs) => format!("record {}s {}", secs.max(1), recording.format),
+                None => format!("record {}", recording.format),
+            });
+        }
+        commands
+    }
+}
+
+/// Remove the snapshot when the player quits normally.
+pub fn clear() {
+    if let Some(path) = snapshot_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::stats::StationSort;
+
+/// Name given with `--instance`, if any.
+static INSTANCE: OnceLock<String> = OnceLock::new();
+
+/// Smallest station pane height (including borders) the user can shrink to.
+pub const MIN_LIST_HEIGHT: u16 = 3;
+
+/// Remembered UI layout. Lives in the state file rather than the config:
+/// it changes as the app is used and is never meant to be hand-edited.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LayoutPrefs {
+    /// Stations and status only; the track moves into the status title.
+    pub compact: bool,
+    pub show_controls: bool,
+    /// Station pane height in rows; `None` fits the whole list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_height: Option<u16>,
+    /// Last theme picked in the app; overrides the config's `theme`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+}
+
+impl Default for LayoutPrefs {
+    fn default() -> Self {
+        Self {
+            compact: false,
+            show_controls: true,
+            list_height: None,
+            theme: None,
+        }
+    }
+}
+
+/// Application state persisted across sessions in
+/// `$XDG_STATE_HOME/lofi_rs/state.toml` (`~/.local/state/lofi_rs/state.toml`).
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppState {
+    pub station_sort: StationSort,
+    pub layout: LayoutPrefs,
+    /// Ambient mixer levels by noise kind ("brown" = 30); absent is off.
+    pub ambient: BTreeMap<String, u32>,
+    /// The first-run cheat sheet has been dismissed.
+    pub welcomed: bool,
+    /// Version whose release notes were last seen; a newer binary shows
+    /// what changed since.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_version: Option<String>,
+}
+
+impl AppState {
+    /// Load the state file. A missing or unreadable file yields the defaults:
+    /// losing remembered state is never worth refusing to start.
+    pub fn load() -> Self {
+        state_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = state_path().ok_or("cannot locate state directory (HOME unset)")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Directory for state files that should survive restarts.
+pub fn state_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(base.join("lofi_rs"))
+}
+
+/// `$XDG_CACHE_HOME/lofi_rs`: responses and artwork fetched before, and
+/// the timeshift buffer.
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(base.join("lofi_rs"))
+}
+
+/// Run as the named instance. Later calls have no effect.
+pub fn init_instance(name: String) {
+    let _ = INSTANCE.set(name);
+}
+
+pub fn instance() -> Option<&'static str> {
+    INSTANCE.get().map(String::as_str)
+}
+
+/// `file` in the state directory, kept apart per instance: "status.json"
+/// is "status.office.json" for the instance "office".
+pub fn instance_path(file: &str) -> Option<PathBuf> {
+    let name = match (instance(), file.rsplit_once('.')) {
+        (Some(instance), Some((stem, extension))) => {
+            format!("{}.{}.{}", stem, instance, extension)
+        }
+        _ => file.to_string(),
+    };
+    Some(state_dir()?.join(name))
+}
+
+fn state_path() -> Option<PathBuf> {
+    instance_path("state.toml")
+}
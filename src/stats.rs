@@ -0,0 +1,440 @@
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::bandwidth::format_bytes;
+use crate::config::Station;
+use crate::state::state_dir;
+use crate::timefmt::format_elapsed;
+use crate::ui::StatsScreen;
+
+/// The same track seen again within this window is one play, not two:
+/// covers reconnects, restarts and metadata that flickers between titles.
+const TRACK_REPEAT_WINDOW_SECS: u64 = 10 * 60;
+/// Failed sessions in a row after which a station is flagged as likely dead.
+pub const LIKELY_DEAD_AFTER: u32 = 3;
+/// Entries on the "most heard tracks" leaderboard.
+const TOP_TRACKS: usize = 20;
+/// Days of per-day listening totals kept, for goals and streaks.
+const DAYS_KEPT: usize = 400;
+/// Months of data use kept.
+const MONTHS_KEPT: usize = 24;
+/// How far back each track's play times are kept, for reports.
+const PLAY_TIMES_KEPT_SECS: u64 = 35 * 24 * 60 * 60;
+
+/// Order of the station list, cycled from the UI and kept in the state file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StationSort {
+    /// Order of the config file.
+    #[default]
+    Manual,
+    Alphabetical,
+    RecentlyPlayed,
+    MostPlayed,
+}
+
+impl StationSort {
+    pub fn next(self) -> Self {
+        match self {
+            StationSort::Manual => StationSort::Alphabetical,
+            StationSort::Alphabetical => StationSort::RecentlyPlayed,
+            StationSort::RecentlyPlayed => StationSort::MostPlayed,
+            StationSort::MostPlayed => StationSort::Manual,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StationSort::Manual => "manual",
+            StationSort::Alphabetical => "A-Z",
+            StationSort::RecentlyPlayed => "recent",
+            StationSort::MostPlayed => "most played",
+        }
+    }
+}
+
+/// Reorder `stations` by `sort`. Ties, and stations without stats, keep the
+/// order of `manual` (the config order).
+pub fn sort_stations(
+    stations: &mut [Station],
+    sort: StationSort,
+    stats: &Stats,
+    manual: &[Station],
+) {
+    let manual_pos = |s: &Station| {
+        manual
+            .iter()
+            .position(|m| m.name == s.name)
+            .unwrap_or(usize::MAX)
+    };
+    let stat = |s: &Station| stats.get(&s.name).cloned().unwrap_or_default();
+    stations.sort_by(|a, b| {
+        let primary = match sort {
+            StationSort::Manual => std::cmp::Ordering::Equal,
+            StationSort::Alphabetical => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            StationSort::RecentlyPlayed => stat(b).last_played.cmp(&stat(a).last_played),
+            StationSort::MostPlayed => stat(b)
+                .plays
+                .cmp(&stat(a).plays)
+                .then(stat(b).listen_secs.cmp(&stat(a).listen_secs)),
+        };
+        primary.then(manual_pos(a).cmp(&manual_pos(b)))
+    });
+}
+
+/// Listening statistics for one station.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StationStats {
+    /// Times the station was started.
+    pub plays: u32,
+    /// Unix time of the last start.
+    pub last_played: u64,
+    /// Total seconds listened.
+    pub listen_secs: u64,
+    /// Sessions in a row where the reconnect supervisor gave up.
+    pub consecutive_failures: u32,
+}
+
+/// Play count for one track, aggregated across stations.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TrackStats {
+    /// Title as first seen; later variants count towards the same entry.
+    pub title: String,
+    pub plays: u32,
+    /// Unix time the track was last seen.
+    pub last_played: u64,
+    /// Unix times of the plays in the last five weeks.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub play_times: Vec<u64>,
+}
+
+/// Per-station statistics, stored as JSON in the state directory and keyed
+/// by station name (URLs may change when tokens are refreshed). Tracks are
+/// keyed by [`track_key`].
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Stats {
+    pub stations: HashMap<String, StationStats>,
+    pub tracks: HashMap<String, TrackStats>,
+    /// Seconds listened per local day ("2024-05-17").
+    pub days: BTreeMap<String, u64>,
+    /// Estimated bytes downloaded per local month ("2024-05").
+    pub months: BTreeMap<String, u64>,
+    /// Estimated bytes downloaded in all.
+    pub downloaded: u64,
+}
+
+/// Progress towards the daily listening goal, for the stats view.
+pub struct GoalProgress {
+    pub today_secs: u64,
+    pub goal_secs: u64,
+    /// Days in a row the goal was met, ending today, or yesterday while
+    /// today's goal is still open.
+    pub streak: u32,
+}
+
+fn day_key(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn month_key() -> String {
+    Local::now().format("%Y-%m").to_string()
+}
+
+/// Normalize a track title so slight metadata differences map to the same
+/// entry: case, punctuation, dash style, "(Remastered)"-like suffixes and
+/// featured artists are ignored.
+pub fn track_key(title: &str) -> String {
+    let lower = title.to_lowercase();
+    let mut stripped = String::with_capacity(lower.len());
+    let mut depth = 0u32;
+    for c in lower.chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => stripped.push(c),
+            _ => {}
+        }
+    }
+    for marker in [" feat. ", " feat ", " ft. ", " ft ", " featuring "] {
+        if let Some(i) = stripped.find(marker) {
+            // Keep the title part when the credit sits in the artist half
+            let rest = stripped[i..]
+                .find(" - ")
+                .map(|j| stripped[i + j..].to_string());
+            stripped.truncate(i);
+            stripped.push_str(&rest.unwrap_or_default());
+        }
+    }
+    let key = stripped
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if key.is_empty() {
+        lower.trim().to_string()
+    } else {
+        key
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Stats {
+    /// Load the stats file; a missing or corrupt file starts fresh.
+    pub fn load() -> Self {
+        stats_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = stats_path().ok_or("cannot locate state directory (HOME unset)")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, station: &str) -> Option<&StationStats> {
+        self.stations.get(station)
+    }
+
+    pub fn record_play(&mut self, station: &str) {
+        let entry = self.stations.entry(station.to_string()).or_default();
+        entry.plays += 1;
+        entry.last_played = unix_now();
+    }
+
+    /// The reconnect supervisor gave up on `station`.
+    pub fn record_failure(&mut self, station: &str) {
+        self.stations
+            .entry(station.to_string())
+            .or_default()
+            .consecutive_failures += 1;
+    }
+
+    /// `station` played fine. Returns whether a failure streak was cleared.
+    pub fn record_success(&mut self, station: &str) -> bool {
+        match self.stations.get_mut(station) {
+            Some(s) if s.consecutive_failures > 0 => {
+                s.consecutive_failures = 0;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_likely_dead(&self, station: &str) -> bool {
+        self.get(station)
+            .is_some_and(|s| s.consecutive_failures >= LIKELY_DEAD_AFTER)
+    }
+
+    /// Names of the stations flagged as likely dead.
+    pub fn likely_dead(&self) -> HashSet<String> {
+        self.stations
+            .iter()
+            .filter(|(_, s)| s.consecutive_failures >= LIKELY_DEAD_AFTER)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Count a play of `title`. Returns `false` when it was deduplicated as
+    /// a repeat of a play inside the repeat window.
+    pub fn record_track(&mut self, title: &str) -> bool {
+        let now = unix_now();
+        let entry = self.tracks.entry(track_key(title)).or_default();
+        let repeat =
+            entry.plays > 0 && now.saturating_sub(entry.last_played) < TRACK_REPEAT_WINDOW_SECS;
+        if entry.title.is_empty() {
+            entry.title = title.to_string();
+        }
+        entry.last_played = now;
+        if !repeat {
+            entry.plays += 1;
+            entry.play_times.push(now);
+            entry
+                .play_times
+                .retain(|&at| now.saturating_sub(at) < PLAY_TIMES_KEPT_SECS);
+        }
+        !repeat
+    }
+
+    /// Most played tracks first; ties go to the most recently heard.
+    pub fn top_tracks(&self, n: usize) -> Vec<&TrackStats> {
+        let mut tracks: Vec<&TrackStats> = self.tracks.values().collect();
+        tracks.sort_by(|a, b| {
+            b.plays
+                .cmp(&a.plays)
+                .then(b.last_played.cmp(&a.last_played))
+        });
+        tracks.truncate(n);
+        tracks
+    }
+
+    /// Snapshot handed to the UI for the stats view.
+    pub fn screen(&self) -> StatsScreen {
+        let mut stations: Vec<(String, StationStats)> = self
+            .stations
+            .iter()
+            .map(|(name, s)| (name.clone(), s.clone()))
+            .collect();
+        stations.sort_by_key(|(_, s)| std::cmp::Reverse(s.listen_secs));
+        StatsScreen {
+            stations,
+            tracks: self
+                .top_tracks(TOP_TRACKS)
+                .into_iter()
+                .map(|t| (t.title.clone(), t.plays))
+                .collect(),
+            queued_listens: 0,
+            queue_error: None,
+            goal: None,
+            data: String::new(),
+        }
+    }
+
+    pub fn add_listen_time(&mut self, station: &str, listened: Duration) {
+        let entry = self.stations.entry(station.to_string()).or_default();
+        entry.listen_secs += listened.as_secs();
+        // All of it counts for today, even when it started before midnight
+        *self
+            .days
+            .entry(day_key(Local::now().date_naive()))
+            .or_default() += listened.as_secs();
+        while self.days.len() > DAYS_KEPT {
+            self.days.pop_first();
+        }
+    }
+
+    pub fn add_download(&mut self, bytes: u64) {
+        if bytes == 0 {
+            return;
+        }
+        self.downloaded += bytes;
+        *self.months.entry(month_key()).or_default() += bytes;
+        while self.months.len() > MONTHS_KEPT {
+            self.months.pop_first();
+        }
+    }
+
+    /// Estimated bytes downloaded this month.
+    pub fn month_download(&self) -> u64 {
+        self.months.get(&month_key()).copied().unwrap_or(0)
+    }
+
+    /// Today's listening against a goal of `goal_minutes`; `None` without
+    /// a goal.
+    pub fn goal_progress(&self, goal_minutes: u32) -> Option<GoalProgress> {
+        let goal_secs = u64::from(goal_minutes) * 60;
+        if goal_secs == 0 {
+            return None;
+        }
+        let listened = |date: NaiveDate| self.days.get(&day_key(date)).copied().unwrap_or(0);
+        let today = Local::now().date_naive();
+        let today_secs = listened(today);
+        let mut day = if today_secs >= goal_secs {
+            Some(today)
+        } else {
+            today.pred_opt()
+        };
+        let mut streak = 0;
+        while let Some(date) = day.filter(|&d| listened(d) >= goal_secs) {
+            streak += 1;
+            day = date.pred_opt();
+        }
+        Some(GoalProgress {
+            today_secs,
+            goal_secs,
+            streak,
+        })
+    }
+}
+
+/// What happened during this run, printed after the terminal is restored.
+pub struct SessionSummary {
+    /// Time spent playing, not counting pauses and stops.
+    listened: Duration,
+    /// Playing since then, when playing at the last [`Self::playing`].
+    playing_since: Option<Instant>,
+    /// Stations in the order they were first played.
+    stations: Vec<String>,
+    /// Distinct tracks, by [`track_key`].
+    tracks: HashSet<String>,
+    /// `elapsed_format` from the config.
+    elapsed_format: String,
+    /// Estimated bytes downloaded.
+    pub downloaded: u64,
+}
+
+impl SessionSummary {
+    pub fn new(elapsed_format: String) -> Self {
+        Self {
+            listened: Duration::ZERO,
+            playing_since: None,
+            elapsed_format,
+            stations: Vec::new(),
+            tracks: HashSet::new(),
+            downloaded: 0,
+        }
+    }
+
+    pub fn station(&mut self, name: &str) {
+        if !self.stations.iter().any(|s| s == name) {
+            self.stations.push(name.to_string());
+        }
+    }
+
+    pub fn track(&mut self, title: &str) {
+        self.tracks.insert(track_key(title));
+    }
+
+    /// Whether audio is playing now; the time since the last call counts
+    /// as listened when it was then.
+    pub fn playing(&mut self, playing: bool) {
+        let now = Instant::now();
+        if let Some(since) = self.playing_since.take() {
+            self.listened += now - since;
+        }
+        self.playing_since = playing.then_some(now);
+    }
+}
+
+impl fmt::Display for SessionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Session summary")?;
+        writeln!(
+            f,
+            "  Listened:  {}",
+            format_elapsed(
+                self.listened + self.playing_since.map_or(Duration::ZERO, |s| s.elapsed()),
+                &self.elapsed_format
+            )
+        )?;
+        writeln!(
+            f,
+            "  Stations:  {} ({})",
+            self.stations.len(),
+            self.stations.join(", ")
+        )?;
+        writeln!(f, "  Tracks:    {} heard", self.tracks.len())?;
+        write!(f, "  Data:      ~{}", format_bytes(self.downloaded))
+    }
+}
+
+fn stats_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("stats.json"))
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::write_private;
+use crate::state::instance_path;
+use crate::timefmt::format_elapsed;
+
+/// How often `lofi_rs status --watch` re-reads the status file.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Playback state as scripts see it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayState {
+    Playing,
+    Buffering,
+    /// Muted with F8, or started paused and waiting for it.
+    Paused,
+    /// The reconnect supervisor gave up, or the machine is asleep.
+    Stopped,
+}
+
+impl PlayState {
+    fn name(self) -> &'static str {
+        match self {
+            PlayState::Playing => "playing",
+            PlayState::Buffering => "buffering",
+            PlayState::Paused => "paused",
+            PlayState::Stopped => "stopped",
+        }
+    }
+}
+
+/// What the running player publishes for `lofi_rs status`, kept in the
+/// state directory while it runs.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Status {
+    /// Process id of the player that wrote it.
+    pub pid: u32,
+    pub station: String,
+    pub url: String,
+    pub track: Option<String>,
+    pub volume: u32,
+    pub state: PlayState,
+    /// Seconds on the current station.
+    pub elapsed: u64,
+    /// Stream bitrate in kbit/s, when the backend reports it.
+    pub bitrate: Option<u32>,
+    /// Cover art of the track's release, with the `musicbrainz` lookup on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artwork: Option<String>,
+    /// The cover art downloaded into the cache directory, for status bars
+    /// and notifications that want a file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artwork_file: Option<PathBuf>,
+}
+
+impl Status {
+    /// Same status apart from the elapsed time, which moves every second.
+    pub fn same_as(&self, other: &Status) -> bool {
+        Status {
+            elapsed: other.elapsed,
+            ..self.clone()
+        } == *other
+    }
+
+    /// One line for headless output: "HH:MM:SS Station — state — track (70%)".
+    pub fn line(&self) -> String {
+        let mut line = format!(
+            "{} {} — {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            self.station,
+            self.state.name()
+        );
+        if let Some(track) = &self.track {
+            line.push_str(&format!(" — {}", track));
+        }
+        line.push_str(&format!(" ({}%)", self.volume));
+        line
+    }
+
+    /// Write the status for readers; failures are ignored.
+    pub fn publish(&self) {
+        let Some(path) = status_path() else {
+            return;
+        };
+        let Ok(json) = serde_json::to_string(self) else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        // Written aside and renamed, so readers never see half a file
+        let _ = write_private(&path, json.as_bytes());
+    }
+}
+
+fn status_path() -> Option<PathBuf> {
+    instance_path("status.json")
+}
+
+/// Remove the status file when the player exits.
+pub fn clear() {
+    if let Some(path) = status_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Status of the running player; `None` when none is running (a file left
+/// behind by a crashed player is ignored).
+fn read() -> Option<Status> {
+    let text = std::fs::read_to_string(status_path()?).ok()?;
+    let status: Status = serde_json::from_str(&text).ok()?;
+    let pid = nix::unistd::Pid::from_raw(status.pid as i32);
+    nix::sys::signal::kill(pid, None).ok()?;
+    Some(status)
+}
+
+pub fn print(
+    status: Option<&Status>,
+    json: bool,
+    elapsed_format: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match (status, json) {
+        (Some(status), true) => println!("{}", serde_json::to_string(status)?),
+        (None, true) => println!("{}", serde_json::json!({ "state": "not running" })),
+        (Some(status), false) => {
+            println!("{} — {}", status.station, status.state.name());
+            if let Some(track) = &status.track {
+                println!("  {}", track);
+            }
+            let mut line = format!(
+                "  volume {}%, {} on station",
+                status.volume,
+                format_elapsed(Duration::from_secs(status.elapsed), elapsed_format)
+            );
+            if let Some(kbps) = status.bitrate {
+                line.push_str(&format!(", {} kbit/s", kbps));
+            }
+            println!("{}", line);
+        }
+        (None, false) => println!("lofi_rs is not running"),
+    }
+    Ok(())
+}
+
+/// `lofi_rs status [--json] [--watch]`: show what the running player is
+/// doing; `watch` prints again on every change (one JSON object per line
+/// with `json`) until interrupted. Elapsed time is written after
+/// `elapsed_format`, see [`format_elapsed`].
+pub async fn run(
+    json: bool,
+    watch: bool,
+    elapsed_format: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    follow(json, watch, elapsed_format, || std::future::ready(read())).await
+}
+
+/// Print the status `read` gives, and with `watch` again whenever it
+/// changes; see [`run`].
+pub async fn follow<F, Fut>(
+    json: bool,
+    watch: bool,
+    elapsed_format: &str,
+    mut read: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<Status>>,
+{
+    let mut last = read().await;
+    print(last.as_ref(), json, elapsed_format)?;
+    if !watch {
+        return Ok(());
+    }
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+    loop {
+        interval.tick().await;
+        let status = read().await;
+        let changed = match (&last, &status) {
+            (Some(a), Some(b)) => !a.same_as(b),
+            (None, None) => false,
+            _ => true,
+        };
+        if changed {
+            print(status.as_ref(), json, elapsed_format)?;
+        }
+        last = status;
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+use tokio::time::{interval_at, Instant, Interval, MissedTickBehavior};
+
+/// Fastest the UI ticks, whatever a widget asks for.
+pub const MIN_TICK: Duration = Duration::from_millis(100);
+/// Tick while the power saver is on; widget requests are ignored then.
+pub const POWER_SAVER_TICK: Duration = Duration::from_secs(5);
+
+/// Widgets that can ask for a finer tick than the configured one.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TickClient {
+    /// The spinner on a station that is starting.
+    Spinner,
+}
+
+pub enum Tick {
+    /// The configured period has passed: update the clock, probe the
+    /// player, run the stall checks.
+    Full,
+    /// An extra tick asked for by a widget: only redraw.
+    Redraw,
+}
+
+/// The UI clock. Ticks at the configured rate, or at the finest rate a
+/// widget currently asks for (down to [`MIN_TICK`]); the extra ticks in
+/// between come out as [`Tick::Redraw`].
+pub struct Ticker {
+    interval: Interval,
+    base: Duration,
+    power_saver: bool,
+    requests: Vec<(TickClient, Duration)>,
+    last_full: Instant,
+    force_full: bool,
+}
+
+impl Ticker {
+    /// A ticker at `base`, clamped to [`MIN_TICK`]..=[`POWER_SAVER_TICK`].
+    /// The first tick comes one period from now.
+    pub fn new(base: Duration, power_saver: bool) -> Self {
+        let base = base.clamp(MIN_TICK, POWER_SAVER_TICK);
+        let period = if power_saver { POWER_SAVER_TICK } else { base };
+        Self {
+            interval: Self::interval(period),
+            base,
+            power_saver,
+            requests: Vec::new(),
+            last_full: Instant::now(),
+            force_full: false,
+        }
+    }
+
+    fn interval(period: Duration) -> Interval {
+        let mut interval = interval_at(Instant::now() + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    }
+
+    /// Period between full ticks.
+    fn full_period(&self) -> Duration {
+        if self.power_saver {
+            POWER_SAVER_TICK
+        } else {
+            self.base
+        }
+    }
+
+    /// Period between ticks of any kind.
+    pub fn period(&self) -> Duration {
+        if self.power_saver {
+            return POWER_SAVER_TICK;
+        }
+        self.requests
+            .iter()
+            .map(|&(_, period)| period.max(MIN_TICK))
+            .fold(self.base, Duration::min)
+    }
+
+    fn reschedule(&mut self, old: Duration) {
+        let period = self.period();
+        if period != old {
+            self.interval = Self::interval(period);
+        }
+    }
+
+    pub fn set_power_saver(&mut self, on: bool) {
+        let old = self.period();
+        self.power_saver = on;
+        self.reschedule(old);
+    }
+
+    /// Ask for ticks at least every `period` on behalf of `client`, or drop
+    /// its request with `None`.
+    pub fn request(&mut self, client: TickClient, period: Option<Duration>) {
+        let old = self.period();
+        self.requests.retain(|&(c, _)| c != client);
+        if let Some(period) = period {
+            self.requests.push((client, period));
+        }
+        self.reschedule(old);
+    }
+
+    /// Make the next tick a full one, right away.
+    pub fn reset_immediately(&mut self) {
+        self.force_full = true;
+        self.interval.reset_immediately();
+    }
+
+    /// Wait for the next tick. Cancel-safe.
+    pub async fn tick(&mut self) -> Tick {
+        self.interval.tick().await;
+        // Half a period of slack so timer jitter doesn't skip a full tick
+        let due = self.full_period().saturating_sub(self.period() / 2);
+        if self.force_full || self.last_full.elapsed() >= due {
+            self.force_full = false;
+            self.last_full = Instant::now();
+            Tick::Full
+        } else {
+            Tick::Redraw
+        }
+    }
+}
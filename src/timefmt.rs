@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+/// Default `elapsed_format`: the status bar's original `01:23:45`.
+pub const DEFAULT_ELAPSED_FORMAT: &str = "%H:%M:%S";
+
+/// Format `elapsed` after `format`, where
+///
+/// - `%H`/`%h` are hours, zero-padded or not,
+/// - `%M`/`%m` minutes and `%S`/`%s` seconds, likewise,
+/// - `%:` is the time separator of the user's locale (`:` or `.`),
+/// - `%%` is a literal `%`.
+///
+/// A unit whose larger units are left out carries them: with no hour
+/// token, `%M` counts all minutes (`"%M:%S"` gives `83:05`). Anything else
+/// is copied as is, so `"%hh %Mm"` gives `1h 23m`.
+pub fn format_elapsed(elapsed: Duration, format: &str) -> String {
+    let total = elapsed.as_secs();
+    let has = |tokens: &[&str]| tokens.iter().any(|t| format.contains(t));
+    let with_hours = has(&["%H", "%h"]);
+    let with_minutes = has(&["%M", "%m"]);
+    let hours = total / 3600;
+    let minutes = if with_hours {
+        total % 3600 / 60
+    } else {
+        total / 60
+    };
+    let seconds = if with_hours || with_minutes {
+        total % 60
+    } else {
+        total
+    };
+
+    let mut out = String::with_capacity(format.len());
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('H') => out.push_str(&format!("{:02}", hours)),
+            Some('h') => out.push_str(&hours.to_string()),
+            Some('M') => out.push_str(&format!("{:02}", minutes)),
+            Some('m') => out.push_str(&minutes.to_string()),
+            Some('S') => out.push_str(&format!("{:02}", seconds)),
+            Some('s') => out.push_str(&seconds.to_string()),
+            Some(':') => out.push(locale_separator()),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+/// Hours/minutes separator of the locale in `LC_ALL`, `LC_TIME` or `LANG`.
+fn locale_separator() -> char {
+    // Locales that write times as 12.34.56
+    const DOTTED: [&str; 3] = ["da", "fi", "id"];
+    let locale = ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    let language = locale.split(['_', '.', '@']).next().unwrap_or("");
+    if DOTTED.contains(&language) {
+        '.'
+    } else {
+        ':'
+    }
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use tokio_rustls::rustls;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{
+    verify_tls12_signature, verify_tls13_signature, CryptoProvider,
+};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{DigitallySignedStruct, SignatureScheme};
+
+use crate::config::Station;
+use crate::net;
+
+/// TLS settings by host, from the stations that have any.
+static BY_HOST: OnceLock<HashMap<String, StationTls>> = OnceLock::new();
+
+/// TLS for a server with a private certificate, under a station's `tls`.
+/// They apply to every connection to the station's host.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StationTls {
+    /// PEM file with the CA that signed the server's certificate; players
+    /// are told to verify against it too (mpv, ffmpeg).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_file: Option<PathBuf>,
+    /// SHA-256 fingerprint of the server's own certificate, hex with or
+    /// without colons (`openssl x509 -noout -fingerprint -sha256`). A
+    /// self-signed certificate matching it is accepted, any other refused.
+    /// Players cannot pin: they play without verifying, as they do by
+    /// default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+}
+
+impl StationTls {
+    /// Check the settings can be used: a readable CA file with
+    /// certificates in it, and a fingerprint of 32 bytes.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(path) = &self.ca_file {
+            let pem = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            if reqwest::Certificate::from_pem_bundle(&pem).map_or(true, |c| c.is_empty()) {
+                return Err(format!("{}: no PEM certificates", path.display()));
+            }
+        }
+        if let Some(fingerprint) = &self.fingerprint {
+            parse_fingerprint(fingerprint).ok_or_else(|| {
+                format!("fingerprint {:?} is not a SHA-256 hex digest", fingerprint)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_fingerprint(text: &str) -> Option<Vec<u8>> {
+    let hex: String = text.chars().filter(|c| *c != ':').collect();
+    if hex.len() != 64 {
+        return None;
+    }
+    (0..64)
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn host(url: &str) -> Option<String> {
+    reqwest::Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(str::to_ascii_lowercase)
+}
+
+/// Remember the TLS settings of `stations` by host. Later calls have no
+/// effect.
+pub fn init(stations: &[Station]) {
+    let by_host = stations
+        .iter()
+        .filter_map(|s| Some((host(&s.url)?, s.tls.clone()?)))
+        .collect();
+    let _ = BY_HOST.set(by_host);
+}
+
+fn settings(url: &str) -> Option<&'static StationTls> {
+    BY_HOST.get()?.get(&host(url)?)
+}
+
+/// The CA file configured for `url`'s host, for the players.
+pub fn ca_file(url: &str) -> Option<&'static PathBuf> {
+    settings(url)?.ca_file.as_ref()
+}
+
+/// [`net::client_builder`] trusting what `url`'s host is configured with:
+/// its pinned certificate, or its CA next to the usual roots.
+pub fn client_builder(url: &str) -> reqwest::ClientBuilder {
+    let builder = net::client_builder();
+    let Some(tls) = settings(url) else {
+        return builder;
+    };
+    if let Some(fingerprint) = tls.fingerprint.as_deref().and_then(parse_fingerprint) {
+        if let Some(config) = pinned_config(fingerprint) {
+            return builder.use_preconfigured_tls(config);
+        }
+    }
+    let certs = tls
+        .ca_file
+        .as_ref()
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|pem| reqwest::Certificate::from_pem_bundle(&pem).ok())
+        .unwrap_or_default();
+    certs
+        .into_iter()
+        .fold(builder, |builder, cert| builder.add_root_certificate(cert))
+}
+
+fn pinned_config(fingerprint: Vec<u8>) -> Option<rustls::ClientConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .ok()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinnedCertificate {
+            fingerprint,
+            provider,
+        }))
+        .with_no_client_auth();
+    Some(config)
+}
+
+/// Accepts exactly the certificate with the pinned SHA-256 fingerprint,
+/// whoever signed it; handshake signatures are still checked.
+#[derive(Debug)]
+struct PinnedCertificate {
+    fingerprint: Vec<u8>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertificate {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let digest = ring::digest::digest(&ring::digest::SHA256, end_entity.as_ref());
+        if digest.as_ref() == self.fingerprint.as_slice() {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "certificate does not match the pinned fingerprint".to_string(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
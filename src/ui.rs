@@ -1,43 +1,680 @@
 use ratatui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
-    Terminal,
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Tabs, Widget, Wrap,
+    },
+    Frame, Terminal,
 };
-use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
-#[derive(Clone)]
-pub struct Station {
+use crate::changelog::ReleaseNotes;
+use crate::config::Station;
+use crate::layout::player_layout;
+use crate::musicbrainz::Recording;
+use crate::player::Capabilities;
+use crate::state::LayoutPrefs;
+use crate::stats::{GoalProgress, StationSort, StationStats};
+use crate::timefmt::{format_elapsed, DEFAULT_ELAPSED_FORMAT};
+
+/// Named color scheme, selected with the `theme` config key.
+#[derive(Clone, Copy)]
+pub struct Theme {
     pub name: &'static str,
-    pub url: &'static str,
-    pub metadata_url: Option<&'static str>,
+    /// Selected station, active tab and similar highlights.
+    pub accent: Color,
+    pub toast_fg: Color,
+    pub toast_bg: Color,
+}
+
+/// Colors the terminal can display, detected once at startup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    /// `NO_COLOR` set or a dumb terminal: no colors at all.
+    None,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// Guess color support from the environment, like most terminal apps do:
+/// `NO_COLOR`, then `COLORTERM`, then `TERM`.
+pub fn detect_color_support() -> ColorSupport {
+    if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return ColorSupport::None;
+    }
+    let colorterm = std::env::var("COLORTERM")
+        .unwrap_or_default()
+        .to_lowercase();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        return ColorSupport::TrueColor;
+    }
+    let term = std::env::var("TERM").unwrap_or_default().to_lowercase();
+    // Windows consoles leave TERM unset but do support colors
+    if term == "dumb" || (term.is_empty() && cfg!(unix)) {
+        ColorSupport::None
+    } else if term.contains("truecolor") || term.contains("direct") {
+        ColorSupport::TrueColor
+    } else if term.contains("256") {
+        ColorSupport::Ansi256
+    } else {
+        ColorSupport::Ansi16
+    }
+}
+
+/// xterm's default values for the 16 palette colors, used to find the
+/// closest palette slot for an RGB color.
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// RGB value of a 256-color palette index (cube and grayscale ramp).
+fn indexed_rgb(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..=15 => ANSI16[i as usize].1,
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = i - 16;
+            (level(i / 36), level((i / 6) % 6), level(i % 6))
+        }
+        _ => {
+            let v = 8 + (i - 232) * 10;
+            (v, v, v)
+        }
+    }
+}
+
+fn rgb_to_indexed(r: u8, g: u8, b: u8) -> u8 {
+    let cube = |v: u8| ((v as u16 * 5 + 127) / 255) as u8;
+    let cube_index = 16 + 36 * cube(r) + 6 * cube(g) + cube(b);
+    let avg = (r as u16 + g as u16 + b as u16) / 3;
+    let gray_index = 232 + ((avg.saturating_sub(3)) / 10).min(23) as u8;
+    let dist = |i: u8| {
+        let (ir, ig, ib) = indexed_rgb(i);
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, ir) + d(g, ig) + d(b, ib)
+    };
+    if dist(gray_index) < dist(cube_index) {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+fn rgb_to_ansi16(r: u8, g: u8, b: u8) -> Color {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+    ANSI16
+        .iter()
+        .min_by_key(|(_, (pr, pg, pb))| d(r, *pr) + d(g, *pg) + d(b, *pb))
+        .map(|(c, _)| *c)
+        .unwrap_or(Color::Reset)
+}
+
+/// Translate `color` into something the terminal can show.
+fn adapt_color(color: Color, support: ColorSupport) -> Color {
+    match (color, support) {
+        (_, ColorSupport::None) => Color::Reset,
+        (Color::Rgb(r, g, b), ColorSupport::Ansi256) => Color::Indexed(rgb_to_indexed(r, g, b)),
+        (Color::Rgb(r, g, b), ColorSupport::Ansi16) => rgb_to_ansi16(r, g, b),
+        (Color::Indexed(i), ColorSupport::Ansi16) if i >= 16 => {
+            let (r, g, b) = indexed_rgb(i);
+            rgb_to_ansi16(r, g, b)
+        }
+        (Color::Indexed(i), ColorSupport::Ansi16) => ANSI16[i as usize].0,
+        (c, _) => c,
+    }
+}
+
+impl Theme {
+    /// The theme with every color translated for `support`.
+    pub fn adapted(self, support: ColorSupport) -> Theme {
+        Theme {
+            accent: adapt_color(self.accent, support),
+            toast_fg: adapt_color(self.toast_fg, support),
+            toast_bg: adapt_color(self.toast_bg, support),
+            ..self
+        }
+    }
 }
 
-pub const STATIONS: &[Station] = &[
-    Station {
-        name: "Lofi 1",
-        url: "https://stream.zeno.fm/0r0xa792kwzuv",
-        metadata_url: None,
+pub const THEMES: &[Theme] = &[
+    Theme {
+        name: "default",
+        accent: Color::Yellow,
+        toast_fg: Color::Black,
+        toast_bg: Color::Yellow,
+    },
+    Theme {
+        name: "ocean",
+        accent: Color::Cyan,
+        toast_fg: Color::Black,
+        toast_bg: Color::Cyan,
+    },
+    Theme {
+        name: "forest",
+        accent: Color::Green,
+        toast_fg: Color::Black,
+        toast_bg: Color::Green,
     },
-    Station {
-        name: "Lofi 2",
-        url: "https://stream.zeno.fm/v5reddyk8rhvv",
-        metadata_url: None,
+    Theme {
+        name: "rose",
+        accent: Color::Magenta,
+        toast_fg: Color::White,
+        toast_bg: Color::Magenta,
     },
-    Station {
-        name: "Code Radio",
-        url: "https://coderadio-admin-v2.freecodecamp.org/listen/coderadio/radio.mp3",
-        metadata_url: Some("https://coderadio-admin-v2.freecodecamp.org/api/nowplaying/coderadio"),
+    // Truecolor pastel; approximated on 256- and 16-color terminals.
+    Theme {
+        name: "dusk",
+        accent: Color::Rgb(244, 154, 194),
+        toast_fg: Color::Rgb(40, 30, 56),
+        toast_bg: Color::Rgb(190, 160, 255),
+    },
+    // Only palette slots, no background: follows whatever base16 (or other)
+    // scheme the terminal has loaded, transparency included. Accent is slot 4,
+    // base16's blue (base0D); toasts draw slot 0 (base00) on it.
+    Theme {
+        name: "terminal",
+        accent: Color::Blue,
+        toast_fg: Color::Black,
+        toast_bg: Color::Blue,
+    },
+    Theme {
+        name: "mono",
+        accent: Color::Reset,
+        toast_fg: Color::Reset,
+        toast_bg: Color::Reset,
     },
 ];
 
+/// Look up a theme by name, falling back to the default theme.
+pub fn theme_by_name(name: &str) -> Theme {
+    THEMES
+        .iter()
+        .copied()
+        .find(|t| t.name.eq_ignore_ascii_case(name))
+        .unwrap_or(THEMES[0])
+}
+
+/// Which glyphs decorate the list and status bar, set with `icons`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconStyle {
+    /// Plain text only.
+    #[default]
+    Off,
+    /// Unicode symbols every modern font has.
+    Unicode,
+    /// Nerd Font private-use glyphs.
+    Nerd,
+}
+
+/// Glyphs used by `draw_ui`.
+#[derive(Clone, Copy)]
+pub struct IconSet {
+    /// Marker before the current station.
+    pub current: &'static str,
+    /// Frames replacing `current` while the station's player starts; as
+    /// wide as `current`.
+    pub spinner: &'static [&'static str],
+    /// Prefix of the track title.
+    pub track: &'static str,
+    pub volume: &'static str,
+    pub muted: &'static str,
+    pub liked: &'static str,
+    /// After the name of a favorite station.
+    pub favorite: &'static str,
+    /// Volume slider cells: set, staged (not applied yet), unset.
+    pub slider: [&'static str; 3],
+}
+
+const PLAIN_ICONS: IconSet = IconSet {
+    current: "->",
+    spinner: &["- ", "\\ ", "| ", "/ "],
+    track: "",
+    volume: "Volume:",
+    muted: "[MUTED]",
+    liked: "[liked]",
+    favorite: "*",
+    slider: ["#", "+", "-"],
+};
+
+const UNICODE_ICONS: IconSet = IconSet {
+    current: "▶ ",
+    spinner: &["⠋ ", "⠙ ", "⠹ ", "⠸ ", "⠼ ", "⠴ ", "⠦ ", "⠧ ", "⠇ ", "⠏ "],
+    track: "♪ ",
+    volume: "🔊",
+    muted: "🔇",
+    liked: "♥",
+    favorite: "★",
+    slider: ["█", "▒", "─"],
+};
+
+const NERD_ICONS: IconSet = IconSet {
+    current: "\u{f04b} ",
+    spinner: &["⠋ ", "⠙ ", "⠹ ", "⠸ ", "⠼ ", "⠴ ", "⠦ ", "⠧ ", "⠇ ", "⠏ "],
+    track: "\u{f001} ",
+    volume: "\u{f028}",
+    muted: "\u{f026}",
+    liked: "\u{f004}",
+    favorite: "\u{f005}",
+    slider: ["█", "▒", "─"],
+};
+
+/// Icons for `style`, falling back to plain text when the locale is not
+/// UTF-8 (the glyphs would come out as mojibake).
+pub fn icon_set(style: IconStyle) -> IconSet {
+    let utf8 = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .map(|v| {
+            let v = v.to_lowercase();
+            v.contains("utf-8") || v.contains("utf8")
+        })
+        // No locale at all: Windows and macOS GUI launches are UTF-8
+        .unwrap_or(!cfg!(unix) || cfg!(target_os = "macos"));
+    match style {
+        _ if !utf8 => PLAIN_ICONS,
+        IconStyle::Off => PLAIN_ICONS,
+        IconStyle::Unicode => UNICODE_ICONS,
+        IconStyle::Nerd => NERD_ICONS,
+    }
+}
+
+/// How long informational toasts stay on screen.
+const TOAST_INFO_TTL: Duration = Duration::from_secs(2);
+/// Errors and warnings linger a little longer so they can be read.
+const TOAST_ERROR_TTL: Duration = Duration::from_secs(4);
+/// Toasts stacked on screen at once; the oldest give way.
+const MAX_TOASTS: usize = 3;
+/// Entries kept in the notification log.
+const LOG_LEN: usize = 200;
+/// How long each frame of the switching spinner is shown.
+pub const SPINNER_FRAME: Duration = Duration::from_millis(150);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    /// Something went wrong but is being handled (reconnecting, retrying).
+    Warning,
+    Error,
+}
+
+/// Transient message drawn over the bottom of the screen.
+#[derive(Clone)]
+pub struct Toast {
+    pub text: String,
+    pub kind: ToastKind,
+    /// `None` keeps the toast up until it is replaced or cleared, for
+    /// messages that wait on the user (e.g. "r to resume").
+    pub expires: Option<Instant>,
+}
+
+impl Toast {
+    pub fn info(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            kind: ToastKind::Info,
+            expires: Some(Instant::now() + TOAST_INFO_TTL),
+        }
+    }
+
+    pub fn error(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            kind: ToastKind::Error,
+            expires: Some(Instant::now() + TOAST_ERROR_TTL),
+        }
+    }
+
+    pub fn warning(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            kind: ToastKind::Warning,
+            expires: Some(Instant::now() + TOAST_ERROR_TTL),
+        }
+    }
+
+    pub fn sticky(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            kind: ToastKind::Info,
+            expires: None,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|at| Instant::now() >= at)
+    }
+
+    /// What the toast is about: its first word. A newer toast on the same
+    /// topic ("Volume 50%" after "Volume 45%") replaces the older one.
+    fn topic(&self) -> &str {
+        self.text
+            .split(|c: char| c.is_whitespace() || c == ':')
+            .next()
+            .unwrap_or("")
+    }
+}
+
+/// A toast as kept in the notification log.
+pub struct LoggedToast {
+    /// Local time it was shown, "15:04:05".
+    pub time: String,
+    pub kind: ToastKind,
+    pub text: String,
+}
+
+/// Toasts on screen, newest last, and the log of everything shown this
+/// session.
+#[derive(Default)]
+pub struct Notifications {
+    shown: Vec<Toast>,
+    log: Vec<LoggedToast>,
+}
+
+impl Notifications {
+    /// Show `toast` on top of the stack. It replaces a sticky toast and a
+    /// live one on the same topic.
+    pub fn push(&mut self, toast: Toast) {
+        self.shown.retain(|t| t.expires.is_some());
+        let replaces = self.shown.last().is_some_and(|last| {
+            !last.is_expired() && last.kind == toast.kind && last.topic() == toast.topic()
+        });
+        let entry = LoggedToast {
+            time: chrono::Local::now().format("%H:%M:%S").to_string(),
+            kind: toast.kind,
+            text: toast.text.clone(),
+        };
+        if replaces {
+            self.shown.pop();
+            self.log.pop();
+        }
+        self.shown.push(toast);
+        if self.shown.len() > MAX_TOASTS {
+            self.shown.remove(0);
+        }
+        self.log.push(entry);
+        if self.log.len() > LOG_LEN {
+            self.log.remove(0);
+        }
+    }
+
+    /// Drop the expired toasts. Returns whether any went.
+    pub fn expire(&mut self) -> bool {
+        let before = self.shown.len();
+        self.shown.retain(|t| !t.is_expired());
+        self.shown.len() != before
+    }
+
+    pub fn shown(&self) -> &[Toast] {
+        &self.shown
+    }
+
+    /// Everything shown this session, oldest first.
+    pub fn log(&self) -> &[LoggedToast] {
+        &self.log
+    }
+}
+
+/// What the settings screen shows; built by `settings::SettingsView`.
+pub struct SettingsScreen {
+    pub tabs: &'static [&'static str],
+    pub pane: usize,
+    pub row: usize,
+    pub rows: Vec<(&'static str, String)>,
+}
+
+/// What the stats view shows; built by `Stats::screen`.
+pub struct StatsScreen {
+    /// Stations by listening time.
+    pub stations: Vec<(String, StationStats)>,
+    /// "Most heard tracks" leaderboard: title and play count.
+    pub tracks: Vec<(String, u32)>,
+    /// Listens waiting to be submitted to a scrobble service.
+    pub queued_listens: usize,
+    /// Why the waiting listens could not be submitted yet.
+    pub queue_error: Option<String>,
+    /// Today against the daily listening goal, when one is set.
+    pub goal: Option<GoalProgress>,
+    /// Estimated data use: "45 MB this session, 1.2 GB this month".
+    pub data: String,
+}
+
+/// What the station detail popup shows; built by `Station::detail_screen`.
+pub struct DetailScreen {
+    pub title: String,
+    pub rows: Vec<(&'static str, String)>,
+    /// Actions offered under the rows, picked with ←/→ and Enter.
+    pub actions: Vec<String>,
+    pub selected: usize,
+    /// Notes being edited: text and cursor.
+    pub editing: Option<(String, usize)>,
+}
+
+/// What a confirmation dialog asks before a destructive action.
+pub struct ConfirmScreen {
+    pub title: String,
+    pub question: String,
+}
+
+/// One station in the timeline view.
+pub struct TimelineEntry {
+    /// Local start time, "15:04".
+    pub time: String,
+    /// Seconds it played.
+    pub length: u64,
+    pub station: String,
+}
+
+/// What the timeline view shows; built by `history::timeline_screen`.
+pub struct TimelineScreen {
+    /// Local day ("Monday 2024-05-06") and its stations, newest first.
+    pub days: Vec<(String, Vec<TimelineEntry>)>,
+    /// First line shown.
+    pub scroll: u16,
+}
+
+/// What the recordings view shows; built by `record::RecordingsView`.
+pub struct RecordingsScreen {
+    pub directory: String,
+    /// File name, size and length.
+    pub rows: Vec<(String, String, String)>,
+    pub selected: usize,
+    /// New name being typed: text and cursor.
+    pub renaming: Option<(String, usize)>,
+}
+
+/// What the output picker shows; built by `output::OutputPicker`.
+pub struct OutputScreen {
+    /// Device label and whether it is the current output.
+    pub devices: Vec<(String, bool)>,
+    pub selected: usize,
+}
+
+/// What the ambient mixer shows; built by `noise::AmbientMixer`.
+pub struct AmbientScreen {
+    /// Layer name and level (0 is off).
+    pub layers: Vec<(String, u32)>,
+    pub selected: usize,
+}
+
+/// Seekable window of the timeshift buffer, in seconds.
+#[derive(Clone, Copy)]
+pub struct Timeshift {
+    /// Length of the buffer.
+    pub buffered: f64,
+    /// How far playback is behind the live edge.
+    pub behind: f64,
+    /// Stream time of the live edge.
+    pub end: f64,
+}
+
+/// What the Mixer pane shows; built by `mixer::Mixer`.
+pub struct MixerScreen {
+    /// Row label, station name and level (`None` when the row is off).
+    pub rows: Vec<(String, String, Option<u32>)>,
+    pub selected: usize,
+}
+
+/// What the command palette shows; built by `palette::Palette`.
+pub struct PaletteScreen {
+    pub input: String,
+    /// Cursor position in `input`, in chars.
+    pub cursor: usize,
+    /// Usage of the commands matching the typed command word.
+    pub hints: Vec<&'static str>,
+}
+
+/// Pane of the player view that the arrow keys act on, cycled with Tab.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Focus {
+    /// ↑/↓ and letters move the station cursor, Enter opens the detail
+    /// popup of the station under it, Esc hands focus back.
+    Stations,
+    /// ↑/↓ change the volume.
+    #[default]
+    Status,
+    /// ↑/↓ scroll the track history (two-column layout only).
+    History,
+}
+
+impl Focus {
+    /// The next pane in Tab order (`forward`) or Shift+Tab order, skipping
+    /// the history pane when it is not shown.
+    pub fn cycle(self, forward: bool, history_shown: bool) -> Self {
+        let order: &[Focus] = if history_shown {
+            &[Focus::Stations, Focus::Status, Focus::History]
+        } else {
+            &[Focus::Stations, Focus::Status]
+        };
+        let i = order.iter().position(|&f| f == self).unwrap_or(1);
+        let n = order.len();
+        order[if forward {
+            (i + 1) % n
+        } else {
+            (i + n - 1) % n
+        }]
+    }
+}
+
 pub struct UiState {
     pub station_index: usize,
     pub volume: u32,
+    /// Volume the backend has taken; differs from `volume` while a change
+    /// is held back or being applied.
+    pub applied_volume: u32,
     pub muted: bool,
     pub elapsed: Duration,
+    /// `elapsed_format` from the config.
+    pub elapsed_format: String,
     pub now_playing: Option<String>,
+    /// Stations flagged as likely dead, by name.
+    pub likely_dead: HashSet<String>,
+    /// The current track is in the liked list.
+    pub liked: bool,
+    /// Playback speed, shown only for on-demand sources.
+    pub speed: Option<f64>,
+    pub theme: Theme,
+    /// What the terminal can display; themes are adapted to it when drawn.
+    pub color_support: ColorSupport,
+    pub icons: IconSet,
+    pub layout: LayoutPrefs,
+    pub station_sort: StationSort,
+    /// The session is being published for ListenAlong.
+    pub sharing: bool,
+    /// Night mode is on.
+    pub night: bool,
+    /// What another MPRIS player is playing, when shown.
+    pub other_player: Option<String>,
+    /// Settings screen, drawn instead of the player view while open.
+    pub settings: Option<SettingsScreen>,
+    /// Stats view, drawn instead of the player view while open.
+    pub stats: Option<StatsScreen>,
+    /// Timeline of station switches, drawn instead of the player view while open.
+    pub timeline: Option<TimelineScreen>,
+    /// Saved recordings, drawn instead of the player view while open.
+    pub recordings: Option<RecordingsScreen>,
+    /// Command palette, drawn over the player view while open.
+    pub palette: Option<PaletteScreen>,
+    /// Confirmation dialog, drawn over everything while open.
+    pub confirm: Option<ConfirmScreen>,
+    /// First-run cheat sheet, with where the config file lives.
+    pub welcome: Option<String>,
+    /// Release notes since the version last run, shown once after upgrading.
+    pub whats_new: Option<Vec<&'static ReleaseNotes>>,
+    /// Station detail popup, drawn over the player view while open.
+    pub detail: Option<DetailScreen>,
+    /// Output device picker, drawn over the player view while open.
+    pub output: Option<OutputScreen>,
+    /// Ambient noise mixer, drawn over the player view while open.
+    pub ambient: Option<AmbientScreen>,
+    /// Mixer pane, drawn over the player view while open.
+    pub mixer: Option<MixerScreen>,
+    /// Time left on the sleep timer.
+    pub sleep_left: Option<Duration>,
+    /// Label of the station rotation playing.
+    pub rotation: Option<String>,
+    /// How long the stream has been recorded, while it is.
+    pub recorded: Option<Duration>,
+    /// Beat generator preset and frequency while it plays.
+    pub beats: Option<String>,
+    /// Power saver on (status bar indicator).
+    pub power_saver: bool,
+    /// Monthly data cap reached, low-bitrate streams played (indicator).
+    pub data_capped: bool,
+    /// Low-data mode on (status bar indicator).
+    pub low_data: bool,
+    /// Buffer fill while the backend is buffering.
+    pub buffering: Option<u32>,
+    /// The selected station's player is still being started.
+    pub switching: bool,
+    /// Newer release and its notes URL, from the opt-in update check.
+    pub update: Option<(String, String)>,
+    /// MusicBrainz data for the current track.
+    pub recording: Option<Recording>,
+    /// Timeshift buffer extent and position, once there is something to seek.
+    pub timeshift: Option<Timeshift>,
+    /// Overlay messages, see [`Toast`], and their log.
+    pub notifications: Notifications,
+    /// Notification log, drawn instead of the player view while open: the
+    /// first line shown.
+    pub log_scroll: Option<u16>,
+    /// Tracks heard this session, newest first.
+    pub recent_tracks: Vec<String>,
+    pub focus: Focus,
+    /// Station under the cursor while the station list has focus.
+    pub station_cursor: usize,
+    /// First line shown in the track history pane.
+    pub history_scroll: u16,
+    /// Where the volume slider's track was last drawn, for mouse input.
+    pub volume_track: Cell<Option<Rect>>,
+    /// Name of the player backend in use.
+    pub backend: &'static str,
+    /// What it can do; controls it cannot perform are dimmed.
+    pub capabilities: Capabilities,
+    /// No terminal: nothing is drawn, and notifications are printed.
+    pub headless: bool,
 }
 
 impl UiState {
@@ -45,30 +682,208 @@ impl UiState {
         Self {
             station_index: 0,
             volume: 70,
+            applied_volume: 70,
             muted: false,
             elapsed: Duration::ZERO,
+            elapsed_format: DEFAULT_ELAPSED_FORMAT.to_string(),
             now_playing: None,
+            likely_dead: HashSet::new(),
+            liked: false,
+            speed: None,
+            theme: THEMES[0],
+            color_support: ColorSupport::Ansi16,
+            icons: PLAIN_ICONS,
+            layout: LayoutPrefs::default(),
+            station_sort: StationSort::Manual,
+            sharing: false,
+            night: false,
+            other_player: None,
+            settings: None,
+            stats: None,
+            palette: None,
+            confirm: None,
+            welcome: None,
+            whats_new: None,
+            detail: None,
+            output: None,
+            ambient: None,
+            mixer: None,
+            timeline: None,
+            recordings: None,
+            sleep_left: None,
+            rotation: None,
+            recorded: None,
+            beats: None,
+            power_saver: false,
+            data_capped: false,
+            low_data: false,
+            buffering: None,
+            switching: false,
+            timeshift: None,
+            update: None,
+            recording: None,
+            notifications: Notifications::default(),
+            log_scroll: None,
+            recent_tracks: Vec::new(),
+            focus: Focus::Status,
+            station_cursor: 0,
+            history_scroll: 0,
+            volume_track: Cell::new(None),
+            backend: "",
+            capabilities: Capabilities::default(),
+            headless: false,
+        }
+    }
+
+    /// Whether streams play their `low_bitrate_url`: power saver, data cap
+    /// or low-data mode.
+    pub fn low_bitrate(&self) -> bool {
+        self.power_saver || self.data_capped || self.low_data
+    }
+
+    /// Show `toast`, see [`Notifications::push`]. Headless, it is printed.
+    pub fn notify(&mut self, toast: Toast) {
+        if self.headless {
+            println!("{} {}", chrono::Local::now().format("%H:%M:%S"), toast.text);
+        }
+        self.notifications.push(toast);
+    }
+
+    /// Volume for a mouse at `column`, when `row` is on the slider's track.
+    pub fn volume_at(&self, column: u16, row: u16) -> Option<u32> {
+        let track = self.volume_track.get()?;
+        (row == track.y && column >= track.x && column < track.x + track.width)
+            .then(|| track_volume(track, column))
+    }
+
+    /// Volume for a mouse at `column` while dragging the slider, also when
+    /// it has left the track.
+    pub fn volume_dragged_to(&self, column: u16) -> Option<u32> {
+        Some(track_volume(self.volume_track.get()?, column))
+    }
+}
+
+/// The volume a column of `track` stands for; the last cell is 100%.
+fn track_volume(track: Rect, column: u16) -> u32 {
+    let cells = column.saturating_sub(track.x).min(track.width - 1) as u32 + 1;
+    (cells * 100).div_ceil(track.width as u32)
+}
+
+/// Cells in the volume slider's track.
+const VOLUME_TRACK: u16 = 30;
+
+/// Volume as a slider: icon, percentage and a track filled up to the
+/// volume. The part the backend has not taken yet is drawn as staged;
+/// while muted the muted icon shows and the track is dimmed.
+struct VolumeSlider<'a> {
+    volume: u32,
+    applied: u32,
+    muted: bool,
+    icons: &'a IconSet,
+    accent: Color,
+}
+
+impl VolumeSlider<'_> {
+    fn label(&self) -> String {
+        let icon = if self.muted {
+            self.icons.muted
+        } else {
+            self.icons.volume
+        };
+        format!("{} {:>3}% ", icon, self.volume)
+    }
+
+    fn width(&self) -> u16 {
+        Line::from(self.label()).width() as u16 + VOLUME_TRACK
+    }
+
+    /// Where the track goes when drawn in `area`.
+    fn track(&self, area: Rect) -> Rect {
+        let label = (Line::from(self.label()).width() as u16).min(area.width);
+        Rect {
+            x: area.x + label,
+            width: VOLUME_TRACK.min(area.width - label),
+            height: area.height.min(1),
+            ..area
         }
     }
 }
 
-pub fn draw_ui<B: Backend>(
-    terminal: &mut Terminal<B>,
-    state: &UiState,
-    stations: &[Station],
-) {
+impl Widget for VolumeSlider<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let track = self.track(area);
+        buf.set_stringn(
+            area.x,
+            area.y,
+            self.label(),
+            area.width as usize,
+            Style::default(),
+        );
+        let cells = |volume: u32| (volume * track.width as u32 / 100) as u16;
+        let set = cells(self.volume.min(self.applied));
+        let staged = cells(self.volume.max(self.applied));
+        let [set_symbol, staged_symbol, unset_symbol] = self.icons.slider;
+        for i in 0..track.width {
+            let (symbol, style) = if i < set {
+                (set_symbol, Style::default().fg(self.accent))
+            } else if i < staged {
+                (staged_symbol, Style::default().fg(self.accent))
+            } else {
+                (unset_symbol, Style::default().add_modifier(Modifier::DIM))
+            };
+            let style = if self.muted {
+                Style::default().add_modifier(Modifier::DIM)
+            } else {
+                style
+            };
+            buf.get_mut(track.x + i, track.y)
+                .set_symbol(symbol)
+                .set_style(style);
+        }
+    }
+}
+
+pub fn draw_ui<B: Backend>(terminal: &mut Terminal<B>, state: &UiState, stations: &[Station]) {
+    if state.headless {
+        return;
+    }
     terminal
         .draw(|f| {
             let size = f.size();
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Length(stations.len() as u16 + 2),
-                    Constraint::Length(3),
-                    Constraint::Length(3),
-                    Constraint::Min(0),
-                ])
-                .split(size);
+            let theme = state.theme.adapted(state.color_support);
+            state.volume_track.set(None);
+            if let Some(screen) = state.settings.as_ref() {
+                draw_settings(f, screen, &theme);
+                draw_confirm(f, state, &theme);
+                draw_toast(f, state, &theme);
+                return;
+            }
+            if let Some(screen) = state.stats.as_ref() {
+                draw_stats(f, screen, &theme);
+                draw_confirm(f, state, &theme);
+                draw_toast(f, state, &theme);
+                return;
+            }
+            if let Some(scroll) = state.log_scroll {
+                draw_log(f, state, scroll, &theme);
+                draw_confirm(f, state, &theme);
+                draw_toast(f, state, &theme);
+                return;
+            }
+            if let Some(screen) = state.timeline.as_ref() {
+                draw_timeline(f, screen, &theme);
+                draw_confirm(f, state, &theme);
+                draw_toast(f, state, &theme);
+                return;
+            }
+            if let Some(screen) = state.recordings.as_ref() {
+                draw_recordings(f, screen, &theme);
+                draw_confirm(f, state, &theme);
+                draw_toast(f, state, &theme);
+                return;
+            }
+            let layout = &state.layout;
+            let areas = player_layout(size, layout, stations.len());
 
             // Stations list
             let items: Vec<ListItem> = stations
@@ -76,64 +891,1008 @@ pub fn draw_ui<B: Backend>(
                 .enumerate()
                 .map(|(i, s)| {
                     let style = if i == state.station_index {
-                        Style::default().fg(Color::Yellow)
+                        Style::default().fg(theme.accent)
                     } else {
                         Style::default()
                     };
-                    ListItem::new(format!(
-                        "{} {}",
-                        if i == state.station_index { "->" } else { "  " },
-                        s.name
-                    ))
-                    .style(style)
+                    let spinner = state.icons.spinner;
+                    let marker = if i == state.station_index && state.switching {
+                        let frame =
+                            (state.elapsed.as_millis() / SPINNER_FRAME.as_millis()) as usize;
+                        spinner[frame % spinner.len()].to_string()
+                    } else if i == state.station_index {
+                        state.icons.current.to_string()
+                    } else {
+                        " ".repeat(state.icons.current.chars().count())
+                    };
+                    let dead = match state.likely_dead.contains(&s.name) {
+                        true if i == state.station_index => " (likely dead — x to remove)",
+                        true => " (likely dead)",
+                        false => "",
+                    };
+                    let favorite = if s.favorite {
+                        format!(" {}", state.icons.favorite)
+                    } else {
+                        String::new()
+                    };
+                    ListItem::new(format!("{} {}{}{}", marker, s.name, favorite, dead)).style(style)
                 })
                 .collect();
-            let list = List::new(items).block(Block::default().borders(Borders::ALL).title("Stations"));
-            f.render_widget(list, chunks[0]);
+            let list_title = match state.station_sort {
+                StationSort::Manual => "Stations".to_string(),
+                sort => format!("Stations (by {})", sort.label()),
+            };
+            let focused = |focus: Focus| {
+                let block = Block::default().borders(Borders::ALL);
+                if state.focus == focus {
+                    block.border_style(Style::default().fg(theme.accent))
+                } else {
+                    block
+                }
+            };
+            let list = List::new(items).block(focused(Focus::Stations).title(list_title));
+            if state.focus == Focus::Stations {
+                let list = list.highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                let cursor = state.station_cursor.min(stations.len().saturating_sub(1));
+                let mut list_state = ListState::default().with_selected(Some(cursor));
+                f.render_stateful_widget(list, areas.stations, &mut list_state);
+            } else {
+                f.render_widget(list, areas.stations);
+            }
 
             // Status
-            let hours = state.elapsed.as_secs() / 3600;
-            let minutes = (state.elapsed.as_secs() % 3600) / 60;
-            let seconds = state.elapsed.as_secs() % 60;
-            let bar_len = 30;
-            let filled = if state.muted {
-                0
-            } else {
-                (state.volume * bar_len) / 100
+            let icons = &state.icons;
+            let speed_status = match state.speed {
+                Some(speed) => format!(" | Speed: {:.2}×", speed),
+                None => String::new(),
+            };
+            let share_status = if state.sharing { " | Sharing" } else { "" };
+            let night_status = if state.night { " | Night" } else { "" };
+            let other_status = match &state.other_player {
+                Some(label) => format!(" | Also playing: {}", label),
+                None => String::new(),
+            };
+            let sleep_status = match state.sleep_left {
+                Some(left) => format!(" | Sleep {}m", left.as_secs().div_ceil(60)),
+                None => String::new(),
+            };
+            let rotation_status = match &state.rotation {
+                Some(label) => format!(" | Rotation {}", label),
+                None => String::new(),
+            };
+            let record_status = match state.recorded {
+                Some(secs) => format!(" | Rec {}", format_elapsed(secs, "%M:%S")),
+                None => String::new(),
+            };
+            let beats_status = match &state.beats {
+                Some(label) => format!(" | Beats {} (g: off)", label),
+                None => String::new(),
+            };
+            let timeshift_status = match state.timeshift {
+                Some(ts) if ts.buffered >= 1.0 => format!(" | {}", timeshift_bar(ts)),
+                _ => String::new(),
             };
-            let bar = format!(
-                "[{}{}]",
-                "#".repeat(filled as usize),
-                "-".repeat((bar_len - filled) as usize)
+            let saver_status = match (state.power_saver, state.data_capped) {
+                (true, _) => " | Power saver",
+                (false, true) => " | Data cap",
+                (false, false) => "",
+            };
+            let low_data_status = if state.low_data { " | Low data" } else { "" };
+            let buffer_status = match state.buffering {
+                Some(percent) => format!(" | Buffering {}%", percent),
+                None => String::new(),
+            };
+            let clock_text = format!(
+                "Elapsed: {} | ",
+                format_elapsed(state.elapsed, &state.elapsed_format)
             );
-            let mute_status = if state.muted { " [MUTED]" } else { "" };
             let status_text = format!(
-                "Elapsed: {:02}:{:02}:{:02} | Volume: {:>3}% {}{}",
-                hours, minutes, seconds, state.volume, bar, mute_status
+                "{}{}{}{}{}{}{}{}{}{}{}{}",
+                speed_status,
+                share_status,
+                night_status,
+                other_status,
+                sleep_status,
+                rotation_status,
+                record_status,
+                beats_status,
+                timeshift_status,
+                saver_status,
+                low_data_status,
+                buffer_status
             );
-            let status = Paragraph::new(status_text)
-                .block(Block::default().borders(Borders::ALL).title("Status"));
-            f.render_widget(status, chunks[1]);
-
+            let slider = VolumeSlider {
+                volume: state.volume,
+                applied: state.applied_volume,
+                muted: state.muted,
+                icons,
+                accent: theme.accent,
+            };
             // Now Playing
             let has_meta = stations
                 .get(state.station_index)
-                .and_then(|s| s.metadata_url)
+                .and_then(|s| s.metadata_url.as_ref())
                 .is_some();
-            let np_text: &str = match state.now_playing.as_deref() {
-                Some(s) => s,
-                None if has_meta => "Loading...",
-                None => "—",
+            let np_text = match state.now_playing.as_deref() {
+                Some(s) => format!("{}{}", icons.track, s),
+                None if has_meta => "Loading...".to_string(),
+                None if !state.capabilities.metadata => {
+                    format!("— ({} reads no track titles)", state.backend)
+                }
+                None => "—".to_string(),
+            };
+            let mut status_title = if layout.compact {
+                format!("Status — {}", np_text)
+            } else {
+                "Status".to_string()
+            };
+            if let Some((version, url)) = &state.update {
+                status_title.push_str(&format!(" · v{} available: {}", version, url));
+            }
+            let status_block = focused(Focus::Status).title(status_title);
+            let status_row = status_block.inner(areas.status);
+            f.render_widget(status_block, areas.status);
+            let status_parts = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Length(Line::from(clock_text.as_str()).width() as u16),
+                    Constraint::Length(slider.width()),
+                    Constraint::Min(0),
+                ])
+                .split(status_row);
+            f.render_widget(Paragraph::new(clock_text), status_parts[0]);
+            // Popups take the mouse
+            let covered = state.detail.is_some()
+                || state.output.is_some()
+                || state.ambient.is_some()
+                || state.mixer.is_some()
+                || state.palette.is_some()
+                || state.confirm.is_some()
+                || state.welcome.is_some()
+                || state.whats_new.is_some();
+            let track = slider.track(status_parts[1]);
+            if !covered && track.width > 0 {
+                state.volume_track.set(Some(track));
+            }
+            f.render_widget(slider, status_parts[1]);
+            f.render_widget(Paragraph::new(status_text), status_parts[2]);
+            let Some(np_area) = areas.now_playing else {
+                draw_detail(f, state, &theme);
+                draw_output(f, state, &theme);
+                draw_ambient(f, state, &theme);
+                draw_mixer(f, state, &theme);
+                draw_palette(f, state, &theme);
+                draw_welcome(f, state, &theme);
+                draw_confirm(f, state, &theme);
+                draw_whats_new(f, state, &theme);
+                draw_toast(f, state, &theme);
+                return;
             };
+
+            let mut np_title = if state.liked {
+                format!("Now Playing {}", icons.liked)
+            } else {
+                "Now Playing".to_string()
+            };
+            if let Some(release) = state.recording.as_ref().and_then(Recording::release_label) {
+                np_title.push_str(&format!(" · {}", release));
+            }
             let now_playing = Paragraph::new(np_text)
-                .block(Block::default().borders(Borders::ALL).title("Now Playing"));
-            f.render_widget(now_playing, chunks[2]);
+                .block(Block::default().borders(Borders::ALL).title(np_title));
+            f.render_widget(now_playing, np_area);
+
+            // Track history (two-column layout)
+            if let Some(history_area) = areas.history {
+                let lines: Vec<Line> = if state.recent_tracks.is_empty() {
+                    vec![Line::from("No tracks heard yet")]
+                } else {
+                    state
+                        .recent_tracks
+                        .iter()
+                        .map(|t| Line::from(format!("{}{}", icons.track, t)))
+                        .collect()
+                };
+                let history = Paragraph::new(lines)
+                    .scroll((state.history_scroll, 0))
+                    .block(focused(Focus::History).title("Recent Tracks"));
+                f.render_widget(history, history_area);
+            }
 
             // Controls
-            let controls_text = "Controls:\nF11: Vol Up | F10: Vol Down | F12: Mute\nF7: Prev Station | F9: Next Station | F8: Play/Pause\nq: Quit";
-            let controls = Paragraph::new(controls_text)
-                .block(Block::default().borders(Borders::ALL).title("Controls"));
-            f.render_widget(controls, chunks[3]);
+            if let Some(controls_area) = areas.controls {
+                let dim = Style::default().add_modifier(Modifier::DIM);
+                let mut lines = vec![Line::from("Controls:")];
+                for row in CONTROLS {
+                    let mut spans = Vec::new();
+                    for (i, item) in row.split(" | ").enumerate() {
+                        if i > 0 {
+                            spans.push(Span::raw(" | "));
+                        }
+                        spans.push(if control_available(item, state.capabilities) {
+                            Span::raw(item)
+                        } else {
+                            Span::styled(item, dim)
+                        });
+                    }
+                    lines.push(Line::from(spans));
+                }
+                let controls = Paragraph::new(lines).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!("Controls ({})", state.backend)),
+                );
+                f.render_widget(controls, controls_area);
+            }
+
+            draw_detail(f, state, &theme);
+            draw_output(f, state, &theme);
+            draw_ambient(f, state, &theme);
+            draw_mixer(f, state, &theme);
+            draw_palette(f, state, &theme);
+            draw_welcome(f, state, &theme);
+            draw_confirm(f, state, &theme);
+            draw_whats_new(f, state, &theme);
+            draw_toast(f, state, &theme);
         })
         .unwrap();
 }
+
+/// Rows of the Controls pane; items are separated by " | ".
+const CONTROLS: [&str; 4] = [
+    "F11: Vol Up | F10: Vol Down | F12: Mute | Tab: Focus pane (↑/↓ act on it, Enter: station details)",
+    "F7: Prev Station | F9: Next Station | u: Undo switch | F8: Play/Pause | ←/→: Seek 30 s | End: Live",
+    "d (hold): Duck | b (hold): Boost | [ / ]: Speed | a: Audio | e: Output | v: Mixer | n: Noise | g/G: Beats/preset | s: Settings",
+    "c: Compact | ?: Hide controls | { / }: List size | t: Theme | F6: Sort | h: Stats | y: History | f: Recordings | k: Backend | w: Rotation | z: Night | j: Notifications | l: Like | p: Share | Ctrl+P: Commands | i: Info | o/O: Homepage/Search | Tab, letter: Jump in list | q: Quit",
+];
+
+/// Whether a backend with `capabilities` can perform a [`CONTROLS`] item.
+fn control_available(item: &str, capabilities: Capabilities) -> bool {
+    match item.split(": ").next().unwrap_or_default() {
+        "F11" | "F10" | "d (hold)" | "b (hold)" => capabilities.runtime_volume,
+        "F8" => capabilities.pause,
+        "←/→" | "End" => capabilities.seek,
+        "[ / ]" => capabilities.speed,
+        _ => true,
+    }
+}
+
+/// Full-screen settings view: tabs on top, the pane's settings below.
+fn draw_settings(f: &mut Frame, screen: &SettingsScreen, theme: &Theme) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(3),
+        ])
+        .split(f.size());
+
+    let tabs = Tabs::new(screen.tabs.to_vec())
+        .select(screen.pane)
+        .highlight_style(
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(Block::default().borders(Borders::ALL).title("Settings"));
+    f.render_widget(tabs, chunks[0]);
+
+    let label_width = screen.rows.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+    let items: Vec<ListItem> = screen
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(i, (label, value))| {
+            let style = if i == screen.row {
+                Style::default().fg(theme.accent)
+            } else {
+                Style::default()
+            };
+            ListItem::new(format!(
+                "{} {:<width$}  {}",
+                if i == screen.row { "->" } else { "  " },
+                label,
+                value,
+                width = label_width
+            ))
+            .style(style)
+        })
+        .collect();
+    let list = List::new(items).block(Block::default().borders(Borders::ALL));
+    f.render_widget(list, chunks[1]);
+
+    let help = Paragraph::new("Tab: Next pane | ↑↓: Select | ←→: Change | Esc: Close")
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(help, chunks[2]);
+}
+
+/// Full-screen stats view: per-station totals above the track leaderboard.
+fn draw_stats(f: &mut Frame, screen: &StatsScreen, theme: &Theme) {
+    let station_rows = screen.stations.len().max(1) as u16 + 2;
+    let goal_rows = if screen.goal.is_some() { 3 } else { 0 };
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(goal_rows), Constraint::Min(0)])
+        .split(f.size());
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(station_rows),
+            Constraint::Min(0),
+            Constraint::Length(3),
+        ])
+        .split(rows[1]);
+
+    if let Some(goal) = &screen.goal {
+        let clock = |secs: u64| format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60);
+        let ratio = (goal.today_secs as f64 / goal.goal_secs as f64).min(1.0);
+        let streak = match goal.streak {
+            0 => String::new(),
+            1 => " · 1 day streak".to_string(),
+            n => format!(" · {} day streak", n),
+        };
+        let label = format!(
+            "{} of {} today ({:.0}%){}",
+            clock(goal.today_secs),
+            clock(goal.goal_secs),
+            ratio * 100.0,
+            streak
+        );
+        let gauge = Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title("Daily goal"))
+            .gauge_style(Style::default().fg(theme.accent))
+            .ratio(ratio)
+            .label(label);
+        f.render_widget(gauge, rows[0]);
+    }
+
+    let name_width = screen
+        .stations
+        .iter()
+        .map(|(n, _)| n.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut station_items: Vec<ListItem> = screen
+        .stations
+        .iter()
+        .map(|(name, s)| {
+            ListItem::new(format!(
+                "{:<width$}  {:>4} plays  {:>3}h {:02}m",
+                name,
+                s.plays,
+                s.listen_secs / 3600,
+                (s.listen_secs % 3600) / 60,
+                width = name_width
+            ))
+        })
+        .collect();
+    if station_items.is_empty() {
+        station_items.push(ListItem::new("Nothing played yet"));
+    }
+    let stations =
+        List::new(station_items).block(Block::default().borders(Borders::ALL).title("Stations"));
+    f.render_widget(stations, chunks[0]);
+
+    let mut track_items: Vec<ListItem> = screen
+        .tracks
+        .iter()
+        .enumerate()
+        .map(|(i, (title, plays))| {
+            let style = if i == 0 {
+                Style::default().fg(theme.accent)
+            } else {
+                Style::default()
+            };
+            ListItem::new(format!("{:>2}. {:>4}×  {}", i + 1, plays, title)).style(style)
+        })
+        .collect();
+    if track_items.is_empty() {
+        track_items.push(ListItem::new("No track metadata seen yet"));
+    }
+    let tracks = List::new(track_items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Most heard tracks"),
+    );
+    f.render_widget(tracks, chunks[1]);
+
+    let mut help = match screen.queued_listens {
+        0 => "Esc: Close".to_string(),
+        1 => "Esc: Close | 1 listen waiting to be scrobbled".to_string(),
+        n => format!("Esc: Close | {} listens waiting to be scrobbled", n),
+    };
+    if !screen.data.is_empty() {
+        help.push_str(&format!(" | Data: {}", screen.data));
+    }
+    if let Some(e) = screen
+        .queue_error
+        .as_ref()
+        .filter(|_| screen.queued_listens > 0)
+    {
+        help.push_str(&format!(" ({})", e));
+    }
+    let help = Paragraph::new(help).block(Block::default().borders(Borders::ALL));
+    f.render_widget(help, chunks[2]);
+}
+
+/// Full-screen timeline of station switches, grouped by day.
+fn draw_timeline(f: &mut Frame, screen: &TimelineScreen, theme: &Theme) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(f.size());
+
+    let mut lines: Vec<Line> = Vec::new();
+    for (day, entries) in &screen.days {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::styled(day.clone(), Style::default().fg(theme.accent)));
+        for e in entries {
+            lines.push(Line::from(format!(
+                "  {}  {:>3}h {:02}m  {}",
+                e.time,
+                e.length / 3600,
+                (e.length % 3600) / 60,
+                e.station
+            )));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(Line::from("No station switches logged yet"));
+    }
+    let timeline = Paragraph::new(lines)
+        .scroll((screen.scroll, 0))
+        .block(Block::default().borders(Borders::ALL).title("History"));
+    f.render_widget(timeline, chunks[0]);
+
+    let help = Paragraph::new("↑/↓ PgUp/PgDn: Scroll | Esc: Close")
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(help, chunks[1]);
+}
+
+/// Full-screen list of saved recordings.
+fn draw_recordings(f: &mut Frame, screen: &RecordingsScreen, theme: &Theme) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(f.size());
+
+    let name_width = screen
+        .rows
+        .iter()
+        .map(|(name, ..)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut items: Vec<ListItem> = screen
+        .rows
+        .iter()
+        .map(|(name, size, length)| {
+            ListItem::new(format!(
+                "{:<width$}  {:>10}  {:>8}",
+                name,
+                size,
+                length,
+                width = name_width
+            ))
+        })
+        .collect();
+    if items.is_empty() {
+        items.push(ListItem::new(
+            "No recordings yet — record <duration> in Ctrl+P",
+        ));
+    }
+    let title = format!("Recordings in {}", screen.directory);
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::REVERSED),
+        );
+    let mut list_state =
+        ListState::default().with_selected((!screen.rows.is_empty()).then_some(screen.selected));
+    f.render_stateful_widget(list, chunks[0], &mut list_state);
+
+    let help = match &screen.renaming {
+        Some((text, cursor)) => {
+            Paragraph::new(input_line("Rename to: ", text, *cursor, Style::default()))
+        }
+        None => {
+            Paragraph::new("↑/↓: Select | Enter: Play | r: Rename | d/Del: Delete | Esc: Close")
+        }
+    };
+    f.render_widget(
+        help.block(Block::default().borders(Borders::ALL)),
+        chunks[1],
+    );
+}
+
+/// Station detail popup, centered over the player view.
+fn draw_detail(f: &mut Frame, state: &UiState, theme: &Theme) {
+    let Some(detail) = state.detail.as_ref() else {
+        return;
+    };
+    let size = f.size();
+    let width = 64.min(size.width);
+    let label_width = detail.rows.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+    let mut lines: Vec<Line> = detail
+        .rows
+        .iter()
+        .map(|(label, value)| {
+            Line::from(format!("{:<width$}  {}", label, value, width = label_width))
+        })
+        .collect();
+    lines.push(Line::from(""));
+    if let Some((text, cursor)) = &detail.editing {
+        lines.push(input_line("Notes: ", text, *cursor, Style::default()));
+        lines.push(Line::from(""));
+        lines.push(Line::styled(
+            "Enter: Save | Esc: Cancel",
+            Style::default().fg(theme.accent),
+        ));
+    } else {
+        if !detail.actions.is_empty() {
+            let mut spans = Vec::new();
+            for (i, action) in detail.actions.iter().enumerate() {
+                let style = if i == detail.selected {
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                spans.push(Span::styled(format!(" {} ", action), style));
+                spans.push(Span::raw(" "));
+            }
+            lines.push(Line::from(spans));
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::styled(
+            "←/→ Enter: Action | o: Open homepage | O: Search track | Esc: Close",
+            Style::default().fg(theme.accent),
+        ));
+    }
+    // Rough height: wrapped rows plus borders
+    let inner = width.saturating_sub(2).max(1) as usize;
+    let wrapped: usize = lines.iter().map(|l| l.width().max(1).div_ceil(inner)).sum();
+    let height = (wrapped as u16 + 2).min(size.height);
+    let area = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + (size.height - height) / 2,
+        width,
+        height,
+    };
+    let popup = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(detail.title.as_str()),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+/// Yes/no dialog for a destructive action, centered over whatever is
+/// shown.
+fn draw_confirm(f: &mut Frame, state: &UiState, theme: &Theme) {
+    let Some(confirm) = state.confirm.as_ref() else {
+        return;
+    };
+    let size = f.size();
+    let width = 48.min(size.width);
+    let lines = vec![
+        Line::from(confirm.question.as_str()),
+        Line::from(""),
+        Line::styled("y: Yes | n/Esc: No", Style::default().fg(theme.accent)),
+    ];
+    let inner = width.saturating_sub(2).max(1) as usize;
+    let wrapped: usize = lines.iter().map(|l| l.width().max(1).div_ceil(inner)).sum();
+    let height = (wrapped as u16 + 2).min(size.height);
+    let area = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + (size.height - height) / 2,
+        width,
+        height,
+    };
+    let popup = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.accent))
+            .title(confirm.title.as_str()),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+/// Keys worth knowing on day one, shown by the first-run cheat sheet.
+const WELCOME_KEYS: [(&str, &str); 9] = [
+    ("F8", "Play / pause"),
+    ("F7 / F9", "Previous / next station"),
+    ("F10 / F11 / F12", "Volume down / up / mute"),
+    ("Tab", "Focus a pane; Enter opens station details"),
+    ("Ctrl+P", "Command palette"),
+    ("s", "Settings"),
+    ("l", "Like the current track"),
+    ("?", "Show or hide the Controls pane"),
+    ("q", "Quit"),
+];
+
+/// First-run cheat sheet, centered over the player view.
+fn draw_welcome(f: &mut Frame, state: &UiState, theme: &Theme) {
+    let Some(config_path) = state.welcome.as_deref() else {
+        return;
+    };
+    let mut lines: Vec<Line> = WELCOME_KEYS
+        .iter()
+        .map(|(key, action)| {
+            Line::from(vec![
+                Span::styled(format!("{:>16}  ", key), Style::default().fg(theme.accent)),
+                Span::raw(*action),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from("Stations and options live in"));
+    lines.push(Line::from(format!("  {}", config_path)));
+    lines.push(Line::from(""));
+    lines.push(Line::styled(
+        "Press any key to start",
+        Style::default().fg(theme.accent),
+    ));
+    let size = f.size();
+    let width = 64.min(size.width);
+    let inner = width.saturating_sub(2).max(1) as usize;
+    let wrapped: usize = lines.iter().map(|l| l.width().max(1).div_ceil(inner)).sum();
+    let height = (wrapped as u16 + 2).min(size.height);
+    let area = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + (size.height - height) / 2,
+        width,
+        height,
+    };
+    let popup = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.accent))
+            .title("Welcome to lofi_rs"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+/// Release notes after an upgrade: new features and changed default keys,
+/// centered over everything.
+fn draw_whats_new(f: &mut Frame, state: &UiState, theme: &Theme) {
+    let Some(releases) = state.whats_new.as_deref() else {
+        return;
+    };
+    let accent = Style::default().fg(theme.accent);
+    let mut lines: Vec<Line> = Vec::new();
+    for notes in releases {
+        lines.push(Line::styled(
+            notes.version,
+            accent.add_modifier(Modifier::BOLD),
+        ));
+        for feature in notes.features {
+            lines.push(Line::from(format!("  • {}", feature)));
+        }
+        if !notes.keys.is_empty() {
+            lines.push(Line::from("  Changed keys:"));
+        }
+        for change in notes.keys {
+            lines.push(Line::from(vec![
+                Span::styled(format!("    {}  ", change.key), accent),
+                Span::raw(format!("{} → {}", change.before, change.after)),
+            ]));
+        }
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::styled("Press any key to continue", accent));
+    let size = f.size();
+    let width = 76.min(size.width);
+    let inner = width.saturating_sub(2).max(1) as usize;
+    let wrapped: usize = lines.iter().map(|l| l.width().max(1).div_ceil(inner)).sum();
+    let height = (wrapped as u16 + 2).min(size.height);
+    let area = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + (size.height - height) / 2,
+        width,
+        height,
+    };
+    let popup = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(accent)
+            .title(format!(
+                "What's new in lofi_rs {}",
+                env!("CARGO_PKG_VERSION")
+            )),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+/// Output device popup, centered over the player view.
+fn draw_output(f: &mut Frame, state: &UiState, theme: &Theme) {
+    let Some(output) = state.output.as_ref() else {
+        return;
+    };
+    let size = f.size();
+    let width = 56.min(size.width);
+    let height = (output.devices.len().max(1) as u16 + 2).min(size.height);
+    let area = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + (size.height - height) / 2,
+        width,
+        height,
+    };
+    let mut items: Vec<ListItem> = output
+        .devices
+        .iter()
+        .enumerate()
+        .map(|(i, (label, current))| {
+            let marker = if *current { "● " } else { "  " };
+            let style = if i == output.selected {
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(format!("{}{}", marker, label)).style(style)
+        })
+        .collect();
+    if items.is_empty() {
+        items.push(ListItem::new("No output devices found"));
+    }
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Output (Enter: use, r: rescan, Esc: close)"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
+
+/// "⏪ -1:30 ███│▒▒ 12:00": time behind live, then the buffer with the
+/// playhead, then its length.
+fn timeshift_bar(ts: Timeshift) -> String {
+    const WIDTH: usize = 12;
+    let clock = |secs: f64| format!("{}:{:02}", secs as u64 / 60, secs as u64 % 60);
+    let played = ((ts.buffered - ts.behind) / ts.buffered * WIDTH as f64).round() as usize;
+    let played = played.min(WIDTH);
+    let bar = format!("{}│{}", "█".repeat(played), "▒".repeat(WIDTH - played));
+    if ts.behind < 1.0 {
+        format!("Live {} {}", bar, clock(ts.buffered))
+    } else {
+        format!("⏪ -{} {} {}", clock(ts.behind), bar, clock(ts.buffered))
+    }
+}
+
+/// Ambient mixer popup: one level bar per noise layer.
+fn draw_ambient(f: &mut Frame, state: &UiState, theme: &Theme) {
+    let Some(ambient) = state.ambient.as_ref() else {
+        return;
+    };
+    let size = f.size();
+    let width = 48.min(size.width);
+    let height = (ambient.layers.len() as u16 + 2).min(size.height);
+    let area = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + (size.height - height) / 2,
+        width,
+        height,
+    };
+    let items: Vec<ListItem> = ambient
+        .layers
+        .iter()
+        .enumerate()
+        .map(|(i, (name, level))| {
+            let filled = (*level as usize + 5) / 10;
+            let bar = format!("{}{}", "█".repeat(filled), "░".repeat(10 - filled));
+            let value = if *level == 0 {
+                "off".to_string()
+            } else {
+                format!("{}%", level)
+            };
+            let style = if i == ambient.selected {
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(format!("{:<12} {} {}", name, bar, value)).style(style)
+        })
+        .collect();
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Ambient (←/→: level, 0: off, Esc: close)"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
+
+/// Mixer popup: main and second stream with their levels.
+fn draw_mixer(f: &mut Frame, state: &UiState, theme: &Theme) {
+    let Some(mixer) = state.mixer.as_ref() else {
+        return;
+    };
+    let size = f.size();
+    let width = 64.min(size.width);
+    let height = (mixer.rows.len() as u16 + 2).min(size.height);
+    let area = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + (size.height - height) / 2,
+        width,
+        height,
+    };
+    let items: Vec<ListItem> = mixer
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(i, (label, station, level))| {
+            let text = match level {
+                Some(level) => {
+                    let filled = (*level as usize + 5) / 10;
+                    format!(
+                        "{:<7} {:<24.24} {}{} {}%",
+                        label,
+                        station,
+                        "█".repeat(filled),
+                        "░".repeat(10 - filled),
+                        level
+                    )
+                }
+                None => format!("{:<7} {}", label, station),
+            };
+            let style = if i == mixer.selected {
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(text).style(style)
+        })
+        .collect();
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Mixer (←/→: level, </>: second station, 0: off, Esc: close)"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
+
+/// Command palette popup: input line on top, matching commands below.
+fn draw_palette(f: &mut Frame, state: &UiState, theme: &Theme) {
+    let Some(palette) = state.palette.as_ref() else {
+        return;
+    };
+    let size = f.size();
+    let width = 50.min(size.width);
+    let height = (palette.hints.len() as u16 + 4)
+        .min(size.height.saturating_sub(2))
+        .max(3);
+    let area = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + 1.min(size.height),
+        width,
+        height,
+    };
+    let mut items = vec![ListItem::new(input_line(
+        "> ",
+        &palette.input,
+        palette.cursor,
+        Style::default()
+            .fg(theme.accent)
+            .add_modifier(Modifier::BOLD),
+    ))];
+    items.push(ListItem::new(""));
+    items.extend(
+        palette
+            .hints
+            .iter()
+            .map(|h| ListItem::new(format!("  {}", h))),
+    );
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Command (Enter: run, Esc: close)"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
+
+/// A text input as a line: `prompt`, then `text` with the char under the
+/// cursor shown reversed.
+fn input_line<'a>(prompt: &'a str, text: &str, cursor: usize, style: Style) -> Line<'a> {
+    let at = text
+        .char_indices()
+        .nth(cursor)
+        .map_or(text.len(), |(i, _)| i);
+    let (before, rest) = text.split_at(at);
+    let (under, after) = rest.split_at(rest.chars().next().map_or(0, char::len_utf8));
+    let under = if under.is_empty() { " " } else { under };
+    Line::from(vec![
+        Span::styled(prompt, style),
+        Span::styled(before.to_string(), style),
+        Span::styled(under.to_string(), style.add_modifier(Modifier::REVERSED)),
+        Span::styled(after.to_string(), style),
+    ])
+}
+
+/// Colors of a toast of `kind`.
+fn toast_style(kind: ToastKind, state: &UiState, theme: &Theme) -> Style {
+    match kind {
+        ToastKind::Info => Style::default().fg(theme.toast_fg).bg(theme.toast_bg),
+        ToastKind::Warning => Style::default()
+            .fg(adapt_color(Color::Black, state.color_support))
+            .bg(adapt_color(Color::Yellow, state.color_support)),
+        ToastKind::Error => Style::default()
+            .fg(adapt_color(Color::White, state.color_support))
+            .bg(adapt_color(Color::Red, state.color_support)),
+    }
+}
+
+/// The toast stack over the bottom of the screen, newest at the bottom.
+fn draw_toast(f: &mut Frame, state: &UiState, theme: &Theme) {
+    let size = f.size();
+    let mut bottom = size.bottom().saturating_sub(1);
+    for toast in state.notifications.shown().iter().rev() {
+        if bottom < size.y + 3 {
+            break;
+        }
+        let text = toast.text.as_str();
+        let width = (text.chars().count() as u16 + 4).min(size.width);
+        let area = Rect {
+            x: size.x + (size.width - width) / 2,
+            y: bottom - 3,
+            width,
+            height: 3,
+        };
+        let popup = Paragraph::new(text)
+            .style(toast_style(toast.kind, state, theme))
+            .block(Block::default().borders(Borders::ALL));
+        f.render_widget(Clear, area);
+        f.render_widget(popup, area);
+        bottom -= 3;
+    }
+}
+
+/// Full-screen log of this session's notifications, newest first.
+fn draw_log(f: &mut Frame, state: &UiState, scroll: u16, theme: &Theme) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(f.size());
+
+    let mut lines: Vec<Line> = state
+        .notifications
+        .log()
+        .iter()
+        .rev()
+        .map(|entry| {
+            let style = match entry.kind {
+                ToastKind::Info => Style::default(),
+                kind => Style::default().fg(toast_style(kind, state, theme).bg.unwrap_or_default()),
+            };
+            Line::styled(format!("{}  {}", entry.time, entry.text), style)
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from("No notifications yet"));
+    }
+    let log = Paragraph::new(lines).scroll((scroll, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Notifications"),
+    );
+    f.render_widget(log, chunks[0]);
+
+    let help = Paragraph::new("↑/↓ PgUp/PgDn: Scroll | Esc: Close")
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(help, chunks[1]);
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::net;
+use crate::state::state_dir;
+use crate::stats::unix_now;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/xsa-dev/lofi_rs/releases/latest";
+/// The result of a check is reused for a day.
+const CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+/// The newest published release.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Release {
+    /// Version without the leading "v".
+    pub version: String,
+    /// Release notes page.
+    pub url: String,
+}
+
+/// Last check, cached in `update.json` in the state directory.
+#[derive(Deserialize, Serialize)]
+struct UpdateCache {
+    checked_at: u64,
+    latest: Option<Release>,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+}
+
+/// Dotted version as numbers; anything after the numeric parts is ignored.
+pub fn version_key(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+fn cache_path() -> Option<std::path::PathBuf> {
+    Some(state_dir()?.join("update.json"))
+}
+
+async fn fetch_latest() -> Result<Release, Box<dyn std::error::Error>> {
+    let release: GithubRelease = net::client_builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent(concat!("lofi_rs/", env!("CARGO_PKG_VERSION")))
+        .build()?
+        .get(RELEASES_URL)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(Release {
+        version: release.tag_name.trim_start_matches('v').to_string(),
+        url: release.html_url,
+    })
+}
+
+/// The latest release if it is newer than this build, asking GitHub at
+/// most once a day. Failures count as "no update".
+pub async fn check() -> Option<Release> {
+    let path = cache_path()?;
+    let cached: Option<UpdateCache> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok());
+    let latest = match cached {
+        Some(cache) if unix_now().saturating_sub(cache.checked_at) < CHECK_INTERVAL_SECS => {
+            cache.latest
+        }
+        _ => {
+            let latest = fetch_latest().await.ok();
+            let cache = UpdateCache {
+                checked_at: unix_now(),
+                latest: latest.clone(),
+            };
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            if let Ok(text) = serde_json::to_string(&cache) {
+                let _ = std::fs::write(&path, text);
+            }
+            latest
+        }
+    }?;
+    (version_key(&latest.version) > version_key(env!("CARGO_PKG_VERSION"))).then_some(latest)
+}
+
+/// Run `check` in the background and send the release if there is one.
+pub fn spawn_check(tx: UnboundedSender<Release>) {
+    tokio::spawn(async move {
+        if let Some(release) = check().await {
+            let _ = tx.send(release);
+        }
+    });
+}
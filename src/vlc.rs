@@ -0,0 +1,226 @@
+use std::ffi::CString;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::Duration;
+
+use tokio::sync::watch;
+use vlc::{Event, EventType, Instance, Media, MediaPlayer, MediaPlayerAudioEx, Meta};
+
+use crate::backend::{BackendFuture, ChangeSender, PlayerBackend, PlayerChange};
+
+/// How long a chime player is kept before it is stopped; the chime itself
+/// is well under a second.
+const CHIME_KEEP: Duration = Duration::from_secs(2);
+
+/// A libvlc object. libvlc may be called from any thread, but the bindings
+/// do not mark their handles `Send` or `Sync`.
+struct Shared<T>(T);
+
+// SAFETY: libvlc instances, media and players are thread-safe
+unsafe impl<T> Send for Shared<T> {}
+unsafe impl<T> Sync for Shared<T> {}
+
+/// The libvlc instance all players share, created on first use. `None`
+/// when libvlc cannot be initialised (no plugins found, for one).
+fn instance() -> Option<&'static Instance> {
+    static INSTANCE: OnceLock<Option<Shared<Instance>>> = OnceLock::new();
+    INSTANCE
+        .get_or_init(|| {
+            let instance = Instance::new()?;
+            // libvlc logs to stderr, which would draw over the UI
+            instance.set_log(|_, _, _| {});
+            instance.set_user_agent("lofi_rs", concat!("lofi_rs/", env!("CARGO_PKG_VERSION")));
+            Some(Shared(instance))
+        })
+        .as_ref()
+        .map(|shared| &shared.0)
+}
+
+/// Whether libvlc can be used.
+pub fn available() -> bool {
+    instance().is_some()
+}
+
+fn media(mrl: &str) -> Option<Media> {
+    let instance = instance()?;
+    if mrl.contains("://") {
+        Media::new_location(instance, mrl)
+    } else {
+        Media::new_path(instance, mrl)
+    }
+}
+
+fn add_option(media: &Media, option: &str) {
+    let Ok(option) = CString::new(option) else {
+        return;
+    };
+    // SAFETY: the media is alive and libvlc copies the option string
+    unsafe { vlc::sys::libvlc_media_add_option(media.raw(), option.as_ptr()) };
+}
+
+fn failed(what: &str) -> io::Error {
+    io::Error::other(format!("libvlc could not {}", what))
+}
+
+/// A stream playing through libvlc in this process. Its events report the
+/// end of the stream, buffering and `now_playing` tags.
+pub struct VlcPlayer {
+    /// Taken by `start_kill`, which stops and releases it off the runtime.
+    player: Option<Arc<Shared<MediaPlayer>>>,
+    media: Arc<Shared<Media>>,
+    ended: watch::Receiver<bool>,
+    ended_tx: Arc<watch::Sender<bool>>,
+}
+
+impl VlcPlayer {
+    /// Play `mrl` with media `options` at `level` (after the volume curve).
+    pub fn start(mrl: &str, options: &[String], level: f64) -> io::Result<Self> {
+        let instance = instance().ok_or_else(|| failed("start"))?;
+        let media = media(mrl).ok_or_else(|| failed("open the stream"))?;
+        for option in options {
+            add_option(&media, option);
+        }
+        let player = MediaPlayer::new(instance).ok_or_else(|| failed("create a player"))?;
+        player.set_media(&media);
+        let (ended_tx, ended) = watch::channel(false);
+        let ended_tx = Arc::new(ended_tx);
+        // The bindings never detach callbacks, so they hold nothing that
+        // keeps the player alive
+        let events = player.event_manager();
+        for event in [
+            EventType::MediaPlayerEndReached,
+            EventType::MediaPlayerEncounteredError,
+        ] {
+            let ended_tx = ended_tx.clone();
+            let _ = events.attach(event, move |_, _| {
+                ended_tx.send_replace(true);
+            });
+        }
+        let _ = player.set_volume(level.round() as i32);
+        player.play().map_err(|_| failed("play the stream"))?;
+        Ok(Self {
+            player: Some(Arc::new(Shared(player))),
+            media: Arc::new(Shared(media)),
+            ended,
+            ended_tx,
+        })
+    }
+
+    fn player(&self) -> io::Result<&MediaPlayer> {
+        self.player
+            .as_ref()
+            .map(|player| &player.0)
+            .ok_or_else(|| failed("reach the stopped player"))
+    }
+}
+
+impl PlayerBackend for VlcPlayer {
+    fn wait(&mut self) -> BackendFuture<'_, ()> {
+        let mut ended = self.ended.clone();
+        Box::pin(async move {
+            let _ = ended.wait_for(|&ended| ended).await;
+        })
+    }
+
+    fn has_ended(&mut self) -> bool {
+        *self.ended.borrow()
+    }
+
+    fn start_kill(&mut self) {
+        let Some(player) = self.player.take() else {
+            return;
+        };
+        let ended_tx = self.ended_tx.clone();
+        // Stopping waits for libvlc's input thread to finish
+        tokio::task::spawn_blocking(move || {
+            player.0.stop();
+            drop(player);
+            ended_tx.send_replace(true);
+        });
+    }
+
+    fn set_volume(&mut self, level: f64) -> BackendFuture<'_, io::Result<()>> {
+        let result = self.player().and_then(|player| {
+            player
+                .set_volume(level.round() as i32)
+                .map_err(|_| failed("set the volume"))
+        });
+        Box::pin(async { result })
+    }
+
+    fn set_paused(&mut self, paused: bool) -> BackendFuture<'_, io::Result<()>> {
+        let result = self.player().map(|player| player.set_pause(paused));
+        Box::pin(async { result })
+    }
+
+    fn set_speed(&mut self, speed: f64) -> BackendFuture<'_, io::Result<()>> {
+        let result = self.player().and_then(|player| {
+            player
+                .set_rate(speed as f32)
+                .map_err(|_| failed("change the speed"))
+        });
+        Box::pin(async { result })
+    }
+
+    fn progress(&mut self) -> BackendFuture<'_, Option<f64>> {
+        let millis = self.player().ok().and_then(|player| player.get_time());
+        Box::pin(async move { millis.map(|ms| ms as f64 / 1000.0) })
+    }
+
+    fn observe(&mut self, generation: u64, changes: ChangeSender) {
+        let Some(player) = self.player.as_ref() else {
+            return;
+        };
+        let buffering = changes.clone();
+        let _ =
+            player
+                .0
+                .event_manager()
+                .attach(EventType::MediaPlayerBuffering, move |event, _| {
+                    if let Event::MediaPlayerBuffering(percent) = event {
+                        let fill = (percent < 100.0).then_some(percent as u32);
+                        let _ = buffering.send((generation, PlayerChange::Buffering(fill)));
+                    }
+                });
+        let media: Weak<Shared<Media>> = Arc::downgrade(&self.media);
+        let _ =
+            self.media
+                .0
+                .event_manager()
+                .attach(EventType::MediaMetaChanged, move |event, _| {
+                    if !matches!(event, Event::MediaMetaChanged(Meta::NowPlaying)) {
+                        return;
+                    }
+                    let Some(media) = media.upgrade() else {
+                        return;
+                    };
+                    if let Some(title) = media.0.get_meta(Meta::NowPlaying) {
+                        let _ = changes.send((generation, PlayerChange::Title(title)));
+                    }
+                });
+    }
+}
+
+/// Play the chime `wav` at `level` (after the volume curve) on a player of
+/// its own.
+pub fn play_chime(wav: &Path, level: f64) {
+    let Some(instance) = instance() else {
+        return;
+    };
+    let (Some(media), Some(player)) = (Media::new_path(instance, wav), MediaPlayer::new(instance))
+    else {
+        return;
+    };
+    player.set_media(&media);
+    let _ = player.set_volume(level.round() as i32);
+    if player.play().is_err() {
+        return;
+    }
+    let player = Shared(player);
+    std::thread::spawn(move || {
+        let player = player;
+        std::thread::sleep(CHIME_KEEP);
+        player.0.stop();
+    });
+}